@@ -2,10 +2,18 @@ use zino::{prelude::*, Request, Response, Result};
 use zino_model::user::{JwtAuthService, User};
 
 pub async fn login(mut req: Request) -> Result {
-    let current_time = DateTime::now();
     let body: Map = req.parse_body().await?;
-    let (user_id, mut data) = User::generate_token(body).await.extract(&req)?;
+    let (user_id, data) = User::generate_token(body).await.extract(&req)?;
+    if data.contains_key("mfa_token") {
+        let mut res = Response::default().context(&req);
+        res.set_json_data(data);
+        return Ok(res.into());
+    }
+    complete_login(req, user_id, data).await
+}
 
+pub(crate) async fn complete_login(req: Request, user_id: Uuid, mut data: Map) -> Result {
+    let current_time = DateTime::now();
     let user_updates = json!({
         "status": "Active",
         "last_login_at": data.remove("current_login_at").and_then(|v| v.as_datetime()),
@@ -30,7 +38,7 @@ pub async fn login(mut req: Request) -> Result {
 }
 
 pub async fn refresh(req: Request) -> Result {
-    let claims = req.parse_jwt_claims(JwtClaims::shared_key())?;
+    let claims = req.parse_jwt_claims(JwtKeySet::shared())?;
     let data = User::refresh_token(&claims).await.extract(&req)?;
     let mut res = Response::default().context(&req);
     res.set_json_data(data);
@@ -38,6 +46,14 @@ pub async fn refresh(req: Request) -> Result {
 }
 
 pub async fn logout(req: Request) -> Result {
+    let claims = req.parse_jwt_claims(JwtKeySet::shared())?;
+    User::revoke_token(&claims).await.extract(&req)?;
+
+    let res = Response::default().context(&req);
+    Ok(res.into())
+}
+
+pub async fn logout_all(req: Request) -> Result {
     let user_session = req
         .get_data::<UserSession<_>>()
         .ok_or_else(|| warn!("401 Unauthorized: the user session is invalid"))
@@ -45,6 +61,8 @@ pub async fn logout(req: Request) -> Result {
 
     let mut mutations = Map::from_entry("status", "SignedOut");
     let user_id = user_session.user_id();
+    User::revoke_all_tokens(user_id).await.extract(&req)?;
+
     let (validation, user) = User::update_by_id(user_id, &mut mutations, None)
         .await
         .extract(&req)?;
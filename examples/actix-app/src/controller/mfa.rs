@@ -0,0 +1,104 @@
+use super::auth;
+use zino::{prelude::*, Request, Response, Result};
+use zino_model::user::{JwtAuthService, MfaAuthService, User};
+
+pub async fn verify(mut req: Request) -> Result {
+    let body: Map = req.parse_body().await?;
+    let (user_id, data) = User::verify_second_factor(body).await.extract(&req)?;
+    auth::complete_login(req, user_id, data).await
+}
+
+pub async fn enroll_totp(mut req: Request) -> Result {
+    let body: Map = req.parse_body().await?;
+    let user_id = parse_user_id(&req, &body).await?;
+    let user: Map = User::fetch_by_id(&user_id).await.extract(&req)?;
+    let account_name = user.get_str("name").unwrap_or_default();
+    let data = User::enroll_totp(&user_id, account_name)
+        .await
+        .extract(&req)?;
+
+    let mut res = Response::default().context(&req);
+    res.set_json_data(data);
+    Ok(res.into())
+}
+
+pub async fn confirm_totp(mut req: Request) -> Result {
+    let body: Map = req.parse_body().await?;
+    let user_id = parse_user_id(&req, &body).await?;
+    let totp_code = body.get_str("totp_code").unwrap_or_default();
+    let data = User::confirm_totp(&user_id, totp_code)
+        .await
+        .extract(&req)?;
+
+    let mut res = Response::default().context(&req);
+    res.set_json_data(data);
+    Ok(res.into())
+}
+
+pub async fn webauthn_options(mut req: Request) -> Result {
+    let body: Map = req.parse_body().await?;
+    let user_id = parse_user_id(&req, &body).await?;
+    let user: Map = User::fetch_by_id(&user_id).await.extract(&req)?;
+    let user_name = user.get_str("name").unwrap_or_default();
+    let data = User::webauthn_creation_options(&user_id, user_name)
+        .await
+        .extract(&req)?;
+
+    let mut res = Response::default().context(&req);
+    res.set_json_data(data);
+    Ok(res.into())
+}
+
+pub async fn register_webauthn(mut req: Request) -> Result {
+    let body: Map = req.parse_body().await?;
+    let user_id = parse_user_id(&req, &body).await?;
+    let data = User::register_webauthn(&user_id, &body)
+        .await
+        .extract(&req)?;
+
+    let mut res = Response::default().context(&req);
+    res.set_json_data(data);
+    Ok(res.into())
+}
+
+pub async fn recovery_codes(req: Request) -> Result {
+    let user_id = parse_session_user_id(&req).await?;
+    let recovery_codes = User::generate_recovery_codes(&user_id)
+        .await
+        .extract(&req)?;
+
+    let mut res = Response::default().context(&req);
+    res.set_json_data(Map::from_entry("recovery_codes", recovery_codes));
+    Ok(res.into())
+}
+
+pub async fn disable(mut req: Request) -> Result {
+    let body: Map = req.parse_body().await?;
+    let user_id = parse_session_user_id(&req).await?;
+    User::disable_mfa(&user_id, &body).await.extract(&req)?;
+
+    let res = Response::default().context(&req);
+    Ok(res.into())
+}
+
+async fn parse_user_id(req: &Request, body: &Map) -> zino::Result<Uuid> {
+    if let Some(enrollment_token) = body.get_str("enrollment_token") {
+        let user_id = User::verify_enrollment_token(enrollment_token)
+            .await
+            .extract(req)?;
+        Ok(user_id)
+    } else {
+        parse_session_user_id(req).await
+    }
+}
+
+async fn parse_session_user_id(req: &Request) -> zino::Result<Uuid> {
+    let claims = req.parse_jwt_claims(JwtKeySet::shared())?;
+    User::verify_jwt_claims(&claims).await.extract(req)?;
+    let user_id = claims
+        .subject()
+        .unwrap_or_default()
+        .parse::<Uuid>()
+        .extract(req)?;
+    Ok(user_id)
+}
@@ -44,13 +44,16 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let mut req = Request::from(req);
-        match req.parse_jwt_claims(JwtClaims::shared_key()) {
+        let (user_id, tenant_id) = match req.parse_jwt_claims(JwtKeySet::shared()) {
             Ok(claims) => {
                 if let Ok(mut user_session) = UserSession::<Uuid>::try_from_jwt_claims(claims) {
                     if let Ok(session_id) = req.parse_session_id() {
                         user_session.set_session_id(session_id);
                     }
+                    let user_id = user_session.user_id().to_string();
+                    let tenant_id = user_session.tenant_id().map(|t| t.to_string());
                     req.set_data(user_session);
+                    (user_id, tenant_id)
                 } else {
                     return Box::pin(async move {
                         let message = "401 Unauthorized: invalid JWT claims";
@@ -66,11 +69,15 @@ where
                     result.map_err(|err| err.into())
                 });
             }
-        }
+        };
 
         let req = ServiceRequest::from(req);
         let fut = self.service.call(req);
         Box::pin(async move {
+            AuditContext::set_current_user(user_id);
+            if let Some(tenant_id) = tenant_id {
+                TenantContext::set_current_tenant(tenant_id);
+            }
             let res = fut.await?;
             Ok(res)
         })
@@ -1,18 +1,23 @@
 use crate::{
-    controller::{auth, file, stats, user},
+    controller::{auth, file, mfa, stats, user},
     middleware,
     model::Tag,
 };
-use actix_web::web::{get, post, scope, ServiceConfig};
-use zino::{DefaultController, RouterConfigure};
-use zino_model::User;
+use actix_web::web::{get, post, resource, scope, ServiceConfig};
+use zino::{
+    prelude::Uuid, AuthorizationController, DefaultController, ResponseCacheInitializer,
+    RouterConfigure,
+};
+use zino_model::{Application, Log, User};
 
 pub fn routes() -> Vec<RouterConfigure> {
     vec![
         auth_router as RouterConfigure,
+        oauth2_router as RouterConfigure,
         file_router as RouterConfigure,
         user_router as RouterConfigure,
         tag_router as RouterConfigure,
+        log_router as RouterConfigure,
     ]
 }
 
@@ -25,14 +30,35 @@ pub fn debug_routes() -> Vec<RouterConfigure> {
 
 fn auth_router(cfg: &mut ServiceConfig) {
     cfg.route("/auth/login", post().to(auth::login));
+    cfg.service(
+        scope("/auth/mfa")
+            .route("/verify", post().to(mfa::verify))
+            .route("/totp/enroll", post().to(mfa::enroll_totp))
+            .route("/totp/confirm", post().to(mfa::confirm_totp))
+            .route("/webauthn/options", post().to(mfa::webauthn_options))
+            .route("/webauthn/register", post().to(mfa::register_webauthn))
+            .route("/recovery-codes", post().to(mfa::recovery_codes))
+            .route("/disable", post().to(mfa::disable)),
+    );
     cfg.service(
         scope("/auth")
             .route("/refresh", get().to(auth::refresh))
             .route("/logout", post().to(auth::logout))
+            .route("/logout-all", post().to(auth::logout_all))
             .wrap(middleware::UserSessionInitializer),
     );
 }
 
+fn oauth2_router(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/oauth2")
+            .route("/authorize", get().to(Application::authorize))
+            .route("/token", post().to(Application::token))
+            .route("/introspect", post().to(Application::introspect))
+            .route("/revoke", post().to(Application::revoke)),
+    );
+}
+
 fn file_router(cfg: &mut ServiceConfig) {
     cfg.service(
         scope("/file")
@@ -49,7 +75,8 @@ fn user_router(cfg: &mut ServiceConfig) {
         .route("/user/{id}/view", get().to(user::view))
         .route("/user/list", get().to(User::list))
         .route("/user/import", post().to(User::import))
-        .route("/user/export", get().to(User::export));
+        .route("/user/export", get().to(User::export))
+        .route("/user/aggregate", get().to(User::aggregate));
 }
 
 fn tag_router(cfg: &mut ServiceConfig) {
@@ -57,8 +84,17 @@ fn tag_router(cfg: &mut ServiceConfig) {
         .route("/tag/{id}/delete", post().to(Tag::soft_delete))
         .route("/tag/{id}/update", post().to(Tag::update))
         .route("/tag/{id}/view", get().to(Tag::view))
-        .route("/tag/list", get().to(Tag::list))
         .route("/tag/tree", get().to(Tag::tree));
+    cfg.service(
+        resource("/tag/list")
+            .route(get().to(Tag::list))
+            .wrap(ResponseCacheInitializer::<Uuid>::default()),
+    );
+}
+
+fn log_router(cfg: &mut ServiceConfig) {
+    cfg.route("/log/{id}/view", get().to(Log::view))
+        .route("/log/list", get().to(Log::list));
 }
 
 fn stats_router(cfg: &mut ServiceConfig) {
@@ -31,7 +31,7 @@ pub async fn login(mut req: Request) -> Result {
 }
 
 pub async fn refresh(req: Request) -> Result {
-    let claims = req.parse_jwt_claims(JwtClaims::shared_key())?;
+    let claims = req.parse_jwt_claims(JwtKeySet::shared())?;
     let data = User::refresh_token(&claims).await.extract(&req)?;
     let mut res = Response::default().context(&req);
     res.set_json_data(data);
@@ -39,6 +39,14 @@ pub async fn refresh(req: Request) -> Result {
 }
 
 pub async fn logout(req: Request) -> Result {
+    let claims = req.parse_jwt_claims(JwtKeySet::shared())?;
+    User::revoke_token(&claims).await.extract(&req)?;
+
+    let res = Response::default().context(&req);
+    Ok(res.into())
+}
+
+pub async fn logout_all(req: Request) -> Result {
     let user_session = req
         .get_data::<UserSession<_>>()
         .ok_or_else(|| warn!("401 Unauthorized: the user session is invalid"))
@@ -46,6 +54,8 @@ pub async fn logout(req: Request) -> Result {
 
     let mut mutations = Map::from_entry("status", "SignedOut");
     let user_id = user_session.user_id();
+    User::revoke_all_tokens(user_id).await.extract(&req)?;
+
     let (validation, user) = User::update_by_id(user_id, &mut mutations, None)
         .await
         .extract(&req)?;
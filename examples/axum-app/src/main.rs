@@ -17,6 +17,8 @@ mod service;
 use zino::prelude::*;
 
 fn main() {
+    zino_core::application::LogShipper::set_sink(zino_model::log::LogStore);
+    zino_core::response::ResponseCache::set_store(zino_extra::cache::GlobalCache);
     zino::Cluster::boot()
         .register(router::routes())
         .register_debug(router::debug_routes())
@@ -5,7 +5,7 @@ use zino_model::user::JwtAuthService;
 
 pub async fn init_user_session(mut req: Request, next: Next<Body>) -> Result<Response> {
     let claims = req
-        .parse_jwt_claims(JwtClaims::shared_key())
+        .parse_jwt_claims(JwtKeySet::shared())
         .map_err(|rejection| rejection.context(&req))?;
     match User::verify_jwt_claims(&claims).await {
         Ok(verified) => {
@@ -15,6 +15,8 @@ pub async fn init_user_session(mut req: Request, next: Next<Body>) -> Result<Res
                 if let Ok(session_id) = req.parse_session_id() {
                     user_session.set_session_id(session_id);
                 }
+                AuditContext::set_current_user(user_session.user_id());
+                TenantContext::set_current_session(&user_session);
                 req.set_data(user_session);
             } else {
                 reject!(req, unauthorized, "invalid JWT claims");
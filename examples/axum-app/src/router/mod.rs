@@ -8,7 +8,8 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use zino::DefaultController;
+use zino::{cache_response, AuthorizationController, DefaultController};
+use zino_model::{Application, Log};
 
 pub fn routes() -> Vec<Router> {
     let mut routes = Vec::new();
@@ -18,10 +19,19 @@ pub fn routes() -> Vec<Router> {
         Router::new()
             .route("/auth/refresh", get(auth::refresh))
             .route("/auth/logout", post(auth::logout))
+            .route("/auth/logout-all", post(auth::logout_all))
             .layer(from_fn(middleware::init_user_session)),
     );
     routes.push(router);
 
+    // OAuth2 controller.
+    let router = Router::new()
+        .route("/oauth2/authorize", get(Application::authorize))
+        .route("/oauth2/token", post(Application::token))
+        .route("/oauth2/introspect", post(Application::introspect))
+        .route("/oauth2/revoke", post(Application::revoke));
+    routes.push(router);
+
     // File controller.
     let router = Router::new()
         .route("/file/upload", post(file::upload))
@@ -37,7 +47,8 @@ pub fn routes() -> Vec<Router> {
         .route("/user/:id/view", get(user::view))
         .route("/user/list", get(User::list))
         .route("/user/import", post(User::import))
-        .route("/user/export", get(User::export));
+        .route("/user/export", get(User::export))
+        .route("/user/aggregate", get(User::aggregate));
     routes.push(router);
 
     // Tag controller.
@@ -46,10 +57,19 @@ pub fn routes() -> Vec<Router> {
         .route("/tag/:id/delete", post(Tag::soft_delete))
         .route("/tag/:id/update", post(Tag::update))
         .route("/tag/:id/view", get(Tag::view))
-        .route("/tag/list", get(Tag::list))
+        .route(
+            "/tag/list",
+            get(Tag::list).layer(from_fn(cache_response::<i64, i64>)),
+        )
         .route("/tag/tree", get(Tag::tree));
     routes.push(router);
 
+    // Log controller.
+    let router = Router::new()
+        .route("/log/:id/view", get(Log::view))
+        .route("/log/list", get(Log::list));
+    routes.push(router);
+
     routes
 }
 
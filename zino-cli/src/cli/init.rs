@@ -1,18 +1,80 @@
+use crate::template::{self, FRAMEWORKS};
 use clap::Parser;
+use std::{fs, path::PathBuf};
 use zino_core::error::Error;
 
 /// Initialize the project for Zino.
 #[derive(Parser)]
 #[clap(name = "init")]
 pub struct Init {
-    /// Template path.
+    /// Project directory.
+    #[clap(default_value = ".")]
+    project_dir: PathBuf,
+    /// Project name. Defaults to the name of the project directory.
     #[clap(long)]
-    template: String,
+    project_name: Option<String>,
+    /// Template path. Defaults to the built-in template of the framework.
+    #[clap(long)]
+    template: Option<PathBuf>,
+    /// Framework feature enabled for `zino`.
+    #[clap(long, default_value = "axum", value_parser = FRAMEWORKS)]
+    framework: String,
+    /// Overwrite the existing files.
+    #[clap(long)]
+    force: bool,
 }
 
 impl Init {
     /// Runs the `init` subcommand.
     pub fn run(self) -> Result<(), Error> {
+        let project_dir = self.project_dir;
+        fs::create_dir_all(&project_dir)?;
+
+        let project_name = match self.project_name {
+            Some(name) => name,
+            None => fs::canonicalize(&project_dir)?
+                .file_name()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_owned())
+                .ok_or_else(|| Error::new("fail to infer the project name"))?,
+        };
+        if project_name.is_empty()
+            || !project_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            let message = format!("invalid project name `{project_name}`");
+            return Err(Error::new(message));
+        }
+
+        let framework = self.framework.as_str();
+        let files = if let Some(template_dir) = self.template {
+            template::local_template(&template_dir)?
+        } else {
+            template::builtin_template(framework)?
+        };
+        if !self.force {
+            for file in files.iter() {
+                let path = project_dir.join(file.path());
+                if path.exists() {
+                    let message = format!("file `{}` already exists", path.display());
+                    return Err(Error::new(message));
+                }
+            }
+        }
+
+        let placeholders = [
+            ("project_name", project_name.as_str()),
+            ("framework", framework),
+        ];
+        for file in files {
+            let path = project_dir.join(file.path());
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, file.render(&placeholders))?;
+            log::info!("created `{}`", path.display());
+        }
         Ok(())
     }
 }
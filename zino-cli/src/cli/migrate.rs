@@ -0,0 +1,136 @@
+use clap::{Parser, Subcommand};
+use convert_case::{Case, Casing};
+use sqlx::AnyPool;
+use std::path::PathBuf;
+use zino_core::{
+    error::Error,
+    orm::{Migration, Migrator},
+};
+
+/// Manage the versioned database migrations.
+#[derive(Parser)]
+#[clap(name = "migrate")]
+pub struct Migrate {
+    /// Migration actions.
+    #[clap(subcommand)]
+    action: Actions,
+    /// Database URL. Defaults to the `DATABASE_URL` env.
+    #[clap(global = true, long)]
+    database_url: Option<String>,
+    /// Migration directory containing the subdirectories `mysql`, `postgres` and `sqlite`.
+    #[clap(global = true, long, default_value = "migrations")]
+    migration_dir: PathBuf,
+    /// Migration table name.
+    #[clap(global = true, long, default_value = "schema_migrations")]
+    table: String,
+}
+
+impl Migrate {
+    /// Runs the `migrate` subcommand.
+    pub fn run(self) -> Result<(), Error> {
+        if let Actions::New { name, dialect } = &self.action {
+            let name = name.to_case(Case::Snake);
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                let message = format!("invalid migration name `{name}`");
+                return Err(Error::new(message));
+            }
+
+            let dialects = match dialect {
+                Some(dialect) => vec![dialect.as_str()],
+                None => DIALECTS.to_vec(),
+            };
+            let migration = Migration::new(
+                name,
+                "-- Write the SQL statements to apply the migration.\n",
+                "-- Write the SQL statements to revert the migration.\n",
+            );
+            for dialect in dialects {
+                let path = migration.write(&self.migration_dir.join(dialect))?;
+                log::info!("created `{}`", path.display());
+            }
+            return Ok(());
+        }
+
+        let database_url = self
+            .database_url
+            .or_else(|| std::env::var("DATABASE_URL").ok())
+            .ok_or_else(|| Error::new("`--database-url` should be specified"))?;
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(async {
+            sqlx::any::install_default_drivers();
+
+            let pool = AnyPool::connect(&database_url).await?;
+            let dialect = dialect_name(pool.acquire().await?.backend_name())?;
+            let mut migrator = Migrator::with_pool(pool.clone(), self.migration_dir.join(dialect));
+            migrator.set_table_name(self.table);
+            match self.action {
+                Actions::Up => {
+                    let num_applied = migrator.migrate().await?;
+                    log::info!("{num_applied} migrations have been applied");
+                }
+                Actions::Down { steps } => {
+                    let num_reverted = migrator.rollback(steps).await?;
+                    log::info!("{num_reverted} migrations have been reverted");
+                }
+                Actions::Status => {
+                    let applied_migrations = migrator.applied_migrations().await?;
+                    for migration in Migration::load_all(migrator.migration_dir())? {
+                        let version = migration.version();
+                        let name = migration.name();
+                        let status = if applied_migrations
+                            .iter()
+                            .any(|applied| applied.0 == version && applied.1 == name)
+                        {
+                            "applied"
+                        } else {
+                            "pending"
+                        };
+                        log::info!("{status} `{version}_{name}`");
+                    }
+                }
+                Actions::New { .. } => (),
+            }
+            pool.close().await;
+            Ok(())
+        })
+    }
+}
+
+/// Returns the dialect name used as the migration subdirectory.
+fn dialect_name(backend_name: &str) -> Result<&'static str, Error> {
+    match backend_name {
+        "MySQL" => Ok("mysql"),
+        "PostgreSQL" => Ok("postgres"),
+        "SQLite" => Ok("sqlite"),
+        backend => {
+            let message = format!("unsupported database backend `{backend}`");
+            Err(Error::new(message))
+        }
+    }
+}
+
+/// Migration actions.
+#[derive(Subcommand)]
+enum Actions {
+    /// Apply the pending migrations.
+    Up,
+    /// Revert the latest applied migrations.
+    Down {
+        /// Number of migrations to revert.
+        #[clap(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// Show the status of the migrations.
+    Status,
+    /// Create an empty migration.
+    New {
+        /// Migration name in snake case, such as `add_user_avatar`.
+        name: String,
+        /// Database dialect. The migration is created for all dialects if absent.
+        #[clap(long, value_parser = DIALECTS)]
+        dialect: Option<String>,
+    },
+}
+
+/// Supported database dialects.
+const DIALECTS: [&str; 3] = ["mysql", "postgres", "sqlite"];
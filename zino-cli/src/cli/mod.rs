@@ -3,6 +3,9 @@
 use clap::Parser;
 
 mod init;
+mod migrate;
+mod new;
+mod secrets;
 
 /// CLI tool for developing Zino applications.
 #[derive(Parser)]
@@ -20,6 +23,12 @@ pub struct Cli {
 }
 
 impl Cli {
+    /// Returns `true` if the verbose logging is enabled.
+    #[inline]
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
     /// Returns the subcommand action.
     #[inline]
     pub fn action(self) -> Subcommands {
@@ -32,4 +41,10 @@ impl Cli {
 pub enum Subcommands {
     /// Initialize the project for Zino.
     Init(init::Init),
+    /// Generate the code for Zino.
+    New(new::New),
+    /// Manage the versioned database migrations.
+    Migrate(migrate::Migrate),
+    /// Manage the encrypted secrets.
+    Secrets(secrets::Secrets),
 }
@@ -0,0 +1,238 @@
+use crate::{
+    database,
+    template::{
+        model::{self, ModelField},
+        FRAMEWORKS,
+    },
+};
+use clap::{Parser, Subcommand};
+use convert_case::{Case, Casing};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use zino_core::error::Error;
+
+/// Generate the code for Zino.
+#[derive(Parser)]
+#[clap(name = "new")]
+pub struct New {
+    /// Generators.
+    #[clap(subcommand)]
+    generator: Generators,
+}
+
+impl New {
+    /// Runs the `new` subcommand.
+    #[inline]
+    pub fn run(self) -> Result<(), Error> {
+        match self.generator {
+            Generators::Model(generator) => generator.run(),
+        }
+    }
+}
+
+/// Code generators.
+#[derive(Subcommand)]
+enum Generators {
+    /// Generate a model with the controller and routes.
+    Model(ModelGenerator),
+}
+
+/// Generate a model with the controller and routes.
+#[derive(Parser)]
+struct ModelGenerator {
+    /// Model name in pascal case, such as `Tag`.
+    name: String,
+    /// Fields specified as `name:type[:attr,attr=value]`, such as `name:String:not_null`.
+    #[clap(long = "field")]
+    fields: Vec<String>,
+    /// Generate the fields from an existing database table.
+    #[clap(long)]
+    table: Option<String>,
+    /// Database URL used to fetch the table columns. Defaults to the `DATABASE_URL` env.
+    #[clap(long)]
+    database_url: Option<String>,
+    /// Project directory.
+    #[clap(long, default_value = ".")]
+    project_dir: PathBuf,
+    /// Framework feature enabled for `zino`. It is detected from `Cargo.toml` if absent.
+    #[clap(long, value_parser = FRAMEWORKS)]
+    framework: Option<String>,
+    /// Generate the model only.
+    #[clap(long)]
+    model_only: bool,
+    /// Overwrite the existing files.
+    #[clap(long)]
+    force: bool,
+}
+
+impl ModelGenerator {
+    /// Runs the model generator.
+    fn run(self) -> Result<(), Error> {
+        let model_name = self.name.to_case(Case::Pascal);
+        let model_name_snake = model_name.to_case(Case::Snake);
+        if model_name.is_empty() || !model_name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            let message = format!("invalid model name `{}`", self.name);
+            return Err(Error::new(message));
+        }
+
+        let fields = if let Some(table_name) = self.table.as_deref() {
+            if !self.fields.is_empty() {
+                return Err(Error::new(
+                    "`--field` can not be used together with `--table`",
+                ));
+            }
+
+            let database_url = self
+                .database_url
+                .or_else(|| std::env::var("DATABASE_URL").ok())
+                .ok_or_else(|| Error::new("`--database-url` should be specified"))?;
+            let runtime = tokio::runtime::Runtime::new()?;
+            let mut fields =
+                runtime.block_on(database::fetch_table_fields(&database_url, table_name))?;
+            model::annotate_reserved_fields(&mut fields);
+            fields
+        } else {
+            let fields = self
+                .fields
+                .iter()
+                .map(|spec| ModelField::parse(spec))
+                .collect::<Result<Vec<_>, _>>()?;
+            model::complete_fields(fields)
+        };
+        if let Some(field) = fields
+            .iter()
+            .find(|field| fields.iter().filter(|f| f.name() == field.name()).count() > 1)
+        {
+            let message = format!("field `{}` is specified more than once", field.name());
+            return Err(Error::new(message));
+        }
+
+        let project_dir = self.project_dir;
+        let src_dir = project_dir.join("src");
+        let model_file = src_dir.join(format!("model/{model_name_snake}.rs"));
+        let controller_file = src_dir.join(format!("controller/{model_name_snake}.rs"));
+        let router_file = src_dir.join(format!("router/{model_name_snake}.rs"));
+        let framework = match self.framework {
+            Some(framework) => framework,
+            None => detect_framework(&project_dir)?,
+        };
+        let generate_routes = !self.model_only && framework != "dioxus-desktop";
+        if !self.force {
+            let mut files = vec![&model_file];
+            if generate_routes {
+                files.extend([&controller_file, &router_file]);
+            }
+            for file in files {
+                if file.exists() {
+                    let message = format!("file `{}` already exists", file.display());
+                    return Err(Error::new(message));
+                }
+            }
+        }
+
+        // Model.
+        write_file(&model_file, &model::render_model(&model_name, &fields))?;
+        update_file(&src_dir.join("model/mod.rs"), |source| {
+            let source = model::insert_module(source, &format!("mod {model_name_snake};"));
+            let export = format!("pub(crate) use {model_name_snake}::{model_name};");
+            Some(append_line(&source, &export))
+        })?;
+        update_file(&src_dir.join("main.rs"), |source| {
+            Some(model::insert_module(source, "mod model;"))
+        })?;
+        if !generate_routes {
+            return Ok(());
+        }
+
+        // Controller.
+        write_file(&controller_file, &model::render_controller(&model_name))?;
+        update_file(&src_dir.join("controller/mod.rs"), |source| {
+            let declaration = format!("pub(crate) mod {model_name_snake};");
+            Some(model::insert_module(source, &declaration))
+        })?;
+
+        // Router.
+        write_file(
+            &router_file,
+            &model::render_router(&model_name, &framework)?,
+        )?;
+        let route = if framework == "actix" {
+            format!("{model_name_snake}::routes as RouterConfigure")
+        } else {
+            format!("{model_name_snake}::routes()")
+        };
+        let router_mod_file = src_dir.join("router/mod.rs");
+        update_file(&router_mod_file, |source| {
+            let source = model::insert_module(source, &format!("mod {model_name_snake};"));
+            let output = model::register_routes(&source, &route);
+            if output.is_none() {
+                log::warn!(
+                    "fail to register the routes in `{}`; please add `{route}` manually",
+                    router_mod_file.display()
+                );
+            }
+            output.or(Some(source))
+        })
+    }
+}
+
+/// Detects the framework feature from the `Cargo.toml` of the project.
+fn detect_framework(project_dir: &Path) -> Result<String, Error> {
+    let manifest = fs::read_to_string(project_dir.join("Cargo.toml"))?;
+    let framework = if manifest.contains("actix-web") {
+        "actix"
+    } else if manifest.contains("dioxus") {
+        "dioxus-desktop"
+    } else {
+        "axum"
+    };
+    Ok(framework.to_owned())
+}
+
+/// Writes the content to a file and creates the parent directory if necessary.
+fn write_file(path: &Path, content: &str) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)?;
+    log::info!("created `{}`", path.display());
+    Ok(())
+}
+
+/// Updates the content of a file. The file will be created if it does not exist.
+fn update_file(path: &Path, f: impl FnOnce(&str) -> Option<String>) -> Result<(), Error> {
+    let source = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    match f(&source) {
+        Some(content) if content != source => {
+            fs::write(path, content)?;
+            log::info!("updated `{}`", path.display());
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Appends a line to the source code if it does not exist.
+fn append_line(source: &str, line: &str) -> String {
+    if source.lines().any(|s| s.trim() == line) {
+        source.to_owned()
+    } else {
+        let mut output = source.trim_end().to_owned();
+        let is_mod_item = output.lines().last().map(|s| s.starts_with("mod "));
+        if let Some(is_mod_item) = is_mod_item {
+            output.push('\n');
+            if is_mod_item && !line.starts_with("mod ") {
+                output.push('\n');
+            }
+        }
+        output.push_str(line);
+        output.push('\n');
+        output
+    }
+}
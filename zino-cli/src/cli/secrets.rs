@@ -0,0 +1,123 @@
+use clap::{Parser, Subcommand};
+use std::{io, path::PathBuf};
+use zino_core::{
+    error::Error,
+    extension::TomlTableExt,
+    state::{Env, SecretCipher, SecretVault, State},
+};
+
+/// Manage the encrypted secrets.
+#[derive(Parser)]
+#[clap(name = "secrets")]
+pub struct Secrets {
+    /// Secret actions.
+    #[clap(subcommand)]
+    action: Actions,
+    /// Application env whose config is used to derive the secret key.
+    #[clap(global = true, long, default_value = "dev")]
+    env: String,
+    /// Vault file. Defaults to the `secrets.vault-file` config or `config/secrets.toml`.
+    #[clap(global = true, long)]
+    vault: Option<PathBuf>,
+}
+
+impl Secrets {
+    /// Runs the `secrets` subcommand.
+    pub fn run(self) -> Result<(), Error> {
+        let env: &'static str = self.env.leak();
+        let mut state = State::new(Env::from(env), ());
+        state.read_config();
+
+        let config = state.config();
+        let cipher = SecretCipher::from_config(config)?;
+        let vault_file = self.vault.unwrap_or_else(|| {
+            config
+                .get_table("secrets")
+                .and_then(|config| config.get_str("vault-file"))
+                .unwrap_or("config/secrets.toml")
+                .into()
+        });
+        match self.action {
+            Actions::Encrypt { value, name } => {
+                let plaintext = match value {
+                    Some(value) => value,
+                    None => read_stdin()?,
+                };
+                if let Some(name) = name {
+                    let mut vault = SecretVault::open(&vault_file, cipher)?;
+                    vault.set(name.as_str(), &plaintext)?;
+                    vault.save()?;
+                    log::info!("saved `{name}` to `{}`", vault_file.display());
+                } else {
+                    println!("{}", cipher.encrypt(&plaintext)?);
+                }
+            }
+            Actions::Decrypt { value, name } => {
+                let plaintext = if let Some(name) = name {
+                    SecretVault::open(&vault_file, cipher)?
+                        .get(&name)?
+                        .ok_or_else(|| Error::new(format!("the secret `{name}` does not exist")))?
+                } else {
+                    let value = match value {
+                        Some(value) => value,
+                        None => read_stdin()?,
+                    };
+                    cipher.decrypt(&value)?
+                };
+                println!("{plaintext}");
+            }
+            Actions::List => {
+                let vault = SecretVault::open(&vault_file, cipher)?;
+                for name in vault.names() {
+                    println!("{name}");
+                }
+            }
+            Actions::Remove { name } => {
+                let mut vault = SecretVault::open(&vault_file, cipher)?;
+                if !vault.remove(&name) {
+                    let message = format!("the secret `{name}` does not exist");
+                    return Err(Error::new(message));
+                }
+                vault.save()?;
+                log::info!("removed `{name}` from `{}`", vault_file.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Secret actions.
+#[derive(Subcommand)]
+enum Actions {
+    /// Encrypt a value as `enc:{ciphertext}`, or save it to the vault file.
+    Encrypt {
+        /// Plaintext. It is read from the standard input if absent.
+        value: Option<String>,
+        /// Secret name. The encrypted value is saved to the vault file if specified.
+        #[clap(long)]
+        name: Option<String>,
+    },
+    /// Decrypt an `enc:{ciphertext}` value, or a secret in the vault file.
+    Decrypt {
+        /// Encrypted value. It is read from the standard input if absent.
+        #[clap(conflicts_with = "name")]
+        value: Option<String>,
+        /// Secret name in the vault file.
+        #[clap(long)]
+        name: Option<String>,
+    },
+    /// List the secret names in the vault file.
+    List,
+    /// Remove a secret from the vault file.
+    Remove {
+        /// Secret name.
+        name: String,
+    },
+}
+
+/// Reads a line from the standard input.
+fn read_stdin() -> Result<String, Error> {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
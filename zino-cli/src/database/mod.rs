@@ -0,0 +1,255 @@
+//! Table introspection for the model generator.
+
+use crate::template::model::ModelField;
+use sqlx::{any::AnyRow, AnyConnection, Connection, Row};
+use zino_core::error::Error;
+
+/// A column fetched from the database table.
+#[derive(Debug, Default)]
+struct TableColumn {
+    /// Column name.
+    name: String,
+    /// Column type, such as `varchar`, `bigint` or `_uuid`.
+    column_type: String,
+    /// A flag indicating whether the column is `NOT NULL`.
+    not_null: bool,
+    /// Default value.
+    default_value: Option<String>,
+    /// Maximum length of characters.
+    max_length: Option<i64>,
+    /// A flag indicating whether the column is the primary key.
+    primary_key: bool,
+    /// A flag indicating whether the column is auto-incremented.
+    auto_increment: bool,
+}
+
+impl TableColumn {
+    /// Converts the column into a model field.
+    fn into_model_field(self) -> ModelField {
+        let column_type = self.column_type.to_ascii_lowercase();
+        let (type_name, is_array) = match column_type.strip_prefix('_') {
+            Some(element_type) => (parse_type_name(element_type), true),
+            None => match column_type.strip_suffix("[]") {
+                Some(element_type) => (parse_type_name(element_type), true),
+                None => (parse_type_name(&column_type), false),
+            },
+        };
+        let type_name = if is_array {
+            format!("Vec<{type_name}>")
+        } else if type_name == "Uuid" && !self.not_null && !self.primary_key {
+            "Option<Uuid>".to_owned()
+        } else {
+            type_name.to_owned()
+        };
+
+        let mut field = ModelField::new(&self.name, type_name);
+        if matches!(
+            column_type.as_str(),
+            "numeric" | "decimal" | "date" | "time" | "timetz"
+        ) {
+            field.push_attribute("column_type", Some(&column_type.to_ascii_uppercase()));
+        }
+        if self.primary_key {
+            field.push_attribute("primary_key", None);
+            if self.auto_increment {
+                field.push_attribute("auto_increment", None);
+            }
+            field.push_attribute("read_only", None);
+        } else if self.not_null {
+            field.push_attribute("not_null", None);
+        }
+        if let Some(max_length) = self.max_length.filter(|&n| n > 0 && n < 65535) {
+            field.push_attribute("max_length", Some(&max_length.to_string()));
+        }
+        if let Some(default_value) = self.default_value.as_deref().and_then(parse_default_value) {
+            field.push_attribute("default_value", Some(default_value));
+        }
+        field
+    }
+}
+
+/// Fetches the columns of a table and converts them into model fields.
+pub(crate) async fn fetch_table_fields(
+    database_url: &str,
+    table_name: &str,
+) -> Result<Vec<ModelField>, Error> {
+    sqlx::any::install_default_drivers();
+
+    let mut conn = AnyConnection::connect(database_url).await?;
+    let columns = match conn.backend_name() {
+        "MySQL" => fetch_mysql_columns(&mut conn, table_name).await?,
+        "PostgreSQL" => fetch_postgres_columns(&mut conn, table_name).await?,
+        "SQLite" => fetch_sqlite_columns(&mut conn, table_name).await?,
+        backend => {
+            let message = format!("unsupported database backend `{backend}`");
+            return Err(Error::new(message));
+        }
+    };
+    conn.close().await?;
+
+    if columns.is_empty() {
+        let message = format!("table `{table_name}` does not exist or has no columns");
+        return Err(Error::new(message));
+    }
+    Ok(columns
+        .into_iter()
+        .map(|column| column.into_model_field())
+        .collect())
+}
+
+/// Fetches the table columns from the MySQL `information_schema`.
+async fn fetch_mysql_columns(
+    conn: &mut AnyConnection,
+    table_name: &str,
+) -> Result<Vec<TableColumn>, Error> {
+    let sql = "SELECT CAST(column_name AS CHAR) AS column_name, \
+            CAST(data_type AS CHAR) AS data_type, \
+            CAST(is_nullable AS CHAR) AS is_nullable, \
+            CAST(COALESCE(column_default, 'NULL') AS CHAR) AS column_default, \
+            CAST(COALESCE(character_maximum_length, 0) AS SIGNED) AS max_length, \
+            CAST(COALESCE(column_key, '') AS CHAR) AS column_key, \
+            CAST(COALESCE(extra, '') AS CHAR) AS extra \
+        FROM information_schema.columns \
+        WHERE table_schema = DATABASE() AND table_name = ? \
+        ORDER BY ordinal_position;";
+    let rows = sqlx::query(sql)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await?;
+    rows.iter()
+        .map(|row| {
+            let column_key: String = row.try_get("column_key")?;
+            let extra: String = row.try_get("extra")?;
+            Ok(TableColumn {
+                name: row.try_get("column_name")?,
+                column_type: row.try_get("data_type")?,
+                not_null: is_not_null(row)?,
+                default_value: Some(row.try_get("column_default")?),
+                max_length: Some(row.try_get("max_length")?),
+                primary_key: column_key == "PRI",
+                auto_increment: extra.contains("auto_increment"),
+            })
+        })
+        .collect()
+}
+
+/// Fetches the table columns from the PostgreSQL `information_schema`.
+async fn fetch_postgres_columns(
+    conn: &mut AnyConnection,
+    table_name: &str,
+) -> Result<Vec<TableColumn>, Error> {
+    let sql = "SELECT c.column_name::text AS column_name, c.udt_name::text AS data_type, \
+            c.is_nullable::text AS is_nullable, \
+            COALESCE(c.column_default::text, 'NULL') AS column_default, \
+            COALESCE(c.character_maximum_length, 0)::int8 AS max_length, \
+            EXISTS(SELECT 1 FROM information_schema.table_constraints t \
+                JOIN information_schema.key_column_usage k \
+                    ON k.constraint_name = t.constraint_name \
+                        AND k.table_schema = t.table_schema \
+                WHERE t.constraint_type = 'PRIMARY KEY' \
+                    AND t.table_schema = c.table_schema \
+                    AND t.table_name = c.table_name \
+                    AND k.column_name = c.column_name) AS primary_key \
+        FROM information_schema.columns c \
+        WHERE c.table_schema = current_schema() AND c.table_name = $1 \
+        ORDER BY c.ordinal_position;";
+    let rows = sqlx::query(sql)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await?;
+    rows.iter()
+        .map(|row| {
+            let default_value: String = row.try_get("column_default")?;
+            let auto_increment = default_value.starts_with("nextval(");
+            Ok(TableColumn {
+                name: row.try_get("column_name")?,
+                column_type: row.try_get("data_type")?,
+                not_null: is_not_null(row)?,
+                default_value: (!auto_increment).then_some(default_value),
+                max_length: Some(row.try_get("max_length")?),
+                primary_key: row.try_get("primary_key")?,
+                auto_increment,
+            })
+        })
+        .collect()
+}
+
+/// Fetches the table columns with the SQLite `pragma_table_info`.
+async fn fetch_sqlite_columns(
+    conn: &mut AnyConnection,
+    table_name: &str,
+) -> Result<Vec<TableColumn>, Error> {
+    let sql = "SELECT name, type, \"notnull\", COALESCE(dflt_value, 'NULL') AS dflt_value, pk \
+        FROM pragma_table_info(?) ORDER BY cid;";
+    let rows = sqlx::query(sql)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await?;
+    rows.iter()
+        .map(|row| {
+            let column_type: String = row.try_get("type")?;
+            let primary_key = row.try_get::<i64, _>("pk")? > 0;
+            let (column_type, max_length) = match column_type.split_once('(') {
+                Some((column_type, length)) => {
+                    let length = length.trim_end_matches(')').parse().ok();
+                    (column_type.trim().to_owned(), length)
+                }
+                None => (column_type, None),
+            };
+            let auto_increment = primary_key && column_type.eq_ignore_ascii_case("integer");
+            Ok(TableColumn {
+                name: row.try_get("name")?,
+                column_type,
+                not_null: row.try_get::<i64, _>("notnull")? != 0,
+                default_value: Some(row.try_get("dflt_value")?),
+                max_length,
+                primary_key,
+                auto_increment,
+            })
+        })
+        .collect()
+}
+
+/// Returns `true` if the `is_nullable` column is `NO`.
+fn is_not_null(row: &AnyRow) -> Result<bool, sqlx::Error> {
+    let is_nullable: String = row.try_get("is_nullable")?;
+    Ok(is_nullable.eq_ignore_ascii_case("no"))
+}
+
+/// Parses the Rust type name for the column type.
+fn parse_type_name(column_type: &str) -> &'static str {
+    match column_type.trim() {
+        "uuid" => "Uuid",
+        "bool" | "boolean" => "bool",
+        "tinyint" | "int1" => "i8",
+        "smallint" | "int2" | "smallserial" => "i16",
+        "int" | "int4" | "mediumint" | "serial" => "i32",
+        "bigint" | "int8" | "integer" | "bigserial" => "i64",
+        "float" | "float4" | "real" => "f32",
+        "double" | "float8" | "double precision" => "f64",
+        "datetime" | "timestamp" | "timestamptz" => "DateTime",
+        "json" | "jsonb" => "Map",
+        "blob" | "bytea" | "binary" | "varbinary" | "longblob" => "Vec<u8>",
+        _ => "String",
+    }
+}
+
+/// Parses the column default value as a literal.
+fn parse_default_value(value: &str) -> Option<&str> {
+    let value = value.split("::").next().unwrap_or(value).trim();
+    if value.eq_ignore_ascii_case("null") {
+        None
+    } else if value.eq_ignore_ascii_case("current_timestamp") || value.eq_ignore_ascii_case("now()")
+    {
+        Some("now")
+    } else if let Some(value) = value.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        Some(value)
+    } else if value.parse::<f64>().is_ok()
+        || value.eq_ignore_ascii_case("true")
+        || value.eq_ignore_ascii_case("false")
+    {
+        Some(value)
+    } else {
+        None
+    }
+}
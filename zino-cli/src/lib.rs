@@ -4,5 +4,7 @@
 #![forbid(unsafe_code)]
 
 mod cli;
+mod database;
+mod template;
 
 pub use cli::{Cli, Subcommands};
@@ -1,11 +1,48 @@
 use clap::Parser;
+use log::{LevelFilter, Log, Metadata, Record};
 use zino_cli::{Cli, Subcommands::*};
 
 fn main() {
-    let result = match Cli::parse().action() {
+    let cli = Cli::parse();
+    let level = if cli.verbose() {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+
+    let result = match cli.action() {
         Init(opts) => opts.run(),
+        New(opts) => opts.run(),
+        Migrate(opts) => opts.run(),
+        Secrets(opts) => opts.run(),
     };
     if let Err(err) = result {
         log::error!("Failed to run the command: {err}");
+        std::process::exit(1);
     }
 }
+
+/// A minimal logger writing messages to the standard error.
+struct Logger;
+
+impl Log for Logger {
+    #[inline]
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}", record.level(), record.args());
+        }
+    }
+
+    #[inline]
+    fn flush(&self) {}
+}
+
+/// Global logger.
+static LOGGER: Logger = Logger;
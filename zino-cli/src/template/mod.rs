@@ -0,0 +1,168 @@
+//! Project templates and the placeholder renderer.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use zino_core::error::Error;
+
+pub(crate) mod model;
+
+/// Frameworks supported by the built-in templates.
+pub(crate) const FRAMEWORKS: [&str; 3] = ["axum", "actix", "dioxus-desktop"];
+
+/// A template file with its relative output path and the content.
+pub(crate) struct TemplateFile {
+    /// Relative path of the rendered file.
+    path: PathBuf,
+    /// Raw content.
+    content: Vec<u8>,
+}
+
+impl TemplateFile {
+    /// Creates a new instance.
+    #[inline]
+    fn new(path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) -> Self {
+        Self {
+            path: path.into(),
+            content: content.into(),
+        }
+    }
+
+    /// Returns the relative path of the rendered file.
+    #[inline]
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Renders the content with the placeholders. Non-UTF-8 content is returned as it is.
+    pub(crate) fn render(&self, placeholders: &[(&str, &str)]) -> Vec<u8> {
+        match std::str::from_utf8(&self.content) {
+            Ok(content) => render(content, placeholders).into_bytes(),
+            Err(_) => self.content.clone(),
+        }
+    }
+}
+
+/// Loads the built-in template for the framework.
+pub(crate) fn builtin_template(framework: &str) -> Result<Vec<TemplateFile>, Error> {
+    macro_rules! template_files {
+        ($dir:literal, [$($path:literal),+ $(,)?]) => {
+            vec![
+                TemplateFile::new(
+                    "Cargo.toml",
+                    include_str!(concat!("../../templates/", $dir, "/Cargo.toml.tpl")),
+                ),
+                $(TemplateFile::new(
+                    $path,
+                    include_str!(concat!("../../templates/", $dir, "/", $path)),
+                ),)+
+            ]
+        };
+    }
+
+    let files = match framework {
+        "axum" => template_files!(
+            "axum",
+            [
+                "config/config.dev.toml",
+                "config/config.prod.toml",
+                "src/main.rs",
+                "src/controller/mod.rs",
+                "src/controller/stats.rs",
+                "src/router/mod.rs",
+            ]
+        ),
+        "actix" => template_files!(
+            "actix",
+            [
+                "config/config.dev.toml",
+                "config/config.prod.toml",
+                "src/main.rs",
+                "src/controller/mod.rs",
+                "src/controller/stats.rs",
+                "src/router/mod.rs",
+            ]
+        ),
+        "dioxus-desktop" => template_files!(
+            "dioxus-desktop",
+            [
+                "config/config.dev.toml",
+                "config/config.prod.toml",
+                "src/main.rs",
+                "src/controller/mod.rs",
+                "src/controller/overview.rs",
+                "src/router/mod.rs",
+            ]
+        ),
+        _ => {
+            let message = format!("there is no built-in template for the framework `{framework}`");
+            return Err(Error::new(message));
+        }
+    };
+    Ok(files)
+}
+
+/// Loads the template files from a local directory.
+/// The `.tpl` extension of a file name will be stripped.
+pub(crate) fn local_template(dir: &Path) -> Result<Vec<TemplateFile>, Error> {
+    if !dir.is_dir() {
+        let message = format!("template directory `{}` does not exist", dir.display());
+        return Err(Error::new(message));
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current_dir) = dirs.pop() {
+        for entry in fs::read_dir(&current_dir)? {
+            let path = entry?.path();
+            let file_name = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            if path.is_dir() {
+                if !matches!(file_name, ".git" | "target") {
+                    dirs.push(path);
+                }
+            } else {
+                let content = fs::read(&path)?;
+                let relative_path = path.strip_prefix(dir).map_err(|err| {
+                    Error::with_source("fail to resolve the template file path", err)
+                })?;
+                let relative_path = match file_name.strip_suffix(".tpl") {
+                    Some(file_name) => relative_path.with_file_name(file_name),
+                    None => relative_path.to_path_buf(),
+                };
+                files.push(TemplateFile::new(relative_path, content));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Replaces the placeholders `{{ key }}` in the content with the values.
+pub(crate) fn render(content: &str, placeholders: &[(&str, &str)]) -> String {
+    let mut output = content.to_owned();
+    for (key, value) in placeholders {
+        output = output.replace(&format!("{{{{ {key} }}}}"), value);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_renders_placeholders() {
+        let content = "name = \"{{ project_name }}\"\nfeatures = [\"{{ framework }}\"]\n";
+        let placeholders = [("project_name", "data-cube"), ("framework", "axum")];
+        assert_eq!(
+            super::render(content, &placeholders),
+            "name = \"data-cube\"\nfeatures = [\"axum\"]\n"
+        );
+        assert_eq!(
+            super::render("{{ unknown }}", &placeholders),
+            "{{ unknown }}"
+        );
+    }
+}
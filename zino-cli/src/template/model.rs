@@ -0,0 +1,387 @@
+//! Code generation for models, controllers and routers.
+
+use convert_case::{Case, Casing};
+use std::fmt::Write;
+use zino_core::error::Error;
+
+/// Reserved fields with their default attributes, following the layout of `zino-model`.
+const RESERVED_FIELDS: [(&str, &str, &str); 5] = [
+    ("content", "Map", "reserved"),
+    ("extra", "Map", "reserved"),
+    (
+        "created_at",
+        "DateTime",
+        "read_only,default_value=now,index_type=btree",
+    ),
+    (
+        "updated_at",
+        "DateTime",
+        "default_value=now,index_type=btree",
+    ),
+    ("version", "u64", ""),
+];
+
+/// A model field parsed from the field list or the table column.
+#[derive(Debug, Clone)]
+pub(crate) struct ModelField {
+    /// Field name.
+    name: String,
+    /// Rust type name.
+    type_name: String,
+    /// Arguments of the `#[schema(...)]` attribute.
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl ModelField {
+    /// Creates a new instance.
+    #[inline]
+    pub(crate) fn new(name: impl Into<String>, type_name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            type_name: type_name.into(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Parses the field specification `name:type[:attr,attr=value]`,
+    /// such as `name:String:not_null,index_type=text`.
+    pub(crate) fn parse(spec: &str) -> Result<Self, Error> {
+        let mut parts = spec.splitn(3, ':');
+        let name = parts.next().unwrap_or_default().trim();
+        let type_name = parts.next().unwrap_or("String").trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let message = format!("invalid field name in `{spec}`");
+            return Err(Error::new(message));
+        }
+
+        let mut field = Self::new(name.to_case(Case::Snake), type_name);
+        if let Some(attributes) = parts.next() {
+            for attribute in attributes.split(',').filter(|s| !s.trim().is_empty()) {
+                if let Some((key, value)) = attribute.split_once('=') {
+                    field.push_attribute(key.trim(), Some(value.trim()));
+                } else {
+                    field.push_attribute(attribute.trim(), None);
+                }
+            }
+        }
+        Ok(field)
+    }
+
+    /// Parses the attributes `attr,attr=value` for a field.
+    fn with_attributes(name: &str, type_name: &str, attributes: &str) -> Self {
+        let spec = format!("{name}:{type_name}:{attributes}");
+        Self::parse(&spec).unwrap_or_else(|_| Self::new(name, type_name))
+    }
+
+    /// Returns the field name.
+    #[inline]
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if the field has the attribute.
+    #[inline]
+    pub(crate) fn has_attribute(&self, key: &str) -> bool {
+        self.attributes.iter().any(|(k, _)| k == key)
+    }
+
+    /// Pushes an attribute to the field.
+    pub(crate) fn push_attribute(&mut self, key: &str, value: Option<&str>) {
+        if !self.has_attribute(key) {
+            self.attributes
+                .push((key.to_owned(), value.map(|s| s.to_owned())));
+        }
+    }
+
+    /// Formats the `#[schema(...)]` attribute.
+    fn format_attribute(&self) -> Option<String> {
+        if self.attributes.is_empty() {
+            return None;
+        }
+
+        let arguments = self
+            .attributes
+            .iter()
+            .map(|(key, value)| match value {
+                Some(value) if value.parse::<i64>().is_ok() => format!("{key} = {value}"),
+                Some(value) => format!("{key} = \"{}\"", value.escape_default()),
+                None => key.to_owned(),
+            })
+            .collect::<Vec<_>>();
+        Some(format!("#[schema({})]", arguments.join(", ")))
+    }
+
+    /// Formats the field declaration.
+    fn format_declaration(&self, output: &mut String) {
+        if let Some(attribute) = self.format_attribute() {
+            let _ = writeln!(output, "    {attribute}");
+        }
+        let _ = writeln!(output, "    {}: {},", self.name, self.type_name);
+    }
+}
+
+/// Completes the fields with the primary key, the extensions and the revisions.
+pub(crate) fn complete_fields(fields: Vec<ModelField>) -> Vec<ModelField> {
+    let mut model_fields = Vec::with_capacity(fields.len() + RESERVED_FIELDS.len() + 1);
+    if !fields
+        .iter()
+        .any(|field| field.name == "id" || field.has_attribute("primary_key"))
+    {
+        let attributes = "primary_key,read_only,constructor=Uuid::now_v7";
+        model_fields.push(ModelField::with_attributes("id", "Uuid", attributes));
+    }
+    model_fields.extend(fields);
+    for (name, type_name, attributes) in RESERVED_FIELDS {
+        if !model_fields.iter().any(|field| field.name == name) {
+            model_fields.push(ModelField::with_attributes(name, type_name, attributes));
+        }
+    }
+    model_fields
+}
+
+/// Applies the default attributes for the reserved fields.
+pub(crate) fn annotate_reserved_fields(fields: &mut [ModelField]) {
+    for field in fields.iter_mut() {
+        if let Some((_, _, attributes)) = RESERVED_FIELDS
+            .iter()
+            .find(|(name, ..)| *name == field.name)
+        {
+            let reserved_field = ModelField::with_attributes(&field.name, "", attributes);
+            for (key, value) in reserved_field.attributes {
+                field.push_attribute(&key, value.as_deref());
+            }
+        }
+    }
+}
+
+/// Renders the model file.
+pub(crate) fn render_model(model_name: &str, fields: &[ModelField]) -> String {
+    let model_name_snake = model_name.to_case(Case::Snake);
+    let mut output = String::new();
+    output += "use serde::{Deserialize, Serialize};\n";
+    output += "use zino::prelude::*;\n";
+    output += "use zino_derive::{DecodeRow, Model, ModelAccessor, ModelHooks, Schema};\n\n";
+    let _ = writeln!(output, "/// The `{model_name_snake}` model.");
+    output += "#[derive(\n";
+    for derive in [
+        "Debug",
+        "Clone",
+        "Default",
+        "Serialize",
+        "Deserialize",
+        "DecodeRow",
+        "Schema",
+        "ModelAccessor",
+        "ModelHooks",
+        "Model",
+    ] {
+        let _ = writeln!(output, "    {derive},");
+    }
+    output += ")]\n";
+    output += "#[serde(default)]\n";
+    let _ = writeln!(output, "pub struct {model_name} {{");
+
+    let is_basic_field =
+        |field: &ModelField| field.name == "id" || field.has_attribute("primary_key");
+    let is_reserved_field =
+        |field: &ModelField| RESERVED_FIELDS.iter().any(|(name, ..)| *name == field.name);
+    let sections = [
+        (
+            "Basic fields.",
+            fields
+                .iter()
+                .filter(|f| is_basic_field(f))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "Info fields.",
+            fields
+                .iter()
+                .filter(|f| !is_basic_field(f) && !is_reserved_field(f))
+                .collect(),
+        ),
+        (
+            "Extensions.",
+            fields
+                .iter()
+                .filter(|f| matches!(f.name.as_str(), "content" | "extra"))
+                .collect(),
+        ),
+        (
+            "Revisions.",
+            fields
+                .iter()
+                .filter(|f| matches!(f.name.as_str(), "created_at" | "updated_at" | "version"))
+                .collect(),
+        ),
+    ];
+    let mut is_first_section = true;
+    for (comment, fields) in sections {
+        if fields.is_empty() {
+            continue;
+        }
+        if !is_first_section {
+            output.push('\n');
+        }
+        let _ = writeln!(output, "    // {comment}");
+        for field in fields {
+            field.format_declaration(&mut output);
+        }
+        is_first_section = false;
+    }
+    output += "}\n";
+    output
+}
+
+/// Renders the controller file.
+pub(crate) fn render_controller(model_name: &str) -> String {
+    format!(
+        r#"use crate::model::{model_name};
+use zino::{{prelude::*, Request, Response, Result}};
+
+pub async fn view(req: Request) -> Result {{
+    let id = req.parse_param("id")?;
+    let model = {model_name}::fetch_by_id(&id).await.extract(&req)?;
+
+    let data = Map::data_entry(model);
+    let mut res = Response::default().context(&req);
+    res.set_json_data(data);
+    Ok(res.into())
+}}
+"#
+    )
+}
+
+/// Renders the router file for the framework.
+pub(crate) fn render_router(model_name: &str, framework: &str) -> Result<String, Error> {
+    let model_name_snake = model_name.to_case(Case::Snake);
+    let output = match framework {
+        "axum" => format!(
+            r#"use crate::{{controller::{model_name_snake}, model::{model_name}}};
+use axum::{{
+    routing::{{get, post}},
+    Router,
+}};
+use zino::DefaultController;
+
+pub(super) fn routes() -> Router {{
+    Router::new()
+        .route("/{model_name_snake}/new", post({model_name}::new))
+        .route("/{model_name_snake}/:id/delete", post({model_name}::soft_delete))
+        .route("/{model_name_snake}/:id/update", post({model_name}::update))
+        .route("/{model_name_snake}/:id/view", get({model_name_snake}::view))
+        .route("/{model_name_snake}/list", get({model_name}::list))
+}}
+"#
+        ),
+        "actix" => format!(
+            r#"use crate::{{controller::{model_name_snake}, model::{model_name}}};
+use actix_web::web::{{get, post, ServiceConfig}};
+use zino::DefaultController;
+
+pub(super) fn routes(cfg: &mut ServiceConfig) {{
+    cfg.route("/{model_name_snake}/new", post().to({model_name}::new))
+        .route("/{model_name_snake}/{{id}}/delete", post().to({model_name}::soft_delete))
+        .route("/{model_name_snake}/{{id}}/update", post().to({model_name}::update))
+        .route("/{model_name_snake}/{{id}}/view", get().to({model_name_snake}::view))
+        .route("/{model_name_snake}/list", get().to({model_name}::list));
+}}
+"#
+        ),
+        _ => {
+            let message = format!("routers are not supported for the framework `{framework}`");
+            return Err(Error::new(message));
+        }
+    };
+    Ok(output)
+}
+
+/// Inserts a module declaration into the source code.
+/// The declaration is placed among the `mod` items in order, or before the first `use` item.
+pub(crate) fn insert_module(source: &str, declaration: &str) -> String {
+    if source.lines().any(|line| line.trim() == declaration) {
+        return source.to_owned();
+    }
+
+    let lines = source.lines().collect::<Vec<_>>();
+    let is_mod_item = |line: &&str| line.starts_with("mod ") || line.starts_with("pub(crate) mod ");
+    let mut output = Vec::with_capacity(lines.len() + 2);
+    if let Some(last_index) = lines.iter().rposition(is_mod_item) {
+        let index = lines
+            .iter()
+            .position(|line| is_mod_item(line) && *line > declaration)
+            .unwrap_or(last_index + 1);
+        output.extend_from_slice(&lines[..index]);
+        output.push(declaration);
+        output.extend_from_slice(&lines[index..]);
+    } else if let Some(index) = lines.iter().position(|line| line.starts_with("use ")) {
+        output.extend_from_slice(&lines[..index]);
+        output.push(declaration);
+        output.push("");
+        output.extend_from_slice(&lines[index..]);
+    } else {
+        output.push(declaration);
+        output.extend_from_slice(&lines);
+    }
+    output.join("\n") + "\n"
+}
+
+/// Registers the routes in the `routes()` function of the router module.
+/// Returns `None` if the function body is not recognized.
+pub(crate) fn register_routes(source: &str, route: &str) -> Option<String> {
+    let start = source.find("pub fn routes()")?;
+    let end = start + source[start..].find("\n}\n")?;
+    let body = &source[start..end];
+    let (old, new) = if let Some(index) = body.rfind("\n    routes") {
+        let position = start + body[..index].trim_end().len();
+        let statement = format!("\n    routes.push({route});");
+        (position..position, statement)
+    } else if let Some(index) = body.rfind("Vec::new()") {
+        let position = start + index;
+        (
+            position..position + "Vec::new()".len(),
+            format!("vec![{route}]"),
+        )
+    } else {
+        let index = body.rfind(']')?;
+        let items = body[..index].trim_end();
+        let position = start + items.len();
+        let item = if items.ends_with('[') {
+            route.to_owned()
+        } else if items[items.rfind('[')?..].contains('\n') {
+            let separator = if items.ends_with(',') { "" } else { "," };
+            format!("{separator}\n        {route},")
+        } else {
+            format!(", {route}")
+        };
+        (position..position, item)
+    };
+    let mut output = source.to_owned();
+    output.replace_range(old, &new);
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_registers_routes() {
+        let source = "mod stats;\n\nuse axum::Router;\n\npub fn routes() -> Vec<Router> {\n    Vec::new()\n}\n";
+        let source = super::insert_module(source, "mod article;");
+        assert_eq!(
+            source,
+            "mod article;\nmod stats;\n\nuse axum::Router;\n\npub fn routes() -> Vec<Router> {\n    Vec::new()\n}\n"
+        );
+
+        let source = super::register_routes(&source, "article::routes()").unwrap();
+        assert!(source.contains("    vec![article::routes()]\n"));
+
+        let source = super::register_routes(&source, "tag::routes()").unwrap();
+        assert!(source.contains("    vec![article::routes(), tag::routes()]\n"));
+
+        let source = "pub fn routes() -> Vec<Router> {\n    let mut routes = Vec::new();\n    routes.push(user_router());\n\n    routes\n}\n";
+        let source = super::register_routes(source, "tag::routes()").unwrap();
+        assert!(source.contains(
+            "    routes.push(user_router());\n    routes.push(tag::routes());\n\n    routes\n}"
+        ));
+    }
+}
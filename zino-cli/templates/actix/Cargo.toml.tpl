@@ -0,0 +1,28 @@
+[package]
+name = "{{ project_name }}"
+version = "0.1.0"
+rust-version = "1.73"
+edition = "2021"
+publish = false
+
+[dependencies]
+actix-web = "4.4.0"
+tracing = "0.1.40"
+
+[dependencies.serde]
+version = "1.0.192"
+features = ["derive"]
+
+[dependencies.zino]
+version = "0.14.4"
+features = ["{{ framework }}"]
+
+[dependencies.zino-core]
+version = "0.15.4"
+features = ["orm-sqlite"]
+
+[dependencies.zino-derive]
+version = "0.12.4"
+
+[dependencies.zino-model]
+version = "0.12.4"
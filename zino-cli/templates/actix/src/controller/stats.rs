@@ -0,0 +1,13 @@
+use zino::{prelude::*, Cluster, Request, Response, Result};
+
+pub async fn index(req: Request) -> Result {
+    let mut res = Response::default().context(&req);
+    let data = json!({
+        "method": req.request_method().as_ref(),
+        "path": req.request_path(),
+        "app_state_data": Cluster::state_data(),
+        "app_sysinfo": Cluster::sysinfo(),
+    });
+    res.set_json_data(data);
+    Ok(res.into())
+}
@@ -0,0 +1,11 @@
+mod controller;
+mod router;
+
+use zino::prelude::*;
+
+fn main() {
+    zino::Cluster::boot()
+        .register(router::routes())
+        .register_debug(router::debug_routes())
+        .run(StaticRecord::new())
+}
@@ -0,0 +1,15 @@
+use crate::controller::stats;
+use actix_web::web::{get, ServiceConfig};
+use zino::RouterConfigure;
+
+pub fn routes() -> Vec<RouterConfigure> {
+    Vec::new()
+}
+
+pub fn debug_routes() -> Vec<RouterConfigure> {
+    vec![stats_router as RouterConfigure]
+}
+
+fn stats_router(cfg: &mut ServiceConfig) {
+    cfg.route("/stats", get().to(stats::index));
+}
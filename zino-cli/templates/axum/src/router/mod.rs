@@ -0,0 +1,16 @@
+use crate::controller::stats;
+use axum::{routing::get, Router};
+
+pub fn routes() -> Vec<Router> {
+    Vec::new()
+}
+
+pub fn debug_routes() -> Vec<Router> {
+    let mut routes = Vec::new();
+
+    // Stats controller.
+    let router = Router::new().route("/stats", get(stats::index));
+    routes.push(router);
+
+    routes
+}
@@ -0,0 +1,11 @@
+use dioxus::prelude::*;
+
+pub fn Overview(cx: Scope) -> Element {
+    render! {
+        div {
+            class: "container",
+            h1 { "{{ project_name }}" }
+            p { "Welcome to your new Zino application." }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#![allow(non_snake_case)]
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+mod controller;
+mod router;
+
+use router::Route;
+use zino::{prelude::*, Desktop};
+
+type App = Desktop<Route>;
+
+fn main() {
+    App::boot()
+        .register(Route::default())
+        .run(StaticRecord::new())
+}
@@ -5,7 +5,7 @@ pub fn bench(c: &mut criterion::Criterion) {
         b.iter(|| {
             let duration = Duration::from_secs_f64(0.0024635);
             let millis = duration.as_secs_f64() * 1000.0;
-            let duration_millis = format!("{:.3}", millis);
+            let duration_millis = format!("{millis:.3}");
             format!("dur={}", duration_millis.trim_end_matches(['.', '0']))
         })
     });
@@ -13,7 +13,7 @@ pub fn bench(c: &mut criterion::Criterion) {
         b.iter(|| {
             let duration = Duration::from_secs_f64(0.0024635);
             let millis = (duration.as_micros() as f64) / 1000.0;
-            format!("dur={}", millis)
+            format!("dur={millis}")
         })
     });
     c.bench_function("format_duration_micros_ryu", |b| {
@@ -89,6 +89,8 @@ pub(super) fn init<APP: Application + ?Sized>() {
         .expect("fail to set an HTTP client for the application");
 
     let retry_policy = ExponentialBackoff::builder().build_with_max_retries(max_retries);
+    // The tracing middleware also injects the OpenTelemetry context into the `traceparent`
+    // header of outgoing requests if the `otlp` feature is enabled.
     let client = ClientBuilder::new(reqwest_client)
         .with(TracingMiddleware::<RequestTiming>::new())
         .with(RetryTransientMiddleware::new_with_policy(retry_policy))
@@ -0,0 +1,357 @@
+use crate::{extension::JsonObjectExt, JsonValue, Map};
+use std::fmt;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    fmt::{format, time::FormatTime, FmtContext, FormatEvent, FormatFields},
+    layer::{Context, Layer},
+    registry::{LookupSpan, SpanRef},
+};
+
+/// Fields of the request context recorded in the spans.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ContextFields {
+    /// Request ID.
+    request_id: Option<String>,
+    /// Trace ID.
+    trace_id: Option<String>,
+    /// User ID.
+    user_id: Option<String>,
+    /// Session ID.
+    session_id: Option<String>,
+    /// Matched route.
+    route: Option<String>,
+    /// Client IP.
+    client_ip: Option<String>,
+}
+
+impl ContextFields {
+    /// Collects the context fields from a list of spans,
+    /// where the former takes precedence over the latter.
+    pub(super) fn from_spans<'a, R: LookupSpan<'a> + 'a>(
+        spans: impl Iterator<Item = SpanRef<'a, R>>,
+    ) -> Self {
+        let mut fields = Self::default();
+        for span in spans {
+            if let Some(span_fields) = span.extensions().get::<Self>() {
+                fields.merge(span_fields);
+            }
+        }
+        fields
+    }
+
+    /// Returns the request ID.
+    #[inline]
+    pub(super) fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Returns the trace ID.
+    #[inline]
+    pub(super) fn trace_id(&self) -> Option<&str> {
+        self.trace_id.as_deref()
+    }
+
+    /// Returns the user ID.
+    #[inline]
+    pub(super) fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// Returns the session ID.
+    #[inline]
+    pub(super) fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Returns the matched route.
+    #[inline]
+    pub(super) fn route(&self) -> Option<&str> {
+        self.route.as_deref()
+    }
+
+    /// Returns the client IP.
+    #[inline]
+    pub(super) fn client_ip(&self) -> Option<&str> {
+        self.client_ip.as_deref()
+    }
+
+    /// Returns `true` if there are no context fields.
+    fn is_empty(&self) -> bool {
+        self.request_id.is_none()
+            && self.trace_id.is_none()
+            && self.user_id.is_none()
+            && self.session_id.is_none()
+            && self.route.is_none()
+            && self.client_ip.is_none()
+    }
+
+    /// Fills the missing fields with the values of another one.
+    fn merge(&mut self, other: &Self) {
+        let pairs = [
+            (&mut self.request_id, &other.request_id),
+            (&mut self.trace_id, &other.trace_id),
+            (&mut self.user_id, &other.user_id),
+            (&mut self.session_id, &other.session_id),
+            (&mut self.route, &other.route),
+            (&mut self.client_ip, &other.client_ip),
+        ];
+        for (field, other_field) in pairs {
+            if field.is_none() {
+                field.clone_from(other_field);
+            }
+        }
+    }
+}
+
+impl Visit for ContextFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let field = match field.name() {
+            "context.request_id" => &mut self.request_id,
+            "context.trace_id" => &mut self.trace_id,
+            "context.user_id" => &mut self.user_id,
+            "context.session_id" => &mut self.session_id,
+            "http.route" => &mut self.route,
+            "client.address" => &mut self.client_ip,
+            _ => return,
+        };
+        *field = Some(value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
+
+/// A layer which stores the context fields in the span extensions.
+pub(super) struct ContextLayer;
+
+impl<S> Layer<S> for ContextLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = ContextFields::default();
+        attrs.record(&mut fields);
+        if !fields.is_empty()
+            && let Some(span) = ctx.span(id)
+        {
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(fields) = extensions.get_mut::<ContextFields>() {
+                values.record(fields);
+            } else {
+                let mut fields = ContextFields::default();
+                values.record(&mut fields);
+                if !fields.is_empty() {
+                    extensions.insert(fields);
+                }
+            }
+        }
+    }
+}
+
+/// Message and fields of an event.
+#[derive(Debug, Default)]
+pub(super) struct EventFields {
+    /// Message.
+    message: String,
+    /// Other fields.
+    fields: Map,
+}
+
+impl EventFields {
+    /// Records the message and fields of an event.
+    pub(super) fn new(event: &Event<'_>) -> Self {
+        let mut fields = Self::default();
+        event.record(&mut fields);
+        fields
+    }
+
+    /// Consumes `self` and returns the message and fields.
+    #[inline]
+    pub(super) fn into_parts(self) -> (String, Map) {
+        (self.message, self.fields)
+    }
+}
+
+impl Visit for EventFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.upsert(field.name(), value);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.upsert(field.name(), value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.upsert(field.name(), value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.upsert(field.name(), value);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_owned();
+        } else {
+            self.fields.upsert(field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields.upsert(field.name(), format!("{value:?}"));
+        }
+    }
+}
+
+/// A JSON formatter which flattens the request context fields.
+pub(super) struct JsonFormat<T> {
+    /// Timer.
+    timer: T,
+    /// Displays the target.
+    display_target: bool,
+    /// Displays the filename.
+    display_filename: bool,
+    /// Displays the line number.
+    display_line_number: bool,
+    /// Displays the thread name.
+    display_thread_names: bool,
+    /// Displays the names of the spans.
+    display_span_list: bool,
+}
+
+impl<T> JsonFormat<T> {
+    /// Creates a new instance.
+    pub(super) fn new(timer: T) -> Self {
+        Self {
+            timer,
+            display_target: true,
+            display_filename: false,
+            display_line_number: false,
+            display_thread_names: false,
+            display_span_list: false,
+        }
+    }
+
+    /// Sets whether to display the target.
+    #[inline]
+    pub(super) fn with_target(mut self, display_target: bool) -> Self {
+        self.display_target = display_target;
+        self
+    }
+
+    /// Sets whether to display the filename.
+    #[inline]
+    pub(super) fn with_file(mut self, display_filename: bool) -> Self {
+        self.display_filename = display_filename;
+        self
+    }
+
+    /// Sets whether to display the line number.
+    #[inline]
+    pub(super) fn with_line_number(mut self, display_line_number: bool) -> Self {
+        self.display_line_number = display_line_number;
+        self
+    }
+
+    /// Sets whether to display the thread name.
+    #[inline]
+    pub(super) fn with_thread_names(mut self, display_thread_names: bool) -> Self {
+        self.display_thread_names = display_thread_names;
+        self
+    }
+
+    /// Sets whether to display the names of the spans.
+    #[inline]
+    pub(super) fn with_span_list(mut self, display_span_list: bool) -> Self {
+        self.display_span_list = display_span_list;
+        self
+    }
+}
+
+impl<S, N, T> FormatEvent<S, N> for JsonFormat<T>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    T: FormatTime,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut timestamp = String::new();
+        self.timer
+            .format_time(&mut format::Writer::new(&mut timestamp))?;
+
+        let metadata = event.metadata();
+        let (message, fields) = EventFields::new(event).into_parts();
+        let mut entry = Map::new();
+        entry.upsert("timestamp", timestamp);
+        entry.upsert("level", metadata.level().as_str());
+        entry.upsert("message", message);
+        if self.display_target {
+            entry.upsert("target", metadata.target());
+        }
+        if self.display_filename
+            && let Some(file) = metadata.file()
+        {
+            entry.upsert("filename", file);
+        }
+        if self.display_line_number
+            && let Some(line) = metadata.line()
+        {
+            entry.upsert("line_number", line);
+        }
+        if self.display_thread_names
+            && let Some(name) = std::thread::current().name()
+        {
+            entry.upsert("thread_name", name);
+        }
+        if let Some(scope) = ctx.event_scope() {
+            let spans = scope.collect::<Vec<_>>();
+            if self.display_span_list {
+                let names = spans
+                    .iter()
+                    .rev()
+                    .map(|span| span.name())
+                    .collect::<Vec<_>>();
+                entry.upsert("spans", names);
+            }
+
+            let context = ContextFields::from_spans(spans.into_iter());
+            let pairs = [
+                ("request_id", context.request_id()),
+                ("trace_id", context.trace_id()),
+                ("user_id", context.user_id()),
+                ("session_id", context.session_id()),
+                ("route", context.route()),
+                ("client_ip", context.client_ip()),
+            ];
+            for (key, value) in pairs {
+                if let Some(value) = value {
+                    entry.upsert(key, value);
+                }
+            }
+        }
+        if !fields.is_empty() {
+            entry.upsert("fields", fields);
+        }
+        writer.write_str(&JsonValue::from(entry).to_string())?;
+        writeln!(writer)
+    }
+}
@@ -0,0 +1,396 @@
+use super::log_format::{ContextFields, EventFields};
+use crate::{datetime::DateTime, error::Error, extension::TomlTableExt, BoxFuture, Map};
+use parking_lot::Mutex;
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        OnceLock,
+    },
+    time::Duration,
+};
+use sysinfo::{System, SystemExt};
+use tokio::sync::Notify;
+use toml::Table;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    layer::{Context, Layer},
+    registry::LookupSpan,
+};
+
+/// A log record captured from a tracing event.
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// Service name.
+    service: &'static str,
+    /// Server host.
+    server_host: &'static str,
+    /// Client IP.
+    client_ip: Option<String>,
+    /// Level.
+    level: Level,
+    /// Topic which is the target of the event.
+    topic: &'static str,
+    /// Message.
+    message: String,
+    /// Source location.
+    source: Option<String>,
+    /// Request ID.
+    request_id: Option<String>,
+    /// Trace ID.
+    trace_id: Option<String>,
+    /// User ID.
+    user_id: Option<String>,
+    /// Matched route.
+    route: Option<String>,
+    /// Other fields of the event.
+    fields: Map,
+    /// Recorded time.
+    recorded_at: DateTime,
+}
+
+impl LogRecord {
+    /// Returns the service name.
+    #[inline]
+    pub fn service(&self) -> &str {
+        self.service
+    }
+
+    /// Returns the server host.
+    #[inline]
+    pub fn server_host(&self) -> &str {
+        self.server_host
+    }
+
+    /// Returns the client IP.
+    #[inline]
+    pub fn client_ip(&self) -> Option<&str> {
+        self.client_ip.as_deref()
+    }
+
+    /// Returns the level.
+    #[inline]
+    pub fn level(&self) -> &'static str {
+        self.level.as_str()
+    }
+
+    /// Returns the topic.
+    #[inline]
+    pub fn topic(&self) -> &str {
+        self.topic
+    }
+
+    /// Returns the message.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the source location in the form `file:line`.
+    #[inline]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the request ID.
+    #[inline]
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Returns the trace ID.
+    #[inline]
+    pub fn trace_id(&self) -> Option<&str> {
+        self.trace_id.as_deref()
+    }
+
+    /// Returns the user ID.
+    #[inline]
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// Returns the matched route.
+    #[inline]
+    pub fn route(&self) -> Option<&str> {
+        self.route.as_deref()
+    }
+
+    /// Returns a reference to the other fields.
+    #[inline]
+    pub fn fields(&self) -> &Map {
+        &self.fields
+    }
+
+    /// Returns the recorded time.
+    #[inline]
+    pub fn recorded_at(&self) -> DateTime {
+        self.recorded_at
+    }
+}
+
+/// A sink for the log records.
+pub trait LogSink: Send + Sync + 'static {
+    /// Inserts a batch of log records.
+    fn insert_logs(&self, records: Vec<LogRecord>) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+/// A layer which ships the log records to the [`LogSink`] in batches.
+///
+/// The events are buffered and flushed by the task of [`LogShipper::run_flusher()`]
+/// periodically, or as soon as the buffer reaches the batch size. The records which
+/// fail to be shipped are kept in the buffer and retried in the next flush.
+pub struct LogShipper {
+    /// Service name.
+    service: &'static str,
+    /// Server host.
+    server_host: &'static str,
+    /// Max level of the events to ship.
+    max_level: Level,
+    /// Batch size.
+    batch_size: usize,
+}
+
+impl LogShipper {
+    /// Creates a new instance with the config.
+    pub(super) fn with_config(service: &'static str, config: &Table) -> Self {
+        let max_level = config
+            .get_str("level")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(Level::WARN);
+        let server_host = System::new().host_name().unwrap_or_default().leak();
+        let max_buffered = config.get_usize("max-buffered").unwrap_or(10000);
+        MAX_BUFFERED.store(max_buffered, Relaxed);
+        let batch_size = config.get_usize("batch-size").unwrap_or(100).max(1);
+        BATCH_SIZE.store(batch_size, Relaxed);
+        if let Some(flush_interval) = config.get_duration("flush-interval") {
+            FLUSH_INTERVAL.set(flush_interval).ok();
+        }
+        Self {
+            service,
+            server_host,
+            max_level,
+            batch_size,
+        }
+    }
+
+    /// Sets the log sink. It should be called before booting the application.
+    ///
+    /// Returns `true` if the log sink has been set successfully.
+    #[inline]
+    pub fn set_sink(sink: impl LogSink) -> bool {
+        LOG_SINK.set(Box::new(sink)).is_ok()
+    }
+
+    /// Returns the log sink if it has been set.
+    #[inline]
+    pub fn sink() -> Option<&'static dyn LogSink> {
+        LOG_SINK.get().map(|sink| sink.as_ref())
+    }
+
+    /// Flushes the buffered log records to the sink in batches.
+    /// The batches are put back into the buffer if they fail to be shipped.
+    pub async fn flush() -> Result<(), Error> {
+        let Some(sink) = Self::sink() else {
+            return Ok(());
+        };
+        let records = mem::take(&mut *LOG_BUFFER.lock());
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        // The events emitted while shipping logs are not shipped to avoid recursions.
+        SHIPPING
+            .scope((), async {
+                let mut failed_records = Vec::new();
+                let mut result = Ok(());
+                for batch in records.chunks(BATCH_SIZE.load(Relaxed)) {
+                    if let Err(err) = sink.insert_logs(batch.to_vec()).await {
+                        tracing::error!("fail to ship the log records: {err}");
+                        failed_records.extend_from_slice(batch);
+                        result = Err(err);
+                    }
+                }
+                if !failed_records.is_empty() {
+                    let mut buffer = LOG_BUFFER.lock();
+                    failed_records.append(&mut buffer);
+
+                    let max_buffered = MAX_BUFFERED.load(Relaxed);
+                    let num_dropped = failed_records.len().saturating_sub(max_buffered);
+                    failed_records.drain(..num_dropped);
+                    *buffer = failed_records;
+                }
+                result
+            })
+            .await
+    }
+
+    /// Runs a flusher which flushes the buffered log records periodically,
+    /// or as soon as the buffer reaches the batch size. It should be spawned
+    /// on the runtime of the application, and returns immediately if no sink has been set.
+    pub async fn run_flusher() {
+        if Self::sink().is_none() {
+            return;
+        }
+
+        let flush_interval = FLUSH_INTERVAL
+            .get()
+            .copied()
+            .unwrap_or_else(|| Duration::from_secs(5));
+        loop {
+            tokio::time::timeout(flush_interval, FLUSH_NOTIFY.notified())
+                .await
+                .ok();
+            Self::flush().await.ok();
+        }
+    }
+}
+
+impl<S> Layer<S> for LogShipper
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > self.max_level
+            || LOG_SINK.get().is_none()
+            || SHIPPING.try_with(|_| ()).is_ok()
+        {
+            return;
+        }
+
+        let context = ctx
+            .event_scope(event)
+            .map(|scope| ContextFields::from_spans(scope))
+            .unwrap_or_default();
+        let (message, fields) = EventFields::new(event).into_parts();
+        let source = metadata
+            .file()
+            .map(|file| format!("{file}:{}", metadata.line().unwrap_or_default()));
+        let record = LogRecord {
+            service: self.service,
+            server_host: self.server_host,
+            client_ip: context.client_ip().map(|s| s.to_owned()),
+            level: *metadata.level(),
+            topic: metadata.target(),
+            message,
+            source,
+            request_id: context.request_id().map(|s| s.to_owned()),
+            trace_id: context.trace_id().map(|s| s.to_owned()),
+            user_id: context.user_id().map(|s| s.to_owned()),
+            route: context.route().map(|s| s.to_owned()),
+            fields,
+            recorded_at: DateTime::now(),
+        };
+
+        let num_buffered = {
+            let mut buffer = LOG_BUFFER.lock();
+            if buffer.len() >= MAX_BUFFERED.load(Relaxed) {
+                return;
+            }
+            buffer.push(record);
+            buffer.len()
+        };
+        if num_buffered >= self.batch_size {
+            FLUSH_NOTIFY.notify_one();
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Indicates that the current task is shipping logs.
+    static SHIPPING: ();
+}
+
+/// Log sink.
+static LOG_SINK: OnceLock<Box<dyn LogSink>> = OnceLock::new();
+
+/// Buffered log records.
+static LOG_BUFFER: Mutex<Vec<LogRecord>> = Mutex::new(Vec::new());
+
+/// Max number of the buffered records.
+static MAX_BUFFERED: AtomicUsize = AtomicUsize::new(10000);
+
+/// Max number of the records shipped in a batch.
+static BATCH_SIZE: AtomicUsize = AtomicUsize::new(100);
+
+/// Flush interval.
+static FLUSH_INTERVAL: OnceLock<Duration> = OnceLock::new();
+
+/// Notification for flushing a full batch.
+static FLUSH_NOTIFY: Notify = Notify::const_new();
+
+#[cfg(test)]
+mod tests {
+    use super::{LogRecord, LogShipper, LogSink};
+    use crate::{error::Error, BoxFuture};
+    use parking_lot::Mutex;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    };
+    use toml::Table;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    struct MemoryLogSink(Arc<Mutex<Vec<Vec<LogRecord>>>>, AtomicBool);
+
+    impl LogSink for MemoryLogSink {
+        fn insert_logs(&self, records: Vec<LogRecord>) -> BoxFuture<'_, Result<(), Error>> {
+            if self.1.swap(false, Relaxed) {
+                return Box::pin(async { Err(Error::new("the sink is unavailable")) });
+            }
+            self.0.lock().push(records);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[test]
+    fn it_ships_log_records() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let sink = MemoryLogSink(batches.clone(), AtomicBool::new(true));
+        assert!(LogShipper::set_sink(sink));
+
+        let mut config = Table::new();
+        config.insert("batch-size".to_owned(), 2.into());
+        let subscriber = tracing_subscriber::registry()
+            .with(super::super::log_format::ContextLayer)
+            .with(LogShipper::with_config("zino", &config));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::warn_span!(
+                "HTTP request",
+                "http.route" = "/user/list",
+                "context.request_id" = tracing::field::Empty,
+            );
+            let _entered = span.enter();
+            span.record("context.request_id", "0190b2c8-4aa4-7a3f-a0a5-8d6a9b1b9a01");
+            tracing::info!("should be skipped");
+            tracing::warn!(user = "alice", "slow query");
+            tracing::error!("fail to connect");
+            tracing::warn!("slow request");
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        // Only the failed batch is put back into the buffer.
+        assert!(runtime.block_on(LogShipper::flush()).is_err());
+        assert_eq!(batches.lock().len(), 1);
+        assert_eq!(batches.lock()[0][0].message(), "slow request");
+        runtime.block_on(LogShipper::flush()).unwrap();
+
+        let batches = batches.lock();
+        assert_eq!(batches.len(), 2);
+
+        let records = &batches[1];
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level(), "WARN");
+        assert_eq!(records[0].message(), "slow query");
+        assert_eq!(records[0].route(), Some("/user/list"));
+        assert_eq!(
+            records[0].request_id(),
+            Some("0190b2c8-4aa4-7a3f-a0a5-8d6a9b1b9a01")
+        );
+        assert_eq!(records[0].fields().get("user"), Some(&"alice".into()));
+        assert_eq!(records[1].level(), "ERROR");
+    }
+}
@@ -67,11 +67,68 @@ pub(super) fn init<APP: Application + ?Sized>() {
                         .unwrap_or_else(|err| panic!("invalid IP address `{addr}`: {err}"));
                 }
             }
+            if let Some(config) = metrics.get_table("otlp") {
+                #[cfg(feature = "otlp")]
+                {
+                    let resource = super::otlp_exporter::resource::<APP>();
+                    match super::otlp_exporter::init_recorder(config, resource) {
+                        Ok(recorder) => {
+                            install_with_otlp(builder, recorder)
+                                .expect("fail to install Prometheus and OTLP exporters");
+                            return;
+                        }
+                        Err(err) => tracing::error!("fail to initialize the OTLP exporter: {err}"),
+                    }
+                }
+                #[cfg(not(feature = "otlp"))]
+                {
+                    let _ = config;
+                    tracing::error!("the `otlp` feature should be enabled");
+                }
+            }
             builder
                 .install()
                 .expect("fail to install Prometheus exporter");
+        } else if exporter == "otlp" {
+            #[cfg(feature = "otlp")]
+            {
+                let config = metrics.get_table("otlp").unwrap_or(metrics);
+                let resource = super::otlp_exporter::resource::<APP>();
+                match super::otlp_exporter::init_recorder(config, resource) {
+                    Ok(recorder) => metrics::set_boxed_recorder(Box::new(recorder))
+                        .expect("fail to install OTLP exporter"),
+                    Err(err) => tracing::error!("fail to initialize the OTLP exporter: {err}"),
+                }
+            }
+            #[cfg(not(feature = "otlp"))]
+            tracing::error!("the `otlp` feature should be enabled");
         } else if !exporter.is_empty() {
             tracing::error!("metrics exporter `{exporter}` is unsupported");
         }
     }
 }
+
+/// Installs the Prometheus exporter alongside the OTLP exporter.
+#[cfg(feature = "otlp")]
+fn install_with_otlp(
+    builder: PrometheusBuilder,
+    otlp_recorder: super::otlp_exporter::OtlpRecorder,
+) -> Result<(), crate::error::Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let (prometheus_recorder, exporter) = {
+        let _guard = runtime.enter();
+        builder.build()?
+    };
+    std::thread::Builder::new()
+        .name("metrics-exporter-prometheus".to_owned())
+        .spawn(move || runtime.block_on(exporter))?;
+
+    let recorder = metrics_util::layers::FanoutBuilder::default()
+        .add_recorder(prometheus_recorder)
+        .add_recorder(otlp_recorder)
+        .build();
+    metrics::set_boxed_recorder(Box::new(recorder))?;
+    Ok(())
+}
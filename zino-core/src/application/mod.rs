@@ -5,6 +5,8 @@ use crate::{
     error::Error,
     extension::{HeaderMapExt, JsonObjectExt, TomlTableExt},
     openapi,
+    request::RateLimiter,
+    response::ResponseCache,
     schedule::{AsyncCronJob, CronJob, Job, JobScheduler},
     state::{Env, State},
     trace::TraceContext,
@@ -12,19 +14,24 @@ use crate::{
 };
 use reqwest::Response;
 use serde::de::DeserializeOwned;
-use std::{env, fs, path::PathBuf, sync::LazyLock, thread};
-use futures::executor::block_on;
-use sqlx::Executor;
+use std::{env, fs, path::PathBuf, sync::LazyLock, thread, time::Duration};
 use toml::value::Table;
 use utoipa::openapi::{OpenApi, OpenApiBuilder};
 
+mod log_format;
+#[cfg(feature = "runtime-tokio")]
+mod log_shipper;
 mod metrics_exporter;
+#[cfg(feature = "otlp")]
+mod otlp_exporter;
 mod secret_key;
 mod server_tag;
 mod static_record;
 mod system_monitor;
 mod tracing_subscriber;
 
+#[cfg(feature = "runtime-tokio")]
+pub use log_shipper::{LogRecord, LogShipper, LogSink};
 pub use server_tag::ServerTag;
 pub use static_record::StaticRecord;
 
@@ -85,6 +92,22 @@ pub trait Application {
             }
         }
 
+        // Watches the config files to reload the hot-reloadable sections.
+        State::subscribe_config("rate-limit", |config| {
+            RateLimiter::from_config(config).set_shared();
+        });
+        State::subscribe_config("response-cache", |config| {
+            ResponseCache::from_config(config).set_shared();
+        });
+        if let Some(config) = SHARED_APP_STATE.get_config("config")
+            && config.get_bool("hot-reload") == Some(true)
+        {
+            let interval = config
+                .get_duration("watch-interval")
+                .unwrap_or_else(|| Duration::from_secs(5));
+            SHARED_APP_STATE.watch_config(interval);
+        }
+
         Self::default()
     }
 
@@ -248,25 +271,54 @@ pub trait Application {
         Ok(data)
     }
     //检测数据库链接是否正确
-    fn check_database()
-    {
-        if cfg!(feature = "orm") {
-            if let connection = super::orm::GlobalConnection::get("main").unwrap(){
-                block_on( async {
-                    if let Err(err)=connection.pool().execute("SELECT 1").await{
+    fn check_database() {
+        #[cfg(feature = "orm")]
+        {
+            use futures::executor::block_on;
+            use sqlx::Executor;
+
+            if let Some(connection) = super::orm::GlobalConnection::get("main") {
+                block_on(async {
+                    if let Err(err) = connection.pool().execute("SELECT 1").await {
                         tracing::error!("connect to database failed : {err}");
                     }
-                } );
+                });
             }
+        }
+    }
 
+    /// Applies the pending database migrations for the `main` connection pool
+    /// if `auto-migrate` is enabled in the `[migration]` config.
+    /// An error is returned if the migrations can not be applied,
+    /// which should abort the startup.
+    async fn migrate_database() -> Result<(), Error> {
+        #[cfg(feature = "orm")]
+        if let Some(config) = SHARED_APP_STATE.get_config("migration")
+            && config.get_bool("auto-migrate") == Some(true)
+        {
+            let Some(connection_pool) = crate::orm::GlobalConnection::get("main") else {
+                crate::bail!("no connection pool is available for the migrations");
+            };
+            let num_migrations = crate::orm::Migrator::new(connection_pool).migrate().await?;
+            if num_migrations > 0 {
+                tracing::warn!("{num_migrations} migrations have been applied");
+            }
         }
+        Ok(())
     }
+
     /// Handles the graceful shutdown.
     async fn shutdown() {
+        #[cfg(feature = "runtime-tokio")]
+        LogShipper::flush().await.ok();
         #[cfg(feature = "orm")]
         {
             crate::orm::GlobalConnection::close_all().await;
         }
+        #[cfg(feature = "otlp")]
+        {
+            otlp_exporter::shutdown();
+        }
     }
 }
 
@@ -0,0 +1,447 @@
+use super::Application;
+use crate::{error::Error, extension::TomlTableExt};
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Recorder,
+    SharedString, Unit,
+};
+use opentelemetry::{
+    global,
+    metrics::{Meter, MeterProvider as _, ObservableGauge, Unit as OtelUnit},
+    KeyValue,
+};
+use opentelemetry_otlp::{
+    HttpExporterBuilder, MetricsExporterBuilder, SpanExporterBuilder, TonicExporterBuilder,
+    WithExportConfig,
+};
+use opentelemetry_sdk::{
+    metrics::MeterProvider,
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self, Sampler, Tracer},
+    Resource,
+};
+use parking_lot::RwLock;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc, LazyLock, OnceLock,
+    },
+    time::Duration,
+};
+use tokio::runtime::{Builder, Runtime};
+use toml::Table;
+
+/// Returns the resource describing the application.
+pub(super) fn resource<APP: Application + ?Sized>() -> Resource {
+    Resource::default().merge(&Resource::new([
+        KeyValue::new("service.name", APP::name()),
+        KeyValue::new("service.version", APP::version()),
+        KeyValue::new("deployment.environment", APP::env().as_str()),
+    ]))
+}
+
+/// Installs a tracer which exports spans to the OTLP collector.
+/// It also sets the W3C trace context as the global propagator.
+pub(super) fn init_tracer(config: &Table, resource: Resource) -> Result<Tracer, Error> {
+    let sample_ratio = config.get_f64("sample-ratio").unwrap_or(1.0);
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(sample_ratio)));
+    let trace_config = trace::config()
+        .with_sampler(sampler)
+        .with_resource(resource);
+    let _guard = OTLP_RUNTIME.enter();
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter_builder::<SpanExporterBuilder>(config))
+        .with_trace_config(trace_config)
+        .install_batch(runtime::Tokio)?;
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(tracer)
+}
+
+/// Installs a meter provider which exports metrics to the OTLP collector,
+/// and returns a recorder for the `metrics` facade.
+pub(super) fn init_recorder(config: &Table, resource: Resource) -> Result<OtlpRecorder, Error> {
+    let interval = config
+        .get_duration("interval")
+        .unwrap_or_else(|| Duration::from_secs(60));
+    let _guard = OTLP_RUNTIME.enter();
+    let mut pipeline = opentelemetry_otlp::new_pipeline()
+        .metrics(runtime::Tokio)
+        .with_exporter(exporter_builder::<MetricsExporterBuilder>(config))
+        .with_resource(resource)
+        .with_period(interval);
+    if let Some(timeout) = config.get_duration("timeout") {
+        pipeline = pipeline.with_timeout(timeout);
+    }
+    let provider = pipeline.build()?;
+    let meter = provider.meter("zino");
+    METER_PROVIDER.set(provider).ok();
+    Ok(OtlpRecorder::new(meter))
+}
+
+/// Flushes the pending spans and metrics, and shuts down the providers.
+pub(super) fn shutdown() {
+    global::shutdown_tracer_provider();
+    if let Some(provider) = METER_PROVIDER.get()
+        && let Err(err) = provider.shutdown()
+    {
+        tracing::error!("fail to shutdown the OTLP meter provider: {err}");
+    }
+}
+
+/// Creates an exporter builder with the `protocol`, `endpoint`, `timeout` and `headers`.
+fn exporter_builder<B>(config: &Table) -> B
+where
+    B: From<TonicExporterBuilder> + From<HttpExporterBuilder>,
+{
+    let protocol = config.get_str("protocol").unwrap_or("grpc");
+    let timeout = config
+        .get_duration("timeout")
+        .unwrap_or_else(|| Duration::from_secs(10));
+    if protocol == "grpc" {
+        let endpoint = config
+            .get_str("endpoint")
+            .unwrap_or("http://localhost:4317");
+        opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_endpoint(endpoint)
+            .with_timeout(timeout)
+            .into()
+    } else {
+        if protocol != "http" && protocol != "http/protobuf" {
+            tracing::warn!("unsupported OTLP protocol `{protocol}`, fallback to `http/protobuf`");
+        }
+        let endpoint = config
+            .get_str("endpoint")
+            .unwrap_or("http://localhost:4318");
+        let mut builder = opentelemetry_otlp::new_exporter()
+            .http()
+            .with_endpoint(endpoint)
+            .with_timeout(timeout);
+        if let Some(headers) = config.get_table("headers") {
+            let headers = headers
+                .iter()
+                .filter_map(|(key, value)| value.as_str().map(|v| (key.to_owned(), v.to_owned())))
+                .collect();
+            builder = builder.with_headers(headers);
+        }
+        builder.into()
+    }
+}
+
+/// A recorder which bridges the `metrics` facade to the OTLP meter.
+pub(super) struct OtlpRecorder {
+    /// Meter.
+    meter: Meter,
+    /// Units and descriptions of the metrics.
+    descriptions: RwLock<HashMap<String, (Option<Unit>, SharedString)>>,
+    /// Registered counters.
+    counters: RwLock<HashMap<Key, Counter>>,
+    /// Registered gauges.
+    gauges: RwLock<HashMap<Key, Gauge>>,
+    /// Registered histograms.
+    histograms: RwLock<HashMap<Key, Histogram>>,
+}
+
+impl OtlpRecorder {
+    /// Creates a new instance.
+    fn new(meter: Meter) -> Self {
+        Self {
+            meter,
+            descriptions: RwLock::new(HashMap::new()),
+            counters: RwLock::new(HashMap::new()),
+            gauges: RwLock::new(HashMap::new()),
+            histograms: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Records the unit and description of a metric.
+    fn describe(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.descriptions
+            .write()
+            .insert(key.as_str().to_owned(), (unit, description));
+    }
+
+    /// Returns the unit and description of a metric.
+    fn description(&self, name: &str) -> (Option<OtelUnit>, String) {
+        self.descriptions
+            .read()
+            .get(name)
+            .map(|(unit, description)| {
+                let unit = unit.map(|unit| OtelUnit::new(unit.as_canonical_label().to_owned()));
+                (unit, description.to_string())
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Recorder for OtlpRecorder {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.describe(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.describe(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.describe(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key) -> Counter {
+        if let Some(counter) = self.counters.read().get(key) {
+            return counter.clone();
+        }
+
+        let name = key.name().to_owned();
+        let (unit, description) = self.description(&name);
+        let mut builder = self.meter.u64_counter(name).with_description(description);
+        if let Some(unit) = unit {
+            builder = builder.with_unit(unit);
+        }
+
+        let counter = OtlpCounter {
+            counter: builder.init(),
+            attributes: parse_attributes(key),
+            value: AtomicU64::new(0),
+        };
+        self.counters
+            .write()
+            .entry(key.clone())
+            .or_insert_with(|| Counter::from_arc(Arc::new(counter)))
+            .clone()
+    }
+
+    fn register_gauge(&self, key: &Key) -> Gauge {
+        if let Some(gauge) = self.gauges.read().get(key) {
+            return gauge.clone();
+        }
+
+        let name = key.name().to_owned();
+        let (unit, description) = self.description(&name);
+        let value = Arc::new(AtomicU64::new(0f64.to_bits()));
+        let attributes = parse_attributes(key);
+        let observed_value = value.clone();
+        let mut builder = self
+            .meter
+            .f64_observable_gauge(name)
+            .with_description(description)
+            .with_callback(move |observer| {
+                observer.observe(f64::from_bits(observed_value.load(Relaxed)), &attributes);
+            });
+        if let Some(unit) = unit {
+            builder = builder.with_unit(unit);
+        }
+
+        let gauge = OtlpGauge {
+            _gauge: builder.init(),
+            value,
+        };
+        self.gauges
+            .write()
+            .entry(key.clone())
+            .or_insert_with(|| Gauge::from_arc(Arc::new(gauge)))
+            .clone()
+    }
+
+    fn register_histogram(&self, key: &Key) -> Histogram {
+        if let Some(histogram) = self.histograms.read().get(key) {
+            return histogram.clone();
+        }
+
+        let name = key.name().to_owned();
+        let (unit, description) = self.description(&name);
+        let mut builder = self.meter.f64_histogram(name).with_description(description);
+        if let Some(unit) = unit {
+            builder = builder.with_unit(unit);
+        }
+
+        let histogram = OtlpHistogram {
+            histogram: builder.init(),
+            attributes: parse_attributes(key),
+        };
+        self.histograms
+            .write()
+            .entry(key.clone())
+            .or_insert_with(|| Histogram::from_arc(Arc::new(histogram)))
+            .clone()
+    }
+}
+
+/// A counter backed by the OTLP meter.
+struct OtlpCounter {
+    /// Counter instrument.
+    counter: opentelemetry::metrics::Counter<u64>,
+    /// Attributes parsed from the labels.
+    attributes: Vec<KeyValue>,
+    /// Accumulated value.
+    value: AtomicU64,
+}
+
+impl CounterFn for OtlpCounter {
+    fn increment(&self, value: u64) {
+        self.value.fetch_add(value, Relaxed);
+        self.counter.add(value, &self.attributes);
+    }
+
+    fn absolute(&self, value: u64) {
+        let prev_value = self.value.fetch_max(value, Relaxed);
+        if value > prev_value {
+            self.counter.add(value - prev_value, &self.attributes);
+        }
+    }
+}
+
+/// A gauge backed by the OTLP meter.
+struct OtlpGauge {
+    /// Observable gauge instrument.
+    _gauge: ObservableGauge<f64>,
+    /// Bits of the current value.
+    value: Arc<AtomicU64>,
+}
+
+impl OtlpGauge {
+    /// Updates the current value.
+    fn update(&self, f: impl Fn(f64) -> f64) {
+        self.value
+            .fetch_update(Relaxed, Relaxed, |bits| {
+                Some(f(f64::from_bits(bits)).to_bits())
+            })
+            .ok();
+    }
+}
+
+impl GaugeFn for OtlpGauge {
+    fn increment(&self, value: f64) {
+        self.update(|v| v + value);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.update(|v| v - value);
+    }
+
+    fn set(&self, value: f64) {
+        self.value.store(value.to_bits(), Relaxed);
+    }
+}
+
+/// A histogram backed by the OTLP meter.
+struct OtlpHistogram {
+    /// Histogram instrument.
+    histogram: opentelemetry::metrics::Histogram<f64>,
+    /// Attributes parsed from the labels.
+    attributes: Vec<KeyValue>,
+}
+
+impl HistogramFn for OtlpHistogram {
+    fn record(&self, value: f64) {
+        self.histogram.record(value, &self.attributes);
+    }
+}
+
+/// Parses the labels of a metric key as attributes.
+fn parse_attributes(key: &Key) -> Vec<KeyValue> {
+    key.labels()
+        .map(|label| KeyValue::new(label.key().to_owned(), label.value().to_owned()))
+        .collect()
+}
+
+/// Dedicated runtime for the OTLP exporters, since the application is booted
+/// before the server runtime has been started.
+static OTLP_RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("otlp-exporter")
+        .enable_all()
+        .build()
+        .expect("fail to build Tokio runtime for the OTLP exporters")
+});
+
+/// Meter provider for the OTLP exporter.
+static METER_PROVIDER: OnceLock<MeterProvider> = OnceLock::new();
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::TraceContext;
+    use opentelemetry_sdk::Resource;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+    };
+    use toml::Table;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn it_exports_spans_to_collector() {
+        // A collector stub which accepts the OTLP/HTTP requests.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0; 4096];
+                let (header_len, content_len) = loop {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buf[..pos]).to_lowercase();
+                        let content_len = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .and_then(|s| s.trim().parse::<usize>().ok())
+                            .unwrap_or_default();
+                        break (pos + 4, content_len);
+                    }
+                };
+                while buf.len() < header_len + content_len {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                    .unwrap();
+                let request_line = String::from_utf8_lossy(&buf[..header_len])
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned();
+                sender
+                    .send((request_line, buf[header_len..].to_vec()))
+                    .unwrap();
+            }
+        });
+
+        let mut config = Table::new();
+        config.insert("protocol".to_owned(), "http".into());
+        config.insert("endpoint".to_owned(), format!("http://{addr}").into());
+        let tracer = super::init_tracer(&config, Resource::default()).unwrap();
+        let provider = tracer.provider().unwrap();
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        let traceparent = "00-76580b47d0bf430ebbb0d1d966b10f2b-0000004000000001-01";
+        let trace_context = TraceContext::from_traceparent(traceparent).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("HTTP request", "otel.kind" = "server");
+            trace_context.set_remote_parent(&span);
+            span.in_scope(|| {
+                let child_context = TraceContext::new();
+                assert_eq!(child_context.trace_id(), trace_context.trace_id());
+            });
+        });
+        assert!(provider.force_flush().into_iter().all(|r| r.is_ok()));
+
+        let (request_line, body) = receiver.recv().unwrap();
+        assert!(request_line.starts_with("POST /v1/traces"));
+
+        let trace_id = trace_context.trace_id().to_be_bytes();
+        let parent_id = trace_context.parent_id().unwrap().to_be_bytes();
+        assert!(body.windows(16).any(|w| w == trace_id));
+        assert!(body.windows(8).any(|w| w == parent_id));
+        assert!(body.windows(12).any(|w| w == b"HTTP request"));
+    }
+}
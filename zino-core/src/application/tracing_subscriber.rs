@@ -1,5 +1,8 @@
-use super::Application;
-use crate::extension::TomlTableExt;
+use super::{
+    log_format::{ContextLayer, JsonFormat},
+    Application,
+};
+use crate::{error::Error, extension::TomlTableExt, state::State};
 use std::{fs, io, path::Path, sync::OnceLock, time::Duration};
 use tracing::Level;
 use tracing_appender::{
@@ -10,6 +13,8 @@ use tracing_subscriber::{
     filter::{EnvFilter, LevelFilter},
     fmt::{time::OffsetTime, writer::MakeWriterExt},
     layer::SubscriberExt,
+    reload::{self, Handle},
+    Registry,
 };
 
 /// Initializes the tracing subscriber.
@@ -24,11 +29,13 @@ pub(super) fn init<APP: Application + ?Sized>() {
 
     let app_env = APP::env();
     let in_dev_mode = app_env.is_dev();
-    let mut env_filter = if in_dev_mode {
+    let default_filter = if in_dev_mode {
         "info,zino=trace,zino_core=trace"
     } else {
         "info"
     };
+    let mut env_filter = default_filter;
+    let mut log_format = if in_dev_mode { "pretty" } else { "json" };
 
     let mut log_dir = "logs";
     let mut log_rotation = "hourly";
@@ -38,6 +45,8 @@ pub(super) fn init<APP: Application + ?Sized>() {
     let mut display_line_number = false;
     let mut display_thread_names = false;
     let mut display_span_list = false;
+    let mut otlp_config = None;
+    let mut sink_config = None;
     if let Some(config) = APP::config().get_table("tracing") {
         if let Some(dir) = config.get_str("log-dir") {
             log_dir = dir;
@@ -51,6 +60,9 @@ pub(super) fn init<APP: Application + ?Sized>() {
         if let Some(filter) = config.get_str("filter") {
             env_filter = filter;
         }
+        if let Some(format) = config.get_str("format") {
+            log_format = format;
+        }
         display_target = config.get_bool("display-target").unwrap_or(true);
         display_filename = config.get_bool("display-filename").unwrap_or(in_dev_mode);
         display_line_number = config
@@ -58,6 +70,8 @@ pub(super) fn init<APP: Application + ?Sized>() {
             .unwrap_or(in_dev_mode);
         display_thread_names = config.get_bool("display-thread-names").unwrap_or(false);
         display_span_list = config.get_bool("display-span-list").unwrap_or(false);
+        otlp_config = config.get_table("otlp");
+        sink_config = config.get_table("sink");
     }
 
     let log_dir = Path::new(log_dir);
@@ -97,6 +111,12 @@ pub(super) fn init<APP: Application + ?Sized>() {
     } else {
         io::stdout.with_max_level(Level::WARN)
     };
+    let json_format = JsonFormat::new(local_offset_time.clone())
+        .with_target(display_target)
+        .with_file(display_filename)
+        .with_line_number(display_line_number)
+        .with_thread_names(display_thread_names)
+        .with_span_list(display_span_list);
     let fmt_layer = tracing_subscriber::fmt::layer()
         .with_target(display_target)
         .with_file(display_filename)
@@ -104,31 +124,91 @@ pub(super) fn init<APP: Application + ?Sized>() {
         .with_thread_names(display_thread_names)
         .with_timer(local_offset_time)
         .with_writer(stdout.and(non_blocking_appender));
-    let filter_layer = EnvFilter::builder()
-        .with_default_directive(LevelFilter::WARN.into())
-        .parse_lossy(env_filter);
-    if in_dev_mode {
-        let pretty_fmt_layer = fmt_layer.pretty();
-        let subscriber = tracing_subscriber::registry()
-            .with(filter_layer)
-            .with(pretty_fmt_layer);
-        tracing::subscriber::set_global_default(subscriber)
-            .expect("fail to set the default subscriber with a `Pretty` formatter");
-    } else {
-        let json_fmt_layer = fmt_layer
-            .json()
-            .with_current_span(true)
-            .with_span_list(display_span_list);
-        let subscriber = tracing_subscriber::registry()
-            .with(filter_layer)
-            .with(json_fmt_layer);
-        tracing::subscriber::set_global_default(subscriber)
-            .expect("fail to set the default subscriber with a `Json` formatter");
-    };
+    let (filter_layer, reload_handle) = reload::Layer::new(parse_env_filter(env_filter));
+
+    // Exports the spans to the OTLP collector.
+    let mut otlp_error: Option<Error> = None;
+    #[cfg(feature = "otlp")]
+    let otlp_layer = otlp_config.and_then(|config| {
+        let resource = super::otlp_exporter::resource::<APP>();
+        match super::otlp_exporter::init_tracer(config, resource) {
+            Ok(tracer) => Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+            Err(err) => {
+                otlp_error = Some(err);
+                None
+            }
+        }
+    });
+    #[cfg(not(feature = "otlp"))]
+    let otlp_layer = otlp_config.map(|_| {
+        otlp_error = Some(Error::new("the `otlp` feature should be enabled"));
+        tracing_subscriber::layer::Identity::new()
+    });
+
+    // Ships the log records to the sink in batches.
+    #[cfg(feature = "runtime-tokio")]
+    let log_shipper =
+        sink_config.map(|config| super::log_shipper::LogShipper::with_config(app_name, config));
+    #[cfg(not(feature = "runtime-tokio"))]
+    let log_shipper = sink_config.map(|_| tracing_subscriber::layer::Identity::new());
+    let subscriber = tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(otlp_layer)
+        .with(ContextLayer)
+        .with(log_shipper);
+    match log_format {
+        "json" => {
+            let json_fmt_layer = fmt_layer.event_format(json_format);
+            tracing::subscriber::set_global_default(subscriber.with(json_fmt_layer))
+                .expect("fail to set the default subscriber with a `Json` formatter");
+        }
+        "compact" => {
+            let compact_fmt_layer = fmt_layer.compact();
+            tracing::subscriber::set_global_default(subscriber.with(compact_fmt_layer))
+                .expect("fail to set the default subscriber with a `Compact` formatter");
+        }
+        _ => {
+            let pretty_fmt_layer = fmt_layer.pretty();
+            tracing::subscriber::set_global_default(subscriber.with(pretty_fmt_layer))
+                .expect("fail to set the default subscriber with a `Pretty` formatter");
+        }
+    }
     TRACING_APPENDER_GUARD
         .set(worker_guard)
         .expect("fail to set the worker guard for the tracing appender");
+    if let Some(err) = otlp_error {
+        tracing::error!("fail to initialize the OTLP exporter: {err}");
+    }
+    #[cfg(feature = "runtime-tokio")]
+    if sink_config.is_some() && super::log_shipper::LogShipper::sink().is_none() {
+        tracing::warn!("the log sink should be set before booting the application");
+    }
+    #[cfg(not(feature = "runtime-tokio"))]
+    if sink_config.is_some() {
+        tracing::error!("the `runtime-tokio` feature should be enabled for the log sink");
+    }
+
+    // Reloads the filter when the `tracing` config has been changed.
+    TRACING_RELOAD_HANDLE.set(reload_handle).ok();
+    State::subscribe_config("tracing", move |config| {
+        let env_filter = config.get_str("filter").unwrap_or(default_filter);
+        if let Some(handle) = TRACING_RELOAD_HANDLE.get()
+            && let Err(err) = handle.reload(parse_env_filter(env_filter))
+        {
+            tracing::error!("fail to reload the tracing filter: {err}");
+        }
+    });
+}
+
+/// Parses the directives of the env filter.
+fn parse_env_filter(env_filter: &str) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::WARN.into())
+        .parse_lossy(env_filter)
 }
 
 /// Tracing appender guard.
 static TRACING_APPENDER_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Reload handle for the tracing filter.
+static TRACING_RELOAD_HANDLE: OnceLock<Handle<EnvFilter, Registry>> = OnceLock::new();
@@ -0,0 +1,292 @@
+use super::UserSession;
+use crate::datetime::DateTime;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Shared policy engine.
+static SHARED_POLICY_ENGINE: LazyLock<RwLock<Arc<PolicyEngine>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(PolicyEngine::new())));
+
+/// Effect of an access policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolicyEffect {
+    /// The access is allowed.
+    Allow,
+    /// The access is denied. This is the default value.
+    #[default]
+    Deny,
+}
+
+impl PolicyEffect {
+    /// Returns `true` if the access is allowed.
+    #[inline]
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allow)
+    }
+
+    /// Returns the effect as `str`.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// An access policy which grants or denies the actions on the resources.
+#[derive(Debug, Clone, Default)]
+pub struct AccessPolicy {
+    /// Policy name.
+    name: String,
+    /// Resource pattern. The segment `*` matches any one segment,
+    /// and the segment `**` matches any remaining segments.
+    resource: String,
+    /// Actions. The action `*` matches any action.
+    actions: Vec<String>,
+    /// Effect.
+    effect: PolicyEffect,
+    /// Roles of the subjects. The policy applies to all users if it is empty.
+    roles: Vec<String>,
+    /// Tenant ID. The policy applies to all tenants if it is `None`.
+    tenant_id: Option<String>,
+    /// Start time of the validity.
+    valid_from: Option<DateTime>,
+    /// End time of the validity.
+    expires_at: Option<DateTime>,
+}
+
+impl AccessPolicy {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(name: impl Into<String>, resource: impl Into<String>, effect: PolicyEffect) -> Self {
+        Self {
+            name: name.into(),
+            resource: resource.into(),
+            effect,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the actions.
+    #[inline]
+    pub fn set_actions(&mut self, actions: Vec<String>) {
+        self.actions = actions;
+    }
+
+    /// Sets the roles of the subjects.
+    #[inline]
+    pub fn set_roles(&mut self, roles: Vec<String>) {
+        self.roles = roles;
+    }
+
+    /// Sets the tenant ID.
+    #[inline]
+    pub fn set_tenant_id(&mut self, tenant_id: impl Into<String>) {
+        self.tenant_id = Some(tenant_id.into());
+    }
+
+    /// Sets the time window of the validity.
+    #[inline]
+    pub fn set_time_window(&mut self, valid_from: Option<DateTime>, expires_at: Option<DateTime>) {
+        self.valid_from = valid_from;
+        self.expires_at = expires_at;
+    }
+
+    /// Returns the policy name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the resource pattern.
+    #[inline]
+    pub fn resource(&self) -> &str {
+        &self.resource
+    }
+
+    /// Returns the effect.
+    #[inline]
+    pub fn effect(&self) -> PolicyEffect {
+        self.effect
+    }
+
+    /// Returns `true` if the policy applies to the user session, the action and the resource
+    /// at the specific time.
+    pub fn applies_to<U, T: ToString>(
+        &self,
+        session: &UserSession<U, String, T>,
+        action: &str,
+        resource: &str,
+        time: DateTime,
+    ) -> bool {
+        if self.valid_from.is_some_and(|dt| time < dt)
+            || self.expires_at.is_some_and(|dt| time >= dt)
+        {
+            return false;
+        }
+        if let Some(tenant_id) = self.tenant_id.as_deref()
+            && session.tenant_id().map(|t| t.to_string()).as_deref() != Some(tenant_id)
+        {
+            return false;
+        }
+        if !self.roles.is_empty() && !self.roles.iter().any(|role| session.has_role(role)) {
+            return false;
+        }
+        if !self
+            .actions
+            .iter()
+            .any(|a| a == "*" || a.eq_ignore_ascii_case(action))
+        {
+            return false;
+        }
+        Self::matches_resource(&self.resource, resource)
+    }
+
+    /// Returns `true` if the resource path matches the pattern.
+    /// The empty segments caused by repeated or trailing `/` are ignored.
+    pub(crate) fn matches_resource(pattern: &str, resource: &str) -> bool {
+        let mut patterns = pattern.split('/').filter(|s| !s.is_empty());
+        let mut segments = resource.split('/').filter(|s| !s.is_empty());
+        loop {
+            match (patterns.next(), segments.next()) {
+                (Some("**"), _) => return true,
+                (Some(pattern), Some(segment)) => {
+                    if pattern != "*" && pattern != segment {
+                        return false;
+                    }
+                }
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// An engine which evaluates the access policies with explicit-deny precedence.
+/// The access is denied if there is no policy allowing it.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyEngine {
+    /// Access policies.
+    policies: Vec<AccessPolicy>,
+}
+
+impl PolicyEngine {
+    /// Creates a new instance without any policies.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            policies: Vec::new(),
+        }
+    }
+
+    /// Adds an access policy.
+    #[inline]
+    pub fn add_policy(&mut self, policy: AccessPolicy) {
+        self.policies.push(policy);
+    }
+
+    /// Returns the access policies.
+    #[inline]
+    pub fn policies(&self) -> &[AccessPolicy] {
+        &self.policies
+    }
+
+    /// Evaluates the policies for the user session, the action and the resource path.
+    pub fn evaluate<U, T: ToString>(
+        &self,
+        session: &UserSession<U, String, T>,
+        action: &str,
+        resource: &str,
+    ) -> PolicyEffect {
+        let now = DateTime::now();
+        let mut effect = PolicyEffect::Deny;
+        for policy in self.policies.iter() {
+            if policy.applies_to(session, action, resource, now) {
+                match policy.effect {
+                    PolicyEffect::Allow => effect = PolicyEffect::Allow,
+                    PolicyEffect::Deny => return PolicyEffect::Deny,
+                }
+            }
+        }
+        effect
+    }
+
+    /// Returns `true` if the access is allowed for the user session.
+    #[inline]
+    pub fn is_allowed<U, T: ToString>(
+        &self,
+        session: &UserSession<U, String, T>,
+        action: &str,
+        resource: &str,
+    ) -> bool {
+        self.evaluate(session, action, resource).is_allowed()
+    }
+
+    /// Sets `self` as the shared policy engine. It replaces the previous one,
+    /// so the policies can be reloaded at runtime.
+    #[inline]
+    pub fn set_shared(self) {
+        if let Ok(mut engine) = SHARED_POLICY_ENGINE.write() {
+            *engine = Arc::new(self);
+        }
+    }
+
+    /// Returns the shared policy engine.
+    #[inline]
+    pub fn shared() -> Arc<Self> {
+        SHARED_POLICY_ENGINE
+            .read()
+            .map(|engine| engine.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessPolicy, PolicyEffect, PolicyEngine};
+    use crate::{auth::UserSession, datetime::DateTime};
+    use std::time::Duration;
+
+    #[test]
+    fn it_evaluates_policies() {
+        let mut engine = PolicyEngine::new();
+        let mut policy = AccessPolicy::new("read-users", "/user/**", PolicyEffect::Allow);
+        policy.set_actions(vec!["GET".to_owned()]);
+        policy.set_roles(vec!["admin".to_owned()]);
+        engine.add_policy(policy);
+
+        let mut policy = AccessPolicy::new("edit-users", "/user/*/update", PolicyEffect::Allow);
+        policy.set_actions(vec!["*".to_owned()]);
+        policy.set_roles(vec!["admin".to_owned()]);
+        policy.set_tenant_id("1");
+        engine.add_policy(policy);
+
+        let mut policy = AccessPolicy::new("no-secrets", "/user/secret", PolicyEffect::Deny);
+        policy.set_actions(vec!["*".to_owned()]);
+        engine.add_policy(policy);
+
+        let mut policy = AccessPolicy::new("expired", "/**", PolicyEffect::Allow);
+        policy.set_actions(vec!["*".to_owned()]);
+        let now = DateTime::now();
+        policy.set_time_window(Some(now - Duration::from_secs(60)), Some(now));
+        engine.add_policy(policy);
+
+        let mut session = UserSession::<i64>::new(1, None);
+        session.set_roles(vec!["admin:user".to_owned()]);
+        assert!(engine.is_allowed(&session, "get", "/user/list"));
+        assert!(engine.is_allowed(&session, "GET", "/user"));
+        assert!(!engine.is_allowed(&session, "POST", "/user/list"));
+        assert!(!engine.is_allowed(&session, "GET", "/user/secret"));
+        assert!(!engine.is_allowed(&session, "GET", "/user//secret/"));
+        assert!(!engine.is_allowed(&session, "GET", "//user/secret"));
+        assert!(!engine.is_allowed(&session, "POST", "/user/1/update"));
+        assert!(!engine.is_allowed(&session, "GET", "/tag/list"));
+
+        session.set_tenant_id(1);
+        assert!(engine.is_allowed(&session, "POST", "/user/1/update"));
+        assert!(!engine.is_allowed(&session, "POST", "/user/1/delete"));
+
+        session.set_roles(vec!["worker".to_owned()]);
+        assert!(!engine.is_allowed(&session, "GET", "/user/list"));
+    }
+}
@@ -0,0 +1,625 @@
+use super::{default_verification_options, JwtClaims, JwtKeySet, JwtVerifier, TokenFamily};
+use crate::{
+    application, bail, crypto,
+    datetime::DateTime,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    warn, JsonValue, Map,
+};
+use sha2::{Digest, Sha256};
+use std::{sync::LazyLock, time::Duration};
+use url::Url;
+
+/// A client registered in the authorization server.
+#[derive(Debug, Clone, Default)]
+pub struct ClientRegistration {
+    /// Client ID.
+    client_id: String,
+    /// Hash of the client secret. It is `None` for a public client.
+    client_secret_hash: Option<String>,
+    /// Redirect URIs.
+    redirect_uris: Vec<String>,
+    /// Grant types.
+    grant_types: Vec<String>,
+    /// Scopes.
+    scopes: Vec<String>,
+}
+
+impl ClientRegistration {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(client_id: impl ToString) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Generates a random client secret. Only the hash of the secret should be stored,
+    /// so the secret can not be recovered once it has been shown to the client.
+    #[inline]
+    pub fn generate_client_secret() -> String {
+        base64::encode_url_safe(rand::random::<[u8; 32]>())
+    }
+
+    /// Hashes the client secret with the SHA-256 digest.
+    #[inline]
+    pub fn hash_client_secret(client_secret: &str) -> String {
+        base64::encode_url_safe(Sha256::digest(client_secret.as_bytes()))
+    }
+
+    /// Sets the hash of the client secret.
+    /// See [`ClientRegistration::hash_client_secret()`] for the hash function.
+    #[inline]
+    pub fn set_client_secret_hash(&mut self, client_secret_hash: impl ToString) {
+        self.client_secret_hash = Some(client_secret_hash.to_string());
+    }
+
+    /// Sets the redirect URIs.
+    #[inline]
+    pub fn set_redirect_uris(&mut self, redirect_uris: Vec<String>) {
+        self.redirect_uris = redirect_uris;
+    }
+
+    /// Sets the grant types.
+    /// Supported grant types: `authorization_code`, `client_credentials` and `refresh_token`.
+    #[inline]
+    pub fn set_grant_types(&mut self, grant_types: Vec<String>) {
+        self.grant_types = grant_types;
+    }
+
+    /// Sets the scopes.
+    #[inline]
+    pub fn set_scopes(&mut self, scopes: Vec<String>) {
+        self.scopes = scopes;
+    }
+
+    /// Returns the client ID.
+    #[inline]
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Returns `true` if the client is a confidential client.
+    #[inline]
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret_hash.is_some()
+    }
+
+    /// Returns `true` if the client supports the grant type.
+    #[inline]
+    pub fn supports_grant_type(&self, grant_type: &str) -> bool {
+        self.grant_types.iter().any(|s| s == grant_type)
+    }
+
+    /// Verifies the hash of the client secret in constant time.
+    fn verify_secret(&self, client_secret: Option<&str>) -> bool {
+        match (self.client_secret_hash.as_deref(), client_secret) {
+            (Some(expected), Some(actual)) => {
+                let actual = Self::hash_client_secret(actual);
+                let (expected, actual) = (expected.as_bytes(), actual.as_bytes());
+                expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual)
+                        .fold(0, |acc, (a, b)| acc | (a ^ b))
+                        == 0
+            }
+            (None, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Resolves the requested scope. It defaults to all the scopes of the client.
+    fn resolve_scope(&self, scope: Option<&str>) -> Result<String, Error> {
+        let Some(scope) = scope.filter(|s| !s.is_empty()) else {
+            return Ok(self.scopes.join(" "));
+        };
+        for s in scope.split_whitespace() {
+            if !self.scopes.iter().any(|scope| scope == s) {
+                bail!("invalid_scope: the scope `{}` is not allowed", s);
+            }
+        }
+        Ok(scope.to_owned())
+    }
+
+    /// Resolves the redirect URI. It can be omitted if there is only one redirect URI.
+    fn resolve_redirect_uri<'a>(&'a self, redirect_uri: Option<&'a str>) -> Result<&'a str, Error> {
+        match redirect_uri {
+            Some(redirect_uri) if self.redirect_uris.iter().any(|s| s == redirect_uri) => {
+                Ok(redirect_uri)
+            }
+            None if self.redirect_uris.len() == 1 => Ok(&self.redirect_uris[0]),
+            _ => bail!("invalid_request: the `redirect_uri` is not registered"),
+        }
+    }
+}
+
+/// An OAuth2 authorization server which issues [`JwtClaims`] tokens.
+///
+/// It supports the authorization-code grant with PKCE, the client-credentials grant
+/// and the refresh-token grant, together with the token introspection and revocation.
+/// The errors are formatted as `{error}: {error_description}`,
+/// where the `error` is the error code defined in RFC 6749.
+///
+/// Each authorization grant is a [`TokenFamily`] in the shared token store,
+/// so the authorization code and the refresh tokens can only be used once.
+pub trait AuthorizationServer {
+    /// Finds the registered client by the client ID.
+    async fn find_client(client_id: &str) -> Result<Option<ClientRegistration>, Error>;
+
+    /// Authenticates the client with the `client_id` and `client_secret` parameters.
+    async fn authenticate_client(params: &Map) -> Result<ClientRegistration, Error> {
+        let Some(client_id) = params.get_str("client_id") else {
+            bail!("invalid_client: the `client_id` should be specified");
+        };
+        let Some(client) = Self::find_client(client_id).await? else {
+            bail!("invalid_client: the client `{}` does not exist", client_id);
+        };
+        if !client.verify_secret(params.get_str("client_secret")) {
+            bail!("invalid_client: the client authentication failed");
+        }
+        Ok(client)
+    }
+
+    /// Issues an authorization code for the subject who has approved the authorization request.
+    /// It returns the `code`, the `state` and the `redirect_uri` with them as the query.
+    async fn authorize(subject: &str, params: &Map) -> Result<Map, Error> {
+        if params.get_str("response_type") != Some("code") {
+            bail!("unsupported_response_type: the `response_type` should be `code`");
+        }
+
+        let Some(client_id) = params.get_str("client_id") else {
+            bail!("invalid_request: the `client_id` should be specified");
+        };
+        let Some(client) = Self::find_client(client_id).await? else {
+            bail!("invalid_request: the client `{}` does not exist", client_id);
+        };
+        if !client.supports_grant_type("authorization_code") {
+            bail!("unauthorized_client: the `authorization_code` grant is not allowed");
+        }
+
+        let redirect_uri = client.resolve_redirect_uri(params.get_str("redirect_uri"))?;
+        let scope = client.resolve_scope(params.get_str("scope"))?;
+        let Some(code_challenge) = params.get_str("code_challenge") else {
+            bail!("invalid_request: the `code_challenge` should be specified");
+        };
+        let code_challenge_method = params.get_str("code_challenge_method").unwrap_or("S256");
+        if code_challenge_method != "S256"
+            && !(code_challenge_method == "plain" && *PLAIN_CODE_CHALLENGE_ALLOWED)
+        {
+            bail!(
+                "invalid_request: the `code_challenge_method` `{}` is unsupported",
+                code_challenge_method
+            );
+        }
+
+        let family = TokenFamily::new(subject);
+        let mut payload = Map::from_entry("jti", family.token_id());
+        payload.upsert("sub", subject);
+        payload.upsert("client_id", client_id);
+        // The `redirect_uri` is required in the token request if it was included
+        // in the authorization request.
+        payload.upsert("redirect_uri", params.get_str("redirect_uri"));
+        payload.upsert("scope", scope);
+        payload.upsert("code_challenge", code_challenge);
+        payload.upsert("code_challenge_method", code_challenge_method);
+        payload.upsert("exp", (DateTime::now() + *CODE_MAX_AGE).timestamp());
+
+        let code = crypto::encrypt(JsonValue::from(payload).to_string().as_bytes(), &*CODE_KEY)
+            .map(base64::encode_url_safe)?;
+        let mut url = Url::parse(redirect_uri)?;
+        url.query_pairs_mut().append_pair("code", &code);
+
+        let mut data = Map::from_entry("code", code);
+        if let Some(state) = params.get_str("state") {
+            url.query_pairs_mut().append_pair("state", state);
+            data.upsert("state", state);
+        }
+        data.upsert("redirect_uri", url.as_str());
+        JwtClaims::token_store().create(family).await?;
+        Ok(data)
+    }
+
+    /// Grants the tokens for the token request.
+    async fn grant_token(params: &Map) -> Result<Map, Error> {
+        let client = Self::authenticate_client(params).await?;
+        let grant_type = params.get_str("grant_type").unwrap_or_default();
+        if !client.supports_grant_type(grant_type) {
+            bail!(
+                "unauthorized_client: the `{}` grant is not allowed",
+                grant_type
+            );
+        }
+
+        let token_store = JwtClaims::token_store();
+        match grant_type {
+            "authorization_code" => {
+                let Some(code) = params.get_str("code") else {
+                    bail!("invalid_request: the `code` should be specified");
+                };
+                let payload = decrypt_authorization_code(code)?;
+                if payload.get_str("client_id") != Some(client.client_id()) {
+                    bail!("invalid_grant: the authorization code was issued to another client");
+                }
+                if params.get_str("redirect_uri") != payload.get_str("redirect_uri") {
+                    bail!("invalid_grant: the `redirect_uri` does not match");
+                }
+                if payload
+                    .get_i64("exp")
+                    .is_some_and(|exp| exp < DateTime::current_timestamp())
+                {
+                    bail!("invalid_grant: the authorization code has expired");
+                }
+
+                let code_verifier = params
+                    .get_str("code_verifier")
+                    .ok_or_else(|| warn!("invalid_grant: the `code_verifier` is absent"))?;
+                let code_challenge = if payload.get_str("code_challenge_method") == Some("S256") {
+                    base64::encode_url_safe(Sha256::digest(code_verifier.as_bytes()))
+                } else {
+                    code_verifier.to_owned()
+                };
+                if payload.get_str("code_challenge") != Some(code_challenge.as_str()) {
+                    bail!("invalid_grant: the `code_verifier` is invalid");
+                }
+
+                let (subject, token_id) = payload
+                    .get_str("sub")
+                    .zip(payload.get_str("jti"))
+                    .ok_or_else(|| warn!("invalid_grant: the authorization code is invalid"))?;
+                let Some((family_id, generation)) = TokenFamily::parse_token_id(token_id) else {
+                    bail!("invalid_grant: the authorization code is invalid");
+                };
+                if !token_store.rotate(family_id, generation).await? {
+                    // Revokes the tokens issued from the authorization code.
+                    token_store.revoke(family_id).await?;
+                    bail!("invalid_grant: the authorization code has been used or revoked");
+                }
+
+                let scope = payload.get_str("scope").unwrap_or_default();
+                let mut claims = JwtClaims::new(subject);
+                claims.set_audience(client.client_id());
+                claims.add_data_entry("client_id", client.client_id());
+                claims.add_data_entry("scope", scope);
+                claims.add_data_entry("sid", family_id);
+
+                let mut data = token_response(&claims, scope);
+                if client.supports_grant_type("refresh_token") {
+                    let token_id = format!("{family_id}:{}", generation + 1);
+                    data.upsert("refresh_token", claims.refresh_token_with_id(token_id)?);
+                }
+                data.upsert("access_token", claims.access_token()?);
+                Ok(data)
+            }
+            "client_credentials" => {
+                let scope = client.resolve_scope(params.get_str("scope"))?;
+                let mut claims = JwtClaims::new(client.client_id());
+                let mut family = TokenFamily::new(client.client_id());
+                family.set_expires_at(claims.expires_at());
+                claims.set_audience(client.client_id());
+                claims.add_data_entry("client_id", client.client_id());
+                claims.add_data_entry("scope", scope.as_str());
+                claims.add_data_entry("sid", family.id());
+
+                let mut data = token_response(&claims, &scope);
+                data.upsert("access_token", claims.access_token()?);
+                token_store.create(family).await?;
+                Ok(data)
+            }
+            "refresh_token" => {
+                let Some(refresh_token) = params.get_str("refresh_token") else {
+                    bail!("invalid_request: the `refresh_token` should be specified");
+                };
+                let claims = JwtKeySet::shared()
+                    .verify_jwt::<Map>(refresh_token, default_verification_options())
+                    .map_err(|err| warn!("invalid_grant: {}", err.message()))?;
+                let Some((family_id, generation)) = claims
+                    .jwt_id()
+                    .filter(|_| claims.is_refresh_token())
+                    .and_then(TokenFamily::parse_token_id)
+                else {
+                    bail!("invalid_grant: the JWT token is not a refresh token");
+                };
+                let client_id = client.client_id();
+                if claims.audience() != Some(client_id)
+                    || claims.data().get_str("client_id") != Some(client_id)
+                {
+                    bail!("invalid_grant: the refresh token was issued to another client");
+                }
+                let Some(subject) = claims.subject() else {
+                    bail!("invalid_grant: the refresh token does not have a subject");
+                };
+
+                // The requested scope should not exceed the scope originally granted.
+                let granted_scope = claims.data().get_str("scope").unwrap_or_default();
+                let scope = match params.get_str("scope").filter(|s| !s.is_empty()) {
+                    Some(scope) => {
+                        for s in scope.split_whitespace() {
+                            if !granted_scope.split_whitespace().any(|scope| scope == s) {
+                                bail!("invalid_scope: the scope `{}` is not granted", s);
+                            }
+                        }
+                        client.resolve_scope(Some(scope))?
+                    }
+                    None => granted_scope.to_owned(),
+                };
+                if !token_store.rotate(family_id, generation).await? {
+                    token_store.revoke(family_id).await?;
+                    bail!("invalid_grant: the refresh token has been reused or revoked");
+                }
+
+                let mut claims = JwtClaims::new(subject);
+                claims.set_audience(client_id);
+                claims.add_data_entry("client_id", client_id);
+                claims.add_data_entry("scope", granted_scope);
+                claims.add_data_entry("sid", family_id);
+
+                // The new refresh token retains the original scope.
+                let token_id = format!("{family_id}:{}", generation + 1);
+                let refresh_token = claims.refresh_token_with_id(token_id)?;
+                claims.add_data_entry("scope", scope.as_str());
+
+                let mut data = token_response(&claims, &scope);
+                data.upsert("refresh_token", refresh_token);
+                data.upsert("access_token", claims.access_token()?);
+                Ok(data)
+            }
+            _ => bail!(
+                "unsupported_grant_type: the grant type `{}` is unsupported",
+                grant_type
+            ),
+        }
+    }
+
+    /// Introspects the token for an authenticated client. See RFC 7662.
+    async fn introspect_token(params: &Map) -> Result<Map, Error> {
+        Self::authenticate_client(params).await?;
+
+        let Some(token) = params.get_str("token") else {
+            bail!("invalid_request: the `token` should be specified");
+        };
+        let Ok(claims) =
+            JwtKeySet::shared().verify_jwt::<Map>(token, default_verification_options())
+        else {
+            return Ok(Map::from_entry("active", false));
+        };
+        if JwtClaims::has_token_store()
+            && let Some(family_id) = claims.token_family_id()
+            && !JwtClaims::token_store().is_active(family_id).await?
+        {
+            return Ok(Map::from_entry("active", false));
+        }
+
+        let data = claims.data();
+        let mut introspection = Map::from_entry("active", true);
+        if let Some(scope) = data.get_str("scope") {
+            introspection.upsert("scope", scope);
+        }
+        if let Some(client_id) = data.get_str("client_id") {
+            introspection.upsert("client_id", client_id);
+        }
+        if claims.is_refresh_token() {
+            introspection.upsert("token_type", "refresh_token");
+        } else {
+            introspection.upsert("token_type", "Bearer");
+        }
+        introspection.upsert("sub", claims.subject());
+        introspection.upsert("exp", claims.expires_at().timestamp());
+        introspection.upsert("iat", claims.issued_at().timestamp());
+        Ok(introspection)
+    }
+
+    /// Revokes the token for an authenticated client, together with all the tokens
+    /// issued from the same authorization grant. See RFC 7009.
+    async fn revoke_token(params: &Map) -> Result<(), Error> {
+        Self::authenticate_client(params).await?;
+
+        let Some(token) = params.get_str("token") else {
+            bail!("invalid_request: the `token` should be specified");
+        };
+        if let Ok(claims) =
+            JwtKeySet::shared().verify_jwt::<Map>(token, default_verification_options())
+            && let Some(family_id) = claims.token_family_id()
+        {
+            JwtClaims::token_store().revoke(family_id).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates the token response without the tokens.
+fn token_response(claims: &JwtClaims, scope: &str) -> Map {
+    let mut data = Map::from_entry("token_type", "Bearer");
+    data.upsert("expires_in", claims.expires_in().as_secs());
+    if !scope.is_empty() {
+        data.upsert("scope", scope);
+    }
+    data
+}
+
+/// Decrypts the authorization code as a payload.
+fn decrypt_authorization_code(code: &str) -> Result<Map, Error> {
+    let payload = base64::decode_url_safe(code)
+        .ok()
+        .and_then(|data| crypto::decrypt(&data, &*CODE_KEY).ok())
+        .and_then(|plaintext| serde_json::from_slice::<Map>(&plaintext).ok());
+    payload.ok_or_else(|| warn!("invalid_grant: the authorization code is invalid"))
+}
+
+/// Max age of the authorization code.
+static CODE_MAX_AGE: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("authorization-server")
+        .and_then(|config| config.get_duration("code-max-age"))
+        .unwrap_or_else(|| Duration::from_secs(60 * 10))
+});
+
+/// A flag to accept the `plain` code challenge method of PKCE.
+/// Only the `S256` method is accepted by default.
+static PLAIN_CODE_CHALLENGE_ALLOWED: LazyLock<bool> = LazyLock::new(|| {
+    State::shared()
+        .get_config("authorization-server")
+        .and_then(|config| config.get_bool("allow-plain-code-challenge"))
+        .unwrap_or_default()
+});
+
+/// Secret key for encrypting the authorization code.
+static CODE_KEY: LazyLock<[u8; 64]> = LazyLock::new(|| {
+    let config = State::shared().config();
+    let checksum: [u8; 32] = config
+        .get_table("authorization-server")
+        .and_then(|t| t.get_str("checksum"))
+        .and_then(|checksum| checksum.as_bytes().first_chunk().copied())
+        .or_else(|| application::SECRET_KEY.get().map(|key| crypto::digest(key)))
+        .unwrap_or_else(|| {
+            tracing::warn!("the `checksum` is not set properly for deriving a secret key");
+
+            let app_name = config.get_str("name").unwrap_or(env!("CARGO_PKG_NAME"));
+            crypto::digest(app_name.as_bytes())
+        });
+    crypto::derive_key("ZINO:AUTHORIZATION-CODE", &checksum)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthorizationServer, ClientRegistration};
+    use crate::{
+        auth::{JwtClaims, MemoryTokenStore, TokenFamily, UserSession},
+        encoding::base64,
+        error::Error,
+        extension::JsonObjectExt,
+        Map,
+    };
+    use futures::executor;
+    use sha2::{Digest, Sha256};
+    use std::time::Duration;
+
+    struct TestServer;
+
+    impl AuthorizationServer for TestServer {
+        async fn find_client(client_id: &str) -> Result<Option<ClientRegistration>, Error> {
+            let mut client = ClientRegistration::new(client_id);
+            client.set_client_secret_hash(ClientRegistration::hash_client_secret("secret"));
+            client.set_redirect_uris(vec!["https://example.com/callback".to_owned()]);
+            client.set_grant_types(vec![
+                "authorization_code".to_owned(),
+                "client_credentials".to_owned(),
+                "refresh_token".to_owned(),
+            ]);
+            client.set_scopes(vec!["read".to_owned(), "write".to_owned()]);
+            Ok(Some(client))
+        }
+    }
+
+    #[test]
+    fn it_grants_oauth2_tokens() {
+        executor::block_on(async {
+            JwtClaims::set_token_store(MemoryTokenStore::new());
+            assert!(JwtClaims::has_token_store());
+
+            let mut params = Map::from_entry("client_id", "app");
+            params.upsert("client_secret", "wrong");
+            params.upsert("grant_type", "client_credentials");
+            let err = TestServer::grant_token(&params).await.unwrap_err();
+            assert!(err.message().starts_with("invalid_client"));
+
+            params.upsert("client_secret", "secret");
+            params.upsert("scope", "read");
+            let data = TestServer::grant_token(&params).await.unwrap();
+            assert_eq!(data.get_str("scope"), Some("read"));
+
+            let mut introspection_params = params.clone();
+            introspection_params.upsert("token", data.get_str("access_token"));
+            let introspection = TestServer::introspect_token(&introspection_params)
+                .await
+                .unwrap();
+            assert_eq!(introspection.get_bool("active"), Some(true));
+            assert_eq!(introspection.get_str("sub"), Some("app"));
+
+            TestServer::revoke_token(&introspection_params)
+                .await
+                .unwrap();
+            let introspection = TestServer::introspect_token(&introspection_params)
+                .await
+                .unwrap();
+            assert_eq!(introspection.get_bool("active"), Some(false));
+
+            let code_verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+            let code_challenge = base64::encode_url_safe(Sha256::digest(code_verifier));
+            let mut params = Map::from_entry("client_id", "app");
+            params.upsert("response_type", "code");
+            params.upsert("state", "xyz");
+            params.upsert("redirect_uri", "https://example.com/callback");
+            params.upsert("code_challenge", code_challenge);
+            params.upsert("code_challenge_method", "S256");
+            params.upsert("code_challenge_method", "plain");
+            let err = TestServer::authorize("alice", &params).await.unwrap_err();
+            assert!(err.message().starts_with("invalid_request"));
+
+            params.upsert("code_challenge_method", "S256");
+            let data = TestServer::authorize("alice", &params).await.unwrap();
+            assert!(data
+                .get_str("redirect_uri")
+                .is_some_and(|s| s.starts_with("https://example.com/callback?code=")));
+
+            let mut params = Map::from_entry("client_id", "app");
+            params.upsert("client_secret", "secret");
+            params.upsert("grant_type", "authorization_code");
+            params.upsert("code", data.get_str("code"));
+            params.upsert("redirect_uri", "https://example.com/callback");
+            params.upsert("code_verifier", "invalid");
+            let err = TestServer::grant_token(&params).await.unwrap_err();
+            assert!(err.message().starts_with("invalid_grant"));
+
+            params.upsert("code_verifier", code_verifier);
+            let data = TestServer::grant_token(&params).await.unwrap();
+            assert!(data.contains_key("access_token") && data.contains_key("refresh_token"));
+
+            let err = TestServer::grant_token(&params).await.unwrap_err();
+            assert!(err.message().starts_with("invalid_grant"));
+
+            let mut introspection_params = params.clone();
+            introspection_params.upsert("token", data.get_str("access_token"));
+            let introspection = TestServer::introspect_token(&introspection_params)
+                .await
+                .unwrap();
+            assert_eq!(introspection.get_bool("active"), Some(false));
+        });
+    }
+
+    #[test]
+    fn it_binds_refresh_tokens_to_clients() {
+        executor::block_on(async {
+            JwtClaims::set_token_store(MemoryTokenStore::new());
+            let family = TokenFamily::new("alice");
+            let mut claims = JwtClaims::with_max_age("alice", Duration::ZERO);
+            claims.set_audience("app");
+            claims.add_data_entry("client_id", "app");
+            claims.add_data_entry("scope", "read");
+            claims.add_data_entry("sid", family.id());
+            let refresh_token = claims.refresh_token_with_id(family.token_id()).unwrap();
+            JwtClaims::token_store().create(family).await.unwrap();
+            assert!(UserSession::<String>::try_from_jwt_claims(claims).is_err());
+
+            let mut params = Map::from_entry("client_id", "other");
+            params.upsert("client_secret", "secret");
+            params.upsert("grant_type", "refresh_token");
+            params.upsert("refresh_token", refresh_token);
+            let err = TestServer::grant_token(&params).await.unwrap_err();
+            assert!(err.message().starts_with("invalid_grant"));
+
+            params.upsert("client_id", "app");
+            params.upsert("scope", "read write");
+            let err = TestServer::grant_token(&params).await.unwrap_err();
+            assert!(err.message().starts_with("invalid_scope"));
+
+            params.upsert("scope", "read");
+            let data = TestServer::grant_token(&params).await.unwrap();
+            assert_eq!(data.get_str("scope"), Some("read"));
+            assert!(data.contains_key("refresh_token"));
+        });
+    }
+}
@@ -1,3 +1,4 @@
+use super::{JwtKey, JwtKeySet, MemoryTokenStore, TokenFamily, TokenStore};
 use crate::{
     crypto,
     datetime::DateTime,
@@ -8,11 +9,18 @@ use crate::{
 };
 use jwt_simple::{
     algorithms::MACLike,
-    claims::{self, Claims, JWTClaims},
+    claims::{self, Audiences, Claims, JWTClaims},
     common::VerificationOptions,
 };
 use serde::{de::DeserializeOwned, Serialize};
-use std::{env, sync::LazyLock, time::Duration};
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        LazyLock, OnceLock,
+    },
+    time::Duration,
+};
 
 /// JWT Claims.
 #[derive(Debug, Clone)]
@@ -35,23 +43,48 @@ impl<T: Default + Serialize + DeserializeOwned> JwtClaims<T> {
         Self(claims)
     }
 
-    /// Generates an access token signed with the shared secret access key.
+    /// Generates a refresh token signed with the signing key of the shared key set.
+    #[inline]
     pub fn refresh_token(&self) -> Result<String, Error> {
-        let mut claims = Claims::create((*DEFAULT_REFRESH_INTERVAL).into());
+        JwtKeySet::shared().sign(self.refresh_claims())
+    }
+
+    /// Generates a refresh token with the token ID as the `jti` claim.
+    /// See [`TokenFamily::token_id`](super::TokenFamily::token_id).
+    #[inline]
+    pub fn refresh_token_with_id(&self, token_id: impl ToString) -> Result<String, Error> {
+        let mut claims = self.refresh_claims();
+        claims.jwt_id = Some(token_id.to_string());
+        JwtKeySet::shared().sign(claims)
+    }
+
+    /// Creates the claims for a refresh token, which is valid after the access token expires.
+    /// The audience, the `client_id` and the `scope` of the access token are retained
+    /// so that the refresh token can only be used by the same client.
+    fn refresh_claims(&self) -> JWTClaims<Map> {
+        let mut data = Map::from_entry("typ", "refresh");
+        if let Ok(JsonValue::Object(custom)) = serde_json::to_value(&self.0.custom) {
+            for key in ["client_id", "scope"] {
+                if let Some(value) = custom.get(key) {
+                    data.upsert(key, value.clone());
+                }
+            }
+        }
+
+        let mut claims = Claims::with_custom_claims(data, (*DEFAULT_REFRESH_INTERVAL).into());
         claims.invalid_before = self
             .0
             .expires_at
             .map(|max_age| max_age - (*DEFAULT_TIME_TOLERANCE).into());
         claims.subject = self.0.subject.as_ref().cloned();
-        JwtClaims::shared_key()
-            .authenticate(claims)
-            .map_err(|err| Error::new(err.to_string()))
+        claims.audiences = self.0.audiences.clone();
+        claims
     }
 
-    /// Generates an access token signed with the shared secret access key.
+    /// Generates an access token signed with the signing key of the shared key set.
     #[inline]
     pub fn access_token(self) -> Result<String, Error> {
-        self.sign_with(JwtClaims::shared_key())
+        JwtKeySet::shared().sign(self.0)
     }
 
     /// Generates a signature with the secret access key.
@@ -60,6 +93,12 @@ impl<T: Default + Serialize + DeserializeOwned> JwtClaims<T> {
         key.authenticate(self.0)
             .map_err(|err| Error::new(err.to_string()))
     }
+
+    /// Generates a signature with the JWT key.
+    #[inline]
+    pub fn sign_with_key(self, key: &JwtKey) -> Result<String, Error> {
+        key.sign(self.0)
+    }
 }
 
 impl<T> JwtClaims<T> {
@@ -69,6 +108,19 @@ impl<T> JwtClaims<T> {
         self.0.nonce = Some(nonce.to_string());
     }
 
+    /// Sets the JWT ID.
+    #[inline]
+    pub fn set_jwt_id(&mut self, jwt_id: impl ToString) {
+        self.0.jwt_id = Some(jwt_id.to_string());
+    }
+
+    /// Sets the audience. The tokens issued to an OAuth client have the `client_id`
+    /// as the audience, while the first-party tokens do not have an audience.
+    #[inline]
+    pub fn set_audience(&mut self, audience: impl ToString) {
+        self.0.audiences = Some(Audiences::AsString(audience.to_string()));
+    }
+
     /// Returns the time the claims were created at.
     #[inline]
     pub fn issued_at(&self) -> DateTime {
@@ -108,12 +160,36 @@ impl<T> JwtClaims<T> {
         self.0.subject.as_deref()
     }
 
+    /// Returns the JWT ID.
+    #[inline]
+    pub fn jwt_id(&self) -> Option<&str> {
+        self.0.jwt_id.as_deref()
+    }
+
     /// Returns the nonce.
     #[inline]
     pub fn nonce(&self) -> Option<&str> {
         self.0.nonce.as_deref()
     }
 
+    /// Returns the audience if it is a single string.
+    #[inline]
+    pub fn audience(&self) -> Option<&str> {
+        match self.0.audiences.as_ref()? {
+            Audiences::AsString(audience) => Some(audience.as_str()),
+            Audiences::AsSet(audiences) if audiences.len() == 1 => {
+                audiences.iter().next().map(|s| s.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the claims have an audience, i.e. issued to an OAuth client.
+    #[inline]
+    pub fn has_audience(&self) -> bool {
+        self.0.audiences.is_some()
+    }
+
     /// Returns the custom data.
     #[inline]
     pub fn data(&self) -> &T {
@@ -127,6 +203,22 @@ impl JwtClaims<Map> {
     pub fn add_data_entry(&mut self, key: impl Into<String>, value: impl Into<JsonValue>) {
         self.0.custom.upsert(key.into(), value.into());
     }
+
+    /// Returns `true` if the claims are for a refresh token.
+    #[inline]
+    pub fn is_refresh_token(&self) -> bool {
+        self.0.custom.get_str("typ") == Some("refresh")
+    }
+
+    /// Returns the token family ID parsed from the `sid` of an access token
+    /// or the `jti` of a refresh token.
+    pub fn token_family_id(&self) -> Option<&str> {
+        self.0.custom.get_str("sid").or_else(|| {
+            self.jwt_id()
+                .and_then(TokenFamily::parse_token_id)
+                .map(|(family_id, _)| family_id)
+        })
+    }
 }
 
 impl JwtClaims<()> {
@@ -135,6 +227,37 @@ impl JwtClaims<()> {
     pub fn shared_key() -> &'static JwtHmacKey {
         LazyLock::force(&SECRET_KEY)
     }
+
+    /// Sets the shared token store for the refresh-token rotation and revocation.
+    /// It returns `false` if the token store has been set or used.
+    ///
+    /// Multi-instance deployments should use a store shared by all the instances,
+    /// such as an ORM-backed one, since the token families are not synchronized.
+    #[inline]
+    pub fn set_token_store(store: impl TokenStore) -> bool {
+        let success = SHARED_TOKEN_STORE.set(Box::new(store)).is_ok();
+        if success {
+            TOKEN_STORE_CONFIGURED.store(true, Relaxed);
+        }
+        success
+    }
+
+    /// Returns `true` if the shared token store has been set explicitly.
+    /// The revocation of access tokens is only enforced in this case.
+    #[inline]
+    pub fn has_token_store() -> bool {
+        TOKEN_STORE_CONFIGURED.load(Relaxed)
+    }
+
+    /// Returns the shared token store.
+    /// It defaults to a [`MemoryTokenStore`] if no token store has been set,
+    /// which does not survive restarts and is not shared between instances.
+    #[inline]
+    pub fn token_store() -> &'static dyn TokenStore {
+        SHARED_TOKEN_STORE
+            .get_or_init(|| Box::new(MemoryTokenStore::new()))
+            .as_ref()
+    }
 }
 
 /// Returns the default time tolerance.
@@ -143,6 +266,12 @@ pub(crate) fn default_time_tolerance() -> Duration {
     *DEFAULT_TIME_TOLERANCE
 }
 
+/// Returns the default refresh interval.
+#[inline]
+pub(crate) fn default_refresh_interval() -> Duration {
+    *DEFAULT_REFRESH_INTERVAL
+}
+
 /// Returns the default verfication options.
 #[inline]
 pub(crate) fn default_verification_options() -> VerificationOptions {
@@ -190,6 +319,12 @@ static DEFAULT_REFRESH_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
         .unwrap_or_else(|| Duration::from_secs(60 * 60 * 24 * 30))
 });
 
+/// Shared token store.
+static SHARED_TOKEN_STORE: OnceLock<Box<dyn TokenStore>> = OnceLock::new();
+
+/// Indicates whether the shared token store has been set explicitly.
+static TOKEN_STORE_CONFIGURED: AtomicBool = AtomicBool::new(false);
+
 /// Shared secret access key for the HMAC algorithm.
 static SECRET_KEY: LazyLock<JwtHmacKey> = LazyLock::new(|| {
     let config = State::shared().config();
@@ -0,0 +1,542 @@
+use super::{JwtClaims, JwtHmacKey};
+use crate::{
+    application::PROJECT_DIR,
+    bail,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    warn, Map,
+};
+use jwt_simple::{
+    algorithms::{
+        ECDSAP256KeyPairLike, ECDSAP256PublicKeyLike, ES256KeyPair, ES256PublicKey, Ed25519KeyPair,
+        Ed25519PublicKey, EdDSAKeyPairLike, EdDSAPublicKeyLike, MACLike, RS256KeyPair,
+        RS256PublicKey, RSAKeyPairLike, RSAPublicKeyLike,
+    },
+    claims::JWTClaims,
+    common::VerificationOptions,
+    token::Token,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, fs, sync::LazyLock};
+use toml::Table;
+
+/// Shared key set for JWT.
+static SHARED_KEY_SET: LazyLock<JwtKeySet> = LazyLock::new(|| {
+    let config = State::shared().get_config("jwt");
+    load_key_set(config, JwtClaims::shared_key())
+});
+
+/// Loads the key set from the `[jwt]` configuration. The HMAC key is used
+/// if no keys are configured, and it is kept as a verification key
+/// only when `hmac-verification` is enabled explicitly.
+fn load_key_set(config: Option<&Table>, hmac_key: &JwtHmacKey) -> JwtKeySet {
+    let mut key_set = JwtKeySet::new();
+    if let Some(config) = config
+        && let Some(keys) = config.get_array("keys")
+    {
+        for config in keys.iter().filter_map(|v| v.as_table()) {
+            match JwtKey::try_from_config(config) {
+                Ok(key) => key_set.add_key(key),
+                Err(err) => tracing::error!("fail to load the JWT key: {err}"),
+            }
+        }
+        if let Some(key_id) = config.get_str("signing-key-id") {
+            if let Err(err) = key_set.set_signing_key_id(key_id) {
+                tracing::error!("fail to set the JWT signing key: {err}");
+            }
+        }
+        if !key_set.keys.is_empty() && config.get_bool("hmac-verification") == Some(true) {
+            // Keeps the outstanding tokens signed with the HMAC key valid.
+            key_set.add_verification_key(JwtKey::from_hmac_key(hmac_key.clone()));
+        }
+    }
+    if key_set.keys.is_empty() {
+        key_set.add_key(JwtKey::from_hmac_key(hmac_key.clone()));
+    }
+    key_set
+}
+
+/// Kinds of the JWT keys.
+enum JwtKeyKind {
+    /// HMAC key.
+    Hmac(JwtHmacKey),
+    /// RSASSA-PKCS1-v1_5 using SHA-256.
+    Rs256(Option<Box<RS256KeyPair>>, RS256PublicKey),
+    /// ECDSA using P-256 and SHA-256.
+    Es256(Option<ES256KeyPair>, ES256PublicKey),
+    /// EdDSA using Ed25519.
+    EdDsa(Option<Ed25519KeyPair>, Ed25519PublicKey),
+}
+
+/// A key for signing or verifying JWT tokens.
+///
+/// The asymmetric keys can be a key pair or a public key only,
+/// where the latter can only be used for the verification.
+pub struct JwtKey {
+    /// Key ID.
+    key_id: Option<String>,
+    /// Key kind.
+    kind: JwtKeyKind,
+}
+
+impl JwtKey {
+    /// Creates a new instance with the HMAC key.
+    #[inline]
+    pub fn from_hmac_key(key: JwtHmacKey) -> Self {
+        Self {
+            key_id: key.key_id().clone(),
+            kind: JwtKeyKind::Hmac(key),
+        }
+    }
+
+    /// Creates a new instance with the PEM-encoded private key for the algorithm.
+    /// Supported algorithms: `RS256`, `ES256` and `EdDSA`.
+    pub fn from_private_pem(algorithm: &str, pem: &str) -> Result<Self, Error> {
+        let kind = match algorithm {
+            "RS256" => {
+                let key_pair =
+                    RS256KeyPair::from_pem(pem).map_err(|err| Error::new(err.to_string()))?;
+                let public_key = key_pair.public_key();
+                JwtKeyKind::Rs256(Some(Box::new(key_pair)), public_key)
+            }
+            "ES256" => {
+                let key_pair =
+                    ES256KeyPair::from_pem(pem).map_err(|err| Error::new(err.to_string()))?;
+                let public_key = key_pair.public_key();
+                JwtKeyKind::Es256(Some(key_pair), public_key)
+            }
+            "EdDSA" => {
+                let key_pair =
+                    Ed25519KeyPair::from_pem(pem).map_err(|err| Error::new(err.to_string()))?;
+                let public_key = key_pair.public_key();
+                JwtKeyKind::EdDsa(Some(key_pair), public_key)
+            }
+            _ => bail!("the JWT algorithm `{}` is unsupported", algorithm),
+        };
+        Ok(Self { key_id: None, kind })
+    }
+
+    /// Creates a new instance with the PEM-encoded public key for the algorithm.
+    /// Supported algorithms: `RS256`, `ES256` and `EdDSA`.
+    pub fn from_public_pem(algorithm: &str, pem: &str) -> Result<Self, Error> {
+        let kind = match algorithm {
+            "RS256" => JwtKeyKind::Rs256(
+                None,
+                RS256PublicKey::from_pem(pem).map_err(|err| Error::new(err.to_string()))?,
+            ),
+            "ES256" => JwtKeyKind::Es256(
+                None,
+                ES256PublicKey::from_pem(pem).map_err(|err| Error::new(err.to_string()))?,
+            ),
+            "EdDSA" => JwtKeyKind::EdDsa(
+                None,
+                Ed25519PublicKey::from_pem(pem).map_err(|err| Error::new(err.to_string()))?,
+            ),
+            _ => bail!("the JWT algorithm `{}` is unsupported", algorithm),
+        };
+        Ok(Self { key_id: None, kind })
+    }
+
+    /// Attempts to load the key from a config table with the fields
+    /// `key-id`, `algorithm`, and one of `private-key` and `public-key`
+    /// which are the paths of PEM files relative to the project directory.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let Some(algorithm) = config.get_str("algorithm") else {
+            bail!("the `algorithm` should be specified");
+        };
+        let key = if let Some(path) = config.get_str("private-key") {
+            let pem = fs::read_to_string(PROJECT_DIR.join(path))?;
+            Self::from_private_pem(algorithm, &pem)?
+        } else if let Some(path) = config.get_str("public-key") {
+            let pem = fs::read_to_string(PROJECT_DIR.join(path))?;
+            Self::from_public_pem(algorithm, &pem)?
+        } else {
+            bail!("the `private-key` or `public-key` should be specified");
+        };
+        if let Some(key_id) = config.get_str("key-id") {
+            Ok(key.with_key_id(key_id))
+        } else {
+            Ok(key)
+        }
+    }
+
+    /// Sets the key ID which is used as the `kid` in the JWT header.
+    pub fn with_key_id(mut self, key_id: &str) -> Self {
+        self.kind = match self.kind {
+            JwtKeyKind::Hmac(key) => JwtKeyKind::Hmac(key.with_key_id(key_id)),
+            JwtKeyKind::Rs256(key_pair, public_key) => JwtKeyKind::Rs256(
+                key_pair.map(|key_pair| Box::new(key_pair.with_key_id(key_id))),
+                public_key.with_key_id(key_id),
+            ),
+            JwtKeyKind::Es256(key_pair, public_key) => JwtKeyKind::Es256(
+                key_pair.map(|key_pair| key_pair.with_key_id(key_id)),
+                public_key.with_key_id(key_id),
+            ),
+            JwtKeyKind::EdDsa(key_pair, public_key) => JwtKeyKind::EdDsa(
+                key_pair.map(|key_pair| key_pair.with_key_id(key_id)),
+                public_key.with_key_id(key_id),
+            ),
+        };
+        self.key_id = Some(key_id.to_owned());
+        self
+    }
+
+    /// Returns the key ID.
+    #[inline]
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the JWT algorithm name.
+    #[inline]
+    pub fn algorithm(&self) -> &'static str {
+        match self.kind {
+            JwtKeyKind::Hmac(_) => JwtHmacKey::jwt_alg_name(),
+            JwtKeyKind::Rs256(..) => "RS256",
+            JwtKeyKind::Es256(..) => "ES256",
+            JwtKeyKind::EdDsa(..) => "EdDSA",
+        }
+    }
+
+    /// Returns `true` if the key can be used for signing.
+    #[inline]
+    pub fn can_sign(&self) -> bool {
+        match &self.kind {
+            JwtKeyKind::Hmac(_) => true,
+            JwtKeyKind::Rs256(key_pair, _) => key_pair.is_some(),
+            JwtKeyKind::Es256(key_pair, _) => key_pair.is_some(),
+            JwtKeyKind::EdDsa(key_pair, _) => key_pair.is_some(),
+        }
+    }
+
+    /// Signs the claims and returns a JWT token.
+    pub fn sign<T: Serialize + DeserializeOwned>(
+        &self,
+        claims: JWTClaims<T>,
+    ) -> Result<String, Error> {
+        let result = match &self.kind {
+            JwtKeyKind::Hmac(key) => key.authenticate(claims),
+            JwtKeyKind::Rs256(Some(key_pair), _) => key_pair.sign(claims),
+            JwtKeyKind::Es256(Some(key_pair), _) => key_pair.sign(claims),
+            JwtKeyKind::EdDsa(Some(key_pair), _) => key_pair.sign(claims),
+            _ => bail!(
+                "the JWT key `{}` can not be used for signing",
+                self.key_id().unwrap_or_default()
+            ),
+        };
+        result.map_err(|err| Error::new(err.to_string()))
+    }
+
+    /// Returns the public key as a JSON Web Key.
+    /// It returns `None` for the HMAC keys.
+    pub fn to_jwk(&self) -> Option<Map> {
+        let mut jwk = match &self.kind {
+            JwtKeyKind::Hmac(_) => return None,
+            JwtKeyKind::Rs256(_, public_key) => {
+                let components = public_key.to_components();
+                let mut jwk = Map::from_entry("kty", "RSA");
+                jwk.upsert("n", base64::encode_url_safe(components.n));
+                jwk.upsert("e", base64::encode_url_safe(components.e));
+                jwk
+            }
+            JwtKeyKind::Es256(_, public_key) => {
+                let bytes = public_key.public_key().to_bytes_uncompressed();
+                let (x, y) = bytes.get(1..)?.split_at(32);
+                let mut jwk = Map::from_entry("kty", "EC");
+                jwk.upsert("crv", "P-256");
+                jwk.upsert("x", base64::encode_url_safe(x));
+                jwk.upsert("y", base64::encode_url_safe(y));
+                jwk
+            }
+            JwtKeyKind::EdDsa(_, public_key) => {
+                let bytes = public_key.public_key().to_bytes();
+                let mut jwk = Map::from_entry("kty", "OKP");
+                jwk.upsert("crv", "Ed25519");
+                jwk.upsert("x", base64::encode_url_safe(bytes));
+                jwk
+            }
+        };
+        jwk.upsert("use", "sig");
+        jwk.upsert("alg", self.algorithm());
+        if let Some(key_id) = self.key_id() {
+            jwk.upsert("kid", key_id);
+        }
+        Some(jwk)
+    }
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("key_id", &self.key_id)
+            .field("algorithm", &self.algorithm())
+            .field("can_sign", &self.can_sign())
+            .finish()
+    }
+}
+
+/// A set of JWT keys which supports the key rotation.
+///
+/// Tokens are signed with the signing key and the key ID is set in the header,
+/// while they can be verified by any key in the set selected by the `kid`.
+#[derive(Debug, Default)]
+pub struct JwtKeySet {
+    /// Keys.
+    keys: Vec<JwtKey>,
+    /// Index of the signing key.
+    signing_key_index: Option<usize>,
+}
+
+impl JwtKeySet {
+    /// Creates a new instance without any keys.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key. The first key which can be used for signing will be the signing key
+    /// unless it is specified by [`set_signing_key_id`](Self::set_signing_key_id).
+    #[inline]
+    pub fn add_key(&mut self, key: JwtKey) {
+        if self.signing_key_index.is_none() && key.can_sign() {
+            self.signing_key_index = Some(self.keys.len());
+        }
+        self.keys.push(key);
+    }
+
+    /// Adds a key which is only used for verification.
+    #[inline]
+    pub fn add_verification_key(&mut self, key: JwtKey) {
+        self.keys.push(key);
+    }
+
+    /// Sets the signing key by the key ID.
+    pub fn set_signing_key_id(&mut self, key_id: &str) -> Result<(), Error> {
+        let Some(index) = self
+            .keys
+            .iter()
+            .position(|key| key.key_id() == Some(key_id))
+        else {
+            bail!("the JWT key `{}` does not exist", key_id);
+        };
+        if !self.keys[index].can_sign() {
+            bail!("the JWT key `{}` can not be used for signing", key_id);
+        }
+        self.signing_key_index = Some(index);
+        Ok(())
+    }
+
+    /// Returns the key by the key ID.
+    #[inline]
+    pub fn get_key(&self, key_id: &str) -> Option<&JwtKey> {
+        self.keys.iter().find(|key| key.key_id() == Some(key_id))
+    }
+
+    /// Returns the signing key.
+    #[inline]
+    pub fn signing_key(&self) -> Option<&JwtKey> {
+        self.signing_key_index
+            .and_then(|index| self.keys.get(index))
+    }
+
+    /// Returns the keys.
+    #[inline]
+    pub fn keys(&self) -> &[JwtKey] {
+        &self.keys
+    }
+
+    /// Signs the claims with the signing key.
+    pub fn sign<T: Serialize + DeserializeOwned>(
+        &self,
+        claims: JWTClaims<T>,
+    ) -> Result<String, Error> {
+        let Some(key) = self.signing_key() else {
+            bail!("there is no JWT key for signing");
+        };
+        key.sign(claims)
+    }
+
+    /// Returns the public keys as a JSON Web Key Set.
+    pub fn to_jwks(&self) -> Map {
+        let keys = self
+            .keys
+            .iter()
+            .filter_map(|key| key.to_jwk())
+            .collect::<Vec<_>>();
+        Map::from_entry("keys", keys)
+    }
+
+    /// Returns the shared key set loaded from the `[[jwt.keys]]` configuration.
+    /// It falls back to the shared HMAC key if no keys are configured. Otherwise,
+    /// the HMAC key is accepted for verification only if `jwt.hmac-verification` is enabled.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        LazyLock::force(&SHARED_KEY_SET)
+    }
+}
+
+/// A verifier for JWT tokens.
+pub trait JwtVerifier {
+    /// Verifies the token and returns the claims.
+    fn verify_jwt<T: Serialize + DeserializeOwned>(
+        &self,
+        token: &str,
+        options: VerificationOptions,
+    ) -> Result<JwtClaims<T>, Error>;
+}
+
+impl<K: MACLike> JwtVerifier for K {
+    #[inline]
+    fn verify_jwt<T: Serialize + DeserializeOwned>(
+        &self,
+        token: &str,
+        options: VerificationOptions,
+    ) -> Result<JwtClaims<T>, Error> {
+        self.verify_token(token, Some(options))
+            .map(JwtClaims)
+            .map_err(|err| Error::new(err.to_string()))
+    }
+}
+
+impl JwtVerifier for JwtKey {
+    fn verify_jwt<T: Serialize + DeserializeOwned>(
+        &self,
+        token: &str,
+        options: VerificationOptions,
+    ) -> Result<JwtClaims<T>, Error> {
+        let options = Some(options);
+        let result = match &self.kind {
+            JwtKeyKind::Hmac(key) => key.verify_token(token, options),
+            JwtKeyKind::Rs256(_, public_key) => public_key.verify_token(token, options),
+            JwtKeyKind::Es256(_, public_key) => public_key.verify_token(token, options),
+            JwtKeyKind::EdDsa(_, public_key) => public_key.verify_token(token, options),
+        };
+        result
+            .map(JwtClaims)
+            .map_err(|err| Error::new(err.to_string()))
+    }
+}
+
+impl JwtVerifier for JwtKeySet {
+    fn verify_jwt<T: Serialize + DeserializeOwned>(
+        &self,
+        token: &str,
+        options: VerificationOptions,
+    ) -> Result<JwtClaims<T>, Error> {
+        let metadata = Token::decode_metadata(token).map_err(|err| Error::new(err.to_string()))?;
+        let key = if let Some(key_id) = metadata.key_id() {
+            self.get_key(key_id)
+                .ok_or_else(|| warn!("the JWT key `{}` does not exist", key_id))?
+        } else {
+            self.keys
+                .iter()
+                .find(|key| key.key_id().is_none() && key.algorithm() == metadata.algorithm())
+                .or_else(|| self.signing_key())
+                .ok_or_else(|| warn!("there is no JWT key for verification"))?
+        };
+        if key.algorithm() != metadata.algorithm() {
+            bail!(
+                "the JWT algorithm `{}` does not match the key",
+                metadata.algorithm()
+            );
+        }
+        key.verify_jwt(token, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JwtKey, JwtKeySet, JwtVerifier};
+    use crate::auth::{default_verification_options, JwtClaims, JwtHmacKey};
+    use jwt_simple::algorithms::{ES256KeyPair, Ed25519KeyPair};
+    use std::{env, fs};
+    use toml::Table;
+
+    #[test]
+    fn it_rotates_jwt_keys() {
+        let es256_pem = ES256KeyPair::generate().to_pem().unwrap();
+        let old_key = JwtKey::from_private_pem("ES256", &es256_pem)
+            .unwrap()
+            .with_key_id("2023");
+
+        let ed25519_pem = Ed25519KeyPair::generate().to_pem();
+        let new_key = JwtKey::from_private_pem("EdDSA", &ed25519_pem)
+            .unwrap()
+            .with_key_id("2024");
+
+        let mut key_set = JwtKeySet::new();
+        key_set.add_key(old_key);
+        let claims = JwtClaims::<()>::new("alice");
+        let old_token = key_set.sign(claims.0.clone()).unwrap();
+
+        key_set.add_key(new_key);
+        assert!(key_set.set_signing_key_id("2024").is_ok());
+        assert!(key_set.set_signing_key_id("2025").is_err());
+        let new_token = key_set.sign(claims.0).unwrap();
+
+        let options = default_verification_options();
+        let verified = key_set.verify_jwt::<()>(&old_token, options.clone());
+        assert_eq!(verified.unwrap().subject(), Some("alice"));
+        let verified = key_set.verify_jwt::<()>(&new_token, options.clone());
+        assert_eq!(verified.unwrap().subject(), Some("alice"));
+
+        let hmac_key = JwtKey::from_hmac_key(JwtHmacKey::generate());
+        let hmac_token = hmac_key.sign(JwtClaims::<()>::new("bob").0).unwrap();
+        key_set.add_verification_key(hmac_key);
+        assert_eq!(key_set.signing_key().unwrap().key_id(), Some("2024"));
+        let verified = key_set.verify_jwt::<()>(&hmac_token, options.clone());
+        assert_eq!(verified.unwrap().subject(), Some("bob"));
+
+        let other_key = JwtKey::from_private_pem("EdDSA", &Ed25519KeyPair::generate().to_pem());
+        assert!(other_key
+            .unwrap()
+            .verify_jwt::<()>(&new_token, options)
+            .is_err());
+
+        let jwks = key_set.to_jwks();
+        let keys = jwks["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0]["kty"], "EC");
+        assert_eq!(keys[1]["kid"], "2024");
+        assert_eq!(keys[1]["crv"], "Ed25519");
+    }
+
+    #[test]
+    fn it_rejects_hmac_tokens_with_asymmetric_keys() {
+        let pem_path = env::temp_dir().join(format!("zino-jwt-{}.pem", crate::Uuid::now_v7()));
+        fs::write(&pem_path, Ed25519KeyPair::generate().to_pem()).unwrap();
+
+        let hmac_key = JwtHmacKey::generate();
+        let hmac_token = JwtKey::from_hmac_key(hmac_key.clone())
+            .sign(JwtClaims::<()>::new("alice").0)
+            .unwrap();
+        let options = default_verification_options();
+        let config = format!(
+            r#"
+            [[keys]]
+            key-id = "2024"
+            algorithm = "EdDSA"
+            private-key = "{}"
+            "#,
+            pem_path.display()
+        );
+        let mut config = config.parse::<Table>().unwrap();
+        let key_set = super::load_key_set(Some(&config), &hmac_key);
+        assert_eq!(key_set.signing_key().unwrap().key_id(), Some("2024"));
+        assert!(key_set
+            .verify_jwt::<()>(&hmac_token, options.clone())
+            .is_err());
+
+        config.insert("hmac-verification".to_owned(), true.into());
+        let key_set = super::load_key_set(Some(&config), &hmac_key);
+        let verified = key_set.verify_jwt::<()>(&hmac_token, options.clone());
+        assert_eq!(verified.unwrap().subject(), Some("alice"));
+
+        let key_set = super::load_key_set(None, &hmac_key);
+        let verified = key_set.verify_jwt::<()>(&hmac_token, options);
+        assert_eq!(verified.unwrap().subject(), Some("alice"));
+        fs::remove_file(pem_path).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    application, bail, crypto,
+    datetime::DateTime,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    warn, Map,
+};
+use std::{sync::LazyLock, time::Duration};
+
+/// A short-lived challenge for the second factor of a login.
+///
+/// It is issued after the first factor has been verified, and encoded as an opaque
+/// encrypted token, so it can not be used as a bearer token for other purposes.
+/// The random `challenge` is also used for the WebAuthn assertions.
+#[derive(Debug, Clone)]
+pub struct MfaChallenge {
+    /// Subject of the login.
+    subject: String,
+    /// Random challenge.
+    challenge: String,
+    /// A flag to indicate the challenge is issued for enrolling the first factor.
+    enrollment: bool,
+    /// Time when the challenge expires at.
+    expires_at: DateTime,
+}
+
+impl MfaChallenge {
+    /// Creates a new instance for the subject.
+    pub fn new(subject: impl ToString) -> Self {
+        let bytes: [u8; 32] = rand::random();
+        Self {
+            subject: subject.to_string(),
+            challenge: base64::encode_url_safe(bytes),
+            enrollment: false,
+            expires_at: DateTime::now() + *CHALLENGE_MAX_AGE,
+        }
+    }
+
+    /// Sets the enrollment flag. An enrollment challenge allows the subject
+    /// to enroll the second factor when no factors have been enrolled.
+    #[inline]
+    pub fn set_enrollment(&mut self, enrollment: bool) {
+        self.enrollment = enrollment;
+    }
+
+    /// Returns the subject.
+    #[inline]
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Returns the random challenge as a URL-safe base64 string.
+    #[inline]
+    pub fn challenge(&self) -> &str {
+        &self.challenge
+    }
+
+    /// Returns `true` if the challenge is issued for the enrollment.
+    #[inline]
+    pub fn is_enrollment(&self) -> bool {
+        self.enrollment
+    }
+
+    /// Returns the time when the challenge expires at.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns the time when the challenge will expire in.
+    #[inline]
+    pub fn expires_in(&self) -> Duration {
+        self.expires_at.span_after_now().unwrap_or_default()
+    }
+
+    /// Encodes `self` as an opaque token.
+    pub fn encode(&self) -> Result<String, Error> {
+        let mut payload = Map::from_entry("sub", self.subject.as_str());
+        payload.upsert("challenge", self.challenge.as_str());
+        payload.upsert("enrollment", self.enrollment);
+        payload.upsert("exp", self.expires_at.timestamp());
+
+        let plaintext = serde_json::to_vec(&payload)?;
+        let data = crypto::encrypt(&plaintext, &*CHALLENGE_KEY)?;
+        Ok(base64::encode_url_safe(data))
+    }
+
+    /// Parses the token encoded by [`encode()`](Self::encode).
+    pub fn parse(token: &str) -> Result<Self, Error> {
+        let payload = base64::decode_url_safe(token)
+            .ok()
+            .and_then(|data| crypto::decrypt(&data, &*CHALLENGE_KEY).ok())
+            .and_then(|plaintext| serde_json::from_slice::<Map>(&plaintext).ok())
+            .ok_or_else(|| warn!("401 Unauthorized: the MFA token is invalid"))?;
+        let expires_at = payload
+            .get_i64("exp")
+            .map(DateTime::from_timestamp)
+            .unwrap_or_default();
+        if expires_at <= DateTime::now() {
+            bail!("401 Unauthorized: the MFA token has expired");
+        }
+
+        let (Some(subject), Some(challenge)) =
+            (payload.get_str("sub"), payload.get_str("challenge"))
+        else {
+            bail!("401 Unauthorized: the MFA token is invalid");
+        };
+        Ok(Self {
+            subject: subject.to_owned(),
+            challenge: challenge.to_owned(),
+            enrollment: payload.get_bool("enrollment").unwrap_or_default(),
+            expires_at,
+        })
+    }
+}
+
+/// Max age of the MFA challenge.
+static CHALLENGE_MAX_AGE: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("mfa")
+        .and_then(|config| config.get_duration("challenge-max-age"))
+        .unwrap_or_else(|| Duration::from_secs(60 * 5))
+});
+
+/// Secret key for encrypting the MFA challenge.
+static CHALLENGE_KEY: LazyLock<[u8; 64]> = LazyLock::new(|| {
+    let config = State::shared().config();
+    let checksum: [u8; 32] = config
+        .get_table("mfa")
+        .and_then(|t| t.get_str("checksum"))
+        .and_then(|checksum| checksum.as_bytes().first_chunk().copied())
+        .or_else(|| application::SECRET_KEY.get().map(|key| crypto::digest(key)))
+        .unwrap_or_else(|| {
+            tracing::warn!("the `checksum` is not set properly for deriving a secret key");
+
+            let app_name = config.get_str("name").unwrap_or(env!("CARGO_PKG_NAME"));
+            crypto::digest(app_name.as_bytes())
+        });
+    crypto::derive_key("ZINO:MFA-CHALLENGE", &checksum)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::MfaChallenge;
+
+    #[test]
+    fn it_encodes_mfa_challenges() {
+        let mut challenge = MfaChallenge::new("alice");
+        challenge.set_enrollment(true);
+
+        let token = challenge.encode().unwrap();
+        let parsed = MfaChallenge::parse(&token).unwrap();
+        assert_eq!(parsed.subject(), "alice");
+        assert_eq!(parsed.challenge(), challenge.challenge());
+        assert!(parsed.is_enrollment());
+        assert!(MfaChallenge::parse(challenge.challenge()).is_err());
+    }
+}
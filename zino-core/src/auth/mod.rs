@@ -4,23 +4,30 @@
 //!
 //! The following optional features are available:
 //!
-//! | Name          | Description                                          | Default? |
-//! |---------------|------------------------------------------------------|----------|
-//! | `auth-oauth2` | Enables the integration with [`oauth2`].             | No       |
-//! | `auth-oidc`   | Enables the integration with [`openidconnect`].      | No       |
-//! | `auth-totp`   | Enables the integration with [`totp-rs`].            | No       |
+//! | Name            | Description                                      | Default? |
+//! |-----------------|--------------------------------------------------|----------|
+//! | `auth-oauth2`   | Enables the integration with [`oauth2`].         | No       |
+//! | `auth-oidc`     | Enables the integration with [`openidconnect`].  | No       |
+//! | `auth-totp`     | Enables the integration with [`totp-rs`].        | No       |
+//! | `auth-webauthn` | Enables the WebAuthn credentials with [`p256`].  | No       |
 //!
 //! [`oauth2`]: https://crates.io/crates/oauth2
 //! [`openidconnect`]: https://crates.io/crates/openidconnect
 //! [`totp-rs`]: https://crates.io/crates/totp-rs
+//! [`p256`]: https://crates.io/crates/p256
 
 mod access_key;
+mod access_policy;
 mod authentication;
 mod authorization_provider;
+mod authorization_server;
 mod client_credentials;
 mod jwt_claims;
+mod jwt_key;
+mod mfa_challenge;
 mod security_token;
 mod session_id;
+mod token_store;
 mod user_session;
 
 #[cfg(feature = "auth-oauth2")]
@@ -29,16 +36,24 @@ mod oauth2_client;
 #[cfg(feature = "auth-oidc")]
 mod oidc_client;
 
+#[cfg(feature = "auth-webauthn")]
+mod webauthn;
+
 pub(crate) use jwt_claims::{default_time_tolerance, default_verification_options};
 pub(crate) use security_token::ParseSecurityTokenError;
 
 pub use access_key::{AccessKeyId, SecretAccessKey};
+pub use access_policy::{AccessPolicy, PolicyEffect, PolicyEngine};
 pub use authentication::Authentication;
 pub use authorization_provider::AuthorizationProvider;
+pub use authorization_server::{AuthorizationServer, ClientRegistration};
 pub use client_credentials::ClientCredentials;
 pub use jwt_claims::{JwtClaims, JwtHmacKey};
+pub use jwt_key::{JwtKey, JwtKeySet, JwtVerifier};
+pub use mfa_challenge::MfaChallenge;
 pub use security_token::SecurityToken;
 pub use session_id::SessionId;
+pub use token_store::{MemoryTokenStore, TokenFamily, TokenStore};
 pub use user_session::UserSession;
 
 #[cfg(feature = "auth-oauth2")]
@@ -46,3 +61,6 @@ pub use oauth2_client::OAuth2Client;
 
 #[cfg(feature = "auth-oidc")]
 pub use oidc_client::OidcClient;
+
+#[cfg(feature = "auth-webauthn")]
+pub use webauthn::{WebAuthnCredential, WebAuthnRelyingParty};
@@ -0,0 +1,226 @@
+use crate::{datetime::DateTime, error::Error, BoxFuture, Uuid};
+use std::{collections::HashMap, sync::Mutex};
+
+/// A family of the rotating refresh tokens issued for a login session.
+///
+/// The refresh tokens of a family are identified by the family ID and the generation,
+/// which is formatted as `{family_id}:{generation}` and used as the `jti` claim.
+/// Only the token of the current generation can be used to refresh the access token,
+/// so the reuse of a rotated token can be detected.
+#[derive(Debug, Clone)]
+pub struct TokenFamily {
+    /// Family ID.
+    id: String,
+    /// Subject of the tokens.
+    subject: String,
+    /// Generation of the current refresh token.
+    generation: u64,
+    /// A flag to indicate the family has been revoked.
+    revoked: bool,
+    /// Time when the family expires at.
+    expires_at: DateTime,
+}
+
+impl TokenFamily {
+    /// Creates a new instance for the subject, expiring in the refresh interval.
+    pub fn new(subject: impl ToString) -> Self {
+        let refresh_interval = super::jwt_claims::default_refresh_interval();
+        Self {
+            id: Uuid::now_v7().to_string(),
+            subject: subject.to_string(),
+            generation: 0,
+            revoked: false,
+            expires_at: DateTime::now() + refresh_interval,
+        }
+    }
+
+    /// Sets the family ID.
+    #[inline]
+    pub fn set_id(&mut self, id: impl ToString) {
+        self.id = id.to_string();
+    }
+
+    /// Sets the generation.
+    #[inline]
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Sets the expiration time.
+    #[inline]
+    pub fn set_expires_at(&mut self, expires_at: DateTime) {
+        self.expires_at = expires_at;
+    }
+
+    /// Returns the family ID.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the subject.
+    #[inline]
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Returns the generation of the current refresh token.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the time when the family expires at.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the family has not been revoked and has not expired.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.revoked && self.expires_at > DateTime::now()
+    }
+
+    /// Returns the token ID of the current refresh token.
+    #[inline]
+    pub fn token_id(&self) -> String {
+        format!("{}:{}", self.id, self.generation)
+    }
+
+    /// Parses the token ID as a family ID and a generation.
+    pub fn parse_token_id(token_id: &str) -> Option<(&str, u64)> {
+        let (family_id, generation) = token_id.rsplit_once(':')?;
+        Some((family_id, generation.parse().ok()?))
+    }
+}
+
+/// A store for the token families, which is used as a revocation list.
+pub trait TokenStore: Send + Sync + 'static {
+    /// Creates a token family.
+    fn create(&self, family: TokenFamily) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Increments the generation of an active token family by 1 if the current generation
+    /// is equal to `generation`. It returns `false` if the family is inactive
+    /// or the generation has been changed.
+    fn rotate(&self, family_id: &str, generation: u64) -> BoxFuture<'_, Result<bool, Error>>;
+
+    /// Revokes a token family.
+    fn revoke(&self, family_id: &str) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Revokes all the token families of the subject.
+    fn revoke_all(&self, subject: &str) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Returns `true` if the token family is active.
+    fn is_active(&self, family_id: &str) -> BoxFuture<'_, Result<bool, Error>>;
+}
+
+/// An in-memory token store. The token families will be lost when the application restarts,
+/// so it is only suitable for a single instance.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    /// Token families.
+    families: Mutex<HashMap<String, TokenFamily>>,
+}
+
+impl MemoryTokenStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the function with the token families.
+    fn with_families<T>(&self, f: impl FnOnce(&mut HashMap<String, TokenFamily>) -> T) -> T {
+        let mut families = self.families.lock().unwrap_or_else(|err| err.into_inner());
+        f(&mut families)
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn create(&self, family: TokenFamily) -> BoxFuture<'_, Result<(), Error>> {
+        let now = DateTime::now();
+        self.with_families(|families| {
+            families.retain(|_, family| family.expires_at > now);
+            families.insert(family.id.clone(), family);
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn rotate(&self, family_id: &str, generation: u64) -> BoxFuture<'_, Result<bool, Error>> {
+        let rotated = self.with_families(|families| {
+            if let Some(family) = families.get_mut(family_id)
+                && family.is_active()
+                && family.generation == generation
+            {
+                family.generation += 1;
+                true
+            } else {
+                false
+            }
+        });
+        Box::pin(async move { Ok(rotated) })
+    }
+
+    fn revoke(&self, family_id: &str) -> BoxFuture<'_, Result<(), Error>> {
+        self.with_families(|families| {
+            if let Some(family) = families.get_mut(family_id) {
+                family.revoked = true;
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn revoke_all(&self, subject: &str) -> BoxFuture<'_, Result<(), Error>> {
+        self.with_families(|families| {
+            for family in families.values_mut() {
+                if family.subject == subject {
+                    family.revoked = true;
+                }
+            }
+        });
+        Box::pin(async { Ok(()) })
+    }
+
+    fn is_active(&self, family_id: &str) -> BoxFuture<'_, Result<bool, Error>> {
+        let active = self.with_families(|families| {
+            families
+                .get(family_id)
+                .is_some_and(|family| family.is_active())
+        });
+        Box::pin(async move { Ok(active) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryTokenStore, TokenFamily, TokenStore};
+    use futures::executor;
+
+    #[test]
+    fn it_rotates_token_families() {
+        let store = MemoryTokenStore::new();
+        let family = TokenFamily::new("alice");
+        let token_id = family.token_id();
+        let (family_id, generation) = TokenFamily::parse_token_id(&token_id).unwrap();
+        assert_eq!(family_id, family.id());
+        assert_eq!(generation, 0);
+
+        executor::block_on(async {
+            store.create(family.clone()).await.unwrap();
+            assert!(store.rotate(family_id, 0).await.unwrap());
+            assert!(!store.rotate(family_id, 0).await.unwrap());
+            assert!(store.is_active(family_id).await.unwrap());
+
+            store.revoke(family_id).await.unwrap();
+            assert!(!store.is_active(family_id).await.unwrap());
+            assert!(!store.rotate(family_id, 1).await.unwrap());
+
+            let family = TokenFamily::new("alice");
+            store.create(family.clone()).await.unwrap();
+            assert!(store.is_active(family.id()).await.unwrap());
+            store.revoke_all("alice").await.unwrap();
+            assert!(!store.is_active(family.id()).await.unwrap());
+        });
+    }
+}
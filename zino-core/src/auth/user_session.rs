@@ -1,6 +1,6 @@
 use super::{AccessKeyId, JwtClaims, SessionId};
 use crate::{
-    application::APP_DOMAIN, crypto::Digest, error::Error, extension::JsonObjectExt, warn,
+    application::APP_DOMAIN, bail, crypto::Digest, error::Error, extension::JsonObjectExt, warn,
 };
 use std::str::FromStr;
 
@@ -99,7 +99,15 @@ where
     <U as FromStr>::Err: std::error::Error,
 {
     /// Attempts to construct an instance from a `JwtClaims`.
+    /// Refresh tokens and the tokens issued to OAuth clients are rejected.
     pub fn try_from_jwt_claims(claims: JwtClaims) -> Result<Self, Error> {
+        if claims.is_refresh_token() {
+            bail!("401 Unauthorized: the JWT token is not an access token");
+        }
+        if claims.has_audience() {
+            bail!("401 Unauthorized: the access token was issued to an OAuth client");
+        }
+
         let data = claims.data();
         let user_id = claims
             .subject()
@@ -0,0 +1,467 @@
+use crate::{
+    application::APP_NMAE,
+    bail,
+    datetime::DateTime,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    warn, JsonValue, Map,
+};
+use ciborium::Value;
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+
+/// A WebAuthn credential registered for a user, such as a passkey.
+///
+/// Only the `ES256` algorithm is supported, which is required by all the authenticators.
+#[derive(Debug, Clone)]
+pub struct WebAuthnCredential {
+    /// Credential ID encoded as the URL-safe base64 string.
+    id: String,
+    /// Public key in the SEC1 encoding.
+    public_key: Vec<u8>,
+    /// Signature counter.
+    sign_count: u32,
+    /// Time when the credential was last used at.
+    last_used_at: Option<DateTime>,
+}
+
+impl WebAuthnCredential {
+    /// Returns the credential ID.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the signature counter.
+    #[inline]
+    pub fn sign_count(&self) -> u32 {
+        self.sign_count
+    }
+
+    /// Returns the time when the credential was last used at.
+    #[inline]
+    pub fn last_used_at(&self) -> Option<DateTime> {
+        self.last_used_at
+    }
+
+    /// Converts `self` to a JSON object for the storage.
+    pub fn to_map(&self) -> Map {
+        let mut map = Map::from_entry("id", self.id.as_str());
+        map.upsert("public_key", base64::encode_url_safe(&self.public_key));
+        map.upsert("sign_count", self.sign_count);
+        if let Some(last_used_at) = self.last_used_at {
+            map.upsert("last_used_at", last_used_at.to_string());
+        }
+        map
+    }
+
+    /// Attempts to construct an instance from a JSON object created by [`to_map()`](Self::to_map).
+    pub fn try_from_map(map: &Map) -> Result<Self, Error> {
+        let Some(id) = map.get_str("id") else {
+            bail!("the WebAuthn credential ID should be specified");
+        };
+        let public_key = map
+            .get_str("public_key")
+            .and_then(|s| base64::decode_url_safe(s).ok())
+            .ok_or_else(|| warn!("the WebAuthn public key is invalid"))?;
+        Ok(Self {
+            id: id.to_owned(),
+            public_key,
+            sign_count: map.get_u32("sign_count").unwrap_or_default(),
+            last_used_at: map.parse_datetime("last_used_at").and_then(|r| r.ok()),
+        })
+    }
+}
+
+/// A WebAuthn relying party which registers and verifies the credentials.
+///
+/// The attestation statement is not verified, which corresponds to the `none`
+/// attestation conveyance preference used by the passkeys.
+#[derive(Debug, Clone)]
+pub struct WebAuthnRelyingParty {
+    /// Relying party ID, which is the effective domain of the origins.
+    id: String,
+    /// Relying party name.
+    name: String,
+    /// Allowed origins.
+    origins: Vec<String>,
+}
+
+impl WebAuthnRelyingParty {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(id: impl ToString, name: impl ToString, origins: Vec<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            origins,
+        }
+    }
+
+    /// Returns the shared relying party configured by the `[webauthn]` table.
+    #[inline]
+    pub fn shared() -> &'static Self {
+        &SHARED_RELYING_PARTY
+    }
+
+    /// Returns the relying party ID.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the options for `navigator.credentials.create()`.
+    /// The challenge and the excluded credential IDs should be URL-safe base64 strings.
+    pub fn creation_options(
+        &self,
+        user_id: &str,
+        user_name: &str,
+        challenge: &str,
+        exclude_credentials: &[&str],
+    ) -> Map {
+        let mut rp = Map::from_entry("id", self.id.as_str());
+        rp.upsert("name", self.name.as_str());
+
+        let mut user = Map::from_entry("id", base64::encode_url_safe(user_id));
+        user.upsert("name", user_name);
+        user.upsert("displayName", user_name);
+
+        let mut pub_key_cred_param = Map::from_entry("type", "public-key");
+        pub_key_cred_param.upsert("alg", COSE_ALGORITHM_ES256);
+
+        let mut authenticator_selection = Map::from_entry("residentKey", "preferred");
+        authenticator_selection.upsert("userVerification", "preferred");
+
+        let mut options = Map::from_entry("challenge", challenge);
+        options.upsert("rp", rp);
+        options.upsert("user", user);
+        options.upsert("pubKeyCredParams", vec![pub_key_cred_param]);
+        options.upsert("timeout", CEREMONY_TIMEOUT);
+        options.upsert("attestation", "none");
+        options.upsert("authenticatorSelection", authenticator_selection);
+        options.upsert(
+            "excludeCredentials",
+            Self::credential_descriptors(exclude_credentials),
+        );
+        options
+    }
+
+    /// Returns the options for `navigator.credentials.get()`.
+    /// The challenge and the allowed credential IDs should be URL-safe base64 strings.
+    pub fn request_options(&self, challenge: &str, allow_credentials: &[&str]) -> Map {
+        let mut options = Map::from_entry("challenge", challenge);
+        options.upsert("rpId", self.id.as_str());
+        options.upsert("timeout", CEREMONY_TIMEOUT);
+        options.upsert("userVerification", "preferred");
+        options.upsert(
+            "allowCredentials",
+            Self::credential_descriptors(allow_credentials),
+        );
+        options
+    }
+
+    /// Registers a credential with the `clientDataJSON` and the `attestationObject`
+    /// of the authenticator response, which are encoded as URL-safe base64 strings.
+    pub fn register(
+        &self,
+        challenge: &str,
+        client_data_json: &str,
+        attestation_object: &str,
+    ) -> Result<WebAuthnCredential, Error> {
+        self.verify_client_data(client_data_json, "webauthn.create", challenge)?;
+
+        let attestation_object = base64::decode_url_safe(attestation_object)
+            .map_err(|err| warn!("invalid attestation object: {}", err))?;
+        let attestation: Value = ciborium::de::from_reader(attestation_object.as_slice())
+            .map_err(|err| warn!("invalid attestation object: {}", err))?;
+        let Some(auth_data) =
+            cbor_map_get(&attestation, &Value::from("authData")).and_then(|value| value.as_bytes())
+        else {
+            bail!("the authenticator data is absent");
+        };
+
+        let flags = self.verify_authenticator_data(auth_data)?;
+        if flags & FLAG_ATTESTED_CREDENTIAL_DATA == 0 {
+            bail!("the attested credential data is absent");
+        }
+
+        // rpIdHash (32) + flags (1) + signCount (4) + aaguid (16) + credentialIdLength (2)
+        let Some(length_bytes) = auth_data.get(53..55) else {
+            bail!("the attested credential data is invalid");
+        };
+        let length = usize::from(u16::from_be_bytes([length_bytes[0], length_bytes[1]]));
+        let Some(credential_id) = auth_data.get(55..55 + length) else {
+            bail!("the credential ID is invalid");
+        };
+        let public_key_bytes = &auth_data[55 + length..];
+        let cose_key: Value = ciborium::de::from_reader(public_key_bytes)
+            .map_err(|err| warn!("invalid credential public key: {}", err))?;
+        let public_key = parse_cose_key(&cose_key)?;
+        Ok(WebAuthnCredential {
+            id: base64::encode_url_safe(credential_id),
+            public_key,
+            sign_count: read_sign_count(auth_data),
+            last_used_at: None,
+        })
+    }
+
+    /// Authenticates the assertion of a credential with the `clientDataJSON`,
+    /// the `authenticatorData` and the `signature` of the authenticator response,
+    /// which are encoded as URL-safe base64 strings.
+    /// The signature counter of the credential is updated if the assertion is valid.
+    pub fn authenticate(
+        &self,
+        credential: &mut WebAuthnCredential,
+        challenge: &str,
+        client_data_json: &str,
+        authenticator_data: &str,
+        signature: &str,
+    ) -> Result<(), Error> {
+        let client_data = self.verify_client_data(client_data_json, "webauthn.get", challenge)?;
+        let auth_data = base64::decode_url_safe(authenticator_data)
+            .map_err(|err| warn!("invalid authenticator data: {}", err))?;
+        self.verify_authenticator_data(&auth_data)?;
+
+        let signature = base64::decode_url_safe(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_der(&bytes).ok())
+            .ok_or_else(|| warn!("the signature is invalid"))?;
+        let verifying_key = VerifyingKey::from_sec1_bytes(&credential.public_key)
+            .map_err(|_| warn!("the credential public key is invalid"))?;
+        let mut message = auth_data.clone();
+        message.extend_from_slice(&Sha256::digest(client_data));
+        if verifying_key.verify(&message, &signature).is_err() {
+            bail!("fail to verify the signature of the credential");
+        }
+
+        let sign_count = read_sign_count(&auth_data);
+        if (sign_count != 0 || credential.sign_count != 0) && sign_count <= credential.sign_count {
+            bail!("the signature counter of the credential has not been increased");
+        }
+        credential.sign_count = sign_count;
+        credential.last_used_at = Some(DateTime::now());
+        Ok(())
+    }
+
+    /// Verifies the client data and returns the decoded bytes.
+    fn verify_client_data(
+        &self,
+        client_data_json: &str,
+        ceremony_type: &str,
+        challenge: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let client_data = base64::decode_url_safe(client_data_json)
+            .map_err(|err| warn!("invalid client data: {}", err))?;
+        let data = serde_json::from_slice::<Map>(&client_data)?;
+        if data.get_str("type") != Some(ceremony_type) {
+            bail!("the client data type should be `{}`", ceremony_type);
+        }
+        if data.get_str("challenge") != Some(challenge) {
+            bail!("the challenge of the client data does not match");
+        }
+        if !data
+            .get_str("origin")
+            .is_some_and(|origin| self.origins.iter().any(|s| s == origin))
+        {
+            bail!("the origin of the client data is not allowed");
+        }
+        Ok(client_data)
+    }
+
+    /// Verifies the authenticator data and returns the flags.
+    fn verify_authenticator_data(&self, auth_data: &[u8]) -> Result<u8, Error> {
+        if auth_data.len() < 37 {
+            bail!("the authenticator data is too short");
+        }
+        if auth_data[..32] != Sha256::digest(self.id.as_bytes())[..] {
+            bail!("the relying party ID hash does not match");
+        }
+
+        let flags = auth_data[32];
+        if flags & FLAG_USER_PRESENT == 0 {
+            bail!("the user is not present");
+        }
+        Ok(flags)
+    }
+
+    /// Returns the public key credential descriptors.
+    fn credential_descriptors(credential_ids: &[&str]) -> Vec<JsonValue> {
+        credential_ids
+            .iter()
+            .map(|id| {
+                let mut descriptor = Map::from_entry("type", "public-key");
+                descriptor.upsert("id", *id);
+                descriptor.into()
+            })
+            .collect()
+    }
+}
+
+/// Reads the signature counter of the authenticator data.
+fn read_sign_count(auth_data: &[u8]) -> u32 {
+    u32::from_be_bytes([auth_data[33], auth_data[34], auth_data[35], auth_data[36]])
+}
+
+/// Gets the value of a CBOR map by the key.
+fn cbor_map_get<'a>(map: &'a Value, key: &Value) -> Option<&'a Value> {
+    map.as_map()?
+        .iter()
+        .find_map(|(k, v)| (k == key).then_some(v))
+}
+
+/// Parses an `ES256` COSE key as the SEC1 encoding of the public key.
+fn parse_cose_key(cose_key: &Value) -> Result<Vec<u8>, Error> {
+    let get = |label: i64| cbor_map_get(cose_key, &Value::from(label));
+    let is_integer = |value: Option<&Value>, expected: i64| {
+        value
+            .and_then(|v| v.as_integer())
+            .is_some_and(|i| i128::from(i) == i128::from(expected))
+    };
+    // kty: EC2 (2), alg: ES256 (-7), crv: P-256 (1)
+    if !is_integer(get(1), 2)
+        || !is_integer(get(3), COSE_ALGORITHM_ES256)
+        || !is_integer(get(-1), 1)
+    {
+        bail!("only the `ES256` algorithm is supported for the credential public key");
+    }
+
+    let x = get(-2).and_then(|v| v.as_bytes());
+    let y = get(-3).and_then(|v| v.as_bytes());
+    let (Some(x), Some(y)) = (x, y) else {
+        bail!("the coordinates of the credential public key are absent");
+    };
+    let mut public_key = Vec::with_capacity(65);
+    public_key.push(0x04);
+    public_key.extend_from_slice(x);
+    public_key.extend_from_slice(y);
+    VerifyingKey::from_sec1_bytes(&public_key)
+        .map_err(|_| warn!("the credential public key is invalid"))?;
+    Ok(public_key)
+}
+
+/// COSE algorithm identifier for `ES256`.
+const COSE_ALGORITHM_ES256: i64 = -7;
+
+/// Flag for the user presence.
+const FLAG_USER_PRESENT: u8 = 0x01;
+
+/// Flag for the attested credential data.
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+/// Timeout of the ceremony in milliseconds.
+const CEREMONY_TIMEOUT: u64 = 300_000;
+
+/// Shared relying party.
+static SHARED_RELYING_PARTY: LazyLock<WebAuthnRelyingParty> = LazyLock::new(|| {
+    let config = State::shared().get_config("webauthn");
+    let rp_id = config
+        .and_then(|config| config.get_str("rp-id"))
+        .unwrap_or("localhost");
+    let rp_name = config
+        .and_then(|config| config.get_str("rp-name"))
+        .unwrap_or(*APP_NMAE);
+    let origins = config
+        .and_then(|config| config.get_str_array("origins"))
+        .map(|origins| origins.into_iter().map(|s| s.to_owned()).collect())
+        .unwrap_or_else(|| vec![format!("https://{rp_id}")]);
+    WebAuthnRelyingParty::new(rp_id, rp_name, origins)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{WebAuthnCredential, WebAuthnRelyingParty};
+    use crate::{encoding::base64, extension::JsonObjectExt, Map};
+    use ciborium::Value;
+    use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+    use sha2::{Digest, Sha256};
+
+    fn encode_client_data(ceremony_type: &str, challenge: &str) -> String {
+        let mut data = Map::from_entry("type", ceremony_type);
+        data.upsert("challenge", challenge);
+        data.upsert("origin", "https://example.com");
+        base64::encode_url_safe(serde_json::to_vec(&data).unwrap())
+    }
+
+    fn authenticator_data(flags: u8, sign_count: u32) -> Vec<u8> {
+        let mut auth_data = Sha256::digest(b"example.com").to_vec();
+        auth_data.push(flags);
+        auth_data.extend_from_slice(&sign_count.to_be_bytes());
+        auth_data
+    }
+
+    #[test]
+    fn it_verifies_webauthn_credentials() {
+        let rp = WebAuthnRelyingParty::new(
+            "example.com",
+            "Example",
+            vec!["https://example.com".to_owned()],
+        );
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let point = signing_key.verifying_key().to_encoded_point(false);
+        let cose_key = Value::Map(vec![
+            (1.into(), 2.into()),
+            (3.into(), (-7).into()),
+            ((-1).into(), 1.into()),
+            ((-2).into(), Value::Bytes(point.x().unwrap().to_vec())),
+            ((-3).into(), Value::Bytes(point.y().unwrap().to_vec())),
+        ]);
+
+        let mut auth_data = authenticator_data(0x41, 0);
+        auth_data.extend_from_slice(&[0; 16]);
+        auth_data.extend_from_slice(&4u16.to_be_bytes());
+        auth_data.extend_from_slice(b"cred");
+        ciborium::ser::into_writer(&cose_key, &mut auth_data).unwrap();
+        let attestation = Value::Map(vec![
+            ("fmt".into(), "none".into()),
+            ("attStmt".into(), Value::Map(Vec::new())),
+            ("authData".into(), Value::Bytes(auth_data)),
+        ]);
+        let mut attestation_object = Vec::new();
+        ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+
+        let attestation_object = base64::encode_url_safe(attestation_object);
+        let client_data_json = encode_client_data("webauthn.create", "challenge-1");
+        assert!(rp
+            .register("challenge-2", &client_data_json, &attestation_object)
+            .is_err());
+        let credential = rp
+            .register("challenge-1", &client_data_json, &attestation_object)
+            .unwrap();
+        assert_eq!(credential.id(), base64::encode_url_safe(b"cred"));
+
+        let mut credential = WebAuthnCredential::try_from_map(&credential.to_map()).unwrap();
+        let client_data_json = encode_client_data("webauthn.get", "challenge-3");
+        let auth_data = authenticator_data(0x01, 1);
+        let mut message = auth_data.clone();
+        message.extend_from_slice(&Sha256::digest(
+            base64::decode_url_safe(&client_data_json).unwrap(),
+        ));
+        let signature: Signature = signing_key.sign(&message);
+        let signature = base64::encode_url_safe(signature.to_der());
+        let auth_data = base64::encode_url_safe(auth_data);
+        assert!(rp
+            .authenticate(
+                &mut credential,
+                "challenge-3",
+                &client_data_json,
+                &auth_data,
+                &signature
+            )
+            .is_ok());
+        assert_eq!(credential.sign_count(), 1);
+
+        // The signature counter should be increased.
+        assert!(rp
+            .authenticate(
+                &mut credential,
+                "challenge-3",
+                &client_data_json,
+                &auth_data,
+                &signature
+            )
+            .is_err());
+    }
+}
@@ -5,7 +5,7 @@ use chrono::{
     format::ParseError, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
     SecondsFormat, TimeZone, Utc,
 };
-use serde::{Deserialize, Serialize, Serializer};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     fmt,
     ops::{Add, AddAssign, Sub, SubAssign},
@@ -21,7 +21,7 @@ pub use duration::{parse_duration, ParseDurationError};
 type LocalDateTime = chrono::DateTime<Local>;
 
 /// A wrapper type for [`chrono::DateTime<Local>`](chrono::DateTime).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DateTime(LocalDateTime);
 
 impl DateTime {
@@ -53,24 +53,24 @@ impl DateTime {
     /// from the number of non-leap seconds since the midnight UTC on January 1, 1970.
     #[inline]
     pub fn from_timestamp(secs: i64) -> Self {
-        let dt = NaiveDateTime::from_timestamp_opt(secs, 0).unwrap_or_default();
-        Self(Local.from_utc_datetime(&dt))
+        let dt = chrono::DateTime::from_timestamp(secs, 0).unwrap_or_default();
+        Self(dt.with_timezone(&Local))
     }
 
     /// Returns a new instance corresponding to a UTC date and time,
     /// from the number of non-leap milliseconds since the midnight UTC on January 1, 1970.
     #[inline]
     pub fn from_timestamp_millis(millis: i64) -> Self {
-        let dt = NaiveDateTime::from_timestamp_millis(millis).unwrap_or_default();
-        Self(Local.from_utc_datetime(&dt))
+        let dt = chrono::DateTime::from_timestamp_millis(millis).unwrap_or_default();
+        Self(dt.with_timezone(&Local))
     }
 
     /// Returns a new instance corresponding to a UTC date and time,
     /// from the number of non-leap microseconds since the midnight UTC on January 1, 1970.
     #[inline]
     pub fn from_timestamp_micros(micros: i64) -> Self {
-        let dt = NaiveDateTime::from_timestamp_micros(micros).unwrap_or_default();
-        Self(Local.from_utc_datetime(&dt))
+        let dt = chrono::DateTime::from_timestamp_micros(micros).unwrap_or_default();
+        Self(dt.with_timezone(&Local))
     }
 
     /// Returns the number of non-leap seconds since the midnight UTC on January 1, 1970.
@@ -445,6 +445,21 @@ impl Serialize for DateTime {
     }
 }
 
+impl<'de> Deserialize<'de> for DateTime {
+    /// Deserializes the date and time from any string which can be parsed by [`FromStr`],
+    /// so that the output of [`Serialize`] can be deserialized. It falls back to
+    /// the deserialization of [`chrono::DateTime<Local>`](chrono::DateTime).
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let datetime = String::deserialize(deserializer)?;
+        if let Ok(dt) = datetime.parse() {
+            Ok(dt)
+        } else {
+            let value = de::value::StrDeserializer::<D::Error>::new(&datetime);
+            LocalDateTime::deserialize(value).map(Self)
+        }
+    }
+}
+
 impl From<LocalDateTime> for DateTime {
     #[inline]
     fn from(dt: LocalDateTime) -> Self {
@@ -553,5 +568,17 @@ mod tests {
         assert!("2023-06-10 05:17:23.713071 +0800"
             .parse::<DateTime>()
             .is_ok());
+
+        let datetime = DateTime::now();
+        let value = serde_json::to_value(datetime).unwrap();
+        let parsed_datetime = serde_json::from_value::<DateTime>(value).unwrap();
+        assert_eq!(
+            parsed_datetime.timestamp_micros(),
+            datetime.timestamp_micros()
+        );
+
+        let value = serde_json::json!("2023-06-10T05:17:23-05:00");
+        let parsed_datetime = serde_json::from_value::<DateTime>(value).unwrap();
+        assert_eq!(parsed_datetime.timestamp(), 1686392243);
     }
 }
@@ -514,9 +514,7 @@ impl JsonObjectExt for Map {
     }
 
     fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
-        let Some(path) = pointer.strip_prefix('/') else {
-            return None;
-        };
+        let path = pointer.strip_prefix('/')?;
         if let Some(position) = path.find('/') {
             let (key, pointer) = path.split_at(position);
             self.get(key)?.pointer(pointer)
@@ -160,7 +160,7 @@ impl NamedFile {
     /// Appends the bytes into a file at the path.
     #[inline]
     pub fn append(&self, path: impl AsRef<Path>) -> Result<(), io::Error> {
-        let mut file = OpenOptions::new().write(true).append(true).open(path)?;
+        let mut file = OpenOptions::new().append(true).open(path)?;
         file.write_all(self.as_ref())
     }
 
@@ -1,11 +1,7 @@
 /// Masks text with masking options.
 pub(crate) fn mask_text(text: &str, num_prefix_chars: usize, num_suffix_chars: usize) -> String {
     let length = text.len();
-    let suffix_index = if length > num_suffix_chars {
-        length - num_suffix_chars
-    } else {
-        0
-    };
+    let suffix_index = length.saturating_sub(num_suffix_chars);
 
     let mut masked_text = String::with_capacity(length);
     for (i, c) in text.chars().enumerate() {
@@ -0,0 +1,91 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    thread::LocalKey,
+};
+
+/// A runtime agnostic task-local storage.
+///
+/// The value is moved into a thread-local slot whenever the scoped future is polled,
+/// and moved back out after the poll, so it works with any async runtime.
+pub(crate) struct TaskLocal<T: 'static> {
+    /// Thread-local slot for the value of the running task.
+    slot: &'static LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> TaskLocal<T> {
+    /// Creates a new instance with the thread-local slot.
+    #[inline]
+    pub(crate) const fn new(slot: &'static LocalKey<RefCell<Option<T>>>) -> Self {
+        Self { slot }
+    }
+
+    /// Runs the future with the value as the task-local value.
+    #[inline]
+    pub(crate) async fn scope<F: Future>(&'static self, value: T, f: F) -> F::Output {
+        Scope {
+            slot: self.slot,
+            value: Some(value),
+            future: Box::pin(f),
+        }
+        .await
+    }
+
+    /// Accesses the task-local value. It returns `None` if the current task
+    /// is not running in a scope of the task-local value.
+    #[inline]
+    pub(crate) fn try_with<R>(&'static self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.slot
+            .try_with(|slot| slot.borrow_mut().as_mut().map(f))
+            .ok()
+            .flatten()
+    }
+}
+
+/// A future which runs with a task-local value.
+struct Scope<T: 'static, F> {
+    /// Thread-local slot for the value.
+    slot: &'static LocalKey<RefCell<Option<T>>>,
+    /// Task-local value when the future is not being polled.
+    value: Option<T>,
+    /// Inner future.
+    future: Pin<Box<F>>,
+}
+
+impl<T: 'static, F> Unpin for Scope<T, F> {}
+
+impl<T: 'static, F: Future> Future for Scope<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let prev = this.slot.with(|slot| slot.replace(this.value.take()));
+        let guard = Guard {
+            slot: this.slot,
+            value: &mut this.value,
+            prev,
+        };
+        let output = this.future.as_mut().poll(cx);
+        drop(guard);
+        output
+    }
+}
+
+/// A guard which moves the task-local value back out of the slot,
+/// even if the inner future panics.
+struct Guard<'a, T: 'static> {
+    /// Thread-local slot for the value.
+    slot: &'static LocalKey<RefCell<Option<T>>>,
+    /// Task-local value to be restored.
+    value: &'a mut Option<T>,
+    /// Previous value in the slot.
+    prev: Option<T>,
+}
+
+impl<T: 'static> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        *self.value = self.slot.with(|slot| slot.replace(self.prev.take()));
+    }
+}
@@ -2,6 +2,7 @@
 #![doc(html_favicon_url = "https://photino.github.io/zino-docs-zh/assets/zino-logo.png")]
 #![doc(html_logo_url = "https://photino.github.io/zino-docs-zh/assets/zino-logo.svg")]
 #![allow(async_fn_in_trait)]
+#![allow(clippy::result_large_err)]
 #![allow(stable_features)]
 #![forbid(unsafe_code)]
 #![feature(associated_type_defaults)]
@@ -10,6 +11,7 @@
 #![feature(doc_auto_cfg)]
 #![feature(lazy_cell)]
 #![feature(let_chains)]
+#![feature(return_type_notation)]
 #![feature(slice_first_last_chunk)]
 
 mod crypto;
@@ -0,0 +1,128 @@
+use self::Aggregation::*;
+use std::fmt;
+
+/// An aggregate function applied to a field in the aggregation pipeline of [`Query`].
+///
+/// [`Query`]: super::Query
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Counts the number of rows. The field `*` is used to count all rows.
+    Count(String),
+    /// Counts the number of distinct values.
+    CountDistinct(String),
+    /// Sums the values.
+    Sum(String),
+    /// Computes the average of the values.
+    Avg(String),
+    /// Finds the minimum value.
+    Min(String),
+    /// Finds the maximum value.
+    Max(String),
+}
+
+impl Aggregation {
+    /// Parses the aggregation from an expression such as `sum(score)`,
+    /// `count(*)`, `count(distinct status)` or `count_distinct(status)`.
+    pub fn parse(expr: &str) -> Option<Self> {
+        let (function, field) = expr.trim().strip_suffix(')')?.split_once('(')?;
+        let field = field.trim();
+        if field.is_empty() {
+            return None;
+        }
+        let aggregation = match function.trim() {
+            "count" => {
+                if let Some(field) = field.strip_prefix("distinct ") {
+                    CountDistinct(field.trim().to_owned())
+                } else {
+                    Count(field.to_owned())
+                }
+            }
+            "count_distinct" => CountDistinct(field.to_owned()),
+            "sum" => Sum(field.to_owned()),
+            "avg" => Avg(field.to_owned()),
+            "min" => Min(field.to_owned()),
+            "max" => Max(field.to_owned()),
+            _ => return None,
+        };
+        Some(aggregation)
+    }
+
+    /// Returns the name of the aggregate function.
+    #[inline]
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            Count(_) => "count",
+            CountDistinct(_) => "count_distinct",
+            Sum(_) => "sum",
+            Avg(_) => "avg",
+            Min(_) => "min",
+            Max(_) => "max",
+        }
+    }
+
+    /// Returns the field to be aggregated.
+    #[inline]
+    pub fn field(&self) -> &str {
+        match self {
+            Count(field) | CountDistinct(field) | Sum(field) | Avg(field) | Min(field)
+            | Max(field) => field,
+        }
+    }
+
+    /// Returns the default alias of the aggregation, i.e. `count` for `count(*)`
+    /// and `{field}_{function}` for the others.
+    pub fn default_alias(&self) -> String {
+        let field = self.field();
+        let function_name = self.function_name();
+        if field == "*" {
+            function_name.to_owned()
+        } else {
+            [field, function_name].join("_")
+        }
+    }
+
+    /// Returns `true` if the aggregate function requires numeric values.
+    #[inline]
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Sum(_) | Avg(_))
+    }
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let field = self.field();
+        match self {
+            CountDistinct(_) => write!(f, "count(distinct {field})"),
+            _ => write!(f, "{}({field})", self.function_name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Aggregation;
+
+    #[test]
+    fn it_parses_aggregations() {
+        let count = Aggregation::parse("count(*)").unwrap();
+        assert_eq!(count, Aggregation::Count("*".to_owned()));
+        assert_eq!(count.default_alias(), "count");
+
+        let count_distinct = Aggregation::parse("count(distinct status)").unwrap();
+        assert_eq!(count_distinct.default_alias(), "status_count_distinct");
+        assert_eq!(count_distinct.to_string(), "count(distinct status)");
+        assert_eq!(
+            Aggregation::parse("count_distinct(status)"),
+            Some(count_distinct)
+        );
+
+        let sum = Aggregation::parse(" sum( score ) ").unwrap();
+        assert_eq!(sum, Aggregation::Sum("score".to_owned()));
+        assert!(sum.is_numeric());
+
+        assert_eq!(Aggregation::parse("median(score)"), None);
+        assert_eq!(Aggregation::parse("max()"), None);
+        assert_eq!(Aggregation::parse("score"), None);
+    }
+}
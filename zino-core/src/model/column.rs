@@ -180,6 +180,29 @@ impl<'a> Column<'a> {
         self.has_attribute("write_only")
     }
 
+    /// Returns `true` if the column has an integer type.
+    pub fn is_integer(&self) -> bool {
+        let type_name = self.type_name();
+        let type_name = type_name
+            .strip_prefix("Option<")
+            .and_then(|s| s.strip_suffix('>'))
+            .unwrap_or(type_name);
+        matches!(
+            type_name,
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+        )
+    }
+
+    /// Returns `true` if the column has a numeric type.
+    pub fn is_numeric(&self) -> bool {
+        let type_name = self.type_name();
+        let type_name = type_name
+            .strip_prefix("Option<")
+            .and_then(|s| s.strip_suffix('>'))
+            .unwrap_or(type_name);
+        self.is_integer() || matches!(type_name, "f32" | "f64" | "Decimal")
+    }
+
     /// Returns the Avro schema.
     pub fn schema(&self) -> Schema {
         let type_name = self.type_name();
@@ -2,6 +2,7 @@
 use crate::{validation::Validation, AvroValue, JsonValue, Map, Record};
 use serde::{de::DeserializeOwned, Serialize};
 
+mod aggregation;
 mod column;
 mod context;
 mod hook;
@@ -14,6 +15,7 @@ mod translation;
 #[doc(no_inline)]
 pub use apache_avro::schema;
 
+pub use aggregation::Aggregation;
 pub use column::{Column, EncodeColumn};
 pub use context::QueryContext;
 pub use hook::ModelHooks;
@@ -1,4 +1,7 @@
+use super::{Aggregation, Column};
 use crate::{
+    encoding::base64,
+    error::Error,
     extension::{JsonObjectExt, JsonValueExt},
     validation::Validation,
     JsonValue, Map, SharedString,
@@ -17,6 +20,12 @@ pub struct Query {
     offset: usize,
     // Limit.
     limit: usize,
+    // Keyset cursor.
+    cursor: Option<Map>,
+    // A flag indicating whether the cursor is used to fetch the previous page.
+    backward: bool,
+    // Aggregations with aliases.
+    aggregations: Vec<(String, Aggregation)>,
 }
 
 impl Query {
@@ -29,6 +38,9 @@ impl Query {
             sort_order: Vec::new(),
             offset: 0,
             limit: 0,
+            cursor: None,
+            backward: false,
+            aggregations: Vec::new(),
         }
     }
 
@@ -77,6 +89,46 @@ impl Query {
                         }
                     }
                 }
+                "cursor" | "after" | "before" => {
+                    if let Some(cursor) = value.as_str() {
+                        match Self::parse_cursor(cursor) {
+                            Ok(mut cursor) => {
+                                let backward = cursor.remove("$before").is_some();
+                                self.cursor = Some(cursor);
+                                self.backward = match key.as_str() {
+                                    "after" => false,
+                                    "before" => true,
+                                    _ => backward,
+                                };
+                            }
+                            Err(err) => validation.record_fail(key.to_owned(), err),
+                        }
+                    }
+                }
+                "aggregate" | "aggregations" => {
+                    if let Some(exprs) = value.parse_str_array() {
+                        for expr in exprs {
+                            let (alias, expr) = match expr.split_once(':') {
+                                Some((alias, expr)) => (Some(alias.trim()), expr),
+                                None => (None, expr),
+                            };
+                            if let Some(alias) = alias
+                                && !is_identifier(alias)
+                            {
+                                let message = format!("invalid aggregation alias `{alias}`");
+                                validation.record(key.to_owned(), message);
+                            } else if let Some(aggregation) = Aggregation::parse(expr) {
+                                let alias = alias
+                                    .map(|s| s.to_owned())
+                                    .unwrap_or_else(|| aggregation.default_alias());
+                                self.aggregations.push((alias, aggregation));
+                            } else {
+                                let message = format!("invalid aggregation `{expr}`");
+                                validation.record(key.to_owned(), message);
+                            }
+                        }
+                    }
+                }
                 "current_page" => {
                     if let Some(result) = value.parse_usize() {
                         match result {
@@ -85,7 +137,7 @@ impl Query {
                         }
                     }
                 }
-                "populate" | "translate" | "show_deleted" | "validate_only" => {
+                "populate" | "translate" | "show_deleted" | "validate_only" | "keyset" => {
                     if let Some(result) = value.parse_bool() {
                         match result {
                             Ok(flag) => {
@@ -144,6 +196,58 @@ impl Query {
         filters
     }
 
+    /// Parses the opaque cursor as a map of the keyset values.
+    fn parse_cursor(cursor: &str) -> Result<Map, Error> {
+        let bytes = base64::decode_url_safe(cursor)?;
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+
+    /// Encodes the keyset values of the model as an opaque cursor.
+    fn encode_cursor(&self, model: &Map, backward: bool) -> Option<String> {
+        let mut cursor = Map::new();
+        for (field, _) in self.sort_order.iter() {
+            let value = model.get(field.as_ref())?;
+            cursor.upsert(field.as_ref(), value.clone());
+        }
+        if cursor.is_empty() {
+            return None;
+        }
+        if backward {
+            cursor.upsert("$before", true);
+        }
+        serde_json::to_vec(&cursor)
+            .ok()
+            .map(base64::encode_url_safe)
+    }
+
+    /// Returns the cursor to fetch the page after the models,
+    /// which have been selected by the query.
+    pub fn next_cursor(&self, models: &[Map]) -> Option<String> {
+        let has_next = if self.backward {
+            true
+        } else {
+            self.limit > 0 && models.len() >= self.limit
+        };
+        has_next
+            .then(|| models.last())
+            .flatten()
+            .and_then(|model| self.encode_cursor(model, false))
+    }
+
+    /// Returns the cursor to fetch the page before the models,
+    /// which have been selected by the query.
+    pub fn prev_cursor(&self, models: &[Map]) -> Option<String> {
+        let has_prev = if self.backward {
+            self.limit > 0 && models.len() >= self.limit
+        } else {
+            self.cursor.is_some()
+        };
+        has_prev
+            .then(|| models.first())
+            .flatten()
+            .and_then(|model| self.encode_cursor(model, true))
+    }
+
     /// Retains the projection fields in the allow list.
     /// If the projection fields are empty, it will be set to the list.
     #[inline]
@@ -194,6 +298,81 @@ impl Query {
         self.limit = limit;
     }
 
+    /// Sets the fields to group the rows by in the aggregation.
+    #[inline]
+    pub fn group_by(&mut self, fields: &[&str]) {
+        self.filters.upsert("$group", fields);
+    }
+
+    /// Adds an aggregation with the alias. If the alias is empty,
+    /// the default alias of the aggregation will be used.
+    #[inline]
+    pub fn aggregate(&mut self, alias: impl Into<String>, aggregation: Aggregation) {
+        let mut alias = alias.into();
+        if alias.is_empty() {
+            alias = aggregation.default_alias();
+        }
+        self.aggregations.retain(|(s, _)| s != &alias);
+        self.aggregations.push((alias, aggregation));
+    }
+
+    /// Adds a filter on the groups, where the keys can be the aggregation aliases.
+    #[inline]
+    pub fn having(&mut self, filter: Map) {
+        if let Some(filters) = self.filters.get_mut("$having")
+            && let Some(filters) = filters.as_array_mut()
+        {
+            filters.push(filter.into());
+        } else {
+            self.filters.upsert("$having", vec![filter]);
+        }
+    }
+
+    /// Validates the group fields, the aggregation aliases and the aggregations
+    /// against the model columns.
+    pub fn validate_aggregations(&self, columns: &[Column]) -> Validation {
+        let mut validation = Validation::new();
+        if let Some(groups) = self.filters.parse_str_array("$group") {
+            for field in groups {
+                if !columns.iter().any(|col| col.name() == field) {
+                    let message = format!("field `{field}` is not a column of the model");
+                    validation.record("$group", message);
+                }
+            }
+        }
+        for (alias, aggregation) in self.aggregations.iter() {
+            if !is_identifier(alias) {
+                let message = format!("invalid aggregation alias `{alias}`");
+                validation.record("aggregations", message);
+                continue;
+            }
+
+            let field = aggregation.field();
+            if field == "*" && matches!(aggregation, Aggregation::Count(_)) {
+                continue;
+            }
+            if let Some(col) = columns.iter().find(|col| col.name() == field) {
+                if aggregation.is_numeric() && !col.is_numeric() {
+                    let message = format!("field `{field}` should be numeric for `{aggregation}`");
+                    validation.record(alias.to_owned(), message);
+                }
+            } else {
+                let message = format!("field `{field}` is not a column of the model");
+                validation.record(alias.to_owned(), message);
+            }
+        }
+        validation
+    }
+
+    /// Sets the keyset cursor with the values of the sort fields.
+    /// The sort order should end with a unique column such as the primary key.
+    /// If `backward` is `true`, the cursor is used to fetch the previous page.
+    #[inline]
+    pub fn set_cursor(&mut self, cursor: Option<Map>, backward: bool) {
+        self.cursor = cursor;
+        self.backward = backward;
+    }
+
     /// Returns a reference to the projection fields.
     #[inline]
     pub fn fields(&self) -> &[String] {
@@ -224,6 +403,54 @@ impl Query {
         self.limit
     }
 
+    /// Returns a reference to the keyset cursor.
+    #[inline]
+    pub fn cursor(&self) -> Option<&Map> {
+        self.cursor.as_ref()
+    }
+
+    /// Returns `true` if the cursor is used to fetch the previous page.
+    #[inline]
+    pub fn is_backward(&self) -> bool {
+        self.cursor.is_some() && self.backward
+    }
+
+    /// Validates the keyset cursor against the sort order.
+    /// Each sort field should be one of the `fields` and have a value in the cursor,
+    /// otherwise the cursor is stale or has been tampered with.
+    pub fn validate_cursor(&self, fields: &[&str]) -> Validation {
+        let mut validation = Validation::new();
+        if let Some(cursor) = self.cursor.as_ref() {
+            for (field, _) in self.sort_order.iter() {
+                let field = field.as_ref();
+                if !fields.contains(&field) {
+                    let message = format!("the sort field `{field}` is not a column");
+                    validation.record("cursor", message);
+                } else if !cursor.contains_key(field) {
+                    let message = format!("the sort field `{field}` is missing in the cursor");
+                    validation.record("cursor", message);
+                }
+            }
+        }
+        validation
+    }
+
+    /// Returns the aggregations with aliases.
+    #[inline]
+    pub fn aggregations(&self) -> &[(String, Aggregation)] {
+        &self.aggregations
+    }
+
+    /// Returns the columns whose references are eagerly loaded with `$join` or `$include`.
+    #[inline]
+    pub fn joined_columns(&self) -> Vec<&str> {
+        let mut columns = self.filters.parse_str_array("$join").unwrap_or_default();
+        if let Some(included_columns) = self.filters.parse_str_array("$include") {
+            columns.extend(included_columns);
+        }
+        columns
+    }
+
     /// Returns `true` if the `flag` has been enabled.
     #[inline]
     pub fn enabled(&self, flag: &str) -> bool {
@@ -236,6 +463,13 @@ impl Query {
         self.enabled("populate")
     }
 
+    /// Returns `true` if the keyset cursor has been requested,
+    /// either by a cursor or by the `keyset` flag for the first page.
+    #[inline]
+    pub fn cursor_enabled(&self) -> bool {
+        self.cursor.is_some() || self.enabled("keyset")
+    }
+
     /// Returns `true` if the `translate` flag has been enabled.
     #[inline]
     pub fn translate_enabled(&self) -> bool {
@@ -264,6 +498,86 @@ impl Default for Query {
             sort_order: Vec::new(),
             offset: 0,
             limit: 10,
+            cursor: None,
+            backward: false,
+            aggregations: Vec::new(),
         }
     }
 }
+
+/// Returns `true` if the string matches `^[A-Za-z_][A-Za-z0-9_]*$`.
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aggregation, Query};
+    use crate::{extension::JsonObjectExt, Map};
+
+    #[test]
+    fn it_encodes_keyset_cursors() {
+        let mut query = Query::default();
+        assert!(!query.cursor_enabled());
+        assert!(query
+            .read_map(&Map::from_entry("keyset", true))
+            .is_success());
+        assert!(query.cursor_enabled());
+        query.set_sort_order("updated_at", true);
+        query.set_sort_order("id", true);
+        query.set_limit(2);
+
+        let mut first_model = Map::from_entry("id", 3);
+        first_model.upsert("updated_at", "2023-11-01T00:00:00Z");
+        let mut last_model = Map::from_entry("id", 2);
+        last_model.upsert("updated_at", "2023-10-01T00:00:00Z");
+        let models = vec![first_model, last_model.clone()];
+        assert!(query.prev_cursor(&models).is_none());
+
+        let next_cursor = query.next_cursor(&models).unwrap();
+        let mut data = Map::from_entry("cursor", next_cursor.as_str());
+        assert!(query.read_map(&data).is_success());
+        assert_eq!(query.cursor(), Some(&last_model));
+        assert!(!query.is_backward());
+
+        let prev_cursor = query.prev_cursor(&models).unwrap();
+        data.upsert("cursor", prev_cursor.as_str());
+        assert!(query.read_map(&data).is_success());
+        assert!(query.is_backward());
+
+        let fields = ["id", "name", "updated_at"];
+        assert!(query.validate_cursor(&fields).is_success());
+        assert!(!query.validate_cursor(&["id", "name"]).is_success());
+        query.set_cursor(Some(Map::from_entry("id", 2)), false);
+        assert!(!query.validate_cursor(&fields).is_success());
+    }
+
+    #[test]
+    fn it_rejects_hostile_aggregation_aliases() {
+        let mut query = Query::default();
+        let data = Map::from_entry("aggregate", "total:sum(score), count(*)");
+        assert!(query.read_map(&data).is_success());
+        assert_eq!(query.aggregations().len(), 2);
+
+        let mut query = Query::default();
+        let data = Map::from_entry(
+            "aggregate",
+            "total` FROM user; DROP TABLE user; --:sum(score)",
+        );
+        assert!(!query.read_map(&data).is_success());
+        assert!(query.aggregations().is_empty());
+
+        let mut query = Query::default();
+        let data = Map::from_entry("aggregate", r#"1st":count(*)"#);
+        assert!(!query.read_map(&data).is_success());
+        assert!(query.aggregations().is_empty());
+
+        let mut query = Query::default();
+        query.aggregate("total`; --", Aggregation::Count("*".into()));
+        assert!(!query.validate_aggregations(&[]).is_success());
+    }
+}
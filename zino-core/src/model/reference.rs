@@ -7,13 +7,46 @@ pub struct Reference<'a> {
     name: &'a str,
     /// Column name.
     column_name: &'a str,
+    /// Snapshot fields of the referenced model, which are projected by the eager loading.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<&'a str>,
+    /// Optional column name of the tenant for the referenced model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant_key: Option<&'a str>,
+    /// A flag to exclude the soft-deleted rows of the referenced model.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    soft_deletable: bool,
 }
 
 impl<'a> Reference<'a> {
     /// Creates a new instance.
     #[inline]
     pub fn new(name: &'a str, column_name: &'a str) -> Self {
-        Self { name, column_name }
+        Self {
+            name,
+            column_name,
+            fields: Vec::new(),
+            tenant_key: None,
+            soft_deletable: false,
+        }
+    }
+
+    /// Sets the snapshot fields of the referenced model, which are used for eager loading.
+    #[inline]
+    pub fn set_fields(&mut self, fields: Vec<&'a str>) {
+        self.fields = fields;
+    }
+
+    /// Sets the tenant column of the referenced model.
+    #[inline]
+    pub fn set_tenant_key(&mut self, tenant_key: Option<&'a str>) {
+        self.tenant_key = tenant_key;
+    }
+
+    /// Sets the flag to exclude the rows whose `status` is `Deleted`.
+    #[inline]
+    pub fn set_soft_deletable(&mut self, soft_deletable: bool) {
+        self.soft_deletable = soft_deletable;
     }
 
     /// Returns the reference name.
@@ -27,4 +60,22 @@ impl<'a> Reference<'a> {
     pub fn column_name(&self) -> &'a str {
         self.column_name
     }
+
+    /// Returns a reference to the snapshot fields of the referenced model.
+    #[inline]
+    pub fn fields(&self) -> &[&'a str] {
+        &self.fields
+    }
+
+    /// Returns the tenant column of the referenced model.
+    #[inline]
+    pub fn tenant_key(&self) -> Option<&'a str> {
+        self.tenant_key
+    }
+
+    /// Returns `true` if the soft-deleted rows of the referenced model are excluded.
+    #[inline]
+    pub fn is_soft_deletable(&self) -> bool {
+        self.soft_deletable
+    }
 }
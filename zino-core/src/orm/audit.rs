@@ -0,0 +1,437 @@
+use super::Schema;
+use crate::{
+    datetime::DateTime, error::Error, extension::JsonObjectExt, helper::TaskLocal, model::Query,
+    request::Context, BoxFuture, JsonValue, Map, Uuid,
+};
+use std::{cell::RefCell, future::Future, sync::OnceLock};
+
+thread_local! {
+    // Slot of the audit context for the running task.
+    static AUDIT_CONTEXT_SLOT: RefCell<Option<AuditContext>> = const { RefCell::new(None) };
+}
+
+/// Audit context for the current task.
+static AUDIT_CONTEXT: TaskLocal<AuditContext> = TaskLocal::new(&AUDIT_CONTEXT_SLOT);
+
+/// Shared sink for the audit entries.
+static AUDIT_SINK: OnceLock<Box<dyn AuditSink>> = OnceLock::new();
+
+/// Request scoped context for the audit trail.
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    /// User ID.
+    user_id: Option<String>,
+    /// Session ID.
+    session_id: Option<String>,
+    /// Request ID.
+    request_id: Option<Uuid>,
+    /// Trace ID.
+    trace_id: Option<Uuid>,
+}
+
+impl AuditContext {
+    /// Creates a new instance from the request context.
+    #[inline]
+    pub fn new(ctx: &Context) -> Self {
+        Self {
+            user_id: None,
+            session_id: ctx.session_id().map(|s| s.to_owned()),
+            request_id: Some(ctx.request_id()),
+            trace_id: Some(ctx.trace_id()),
+        }
+    }
+
+    /// Runs the future with `self` as the audit context of the current task.
+    #[inline]
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        AUDIT_CONTEXT.scope(self, f).await
+    }
+
+    /// Returns a copy of the audit context of the current task.
+    #[inline]
+    pub fn current() -> Option<Self> {
+        AUDIT_CONTEXT.try_with(|ctx| ctx.clone())
+    }
+
+    /// Sets the user ID for the audit context of the current task.
+    /// It has no effects if the task is not running in an audit scope.
+    /// The user ID is also recorded in the current span.
+    #[inline]
+    pub fn set_current_user(user_id: impl ToString) {
+        let user_id = user_id.to_string();
+        tracing::Span::current().record("context.user_id", user_id.as_str());
+        AUDIT_CONTEXT.try_with(|ctx| ctx.user_id = Some(user_id));
+    }
+
+    /// Returns the user ID.
+    #[inline]
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// Returns the session ID.
+    #[inline]
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Returns the request ID.
+    #[inline]
+    pub fn request_id(&self) -> Option<Uuid> {
+        self.request_id
+    }
+
+    /// Returns the trace ID.
+    #[inline]
+    pub fn trace_id(&self) -> Option<Uuid> {
+        self.trace_id
+    }
+}
+
+/// An audit entry for a write operation on the model.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// Model name.
+    model_name: &'static str,
+    /// Model ID.
+    model_id: String,
+    /// Action name.
+    action: &'static str,
+    /// Field-level diff between the old and new values.
+    diff: Map,
+    /// Audit context.
+    context: AuditContext,
+    /// Recorded time.
+    recorded_at: DateTime,
+}
+
+impl AuditEntry {
+    /// Creates a new instance with the audit context of the current task.
+    #[inline]
+    pub fn new(model_name: &'static str, model_id: impl ToString, action: &'static str) -> Self {
+        Self {
+            model_name,
+            model_id: model_id.to_string(),
+            action,
+            diff: Map::new(),
+            context: AuditContext::current().unwrap_or_default(),
+            recorded_at: DateTime::now(),
+        }
+    }
+
+    /// Sets the diff between the old and new values of the model.
+    #[inline]
+    pub fn set_diff(&mut self, old_values: Option<&Map>, new_values: Option<&Map>) {
+        self.diff = Self::compute_diff(old_values, new_values);
+    }
+
+    /// Returns the model name.
+    #[inline]
+    pub fn model_name(&self) -> &'static str {
+        self.model_name
+    }
+
+    /// Returns the model ID.
+    #[inline]
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// Returns the action name.
+    /// It takes one of the values: `insert`, `update`, `soft_delete` and `delete`.
+    #[inline]
+    pub fn action(&self) -> &'static str {
+        self.action
+    }
+
+    /// Returns a reference to the field-level diff.
+    #[inline]
+    pub fn diff(&self) -> &Map {
+        &self.diff
+    }
+
+    /// Returns a reference to the audit context.
+    #[inline]
+    pub fn context(&self) -> &AuditContext {
+        &self.context
+    }
+
+    /// Returns the recorded time.
+    #[inline]
+    pub fn recorded_at(&self) -> DateTime {
+        self.recorded_at
+    }
+
+    /// Consumes the entry and returns as a json object.
+    pub fn into_map(self) -> Map {
+        let context = self.context;
+        let mut map = Map::new();
+        map.upsert("model_name", self.model_name);
+        map.upsert("model_id", self.model_id);
+        map.upsert("action", self.action);
+        map.upsert("diff", self.diff);
+        map.upsert("user_id", context.user_id);
+        map.upsert("session_id", context.session_id);
+        map.upsert("request_id", context.request_id.map(|id| id.to_string()));
+        map.upsert("trace_id", context.trace_id.map(|id| id.to_string()));
+        map.upsert("recorded_at", self.recorded_at.to_string());
+        map
+    }
+
+    /// Records the entry with the shared audit sink.
+    /// If there is no sink, the entry will be emitted as a tracing event.
+    pub async fn record(self) -> Result<(), Error> {
+        if let Some(sink) = AUDIT_SINK.get() {
+            sink.record(self).await
+        } else {
+            let model_name = self.model_name;
+            let model_id = self.model_id.as_str();
+            let action = self.action;
+            let user_id = self.context.user_id();
+            let trace_id = self.context.trace_id().map(|id| id.to_string());
+            let diff = JsonValue::from(self.diff.clone()).to_string();
+            tracing::info!(
+                model_name,
+                model_id,
+                action,
+                user_id,
+                trace_id,
+                diff,
+                "the model has been audited"
+            );
+            Ok(())
+        }
+    }
+
+    /// Computes the field-level diff between the old and new values.
+    /// Each changed field maps to an object with the `old` and `new` values.
+    pub fn compute_diff(old_values: Option<&Map>, new_values: Option<&Map>) -> Map {
+        let empty_map = Map::new();
+        let old_values = old_values.unwrap_or(&empty_map);
+        let new_values = new_values.unwrap_or(&empty_map);
+        let mut diff = Map::new();
+        for (key, old_value) in old_values {
+            let new_value = new_values.get(key).unwrap_or(&JsonValue::Null);
+            if old_value != new_value {
+                let mut change = Map::from_entry("old", old_value.clone());
+                change.upsert("new", new_value.clone());
+                diff.upsert(key, change);
+            }
+        }
+        for (key, new_value) in new_values {
+            if !old_values.contains_key(key) && !new_value.is_null() {
+                let mut change = Map::from_entry("old", JsonValue::Null);
+                change.upsert("new", new_value.clone());
+                diff.upsert(key, change);
+            }
+        }
+        diff
+    }
+
+    /// Sets the shared audit sink. It returns `false` if the sink has been set.
+    #[inline]
+    pub fn set_sink(sink: impl AuditSink) -> bool {
+        AUDIT_SINK.set(Box::new(sink)).is_ok()
+    }
+}
+
+/// A hook running before writing the model selected by the primary key.
+/// It returns the old values if the audit trail is enabled for the model.
+pub(super) async fn before_write<M: Schema>(
+    primary_key: &M::PrimaryKey,
+) -> Result<Option<Map>, Error> {
+    if M::AUDIT_ENABLED {
+        M::find_by_id::<Map>(primary_key).await
+    } else {
+        Ok(None)
+    }
+}
+
+/// A hook running before the mutation of a model selected by the query.
+/// It returns the old values if the audit trail is enabled for the model.
+pub(super) async fn before_mutation<M: Schema>(query: &Query) -> Result<Option<Map>, Error> {
+    if M::AUDIT_ENABLED {
+        let mut audit_query = M::default_query();
+        audit_query.append_filters(&mut query.filters().clone());
+        for (field, descending) in query.sort_order() {
+            audit_query.set_sort_order(field.clone(), *descending);
+        }
+        M::find_one::<Map>(&audit_query).await
+    } else {
+        Ok(None)
+    }
+}
+
+/// A hook running before the mutation or deletion of many models selected by the query.
+/// It returns the old values if the audit trail is enabled for the model.
+/// The number of the audited models is limited by the max rows of a query.
+pub(super) async fn before_mutations<M: Schema>(query: &Query) -> Result<Vec<Map>, Error> {
+    if M::AUDIT_ENABLED {
+        let mut audit_query = M::default_query();
+        audit_query.append_filters(&mut query.filters().clone());
+        audit_query.set_limit(0);
+        M::find::<Map>(&audit_query).await
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// A hook running after inserting the model with the values.
+pub(super) async fn after_insert<M: Schema>(mut new_values: Map, last_insert_id: Option<i64>) {
+    if M::AUDIT_ENABLED {
+        if let Some(last_insert_id) = last_insert_id
+            && M::primary_key_column().auto_increment()
+        {
+            new_values.upsert(M::PRIMARY_KEY_NAME, last_insert_id);
+        }
+        record_changes::<M>("insert", None, Some(&new_values)).await;
+    }
+}
+
+/// A hook running after updating the model with the old values returned by
+/// [`before_write`] or [`before_mutation`]. The action is recorded as `soft_delete`
+/// if the status has been changed to `Deleted`.
+pub(super) async fn after_update<M: Schema>(old_values: Option<Map>) -> Result<(), Error> {
+    let Some(primary_key) = old_values
+        .as_ref()
+        .and_then(|values| values.get(M::PRIMARY_KEY_NAME))
+    else {
+        return Ok(());
+    };
+
+    let mut query = M::default_query();
+    query.add_filter(M::PRIMARY_KEY_NAME, primary_key.clone());
+
+    let new_values = M::find_one::<Map>(&query).await?;
+    let action = update_action(old_values.as_ref(), new_values.as_ref());
+    record_changes::<M>(action, old_values.as_ref(), new_values.as_ref()).await;
+    Ok(())
+}
+
+/// A hook running after inserting many models with the values.
+pub(super) async fn after_inserts<M: Schema>(new_values: Vec<Map>) {
+    for values in new_values {
+        record_changes::<M>("insert", None, Some(&values)).await;
+    }
+}
+
+/// A hook running after updating many models with the old values
+/// returned by [`before_mutations`].
+pub(super) async fn after_updates<M: Schema>(old_values: Vec<Map>) -> Result<(), Error> {
+    let primary_key_name = M::PRIMARY_KEY_NAME;
+    let primary_keys = old_values
+        .iter()
+        .filter_map(|values| values.get(primary_key_name).cloned())
+        .collect::<Vec<_>>();
+    if primary_keys.is_empty() {
+        return Ok(());
+    }
+
+    // The models are selected by the primary keys since the mutation
+    // may have changed the fields of the original filters.
+    let mut query = M::default_query();
+    query.add_filter(primary_key_name, Map::from_entry("$in", primary_keys));
+    query.set_limit(0);
+
+    let new_values = M::find::<Map>(&query).await?;
+    for old_values in old_values {
+        let primary_key = old_values.get(primary_key_name);
+        let new_values = new_values
+            .iter()
+            .find(|values| values.get(primary_key_name) == primary_key);
+        let action = update_action(Some(&old_values), new_values);
+        record_changes::<M>(action, Some(&old_values), new_values).await;
+    }
+    Ok(())
+}
+
+/// A hook running after deleting many models with the old values
+/// returned by [`before_mutations`].
+pub(super) async fn after_deletes<M: Schema>(old_values: Vec<Map>) {
+    for values in old_values {
+        record_changes::<M>("delete", Some(&values), None).await;
+    }
+}
+
+/// Returns the action of an update. It is `soft_delete`
+/// if the status has been changed to `Deleted`.
+fn update_action(old_values: Option<&Map>, new_values: Option<&Map>) -> &'static str {
+    let is_deleted =
+        |values: Option<&Map>| values.and_then(|m| m.get_str("status")) == Some("Deleted");
+    if is_deleted(new_values) && !is_deleted(old_values) {
+        "soft_delete"
+    } else {
+        "update"
+    }
+}
+
+/// A hook running after deleting the model with the old values returned by [`before_write`].
+pub(super) async fn after_delete<M: Schema>(old_values: Option<Map>) {
+    if M::AUDIT_ENABLED {
+        record_changes::<M>("delete", old_values.as_ref(), None).await;
+    }
+}
+
+/// Records the changes of a model in the audit trail.
+/// Write-only fields are excluded from the diff, and the failure is logged
+/// instead of being propagated since the write operation has been committed.
+async fn record_changes<M: Schema>(
+    action: &'static str,
+    old_values: Option<&Map>,
+    new_values: Option<&Map>,
+) {
+    let model_id = new_values
+        .or(old_values)
+        .and_then(|values| values.get(M::PRIMARY_KEY_NAME))
+        .map(|value| match value {
+            JsonValue::String(s) => s.to_owned(),
+            _ => value.to_string(),
+        })
+        .unwrap_or_default();
+    let mut entry = AuditEntry::new(M::MODEL_NAME, model_id, action);
+    entry.set_diff(old_values, new_values);
+    for field in M::write_only_fields() {
+        entry.diff.remove(*field);
+    }
+    if let Err(err) = entry.record().await {
+        tracing::error!(
+            model_name = M::MODEL_NAME,
+            action,
+            "fail to record the audit entry: {err}"
+        );
+    }
+}
+
+/// A sink to store the audit entries.
+pub trait AuditSink: Send + Sync + 'static {
+    /// Records an audit entry.
+    fn record(&self, entry: AuditEntry) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AuditEntry;
+    use crate::{extension::JsonObjectExt, Map};
+
+    #[test]
+    fn it_computes_field_level_diffs() {
+        let mut old_values = Map::from_entry("name", "alice");
+        old_values.upsert("status", "Active");
+        old_values.upsert("version", 1);
+        let mut new_values = old_values.clone();
+        new_values.upsert("status", "Deleted");
+        new_values.upsert("version", 2);
+        new_values.upsert("description", "deleted by admin");
+
+        let diff = AuditEntry::compute_diff(Some(&old_values), Some(&new_values));
+        assert_eq!(diff.len(), 3);
+        assert!(!diff.contains_key("name"));
+        assert_eq!(diff["status"]["old"], "Active");
+        assert_eq!(diff["status"]["new"], "Deleted");
+        assert!(diff["description"]["old"].is_null());
+
+        let diff = AuditEntry::compute_diff(Some(&old_values), None);
+        assert_eq!(diff.len(), 3);
+        assert!(diff["name"]["new"].is_null());
+    }
+}
@@ -1,3 +1,4 @@
+use super::Dialect;
 use crate::{
     extension::JsonObjectExt,
     model::{Column, EncodeColumn},
@@ -5,15 +6,15 @@ use crate::{
 
 /// Extension trait for [`Column`](crate::model::Column).
 pub(super) trait ColumnExt {
-    /// Returns the field definition.
-    fn field_definition(&self, primary_key_name: &str) -> String;
+    /// Returns the field definition for the SQL dialect.
+    fn field_definition(&self, dialect: Dialect, primary_key_name: &str) -> String;
 
-    /// Returns the type annotation.
-    fn type_annotation(&self) -> &'static str;
+    /// Returns the type annotation for the SQL dialect.
+    fn type_annotation(&self, dialect: Dialect) -> &'static str;
 }
 
 impl<'a> ColumnExt for Column<'a> {
-    fn field_definition(&self, primary_key_name: &str) -> String {
+    fn field_definition(&self, dialect: Dialect, primary_key_name: &str) -> String {
         let column_name = self
             .extra()
             .get_str("column_name")
@@ -25,11 +26,7 @@ impl<'a> ColumnExt for Column<'a> {
         }
         if let Some(value) = self.default_value() {
             if self.auto_increment() {
-                definition += if cfg!(any(
-                    feature = "orm-mariadb",
-                    feature = "orm-mysql",
-                    feature = "orm-tidb"
-                )) {
+                definition += if dialect.is_mysql_compatible() {
                     " AUTO_INCREMENT"
                 } else {
                     // PostgreSQL does not support `AUTO INCREMENT` and SQLite does not need it.
@@ -37,14 +34,14 @@ impl<'a> ColumnExt for Column<'a> {
                 };
             } else if self.auto_random() {
                 // Only TiDB supports this feature.
-                definition += if cfg!(feature = "orm-tidb") {
+                definition += if dialect == Dialect::TiDb {
                     " AUTO_RANDOM"
                 } else {
                     ""
                 };
             } else {
                 let value = self.format_value(value);
-                if cfg!(feature = "orm-sqlite") && value.contains('(') {
+                if dialect.is_sqlite() && value.contains('(') {
                     definition = format!("{definition} DEFAULT ({value})");
                 } else {
                     definition = format!("{definition} DEFAULT {value}");
@@ -56,8 +53,8 @@ impl<'a> ColumnExt for Column<'a> {
         definition
     }
 
-    fn type_annotation(&self) -> &'static str {
-        if cfg!(feature = "orm-postgres") {
+    fn type_annotation(&self, dialect: Dialect) -> &'static str {
+        if dialect.is_postgres() {
             match self.column_type() {
                 "UUID" => "::UUID",
                 "BIGINT" | "BIGSERIAL" => "::BIGINT",
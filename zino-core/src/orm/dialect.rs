@@ -0,0 +1,102 @@
+use self::Dialect::*;
+use std::fmt;
+
+/// SQL dialect of a database service.
+///
+/// The dialect is determined by the database driver enabled at compile time,
+/// and it can not be used to select another driver at runtime.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// The MariaDB dialect.
+    MariaDb,
+    /// The MySQL dialect.
+    MySql,
+    /// The PostgreSQL dialect.
+    Postgres,
+    /// The SQLite dialect.
+    Sqlite,
+    /// The TiDB dialect.
+    TiDb,
+}
+
+impl Dialect {
+    /// Returns `true` if `self` is compatible with the MySQL dialect.
+    #[inline]
+    pub fn is_mysql_compatible(&self) -> bool {
+        matches!(self, MariaDb | MySql | TiDb)
+    }
+
+    /// Returns `true` if `self` is the PostgreSQL dialect.
+    #[inline]
+    pub fn is_postgres(&self) -> bool {
+        matches!(self, Postgres)
+    }
+
+    /// Returns `true` if `self` is the SQLite dialect.
+    #[inline]
+    pub fn is_sqlite(&self) -> bool {
+        matches!(self, Sqlite)
+    }
+
+    /// Returns the family name shared by the compatible dialects.
+    /// It takes one of the values: `mysql`, `postgres` and `sqlite`.
+    #[inline]
+    pub fn family_name(&self) -> &'static str {
+        match self {
+            MariaDb | MySql | TiDb => "mysql",
+            Postgres => "postgres",
+            Sqlite => "sqlite",
+        }
+    }
+
+    /// Returns `self` as `&'static str`.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MariaDb => "mariadb",
+            MySql => "mysql",
+            Postgres => "postgres",
+            Sqlite => "sqlite",
+            TiDb => "tidb",
+        }
+    }
+}
+
+impl Default for Dialect {
+    /// Returns the default dialect of the enabled database driver.
+    #[inline]
+    fn default() -> Self {
+        if cfg!(feature = "orm-mariadb") {
+            MariaDb
+        } else if cfg!(feature = "orm-tidb") {
+            TiDb
+        } else if cfg!(feature = "orm-mysql") {
+            MySql
+        } else if cfg!(feature = "orm-postgres") {
+            Postgres
+        } else {
+            Sqlite
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dialect = self.as_str();
+        write!(f, "{dialect}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dialect;
+
+    #[test]
+    fn it_formats_dialect_names() {
+        assert_eq!(Dialect::TiDb.family_name(), "mysql");
+        assert_eq!(Dialect::Postgres.to_string(), "postgres");
+        assert_eq!(Dialect::default().to_string(), super::super::DRIVER_NAME);
+    }
+}
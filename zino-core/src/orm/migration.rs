@@ -0,0 +1,1086 @@
+use super::{column::ColumnExt, query::QueryExt, ConnectionPool, DatabaseDriver, Dialect};
+use crate::{
+    application::PROJECT_DIR,
+    bail,
+    datetime::DateTime,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    model::{Column, EncodeColumn, Query},
+    state::State,
+};
+use sqlx::{database::HasArguments, Database, Executor, FromRow, IntoArguments, Pool};
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// A versioned migration with the `up` and `down` SQL scripts.
+///
+/// Each migration is stored as a directory named `{version}_{name}`
+/// which contains the files `up.sql` and `down.sql`.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// Version.
+    version: String,
+    /// Name.
+    name: String,
+    /// SQL script to apply the migration.
+    up: String,
+    /// SQL script to revert the migration.
+    down: String,
+}
+
+impl Migration {
+    /// Creates a new instance with the current timestamp as the version.
+    #[inline]
+    pub fn new(name: impl Into<String>, up: impl Into<String>, down: impl Into<String>) -> Self {
+        Self {
+            version: DateTime::now().format("%Y%m%d%H%M%S"),
+            name: name.into(),
+            up: up.into(),
+            down: down.into(),
+        }
+    }
+
+    /// Returns the version.
+    #[inline]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the SQL script to apply the migration.
+    #[inline]
+    pub fn up(&self) -> &str {
+        &self.up
+    }
+
+    /// Returns the SQL script to revert the migration.
+    #[inline]
+    pub fn down(&self) -> &str {
+        &self.down
+    }
+
+    /// Loads a migration from the directory named `{version}_{name}`.
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let dir_name = dir.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        let Some((version, name)) = parse_dir_name(dir_name) else {
+            bail!("invalid migration directory `{}`", dir.display());
+        };
+        let up = fs::read_to_string(dir.join("up.sql"))?;
+        let down = fs::read_to_string(dir.join("down.sql"))?;
+        Ok(Self {
+            version: version.to_owned(),
+            name: name.to_owned(),
+            up,
+            down,
+        })
+    }
+
+    /// Loads all the migrations in the directory ordered by version.
+    pub fn load_all(dir: &Path) -> Result<Vec<Self>, Error> {
+        let mut migrations = Vec::new();
+        if dir.exists() {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() && path.join("up.sql").exists() {
+                    migrations.push(Self::load(&path)?);
+                }
+            }
+        }
+        migrations.sort_by(|a, b| a.version.cmp(&b.version).then_with(|| a.name.cmp(&b.name)));
+        Ok(migrations)
+    }
+
+    /// Writes the migration into the directory and returns the path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, Error> {
+        let path = dir.join(format!("{}_{}", self.version, self.name));
+        fs::create_dir_all(&path)?;
+        fs::write(path.join("up.sql"), &self.up)?;
+        fs::write(path.join("down.sql"), &self.down)?;
+        Ok(path)
+    }
+}
+
+/// A migrator which applies or reverts the versioned migrations.
+/// The applied versions are recorded in the migration table.
+///
+/// It is generic over the database, so that the migrations can also be managed
+/// with a pool connected at runtime, such as `sqlx::AnyPool` in the CLI.
+#[derive(Debug)]
+pub struct Migrator<DB: Database = DatabaseDriver> {
+    /// Connection pool.
+    pool: Pool<DB>,
+    /// Migration table name.
+    table_name: String,
+    /// Migration directory.
+    migration_dir: PathBuf,
+}
+
+impl Migrator {
+    /// Creates a new instance for the connection pool.
+    #[inline]
+    pub fn new(connection_pool: &'static ConnectionPool) -> Self {
+        let mut migrator = Self::with_pool(
+            connection_pool.pool().clone(),
+            Self::default_migration_dir(connection_pool.dialect()),
+        );
+        migrator.set_table_name(*MIGRATION_TABLE);
+        migrator
+    }
+
+    /// Returns the default migration directory for the SQL dialect.
+    #[inline]
+    pub fn default_migration_dir(dialect: Dialect) -> PathBuf {
+        MIGRATION_DIR.join(dialect.family_name())
+    }
+}
+
+impl<DB> Migrator<DB>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'r> (String, String): FromRow<'r, DB::Row>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+{
+    /// Creates a new instance with the pool and the migration directory.
+    #[inline]
+    pub fn with_pool(pool: Pool<DB>, migration_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            pool,
+            table_name: DEFAULT_MIGRATION_TABLE.to_owned(),
+            migration_dir: migration_dir.into(),
+        }
+    }
+
+    /// Sets the migration table name.
+    #[inline]
+    pub fn set_table_name(&mut self, table_name: impl Into<String>) {
+        self.table_name = table_name.into();
+    }
+
+    /// Sets the migration directory.
+    #[inline]
+    pub fn set_migration_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.migration_dir = dir.into();
+    }
+
+    /// Returns the migration table name.
+    #[inline]
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Returns the migration directory.
+    #[inline]
+    pub fn migration_dir(&self) -> &Path {
+        &self.migration_dir
+    }
+
+    /// Returns the versions and names of the applied migrations ordered by version.
+    pub async fn applied_migrations(&self) -> Result<Vec<(String, String)>, Error> {
+        self.create_table().await?;
+
+        let table_name = &self.table_name;
+        let sql = format!("SELECT version, name FROM {table_name} ORDER BY version, name;");
+        let migrations = sqlx::query_as(&sql).fetch_all(&self.pool).await?;
+        Ok(migrations)
+    }
+
+    /// Returns the migrations which have not been applied.
+    pub async fn pending_migrations(&self) -> Result<Vec<Migration>, Error> {
+        let applied_migrations = self.applied_migrations().await?;
+        let migrations = Migration::load_all(&self.migration_dir)?
+            .into_iter()
+            .filter(|m| {
+                !applied_migrations
+                    .iter()
+                    .any(|(version, name)| m.version == *version && m.name == *name)
+            })
+            .collect();
+        Ok(migrations)
+    }
+
+    /// Applies the pending migrations in order and returns the number of applied migrations.
+    /// Each migration is executed inside a transaction.
+    pub async fn migrate(&self) -> Result<usize, Error> {
+        let table_name = &self.table_name;
+        let migrations = self.pending_migrations().await?;
+        for migration in migrations.iter() {
+            let version = migration.version();
+            let name = migration.name();
+            let sql = format!(
+                "INSERT INTO {table_name} (version, name) VALUES ({}, {});",
+                Query::escape_string(version),
+                Query::escape_string(name),
+            );
+            let mut transaction = self.pool.begin().await?;
+            if !is_blank_script(migration.up()) {
+                (&mut *transaction).execute(migration.up()).await?;
+            }
+            (&mut *transaction).execute(sql.as_str()).await?;
+            transaction.commit().await?;
+            tracing::warn!(
+                version,
+                name,
+                "the migration `{version}_{name}` has been applied"
+            );
+        }
+        Ok(migrations.len())
+    }
+
+    /// Reverts the latest applied migrations and returns the number of reverted migrations.
+    pub async fn rollback(&self, steps: usize) -> Result<usize, Error> {
+        let table_name = &self.table_name;
+        let migrations = Migration::load_all(&self.migration_dir)?;
+        let applied_migrations = self.applied_migrations().await?;
+        let mut num_reverted = 0;
+        for (version, name) in applied_migrations.iter().rev().take(steps) {
+            let Some(migration) = migrations
+                .iter()
+                .find(|m| m.version == *version && m.name == *name)
+            else {
+                bail!("the migration `{}_{}` does not exist", version, name);
+            };
+            let sql = format!(
+                "DELETE FROM {table_name} WHERE version = {} AND name = {};",
+                Query::escape_string(version),
+                Query::escape_string(name),
+            );
+            let mut transaction = self.pool.begin().await?;
+            if !is_blank_script(migration.down()) {
+                (&mut *transaction).execute(migration.down()).await?;
+            }
+            (&mut *transaction).execute(sql.as_str()).await?;
+            transaction.commit().await?;
+            num_reverted += 1;
+            tracing::warn!(
+                version,
+                name,
+                "the migration `{version}_{name}` has been reverted"
+            );
+        }
+        Ok(num_reverted)
+    }
+
+    /// Creates the migration table if it does not exist.
+    async fn create_table(&self) -> Result<(), Error> {
+        let table_name = &self.table_name;
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {table_name} (\n  \
+                version VARCHAR(64) NOT NULL,\n  \
+                name VARCHAR(255) NOT NULL,\n  \
+                applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,\n  \
+                PRIMARY KEY (version, name)\n\
+            );"
+        );
+        (&self.pool).execute(sql.as_str()).await?;
+        Ok(())
+    }
+}
+
+/// A column fetched from the database table.
+#[derive(Debug)]
+pub(super) struct TableColumn {
+    /// Column name.
+    name: String,
+    /// Column type.
+    column_type: String,
+    /// Default value.
+    default_value: Option<String>,
+    /// A flag which indicates whether the column is not null.
+    not_null: bool,
+}
+
+impl TableColumn {
+    /// Returns the name.
+    #[inline]
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the column type.
+    #[inline]
+    pub(super) fn column_type(&self) -> &str {
+        &self.column_type
+    }
+
+    /// Returns `true` if the column can not be null.
+    #[inline]
+    pub(super) fn is_not_null(&self) -> bool {
+        self.not_null
+    }
+
+    /// Returns the column definition, which is used to restore the column.
+    /// The `NOT NULL` constraint is only restored for the MySQL-compatible dialects.
+    fn definition(&self, dialect: Dialect) -> String {
+        let column_name = &self.name;
+        let column_type = &self.column_type;
+        let mut definition = format!("{column_name} {column_type}");
+        if dialect.is_mysql_compatible() && self.not_null {
+            definition += " NOT NULL";
+        }
+        if let Some(value) = self.default_value.as_deref() {
+            let value = format_default_value(dialect, value);
+            definition = format!("{definition} DEFAULT {value}");
+        }
+        definition
+    }
+}
+
+/// Fetches the columns of the table ordered by position.
+pub(super) async fn fetch_table_columns(
+    connection_pool: &ConnectionPool,
+    table_name: &str,
+) -> Result<Vec<TableColumn>, Error> {
+    let dialect = connection_pool.dialect();
+    let sql = if dialect.is_mysql_compatible() {
+        let table_schema = connection_pool.database();
+        format!(
+            "SELECT CAST(column_name AS CHAR), CAST(column_type AS CHAR), \
+                    CAST(column_default AS CHAR), CAST(is_nullable AS CHAR) \
+                FROM information_schema.columns \
+                    WHERE table_schema = '{table_schema}' AND table_name = '{table_name}' \
+                        ORDER BY ordinal_position;"
+        )
+    } else if dialect.is_postgres() {
+        format!(
+            "SELECT column_name::text, udt_name::text, column_default::text, is_nullable::text \
+                FROM information_schema.columns \
+                    WHERE table_schema = 'public' AND table_name = '{table_name}' \
+                        ORDER BY ordinal_position;"
+        )
+    } else {
+        format!(
+            "SELECT name, type, dflt_value, CASE \"notnull\" WHEN 0 THEN 'YES' ELSE 'NO' END \
+                FROM pragma_table_info('{table_name}') ORDER BY cid;"
+        )
+    };
+    let rows: Vec<(String, String, Option<String>, String)> = sqlx::query_as(&sql)
+        .fetch_all(connection_pool.pool())
+        .await?;
+    let columns = rows
+        .into_iter()
+        .map(
+            |(name, column_type, default_value, is_nullable)| TableColumn {
+                name,
+                column_type,
+                default_value,
+                not_null: is_nullable.eq_ignore_ascii_case("NO"),
+            },
+        )
+        .collect();
+    Ok(columns)
+}
+
+/// Fetches the names of the indexes in the table.
+async fn fetch_table_indexes(
+    connection_pool: &ConnectionPool,
+    table_name: &str,
+) -> Result<Vec<String>, Error> {
+    let dialect = connection_pool.dialect();
+    let sql = if dialect.is_mysql_compatible() {
+        let table_schema = connection_pool.database();
+        format!(
+            "SELECT DISTINCT CAST(index_name AS CHAR) FROM information_schema.statistics \
+                WHERE table_schema = '{table_schema}' AND table_name = '{table_name}';"
+        )
+    } else if dialect.is_postgres() {
+        format!(
+            "SELECT indexname::text FROM pg_indexes \
+                WHERE schemaname = 'public' AND tablename = '{table_name}';"
+        )
+    } else {
+        format!(
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = '{table_name}';"
+        )
+    };
+    let indexes = sqlx::query_scalar(&sql)
+        .fetch_all(connection_pool.pool())
+        .await?;
+    Ok(indexes)
+}
+
+/// Schema of a table fetched from the database.
+#[derive(Debug, Default)]
+struct TableSchema {
+    /// Columns ordered by position.
+    columns: Vec<TableColumn>,
+    /// Index names.
+    indexes: Vec<String>,
+    /// Table definition, which is only fetched for SQLite.
+    definition: Option<String>,
+    /// Index definitions, which are only fetched for SQLite.
+    index_definitions: Vec<String>,
+}
+
+/// Diffs the columns and indexes against the table schema,
+/// and generates the `up` and `down` SQL scripts for a migration.
+/// It returns `None` if the table schema is up to date.
+pub(super) async fn diff_table_schema(
+    connection_pool: &ConnectionPool,
+    table_name: &str,
+    primary_key_name: &str,
+    columns: &[Column<'_>],
+) -> Result<Option<(String, String)>, Error> {
+    let dialect = connection_pool.dialect();
+    let mut table_schema = TableSchema {
+        columns: fetch_table_columns(connection_pool, table_name).await?,
+        ..TableSchema::default()
+    };
+    if !table_schema.columns.is_empty() {
+        table_schema.indexes = fetch_table_indexes(connection_pool, table_name).await?;
+        if dialect.is_sqlite() {
+            // SQLite does not support altering a column, so the definitions
+            // are required to rebuild the table.
+            table_schema.definition = sqlx::query_scalar(&format!(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = '{table_name}';"
+            ))
+            .fetch_optional(connection_pool.pool())
+            .await?;
+            table_schema.index_definitions = sqlx::query_scalar(&format!(
+                "SELECT sql FROM sqlite_master \
+                    WHERE type = 'index' AND tbl_name = '{table_name}' AND sql IS NOT NULL;"
+            ))
+            .fetch_all(connection_pool.pool())
+            .await?;
+        }
+    }
+    diff_schema(
+        dialect,
+        table_name,
+        primary_key_name,
+        columns,
+        &table_schema,
+    )
+}
+
+/// Diffs the columns and indexes against the table schema fetched from the database.
+fn diff_schema(
+    dialect: Dialect,
+    table_name: &str,
+    primary_key_name: &str,
+    columns: &[Column<'_>],
+    table_schema: &TableSchema,
+) -> Result<Option<(String, String)>, Error> {
+    let mut changes = Vec::new();
+    let table_columns = &table_schema.columns;
+    if table_columns.is_empty() {
+        let definitions = columns
+            .iter()
+            .map(|col| col.field_definition(dialect, primary_key_name))
+            .collect::<Vec<_>>()
+            .join(",\n  ");
+        changes.push((
+            format!("CREATE TABLE IF NOT EXISTS {table_name} (\n  {definitions}\n);"),
+            format!("DROP TABLE IF EXISTS {table_name};"),
+        ));
+        for col in columns {
+            if let Some(index_type) = col.index_type()
+                && let Some(index_definition) =
+                    index_definition(dialect, table_name, column_name(col), index_type)
+            {
+                changes.push((index_definition, String::new()));
+            }
+        }
+        return Ok(Some(format_scripts(changes)));
+    }
+
+    // Column renames, additions and modifications.
+    let mut renamed_columns = Vec::new();
+    let mut added_columns = Vec::new();
+    let mut modified_columns = Vec::new();
+    for col in columns {
+        let column_name = column_name(col);
+        if let Some(table_column) = table_columns.iter().find(|c| c.name == column_name) {
+            if column_name == primary_key_name || col.auto_increment() {
+                continue;
+            }
+
+            let column_type = col.column_type();
+            let type_changed = normalize_column_type(dialect, column_type)
+                != normalize_column_type(dialect, &table_column.column_type);
+            let not_null = col.is_not_null() && col.default_value().is_none();
+            let not_null_changed = not_null != table_column.not_null;
+            if type_changed || not_null_changed {
+                modified_columns.push((col, table_column, type_changed, not_null_changed));
+            }
+        } else if let Some(renamed_from) = col.extra().get_str("renamed_from")
+            && let Some(table_column) = table_columns.iter().find(|c| c.name == renamed_from)
+        {
+            renamed_columns.push((col, table_column));
+        } else {
+            added_columns.push(col);
+        }
+    }
+
+    // Column drops.
+    let dropped_columns = table_columns
+        .iter()
+        .filter(|c| {
+            !columns.iter().any(|col| column_name(col) == c.name)
+                && !renamed_columns.iter().any(|(_, rc)| rc.name == c.name)
+        })
+        .collect::<Vec<_>>();
+
+    // SQLite can not add a `NOT NULL` column without a default value to the existing rows.
+    if dialect.is_sqlite()
+        && let Some(col) = added_columns
+            .iter()
+            .find(|col| col.is_not_null() && col.default_value().is_none())
+    {
+        bail!(
+            "the `NOT NULL` column `{}` can not be added to the table `{}` without a default value",
+            column_name(col),
+            table_name
+        );
+    }
+
+    // Index changes.
+    let table_indexes = &table_schema.indexes;
+    let index_definitions = columns
+        .iter()
+        .filter_map(|col| {
+            let column_name = column_name(col);
+            let index_type = col.index_type()?;
+            let index_definition = index_definition(dialect, table_name, column_name, index_type)?;
+            Some((
+                format!("{table_name}_{column_name}_index"),
+                index_definition,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let dropped_indexes = table_columns
+        .iter()
+        .filter_map(|c| {
+            let column_name = c.name.as_str();
+            let index_name = format!("{table_name}_{column_name}_index");
+            (table_indexes.contains(&index_name)
+                && !index_definitions
+                    .iter()
+                    .any(|(name, _)| name == &index_name))
+            .then_some((index_name, column_name))
+        })
+        .collect::<Vec<_>>();
+    let added_indexes = index_definitions
+        .iter()
+        .filter(|(name, _)| !table_indexes.contains(name))
+        .collect::<Vec<_>>();
+
+    if dialect.is_sqlite() && !modified_columns.is_empty() {
+        // SQLite does not support altering a column, so the table has to be rebuilt.
+        let Some(table_definition) = table_schema
+            .definition
+            .as_deref()
+            .and_then(|sql| sql.find('(').map(|index| &sql[index..]))
+        else {
+            bail!("fail to get the definition of the table `{}`", table_name);
+        };
+
+        let mut new_columns = Vec::new();
+        let mut old_columns = Vec::new();
+        for col in columns {
+            let column_name = column_name(col);
+            if table_columns.iter().any(|c| c.name == column_name) {
+                new_columns.push(column_name);
+                old_columns.push(column_name);
+            } else if let Some((_, table_column)) = renamed_columns
+                .iter()
+                .find(|(rc, _)| column_name == self::column_name(rc))
+            {
+                new_columns.push(column_name);
+                old_columns.push(table_column.name());
+            }
+        }
+
+        let new_table_name = format!("{table_name}_migration");
+        let new_columns = new_columns.join(", ");
+        let old_columns = old_columns.join(", ");
+        let definitions = columns
+            .iter()
+            .map(|col| col.field_definition(dialect, primary_key_name))
+            .collect::<Vec<_>>()
+            .join(",\n  ");
+        let mut up = vec![
+            format!("CREATE TABLE {new_table_name} (\n  {definitions}\n);"),
+            format!(
+                "INSERT INTO {new_table_name} ({new_columns}) \
+                    SELECT {old_columns} FROM {table_name};"
+            ),
+            format!("DROP TABLE {table_name};"),
+            format!("ALTER TABLE {new_table_name} RENAME TO {table_name};"),
+        ];
+        up.extend(
+            index_definitions
+                .into_iter()
+                .map(|(_, definition)| definition),
+        );
+
+        let mut down = vec![
+            format!("CREATE TABLE {new_table_name} {table_definition};"),
+            format!(
+                "INSERT INTO {new_table_name} ({old_columns}) \
+                    SELECT {new_columns} FROM {table_name};"
+            ),
+            format!("DROP TABLE {table_name};"),
+            format!("ALTER TABLE {new_table_name} RENAME TO {table_name};"),
+        ];
+        down.extend(
+            table_schema
+                .index_definitions
+                .iter()
+                .map(|sql| format!("{sql};")),
+        );
+        return Ok(Some((up.join("\n"), down.join("\n"))));
+    }
+
+    for (index_name, column_name) in dropped_indexes {
+        changes.push((
+            drop_index_sql(dialect, table_name, &index_name),
+            format!("CREATE INDEX {index_name} ON {table_name} ({column_name});"),
+        ));
+    }
+    for (col, table_column) in renamed_columns {
+        let column_name = column_name(col);
+        let old_column_name = table_column.name();
+        changes.push((
+            format!("ALTER TABLE {table_name} RENAME COLUMN {old_column_name} TO {column_name};"),
+            format!("ALTER TABLE {table_name} RENAME COLUMN {column_name} TO {old_column_name};"),
+        ));
+    }
+    for col in added_columns {
+        let column_name = column_name(col);
+        let column_definition = col.field_definition(dialect, primary_key_name);
+        changes.push((
+            format!("ALTER TABLE {table_name} ADD COLUMN {column_definition};"),
+            format!("ALTER TABLE {table_name} DROP COLUMN {column_name};"),
+        ));
+    }
+    for (col, table_column, type_changed, not_null_changed) in modified_columns {
+        let column_name = column_name(col);
+        if dialect.is_mysql_compatible() {
+            let column_definition = col.field_definition(dialect, primary_key_name);
+            let old_column_definition = table_column.definition(dialect);
+            changes.push((
+                format!("ALTER TABLE {table_name} MODIFY COLUMN {column_definition};"),
+                format!("ALTER TABLE {table_name} MODIFY COLUMN {old_column_definition};"),
+            ));
+        } else {
+            if type_changed {
+                let column_type = col.column_type();
+                let old_column_type = table_column.column_type();
+                changes.push((
+                    format!(
+                        "ALTER TABLE {table_name} ALTER COLUMN {column_name} \
+                            TYPE {column_type} USING {column_name}::{column_type};"
+                    ),
+                    format!(
+                        "ALTER TABLE {table_name} ALTER COLUMN {column_name} \
+                            TYPE {old_column_type} USING {column_name}::{old_column_type};"
+                    ),
+                ));
+            }
+            if not_null_changed {
+                let (set_not_null, drop_not_null) = (
+                    format!("ALTER TABLE {table_name} ALTER COLUMN {column_name} SET NOT NULL;"),
+                    format!("ALTER TABLE {table_name} ALTER COLUMN {column_name} DROP NOT NULL;"),
+                );
+                if table_column.is_not_null() {
+                    changes.push((drop_not_null, set_not_null));
+                } else {
+                    changes.push((set_not_null, drop_not_null));
+                }
+            }
+        }
+    }
+    for table_column in dropped_columns {
+        let column_name = table_column.name();
+        let column_definition = table_column.definition(dialect);
+        changes.push((
+            format!("ALTER TABLE {table_name} DROP COLUMN {column_name};"),
+            format!("ALTER TABLE {table_name} ADD COLUMN {column_definition};"),
+        ));
+    }
+    for (index_name, index_definition) in added_indexes {
+        changes.push((
+            index_definition.to_owned(),
+            drop_index_sql(dialect, table_name, index_name),
+        ));
+    }
+
+    if changes.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(format_scripts(changes)))
+    }
+}
+
+/// Formats the `up` and `down` SQL scripts. The `down` statements are in the reverse order.
+fn format_scripts(changes: Vec<(String, String)>) -> (String, String) {
+    let mut up = Vec::with_capacity(changes.len());
+    let mut down = Vec::with_capacity(changes.len());
+    for (up_sql, down_sql) in changes {
+        up.push(up_sql);
+        if !down_sql.is_empty() {
+            down.push(down_sql);
+        }
+    }
+    down.reverse();
+    (up.join("\n"), down.join("\n"))
+}
+
+/// Returns the column name in the table.
+fn column_name<'a>(col: &'a Column<'_>) -> &'a str {
+    col.extra()
+        .get_str("column_name")
+        .unwrap_or_else(|| col.name())
+}
+
+/// Returns the definition of a single-column index.
+/// Text search indexes are not included since they are shared by several columns.
+fn index_definition(
+    dialect: Dialect,
+    table_name: &str,
+    column_name: &str,
+    index_type: &str,
+) -> Option<String> {
+    let index_name = format!("{table_name}_{column_name}_index");
+    if dialect.is_mysql_compatible() {
+        let index_type = index_type.to_uppercase();
+        match index_type.as_str() {
+            "UNIQUE" | "SPATIAL" => Some(format!(
+                "CREATE {index_type} INDEX {index_name} ON {table_name} ({column_name});"
+            )),
+            "BTREE" | "HASH" => Some(format!(
+                "CREATE INDEX {index_name} ON {table_name} ({column_name}) USING {index_type};"
+            )),
+            _ => None,
+        }
+    } else if dialect.is_postgres() {
+        if index_type.starts_with("text") {
+            None
+        } else if index_type == "unique" {
+            Some(format!(
+                "CREATE UNIQUE INDEX IF NOT EXISTS {index_name} ON {table_name} ({column_name});"
+            ))
+        } else {
+            let sort_order = if index_type == "btree" { " DESC" } else { "" };
+            Some(format!(
+                "CREATE INDEX IF NOT EXISTS {index_name} \
+                    ON {table_name} USING {index_type}({column_name}{sort_order});"
+            ))
+        }
+    } else if index_type == "unique" {
+        Some(format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS {index_name} ON {table_name} ({column_name});"
+        ))
+    } else {
+        Some(format!(
+            "CREATE INDEX IF NOT EXISTS {index_name} ON {table_name} ({column_name});"
+        ))
+    }
+}
+
+/// Returns the SQL statement to drop an index.
+fn drop_index_sql(dialect: Dialect, table_name: &str, index_name: &str) -> String {
+    if dialect.is_mysql_compatible() {
+        format!("DROP INDEX {index_name} ON {table_name};")
+    } else {
+        format!("DROP INDEX IF EXISTS {index_name};")
+    }
+}
+
+/// Formats the default value fetched from the table schema as an expression.
+fn format_default_value(dialect: Dialect, value: &str) -> Cow<'_, str> {
+    if dialect.is_mysql_compatible() {
+        // MySQL returns the string literals without quotes.
+        if value.parse::<f64>().is_ok()
+            || value.ends_with(')')
+            || value.to_ascii_uppercase().starts_with("CURRENT_TIMESTAMP")
+        {
+            value.into()
+        } else {
+            Query::escape_string(value).into()
+        }
+    } else if dialect.is_sqlite() && value.contains('(') {
+        format!("({value})").into()
+    } else {
+        value.into()
+    }
+}
+
+/// Normalizes the column type so that the synonyms can be compared.
+fn normalize_column_type(dialect: Dialect, column_type: &str) -> String {
+    let column_type = column_type.trim().to_ascii_lowercase();
+    if dialect.is_mysql_compatible() {
+        let column_type = match column_type.as_str() {
+            "bool" | "boolean" => "tinyint(1)".to_owned(),
+            "numeric" | "decimal" => "decimal(10,0)".to_owned(),
+            "integer" => "int".to_owned(),
+            _ => column_type,
+        };
+        // Display widths of integer types are deprecated except for `tinyint(1)`.
+        if column_type != "tinyint(1)"
+            && let Some((prefix, suffix)) = column_type.split_once('(')
+            && prefix.ends_with("int")
+            && let Some((_, suffix)) = suffix.split_once(')')
+        {
+            format!("{prefix}{suffix}")
+        } else {
+            column_type
+        }
+    } else if dialect.is_postgres() {
+        if let Some(element_type) = column_type.strip_suffix("[]") {
+            return format!("_{}", normalize_column_type(dialect, element_type));
+        }
+
+        let base_type = column_type
+            .split_once('(')
+            .map(|(s, _)| s.trim())
+            .unwrap_or(&column_type);
+        let column_type = match base_type {
+            "bigint" | "bigserial" | "serial8" => "int8",
+            "int" | "integer" | "serial" | "serial4" => "int4",
+            "smallint" | "smallserial" | "serial2" => "int2",
+            "boolean" => "bool",
+            "double precision" => "float8",
+            "real" => "float4",
+            "decimal" => "numeric",
+            "character varying" => "varchar",
+            "character" | "char" => "bpchar",
+            "timestamp with time zone" => "timestamptz",
+            "timestamp without time zone" => "timestamp",
+            "time with time zone" => "timetz",
+            "time without time zone" => "time",
+            _ => base_type,
+        };
+        column_type.to_owned()
+    } else {
+        column_type
+    }
+}
+
+/// Returns `true` if the SQL script has no statements.
+fn is_blank_script(script: &str) -> bool {
+    script
+        .lines()
+        .map(|line| line.trim())
+        .all(|line| line.is_empty() || line.starts_with("--"))
+}
+
+/// Parses the migration directory name as `(version, name)`.
+fn parse_dir_name(dir_name: &str) -> Option<(&str, &str)> {
+    let (version, name) = dir_name.split_once('_')?;
+    (!version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) && !name.is_empty())
+        .then_some((version, name))
+}
+
+/// Default migration table name.
+const DEFAULT_MIGRATION_TABLE: &str = "schema_migrations";
+
+/// Migration table name.
+static MIGRATION_TABLE: LazyLock<&'static str> = LazyLock::new(|| {
+    State::shared()
+        .get_config("migration")
+        .and_then(|config| config.get_str("table"))
+        .unwrap_or(DEFAULT_MIGRATION_TABLE)
+});
+
+/// Migration directory containing the subdirectories for the dialects.
+static MIGRATION_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = State::shared()
+        .get_config("migration")
+        .and_then(|config| config.get_str("dir"))
+        .unwrap_or("migrations");
+    PROJECT_DIR.join(dir)
+});
+
+/// A flag which indicates whether the table schemas are managed by the migrations
+/// instead of altering the tables directly.
+pub(super) static MANAGED_SCHEMA: LazyLock<bool> = LazyLock::new(|| {
+    State::shared()
+        .get_config("migration")
+        .and_then(|config| config.get_bool("managed"))
+        .unwrap_or_default()
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        diff_schema, is_blank_script, normalize_column_type, parse_dir_name, Dialect, TableColumn,
+        TableSchema,
+    };
+    use crate::model::Column;
+
+    fn table_column(name: &str, column_type: &str, not_null: bool) -> TableColumn {
+        TableColumn {
+            name: name.to_owned(),
+            column_type: column_type.to_owned(),
+            default_value: None,
+            not_null,
+        }
+    }
+
+    fn column<'a>(name: &'a str, column_type: &str, not_null: bool) -> Column<'a> {
+        let mut col = Column::new(name, "String", not_null);
+        col.set_extra_attribute("column_type", column_type);
+        col
+    }
+
+    #[test]
+    fn it_parses_migration_dir_name() {
+        assert_eq!(
+            parse_dir_name("20231120083000_migrate_app_user"),
+            Some(("20231120083000", "migrate_app_user"))
+        );
+        assert_eq!(parse_dir_name("migrate_app_user"), None);
+        assert_eq!(parse_dir_name("20231120083000_"), None);
+        assert!(is_blank_script("-- Nothing to do.\n\n"));
+        assert!(!is_blank_script(
+            "-- Add a column.\nALTER TABLE t ADD COLUMN c INT;"
+        ));
+    }
+
+    #[test]
+    fn it_normalizes_column_types() {
+        assert_eq!(normalize_column_type(Dialect::MySql, "INT(11)"), "int");
+        assert_eq!(
+            normalize_column_type(Dialect::MySql, "BOOLEAN"),
+            "tinyint(1)"
+        );
+        assert_eq!(
+            normalize_column_type(Dialect::MySql, "bigint(20) unsigned"),
+            "bigint unsigned"
+        );
+        assert_eq!(
+            normalize_column_type(Dialect::Postgres, "character varying(255)"),
+            "varchar"
+        );
+        assert_eq!(
+            normalize_column_type(Dialect::Postgres, "INTEGER[]"),
+            "_int4"
+        );
+        assert_eq!(
+            normalize_column_type(Dialect::Postgres, "TIMESTAMP WITH TIME ZONE"),
+            "timestamptz"
+        );
+        assert_eq!(
+            normalize_column_type(Dialect::Sqlite, " INTEGER "),
+            "integer"
+        );
+    }
+
+    #[test]
+    fn it_diffs_table_schemas() {
+        let mut primary_key = column("id", "UUID", true);
+        primary_key.set_extra_attribute("primary_key", true);
+        let mut nickname = column("nickname", "TEXT", false);
+        nickname.set_index_type("btree");
+        let mut title = column("title", "TEXT", false);
+        title.set_extra_attribute("renamed_from", "subject");
+        let columns = [
+            primary_key,
+            column("name", "TEXT", false),
+            column("count", "BIGINT", false),
+            nickname,
+            title,
+        ];
+
+        let table_schema = TableSchema {
+            columns: vec![
+                table_column("id", "uuid", true),
+                table_column("name", "text", false),
+                table_column("count", "int4", false),
+                table_column("subject", "text", false),
+                table_column("obsolete", "text", false),
+            ],
+            indexes: vec!["t_obsolete_index".to_owned()],
+            ..TableSchema::default()
+        };
+        let (up, down) = diff_schema(Dialect::Postgres, "t", "id", &columns, &table_schema)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            up.lines().collect::<Vec<_>>(),
+            [
+                "DROP INDEX IF EXISTS t_obsolete_index;",
+                "ALTER TABLE t RENAME COLUMN subject TO title;",
+                "ALTER TABLE t ADD COLUMN nickname TEXT;",
+                "ALTER TABLE t ALTER COLUMN count TYPE BIGINT USING count::BIGINT;",
+                "ALTER TABLE t DROP COLUMN obsolete;",
+                "CREATE INDEX IF NOT EXISTS t_nickname_index ON t USING btree(nickname DESC);",
+            ]
+        );
+        assert_eq!(
+            down.lines().collect::<Vec<_>>(),
+            [
+                "DROP INDEX IF EXISTS t_nickname_index;",
+                "ALTER TABLE t ADD COLUMN obsolete text;",
+                "ALTER TABLE t ALTER COLUMN count TYPE int4 USING count::int4;",
+                "ALTER TABLE t DROP COLUMN nickname;",
+                "ALTER TABLE t RENAME COLUMN title TO subject;",
+                "CREATE INDEX t_obsolete_index ON t (obsolete);",
+            ]
+        );
+
+        let table_schema = TableSchema {
+            columns: vec![
+                table_column("id", "uuid", true),
+                table_column("name", "text", false),
+            ],
+            ..TableSchema::default()
+        };
+        let columns = [column("id", "UUID", true), column("name", "TEXT", false)];
+        assert!(
+            diff_schema(Dialect::Postgres, "t", "id", &columns, &table_schema)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn it_rebuilds_sqlite_tables() {
+        let table_schema = TableSchema {
+            columns: vec![
+                table_column("id", "TEXT", false),
+                table_column("count", "TEXT", false),
+            ],
+            definition: Some("CREATE TABLE t (id TEXT PRIMARY KEY, count TEXT)".to_owned()),
+            ..TableSchema::default()
+        };
+        let columns = [
+            column("id", "TEXT", true),
+            column("count", "INTEGER", false),
+            column("note", "TEXT", false),
+        ];
+        let (up, down) = diff_schema(Dialect::Sqlite, "t", "id", &columns, &table_schema)
+            .unwrap()
+            .unwrap();
+        assert!(up.contains("INSERT INTO t_migration (id, count) SELECT id, count FROM t;"));
+        assert!(down.contains("CREATE TABLE t_migration (id TEXT PRIMARY KEY, count TEXT);"));
+
+        let columns = [
+            column("id", "TEXT", true),
+            column("count", "INTEGER", false),
+            column("note", "TEXT", true),
+        ];
+        let err = diff_schema(Dialect::Sqlite, "t", "id", &columns, &table_schema).unwrap_err();
+        assert!(err.message().contains("`note`"));
+
+        let mut note = column("note", "TEXT", true);
+        note.set_default_value("draft");
+        let columns = [
+            column("id", "TEXT", true),
+            column("count", "INTEGER", false),
+            note,
+        ];
+        diff_schema(Dialect::Sqlite, "t", "id", &columns, &table_schema).unwrap();
+    }
+}
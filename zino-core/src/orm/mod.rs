@@ -56,6 +56,14 @@
 //!     "category": "Rustacean",
 //! });
 //! let records = User::query::<Record>(sql, params.as_object()).await?;
+//!
+//! // Eager-loads the referenced models of the joinable columns in a single round trip,
+//! // which decodes the nested objects in the `manager_id_populated` and `tags_populated` fields.
+//! let query = Query::new(json!({
+//!     "status": "Active",
+//!     "$join": ["manager_id", "tags"],
+//! }));
+//! let users = User::find::<Map>(&query).await?;
 //! ```
 //!
 //! [`Mongoose`]: https://mongoosejs.com/
@@ -80,17 +88,25 @@ use std::{
 use toml::value::Table;
 
 mod accessor;
+mod audit;
 mod column;
 mod decode;
+mod dialect;
 mod helper;
+mod migration;
 mod mutation;
 mod query;
 mod schema;
+mod tenant;
 
 pub use accessor::ModelAccessor;
+pub use audit::{AuditContext, AuditEntry, AuditSink};
 pub use decode::{decode, decode_array};
+pub use dialect::Dialect;
 pub use helper::ModelHelper;
+pub use migration::{Migration, Migrator};
 pub use schema::Schema;
+pub use tenant::TenantContext;
 
 cfg_if::cfg_if! {
     if #[cfg(any(feature = "orm-mariadb", feature = "orm-mysql", feature = "orm-tidb"))] {
@@ -206,6 +222,8 @@ pub struct ConnectionPool {
     name: &'static str,
     /// Database.
     database: &'static str,
+    /// SQL dialect.
+    dialect: Dialect,
     /// Pool.
     pool: Pool<DatabaseDriver>,
     /// Availability.
@@ -237,6 +255,12 @@ impl ConnectionPool {
         self.database
     }
 
+    /// Returns the SQL dialect.
+    #[inline]
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
     /// Returns a reference to the pool.
     #[inline]
     pub fn pool(&self) -> &Pool<DatabaseDriver> {
@@ -296,6 +320,7 @@ impl ConnectionPool {
         Self {
             name,
             database,
+            dialect: Dialect::default(),
             pool,
             available: AtomicBool::new(true),
         }
@@ -1,5 +1,5 @@
 /// Generates SQL `SET` expressions.
-use super::{query::QueryExt, DatabaseDriver, Schema};
+use super::{query::QueryExt, tenant, DatabaseDriver, Schema};
 use crate::model::{EncodeColumn, Mutation, Query};
 
 /// Extension trait for [`Mutation`](crate::model::Mutation).
@@ -24,7 +24,9 @@ impl MutationExt<DatabaseDriver> for Mutation {
                     if let Some(update) = value.as_object() {
                         for (key, value) in update.iter() {
                             if fields.contains(key)
-                                && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
+                                && let Some(col) = M::get_column(key).filter(|c| {
+                                    !c.is_read_only() && !tenant::is_tenant_key::<M>(key)
+                                })
                             {
                                 let key = Query::format_field(key);
                                 let value = col.encode_value(Some(value));
@@ -38,7 +40,9 @@ impl MutationExt<DatabaseDriver> for Mutation {
                     if let Some(update) = value.as_object() {
                         for (key, value) in update.iter() {
                             if fields.contains(key)
-                                && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
+                                && let Some(col) = M::get_column(key).filter(|c| {
+                                    !c.is_read_only() && !tenant::is_tenant_key::<M>(key)
+                                })
                             {
                                 let key = Query::format_field(key);
                                 let value = col.encode_value(Some(value));
@@ -52,7 +56,9 @@ impl MutationExt<DatabaseDriver> for Mutation {
                     if let Some(update) = value.as_object() {
                         for (key, value) in update.iter() {
                             if fields.contains(key)
-                                && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
+                                && let Some(col) = M::get_column(key).filter(|c| {
+                                    !c.is_read_only() && !tenant::is_tenant_key::<M>(key)
+                                })
                             {
                                 let key = Query::format_field(key);
                                 let value = col.encode_value(Some(value));
@@ -66,7 +72,9 @@ impl MutationExt<DatabaseDriver> for Mutation {
                     if let Some(update) = value.as_object() {
                         for (key, value) in update.iter() {
                             if fields.contains(key)
-                                && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
+                                && let Some(col) = M::get_column(key).filter(|c| {
+                                    !c.is_read_only() && !tenant::is_tenant_key::<M>(key)
+                                })
                             {
                                 let key = Query::format_field(key);
                                 let value = col.encode_value(Some(value));
@@ -78,7 +86,8 @@ impl MutationExt<DatabaseDriver> for Mutation {
                 }
                 _ => {
                     if (permissive || fields.contains(key))
-                        && let Some(col) = M::get_column(key).filter(|c| !c.is_read_only())
+                        && let Some(col) = M::get_column(key)
+                            .filter(|c| !c.is_read_only() && !tenant::is_tenant_key::<M>(key))
                     {
                         let key = Query::format_field(key);
                         let value = col.encode_value(Some(value));
@@ -3,7 +3,7 @@ use crate::{
     datetime::DateTime,
     error::Error,
     extension::{JsonObjectExt, JsonValueExt},
-    model::{Column, DecodeRow, EncodeColumn, Query},
+    model::{Aggregation, Column, DecodeRow, EncodeColumn, Query},
     AvroValue, JsonValue, Map, Record, SharedString, Uuid,
 };
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
@@ -280,7 +280,7 @@ impl<'c> EncodeColumn<DatabaseDriver> for Column<'c> {
                                 .collect::<Vec<_>>()
                                 .join(" OR ")
                         } else {
-                            let value = Query::escape_string(format!("{value}"));
+                            let value = Query::escape_string(value);
                             format!(r#"{field} RLIKE {value}"#)
                         }
                     } else if value.contains(',') {
@@ -552,6 +552,26 @@ impl QueryExt<DatabaseDriver> for Query {
         self.limit()
     }
 
+    #[inline]
+    fn query_cursor(&self) -> Option<&Map> {
+        self.cursor()
+    }
+
+    #[inline]
+    fn query_backward(&self) -> bool {
+        self.is_backward()
+    }
+
+    #[inline]
+    fn joined_columns(&self) -> Vec<&str> {
+        Query::joined_columns(self)
+    }
+
+    #[inline]
+    fn query_aggregations(&self) -> &[(String, Aggregation)] {
+        self.aggregations()
+    }
+
     #[inline]
     fn placeholder(_n: usize) -> SharedString {
         "?".into()
@@ -569,12 +589,12 @@ impl QueryExt<DatabaseDriver> for Query {
         if field.contains('.') {
             field
                 .split('.')
-                .map(|s| format!("`{s}`"))
+                .map(|s| format!("`{}`", s.replace('`', "``")))
                 .collect::<Vec<_>>()
                 .join(".")
                 .into()
         } else {
-            format!("`{field}`").into()
+            format!("`{}`", field.replace('`', "``")).into()
         }
     }
 
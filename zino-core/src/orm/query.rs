@@ -1,7 +1,7 @@
-use super::Schema;
+use super::{tenant, Dialect, Schema};
 use crate::{
     extension::{JsonObjectExt, JsonValueExt},
-    model::EncodeColumn,
+    model::{Aggregation, EncodeColumn},
     JsonValue, Map, SharedString,
 };
 use std::{borrow::Cow, fmt::Display};
@@ -29,6 +29,18 @@ pub(super) trait QueryExt<DB> {
     /// Returns the query limit.
     fn query_limit(&self) -> usize;
 
+    /// Returns the keyset cursor.
+    fn query_cursor(&self) -> Option<&Map>;
+
+    /// Returns `true` if the cursor is used to fetch the previous page.
+    fn query_backward(&self) -> bool;
+
+    /// Returns the columns whose references are eagerly loaded.
+    fn joined_columns(&self) -> Vec<&str>;
+
+    /// Returns the aggregations with aliases.
+    fn query_aggregations(&self) -> &[(String, Aggregation)];
+
     /// Returns a placeholder for the n-th parameter.
     fn placeholder(n: usize) -> SharedString;
 
@@ -78,10 +90,11 @@ pub(super) trait QueryExt<DB> {
         }
     }
 
-    /// Formats the query filters to generate SQL `WHERE` expression.
-    fn format_filters<M: Schema>(&self) -> String {
+    /// Formats the query filters to generate SQL `WHERE` expression in the SQL dialect.
+    fn format_filters<M: Schema>(&self, dialect: Dialect) -> String {
         let filters = self.query_filters();
-        if filters.is_empty() {
+        let tenant_filter = tenant::format_tenant_filter::<M>();
+        if filters.is_empty() && self.query_cursor().is_none() && tenant_filter.is_none() {
             return String::new();
         }
 
@@ -115,13 +128,9 @@ pub(super) trait QueryExt<DB> {
                 }
                 "$rand" => {
                     if let Some(Ok(value)) = value.parse_f64() {
-                        let condition = if cfg!(any(
-                            feature = "orm-mariadb",
-                            feature = "orm-mysql",
-                            feature = "orm-tidb"
-                        )) {
+                        let condition = if dialect.is_mysql_compatible() {
                             format!("rand() < {value}")
-                        } else if cfg!(feature = "orm-postgres") {
+                        } else if dialect.is_postgres() {
                             format!("random() < {value}")
                         } else {
                             let value = (value * i64::MAX as f64) as i64;
@@ -152,6 +161,12 @@ pub(super) trait QueryExt<DB> {
                 }
             }
         }
+        if let Some(condition) = self.format_keyset::<M>() {
+            conditions.push(condition);
+        }
+        if let Some(condition) = tenant_filter {
+            conditions.push(condition);
+        }
         if !conditions.is_empty() {
             expression += &format!("WHERE {}", conditions.join(" AND "));
         };
@@ -163,8 +178,10 @@ pub(super) trait QueryExt<DB> {
                 .join(", ");
             expression += &format!(" GROUP BY {groups}");
             if let Some(filters) = filters.get_array("$having") {
-                let condition = Self::format_logical_filters::<M>(filters, " AND ");
-                expression += &format!(" HAVING {condition}");
+                let condition = self.format_having::<M>(filters, dialect);
+                if !condition.is_empty() {
+                    expression += &format!(" HAVING {condition}");
+                }
             }
         }
         expression
@@ -260,16 +277,285 @@ pub(super) trait QueryExt<DB> {
         }
     }
 
+    /// Formats the keyset cursor to generate a SQL condition.
+    /// The cursor should have been checked by [`Query::validate_cursor()`],
+    /// since it is ignored if any of the sort fields is not a column or has no value.
+    fn format_keyset<M: Schema>(&self) -> Option<String> {
+        let cursor = self.query_cursor()?;
+        let backward = self.query_backward();
+        let mut keys = Vec::new();
+        for (key, descending) in self.query_order() {
+            let col = M::get_column(key)?;
+            let value = col.encode_value(Some(cursor.get(key.as_ref())?));
+            keys.push((Self::format_field(key), value, *descending != backward));
+        }
+
+        let (_, _, descending) = keys.first()?;
+        if keys.iter().all(|key| key.2 == *descending) {
+            let operator = if *descending { "<" } else { ">" };
+            let fields = keys
+                .iter()
+                .map(|(field, ..)| field.as_ref())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let values = keys
+                .iter()
+                .map(|(_, value, _)| value.as_ref())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(format!("({fields}) {operator} ({values})"))
+        } else {
+            let mut conditions = Vec::with_capacity(keys.len());
+            for (index, (field, value, descending)) in keys.iter().enumerate() {
+                let operator = if *descending { "<" } else { ">" };
+                let mut expressions = keys[..index]
+                    .iter()
+                    .map(|(field, value, _)| format!("{field} = {value}"))
+                    .collect::<Vec<_>>();
+                expressions.push(format!("{field} {operator} {value}"));
+                conditions.push(format!("({})", expressions.join(" AND ")));
+            }
+            Some(format!("({})", conditions.join(" OR ")))
+        }
+    }
+
+    /// Formats the `HAVING` conditions, where the aggregation aliases
+    /// are replaced with the aggregate expressions.
+    fn format_having<M: Schema>(&self, filters: &[JsonValue], dialect: Dialect) -> String {
+        let aggregations = self.query_aggregations();
+        let mut conditions = Vec::with_capacity(filters.len());
+        for filter in filters {
+            if let JsonValue::Object(filter) = filter {
+                let mut column_filter = Map::new();
+                for (key, value) in filter {
+                    if let Some((_, aggregation)) =
+                        aggregations.iter().find(|(alias, _)| alias == key)
+                    {
+                        let expr = Self::format_aggregation::<M>(aggregation, dialect);
+                        let condition = Self::format_aggregation_filter(&expr, value);
+                        if !condition.is_empty() {
+                            conditions.push(condition);
+                        }
+                    } else {
+                        column_filter.upsert(key, value.clone());
+                    }
+                }
+                if !column_filter.is_empty() {
+                    let filters = [column_filter.into()];
+                    let condition = Self::format_logical_filters::<M>(&filters, " AND ");
+                    if !condition.is_empty() {
+                        conditions.push(condition);
+                    }
+                }
+            }
+        }
+        conditions.join(" AND ")
+    }
+
+    /// Formats a filter on the aggregate expression.
+    fn format_aggregation_filter(expr: &str, value: &JsonValue) -> String {
+        let format_value = |value: &JsonValue| match value {
+            JsonValue::Number(value) => Some(value.to_string()),
+            JsonValue::Bool(value) => Some(value.to_string()),
+            JsonValue::String(value) => {
+                if let Ok(value) = value.parse::<i64>() {
+                    Some(value.to_string())
+                } else if let Ok(value) = value.parse::<f64>()
+                    && value.is_finite()
+                {
+                    Some(value.to_string())
+                } else {
+                    Some(Self::escape_string(value))
+                }
+            }
+            _ => None,
+        };
+        if let Some(filter) = value.as_object() {
+            let mut conditions = Vec::with_capacity(filter.len());
+            for (name, value) in filter {
+                let operator = match name.as_str() {
+                    "$eq" => "=",
+                    "$ne" => "<>",
+                    "$lt" => "<",
+                    "$le" => "<=",
+                    "$gt" => ">",
+                    "$ge" => ">=",
+                    _ => continue,
+                };
+                if let Some(value) = format_value(value) {
+                    conditions.push(format!("{expr} {operator} {value}"));
+                }
+            }
+            conditions.join(" AND ")
+        } else if let Some(value) = format_value(value) {
+            format!("{expr} = {value}")
+        } else {
+            String::new()
+        }
+    }
+
+    /// Formats the aggregate expression in the SQL dialect.
+    fn format_aggregation<M: Schema>(aggregation: &Aggregation, dialect: Dialect) -> String {
+        let field = aggregation.field();
+        if field == "*" {
+            return format!("count({field})");
+        }
+
+        let column = M::get_column(field);
+        let field = Self::format_field(field);
+        match aggregation {
+            Aggregation::Count(_) => format!("count({field})"),
+            Aggregation::CountDistinct(_) => format!("count(distinct {field})"),
+            Aggregation::Sum(_) => {
+                let expr = format!("sum({field})");
+                if dialect.is_sqlite() {
+                    expr
+                } else if column.is_some_and(|col| col.is_integer()) {
+                    if dialect.is_postgres() {
+                        format!("CAST({expr} AS BIGINT)")
+                    } else {
+                        format!("CAST({expr} AS SIGNED)")
+                    }
+                } else if column.is_some_and(|col| matches!(col.type_name(), "f32" | "f64")) {
+                    if dialect.is_postgres() {
+                        format!("CAST({expr} AS DOUBLE PRECISION)")
+                    } else {
+                        format!("CAST({expr} AS DOUBLE)")
+                    }
+                } else {
+                    expr
+                }
+            }
+            Aggregation::Avg(_) => {
+                let expr = format!("avg({field})");
+                if dialect.is_postgres() {
+                    format!("CAST({expr} AS DOUBLE PRECISION)")
+                } else if dialect.is_mysql_compatible() {
+                    format!("CAST({expr} AS DOUBLE)")
+                } else {
+                    expr
+                }
+            }
+            Aggregation::Min(_) => format!("min({field})"),
+            Aggregation::Max(_) => format!("max({field})"),
+        }
+    }
+
+    /// Formats the group fields and the aggregations to generate the projection
+    /// in the SQL dialect.
+    fn format_aggregations<M: Schema>(&self, dialect: Dialect) -> String {
+        let mut fields = self
+            .query_filters()
+            .parse_str_array("$group")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|field| Self::format_field(field).into_owned())
+            .collect::<Vec<_>>();
+        for (alias, aggregation) in self.query_aggregations() {
+            let expr = Self::format_aggregation::<M>(aggregation, dialect);
+            let alias = Self::format_field(alias);
+            fields.push(format!("{expr} AS {alias}"));
+        }
+        fields.join(", ")
+    }
+
+    /// Formats the table fields with the eager-loading joins for the references
+    /// of the joinable columns in `$join` or `$include`, and returns the projection
+    /// and the `LEFT JOIN` expressions. The snapshot fields of the referenced models
+    /// are decoded as nested objects in the `{column}_populated` fields,
+    /// and the soft-deleted rows or the rows of other tenants are excluded.
+    fn format_joins<M: Schema>(&self, dialect: Dialect) -> (Cow<'_, str>, String) {
+        let model_name = M::model_name();
+        let projection = self.format_table_fields::<M>();
+        let mut fields = Vec::new();
+        let mut joins = Vec::new();
+        for column_name in self.joined_columns() {
+            let Some(col) = M::get_column(column_name).filter(|c| c.has_attribute("joinable"))
+            else {
+                continue;
+            };
+            let Some(reference) = col.reference().filter(|r| !r.fields().is_empty()) else {
+                continue;
+            };
+            let ref_table_name = Self::format_field(reference.name());
+            let join_alias = [column_name, "joined"].join("_");
+            let populated_field = [column_name, "populated"].join("_");
+            let source_field = [model_name, column_name].join(".");
+            let source_field = Self::format_field(&source_field);
+            let key = [join_alias.as_str(), reference.column_name()].join(".");
+            let key = Self::format_field(&key);
+            let object = format_json_object::<DB, Self>(dialect, &join_alias, reference.fields());
+            let mut conditions = Vec::new();
+            if reference.is_soft_deletable() {
+                let status_field = [join_alias.as_str(), "status"].join(".");
+                let status_field = Self::format_field(&status_field);
+                conditions.push(format!("{status_field} <> 'Deleted'"));
+            }
+            if let Some(condition) = tenant::format_reference_tenant_filter(reference, &join_alias)
+            {
+                conditions.push(condition);
+            }
+            if col.type_name().starts_with("Vec<") {
+                let subquery = if dialect.is_mysql_compatible() {
+                    conditions.insert(
+                        0,
+                        format!("JSON_CONTAINS({source_field}, JSON_ARRAY({key}))"),
+                    );
+                    format!(
+                        "SELECT JSON_ARRAYAGG({object}) FROM {ref_table_name} AS {join_alias} \
+                            WHERE {}",
+                        conditions.join(" AND ")
+                    )
+                } else if dialect.is_postgres() {
+                    conditions.insert(0, format!("{key} = ANY({source_field})"));
+                    format!(
+                        "SELECT json_agg({object} ORDER BY array_position({source_field}, {key})) \
+                            FROM {ref_table_name} AS {join_alias} WHERE {}",
+                        conditions.join(" AND ")
+                    )
+                } else {
+                    conditions.insert(0, format!("{key} = {join_alias}_keys.value"));
+                    format!(
+                        "SELECT json_group_array({object}) \
+                            FROM json_each({source_field}) AS {join_alias}_keys \
+                                INNER JOIN {ref_table_name} AS {join_alias} ON {}",
+                        conditions.join(" AND ")
+                    )
+                };
+                fields.push(format!("({subquery}) AS {populated_field}"));
+            } else {
+                conditions.insert(0, format!("{key} = {source_field}"));
+                joins.push(format!(
+                    "LEFT JOIN {ref_table_name} AS {join_alias} ON {}",
+                    conditions.join(" AND ")
+                ));
+                fields.push(format!(
+                    "CASE WHEN {key} IS NULL THEN NULL ELSE {object} END AS {populated_field}"
+                ));
+            }
+        }
+        if fields.is_empty() {
+            return (projection, String::new());
+        }
+        if projection == "*" {
+            fields.insert(0, format!("{}.*", Self::format_field(model_name)));
+        } else {
+            fields.insert(0, projection.into_owned());
+        }
+        (fields.join(", ").into(), joins.join(" "))
+    }
+
     /// Formats the query sort to generate SQL `ORDER BY` expression.
     fn format_sort(&self) -> String {
         let sort_order = self.query_order();
         if sort_order.is_empty() {
             String::new()
         } else {
+            let backward = self.query_backward();
             let sort_order = sort_order
                 .iter()
                 .map(|(sort, descending)| {
-                    if *descending {
+                    if *descending != backward {
                         format!("{sort} DESC")
                     } else {
                         format!("{sort} ASC")
@@ -287,7 +573,34 @@ pub(super) trait QueryExt<DB> {
             return String::new();
         }
 
+        if self.query_cursor().is_some() {
+            return format!("LIMIT {limit}");
+        }
+
         let offset = self.query_offset();
         format!("LIMIT {limit} OFFSET {offset}")
     }
 }
+
+/// Formats the fields of the aliased table as a JSON object for the SQL dialect.
+fn format_json_object<DB, Q: QueryExt<DB> + ?Sized>(
+    dialect: Dialect,
+    table_alias: &str,
+    fields: &[&str],
+) -> String {
+    let entries = fields
+        .iter()
+        .map(|&field| {
+            let value = Q::format_field(&format!("{table_alias}.{field}")).into_owned();
+            format!("'{field}', {value}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    if dialect.is_mysql_compatible() {
+        format!("JSON_OBJECT({entries})")
+    } else if dialect.is_postgres() {
+        format!("json_build_object({entries})")
+    } else {
+        format!("json_object({entries})")
+    }
+}
@@ -1,15 +1,21 @@
 use super::{
-    column::ColumnExt, mutation::MutationExt, query::QueryExt, ConnectionPool, DatabaseDriver,
-    DatabaseRow, ModelHelper,
+    audit, column::ColumnExt, migration, mutation::MutationExt, query::QueryExt, tenant,
+    ConnectionPool, DatabaseDriver, DatabaseRow, Migration, ModelHelper,
 };
 use crate::{
     bail,
     error::Error,
     extension::{JsonObjectExt, JsonValueExt},
     model::{Column, DecodeRow, EncodeColumn, ModelHooks, Mutation, Query, QueryContext},
+    response::ResponseCache,
     warn, BoxFuture, JsonValue, Map, Uuid,
 };
-use futures::TryStreamExt;
+use futures::{
+    channel::mpsc,
+    future,
+    stream::{self, BoxStream, StreamExt},
+    SinkExt, TryStreamExt,
+};
 use serde::de::DeserializeOwned;
 use sqlx::{Decode, Row, Transaction, Type};
 use std::{fmt::Display, sync::atomic::Ordering::Relaxed};
@@ -31,6 +37,12 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     const WRITER_NAME: &'static str = "main";
     /// Optional custom table name.
     const TABLE_NAME: Option<&'static str> = None;
+    /// A flag to record the write operations in the audit trail.
+    const AUDIT_ENABLED: bool = false;
+    /// A flag to guard the updates by the `version` column for the optimistic concurrency control.
+    const VERSION_CHECK_ENABLED: bool = false;
+    /// Optional column name of the tenant for the row-level multi-tenancy.
+    const TENANT_KEY_NAME: Option<&'static str> = None;
 
     /// Returns the primary key.
     fn primary_key(&self) -> &Self::PrimaryKey;
@@ -172,14 +184,16 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Creates a database table for the model.
     async fn create_table() -> Result<(), Error> {
-        let pool = Self::init_writer()?.pool();
+        let connection_pool = Self::init_writer()?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_create_table().await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let columns = Self::columns()
             .iter()
-            .map(|col| col.field_definition(primary_key_name))
+            .map(|col| col.field_definition(dialect, primary_key_name))
             .collect::<Vec<_>>()
             .join(",\n  ");
         let sql = format!("CREATE TABLE IF NOT EXISTS {table_name} (\n  {columns}\n);");
@@ -189,77 +203,49 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     }
 
     /// Synchronizes the table schema for the model.
+    ///
+    /// If `managed` is enabled in the `[migration]` config, the table schema is managed
+    /// by the versioned migrations, and the schema changes will only be reported
+    /// instead of adding the columns directly. No migration files are written here;
+    /// use [`generate_migration()`](Self::generate_migration) to create them.
     async fn synchronize_schema() -> Result<(), Error> {
+        if *migration::MANAGED_SCHEMA {
+            if let Some(migration) = Self::generate_migration().await? {
+                tracing::warn!(
+                    model_name = Self::model_name(),
+                    table_name = Self::table_name(),
+                    up = migration.up(),
+                    "the table schema is out of date and a migration should be generated",
+                );
+            }
+            return Ok(());
+        }
+
         let connection_pool = Self::init_writer()?;
+        let dialect = connection_pool.dialect();
         let pool = connection_pool.pool();
 
         let table_name = Self::table_name();
-        let sql = if cfg!(any(
-            feature = "orm-mariadb",
-            feature = "orm-mysql",
-            feature = "orm-tidb"
-        )) {
-            let table_schema = connection_pool.database();
-            format!(
-                "SELECT column_name, data_type, column_default, is_nullable \
-                    FROM information_schema.columns \
-                        WHERE table_schema = '{table_schema}' AND table_name = '{table_name}';"
-            )
-        } else if cfg!(feature = "orm-postgres") {
-            format!(
-                "SELECT column_name, data_type, column_default, is_nullable \
-                    FROM information_schema.columns \
-                        WHERE table_schema = 'public' AND table_name = '{table_name}';"
-            )
-        } else {
-            format!(
-                "SELECT p.name AS column_name, p.type AS data_type, \
-                        p.dflt_value AS column_default, p.[notnull] AS is_not_null \
-                    FROM sqlite_master m LEFT OUTER JOIN pragma_table_info((m.name)) p
-                        ON m.name <> p.name WHERE m.name = '{table_name}';"
-            )
-        };
-        let mut rows = sqlx::query(&sql).fetch(pool);
-        let mut data = Vec::new();
-        while let Some(row) = rows.try_next().await? {
-            data.push(Map::decode_row(&row)?);
-        }
-
+        let table_columns = migration::fetch_table_columns(connection_pool, table_name).await?;
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         for col in Self::columns() {
             let column_name = col.name();
-            let column_opt = data.iter().find(|d| {
-                d.get_str("column_name")
-                    .or_else(|| d.get_str("COLUMN_NAME"))
-                    == Some(column_name)
-            });
-            if let Some(d) = column_opt
-                && let Some(data_type) = d.get_str("data_type").or_else(|| d.get_str("DATA_TYPE"))
-            {
-                let column_default = d
-                    .get_str("column_default")
-                    .or_else(|| d.get_str("COLUMN_DEFAULT"));
-                let is_not_null = if cfg!(any(feature = "orm-mysql", feature = "orm-postgres")) {
-                    d.get_str("is_nullable")
-                        .or_else(|| d.get_str("IS_NULLABLE"))
-                        .unwrap_or("YES")
-                        .eq_ignore_ascii_case("NO")
-                } else {
-                    d.get_str("is_not_null") == Some("1")
-                };
+            let column_opt = table_columns.iter().find(|c| c.name() == column_name);
+            if let Some(table_column) = column_opt {
+                let data_type = table_column.column_type();
+                let is_not_null = table_column.is_not_null();
                 if col.is_not_null() != is_not_null && column_name != primary_key_name {
                     tracing::warn!(
                         model_name = Self::model_name(),
                         table_name,
                         column_name,
                         data_type,
-                        column_default,
                         is_not_null,
                         "the `NOT NULL` constraint of the column `{column_name}` should be updated",
                     );
                 }
             } else {
-                let column_definition = col.field_definition(primary_key_name);
+                let column_definition = col.field_definition(dialect, primary_key_name);
                 let sql = format!("ALTER TABLE {table_name} ADD COLUMN {column_definition};");
                 sqlx::query(&sql).execute(pool).await?;
                 tracing::warn!(
@@ -273,18 +259,35 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         Ok(())
     }
 
+    /// Generates a versioned migration by diffing the columns and indexes
+    /// against the table schema. It returns `None` if the table schema is up to date.
+    ///
+    /// A column can be renamed with the attribute `#[schema(renamed_from = "name")]`.
+    /// The migration should be written by a development tool into
+    /// [`Migrator::default_migration_dir()`](super::Migrator::default_migration_dir).
+    async fn generate_migration() -> Result<Option<Migration>, Error> {
+        let connection_pool = Self::init_writer()?;
+        let table_name = Self::table_name();
+        let scripts = migration::diff_table_schema(
+            connection_pool,
+            table_name,
+            Self::PRIMARY_KEY_NAME,
+            Self::columns(),
+        )
+        .await?;
+        Ok(scripts.map(|(up, down)| Migration::new(format!("migrate_{table_name}"), up, down)))
+    }
+
     /// Creates indexes for the model.
     async fn create_indexes() -> Result<u64, Error> {
-        let pool = Self::init_writer()?.pool();
+        let connection_pool = Self::init_writer()?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
 
         let table_name = Self::table_name();
         let columns = Self::columns();
         let mut rows = 0;
-        if cfg!(any(
-            feature = "orm-mariadb",
-            feature = "orm-mysql",
-            feature = "orm-tidb"
-        )) {
+        if dialect.is_mysql_compatible() {
             let sql = format!("SHOW INDEXES FROM {table_name}");
             let indexes = sqlx::query(&sql).fetch_all(pool).await?;
             if indexes.len() > 1 {
@@ -334,7 +337,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
                     .rows_affected()
                     .max(rows);
             }
-        } else if cfg!(feature = "orm-postgres") {
+        } else if dialect.is_postgres() {
             let mut text_search_columns = Vec::new();
             let mut text_search_languages = Vec::new();
             for col in columns {
@@ -411,7 +414,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_writer().await?.pool();
         let model_data = self.before_insert().await?;
 
-        let map = self.into_map();
+        let mut map = self.into_map();
+        tenant::fill_tenant::<Self>(&mut map)?;
+
         let table_name = Self::table_name();
         let columns = Self::columns();
 
@@ -443,6 +448,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
         Self::after_insert(&ctx, model_data).await?;
+        after_write::<Self>().await;
+        if success {
+            audit::after_insert::<Self>(map, last_insert_id).await;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -458,15 +467,21 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_writer().await?.pool();
         let columns = Self::columns();
         let mut values = Vec::with_capacity(models.len());
+        let mut new_values = Vec::new();
         for mut model in models.into_iter() {
             let _model_data = model.before_insert().await?;
 
-            let map = model.into_map();
+            let mut map = model.into_map();
+            tenant::fill_tenant::<Self>(&mut map)?;
+
             let entries = columns
                 .iter()
                 .map(|col| col.encode_value(map.get(col.name())))
                 .collect::<Vec<_>>();
             values.push(format!("({})", entries.join(", ")));
+            if Self::AUDIT_ENABLED {
+                new_values.push(map);
+            }
         }
 
         let table_name = Self::table_name();
@@ -480,10 +495,16 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        after_write::<Self>().await;
+        audit::after_inserts::<Self>(new_values).await;
         Ok(ctx)
     }
 
     /// Updates the model in the table.
+    ///
+    /// If [`VERSION_CHECK_ENABLED`](Self::VERSION_CHECK_ENABLED) is `true`,
+    /// the update is restricted to the row of the current version and the version is
+    /// incremented atomically. A `409 Conflict` error is returned if no rows are affected.
     async fn update(mut self) -> Result<QueryContext, Error> {
         let pool = Self::acquire_writer().await?.pool();
         let model_data = self.before_update().await?;
@@ -491,13 +512,23 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let primary_key = Query::escape_string(self.primary_key());
-        let map = self.into_map();
+        let old_values = audit::before_write::<Self>(self.primary_key()).await?;
+        let mut map = self.into_map();
+        tenant::fill_tenant::<Self>(&mut map)?;
+
         let read_only_fields = Self::read_only_fields();
         let num_writable_fields = Self::fields().len() - read_only_fields.len();
         let mut mutations = Vec::with_capacity(num_writable_fields);
+        let mut version_filter = None;
         for col in Self::columns() {
             let field = col.name();
-            if !read_only_fields.contains(&field) {
+            if field == "version" && Self::VERSION_CHECK_ENABLED && col.is_integer() {
+                // Optimistic concurrency control with the `version` column
+                let version = col.encode_value(map.get(field));
+                let field = Query::format_field(field);
+                mutations.push(format!("{field} = {field} + 1"));
+                version_filter = Some(format!(" AND {field} = {version}"));
+            } else if !read_only_fields.contains(&field) {
                 let value = col.encode_value(map.get(field));
                 let field = Query::format_field(field);
                 mutations.push(format!("{field} = {value}"));
@@ -505,18 +536,33 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
 
         let mutations = mutations.join(", ");
+        let version_guarded = version_filter.is_some();
+        let version_filter = version_filter.unwrap_or_default();
+        let tenant_filter = tenant::format_tenant_condition::<Self>();
         let sql = format!(
-            "UPDATE {table_name} SET {mutations} WHERE {primary_key_name} = {primary_key};"
+            "UPDATE {table_name} SET {mutations} \
+                WHERE {primary_key_name} = {primary_key}{version_filter}{tenant_filter};"
         );
 
         let mut ctx = Self::before_scan(&sql).await?;
         let query_result = sqlx::query(&sql).execute(pool).await?;
         let rows_affected = query_result.rows_affected();
+        if version_guarded && rows_affected == 0 {
+            bail!(
+                "409 Conflict: there is a version conflict for the model `{}`",
+                Self::MODEL_NAME
+            );
+        }
+
         let success = rows_affected == 1;
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
         Self::after_update(&ctx, model_data).await?;
+        after_write::<Self>().await;
+        if success {
+            audit::after_update::<Self>(old_values).await?;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -528,19 +574,35 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     }
 
     /// Updates at most one model selected by the query in the table.
+    ///
+    /// If the query has a filter on the integer `version` column, the version is incremented
+    /// atomically and a `409 Conflict` error is returned if no rows are affected.
     async fn update_one(query: &Query, mutation: &mut Mutation) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_mutation(query, mutation).await?;
 
+        let old_values = audit::before_mutation::<Self>(query).await?;
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = query.format_table_name::<Self>();
-        let filters = query.format_filters::<Self>();
-        let updates = mutation.format_updates::<Self>();
-        let sql = if cfg!(any(
-            feature = "orm-mariadb",
-            feature = "orm-mysql",
-            feature = "orm-tidb"
-        )) {
+        let filters = query.format_filters::<Self>(dialect);
+        let mut updates = mutation.format_updates::<Self>();
+
+        // Optimistic concurrency control with the `version` column
+        let version_guarded = query.filters().get("version").is_some_and(|v| v.is_u64())
+            && Self::get_column("version").is_some_and(|col| col.is_integer());
+        if version_guarded && !mutation.updates().contains_key("version") {
+            let field = Query::format_field("version");
+            let increment = format!("{field} = {field} + 1");
+            if updates.is_empty() {
+                updates = increment;
+            } else {
+                updates = format!("{updates}, {increment}");
+            }
+        }
+
+        let sql = if dialect.is_mysql_compatible() {
             // MySQL doesn't yet support 'LIMIT & IN/ALL/ANY/SOME subquery'
             // and self-referencing in UPDATE/DELETE
             format!(
@@ -559,11 +621,22 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut ctx = Self::before_scan(&sql).await?;
         let query_result = sqlx::query(&sql).execute(pool).await?;
         let rows_affected = query_result.rows_affected();
+        if version_guarded && rows_affected == 0 {
+            bail!(
+                "409 Conflict: there is a version conflict for the model `{}`",
+                Self::MODEL_NAME
+            );
+        }
+
         let success = rows_affected <= 1;
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
         Self::after_mutation(&ctx).await?;
+        after_write::<Self>().await;
+        if rows_affected == 1 {
+            audit::after_update::<Self>(old_values).await?;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -576,11 +649,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Updates many models selected by the query in the table.
     async fn update_many(query: &Query, mutation: &mut Mutation) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_mutation(query, mutation).await?;
 
+        let old_values = audit::before_mutations::<Self>(query).await?;
         let table_name = query.format_table_name::<Self>();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let updates = mutation.format_updates::<Self>();
         let sql = format!("UPDATE {table_name} SET {updates} {filters};");
 
@@ -591,15 +667,24 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
         Self::after_mutation(&ctx).await?;
+        after_write::<Self>().await;
+        audit::after_updates::<Self>(old_values).await?;
         Ok(ctx)
     }
 
     /// Updates or inserts the model into the table.
+    /// For a model with the tenant scoping, it only updates the row of the current tenant
+    /// and is unsupported for the MySQL-compatible databases.
     async fn upsert(mut self) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         let model_data = self.before_upsert().await?;
 
-        let map = self.into_map();
+        let mut map = self.into_map();
+        tenant::fill_tenant::<Self>(&mut map)?;
+
+        let tenant_filter = tenant::format_upsert_filter::<Self>(dialect)?;
         let table_name = Self::table_name();
         let fields = Self::fields();
         let num_fields = fields.len();
@@ -610,7 +695,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         for col in Self::columns() {
             let field = col.name();
             let value = col.encode_value(map.get(field));
-            if !read_only_fields.contains(&field) {
+            if !read_only_fields.contains(&field) && !tenant::is_tenant_key::<Self>(field) {
                 let field = Query::format_field(field);
                 mutations.push(format!("{field} = {value}"));
             }
@@ -620,11 +705,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let fields = fields.join(", ");
         let values = values.join(", ");
         let mutations = mutations.join(", ");
-        let sql = if cfg!(any(
-            feature = "orm-mariadb",
-            feature = "orm-mysql",
-            feature = "orm-tidb"
-        )) {
+        let sql = if dialect.is_mysql_compatible() {
             format!(
                 "INSERT INTO {table_name} ({fields}) VALUES ({values}) \
                     ON DUPLICATE KEY UPDATE {mutations};"
@@ -635,7 +716,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             // Both PostgreQL and SQLite (3.24+) support this syntax.
             format!(
                 "INSERT INTO {table_name} ({fields}) VALUES ({values}) \
-                    ON CONFLICT ({primary_key_name}) DO UPDATE SET {mutations};"
+                    ON CONFLICT ({primary_key_name}) DO UPDATE SET {mutations}{tenant_filter};"
             )
         };
 
@@ -650,6 +731,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
         Self::after_upsert(&ctx, model_data).await?;
+        after_write::<Self>().await;
         if success {
             Ok(ctx)
         } else {
@@ -662,21 +744,28 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Deletes the model in the table.
     async fn delete(mut self) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         let model_data = self.before_delete().await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let primary_key = self.primary_key();
+        let old_values = audit::before_write::<Self>(primary_key).await?;
         let placeholder = Query::placeholder(1);
-        let sql = if cfg!(feature = "orm-postgres") {
-            let type_annotation = Self::primary_key_column().type_annotation();
+        let tenant_filter = tenant::format_tenant_condition::<Self>();
+        let sql = if dialect.is_postgres() {
+            let type_annotation = Self::primary_key_column().type_annotation(dialect);
             format!(
                 "DELETE FROM {table_name} \
-                    WHERE {primary_key_name} = ({placeholder}){type_annotation};"
+                    WHERE {primary_key_name} = ({placeholder}){type_annotation}{tenant_filter};"
             )
         } else {
-            format!("DELETE FROM {table_name} WHERE {primary_key_name} = {placeholder};")
+            format!(
+                "DELETE FROM {table_name} \
+                    WHERE {primary_key_name} = {placeholder}{tenant_filter};"
+            )
         };
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -689,6 +778,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
         self.after_delete(&ctx, model_data).await?;
+        after_write::<Self>().await;
+        if success {
+            audit::after_delete::<Self>(old_values).await;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -701,12 +794,15 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Deletes at most one model selected by the query in the table.
     async fn delete_one(query: &Query) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
+        let old_values = audit::before_mutation::<Self>(query).await?;
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = query.format_table_name::<Self>();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sort = query.format_sort();
         let sql = format!(
             "DELETE FROM {table_name} WHERE {primary_key_name} IN \
@@ -721,6 +817,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
         Self::after_query(&ctx).await?;
+        after_write::<Self>().await;
+        if rows_affected == 1 {
+            audit::after_delete::<Self>(old_values).await;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -733,11 +833,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Deletes many models selected by the query in the table.
     async fn delete_many(query: &Query) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
+        let old_values = audit::before_mutations::<Self>(query).await?;
         let table_name = query.format_table_name::<Self>();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sql = format!("DELETE FROM {table_name} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -747,6 +850,8 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
         Self::after_query(&ctx).await?;
+        after_write::<Self>().await;
+        audit::after_deletes::<Self>(old_values).await;
         Ok(ctx)
     }
 
@@ -755,15 +860,21 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     async fn find<T: DecodeRow<DatabaseRow, Error = Error>>(
         query: &Query,
     ) -> Result<Vec<T>, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
+        if !query.validate_cursor(Self::fields()).is_success() {
+            bail!("400 Bad Request: the cursor does not match the sort fields");
+        }
 
         let table_name = query.format_table_name::<Self>();
-        let projection = query.format_table_fields::<Self>();
-        let filters = query.format_filters::<Self>();
+        let (projection, joins) = query.format_joins::<Self>(dialect);
+        let filters = query.format_filters::<Self>(dialect);
         let sort = query.format_sort();
         let pagination = query.format_pagination();
-        let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
+        let sql =
+            format!("SELECT {projection} FROM {table_name} {joins} {filters} {sort} {pagination};");
 
         let mut ctx = Self::before_scan(&sql).await?;
         let mut rows = sqlx::query(&sql).fetch(pool);
@@ -775,6 +886,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             data.push(T::decode_row(&row)?);
             max_rows -= 1;
         }
+        if query.is_backward() {
+            data.reverse();
+        }
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
@@ -794,19 +908,86 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         serde_json::from_value(data.into()).map_err(Error::from)
     }
 
+    /// Finds a list of models selected by the query in the table,
+    /// and returns a stream of the rows decoded as instances of type `T`.
+    ///
+    /// The rows are fetched when the stream is polled, and the fetching is suspended
+    /// when the buffer of the stream is full, so the memory usage is bounded.
+    /// The stream ends after the first error, and the `after_scan` and `after_query` hooks
+    /// are called when all the rows have been fetched. Since the rows can not be reversed
+    /// without buffering them, an error is returned for a backward cursor.
+    async fn stream<T>(query: &Query) -> Result<BoxStream<'static, Result<T, Error>>, Error>
+    where
+        T: DecodeRow<DatabaseRow, Error = Error> + Send + 'static,
+        Self: ModelHooks<after_scan(..): Send, after_query(..): Send>,
+    {
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
+        Self::before_query(query).await?;
+        if query.is_backward() {
+            bail!("the backward cursor is unsupported for a stream of rows");
+        }
+        if !query.validate_cursor(Self::fields()).is_success() {
+            bail!("400 Bad Request: the cursor does not match the sort fields");
+        }
+
+        let table_name = query.format_table_name::<Self>();
+        let (projection, joins) = query.format_joins::<Self>(dialect);
+        let filters = query.format_filters::<Self>(dialect);
+        let sort = query.format_sort();
+        let pagination = query.format_pagination();
+        let sql =
+            format!("SELECT {projection} FROM {table_name} {joins} {filters} {sort} {pagination};");
+        let mut ctx = Self::before_scan(&sql).await?;
+
+        let (mut sender, receiver) = mpsc::channel(128);
+        let producer = async move {
+            let mut rows = sqlx::query(&sql).fetch(pool);
+            let mut num_rows = 0;
+            while let Some(result) = rows.next().await {
+                let item = result
+                    .map_err(Error::from)
+                    .and_then(|row| T::decode_row(&row));
+                let failed = item.is_err();
+                if sender.send(item).await.is_err() || failed {
+                    return;
+                }
+                num_rows += 1;
+            }
+            drop(rows);
+            ctx.set_query(sql);
+            ctx.set_query_result(Some(num_rows), true);
+            let result = async {
+                Self::after_scan(&ctx).await?;
+                Self::after_query(&ctx).await
+            };
+            if let Err(err) = result.await {
+                sender.send(Err(err)).await.ok();
+            }
+        };
+
+        // The producer is driven by polling the stream, so no background task is spawned.
+        let producer = stream::once(producer).filter_map(|_| future::ready(None));
+        Ok(stream::select(producer, receiver).boxed())
+    }
+
     /// Finds one model selected by the query in the table,
     /// and decodes it as an instance of type `T`.
     async fn find_one<T: DecodeRow<DatabaseRow, Error = Error>>(
         query: &Query,
     ) -> Result<Option<T>, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
         let table_name = query.format_table_name::<Self>();
-        let projection = query.format_table_fields::<Self>();
-        let filters = query.format_filters::<Self>();
+        let (projection, joins) = query.format_joins::<Self>(dialect);
+        let filters = query.format_filters::<Self>(dialect);
         let sort = query.format_sort();
-        let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} LIMIT 1;");
+        let sql =
+            format!("SELECT {projection} FROM {table_name} {joins} {filters} {sort} LIMIT 1;");
 
         let mut ctx = Self::before_scan(&sql).await?;
         let (num_rows, data) = if let Some(row) = sqlx::query(&sql).fetch_optional(pool).await? {
@@ -842,12 +1023,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     where
         T: Send + Unpin + Type<DatabaseDriver> + for<'r> Decode<'r, DatabaseDriver>,
     {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
         let table_name = Self::table_name();
         let projection = query.format_projection();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sort = query.format_sort();
         let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} LIMIT 1;");
 
@@ -866,12 +1049,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     where
         T: Send + Unpin + Type<DatabaseDriver> + for<'r> Decode<'r, DatabaseDriver>,
     {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sort = query.format_sort();
         let pagination = query.format_pagination();
         let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
@@ -886,6 +1071,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             data.push(row.try_get_unchecked(0)?);
             max_rows -= 1;
         }
+        if query.is_backward() {
+            data.reverse();
+        }
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
@@ -900,7 +1088,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         data: &mut Vec<Map>,
         columns: [&str; N],
     ) -> Result<u64, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
@@ -927,7 +1117,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sql = format!("SELECT {projection} FROM {table_name} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -991,7 +1181,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         data: &mut Map,
         columns: [&str; N],
     ) -> Result<(), Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
@@ -1016,7 +1208,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
         let table_name = Self::table_name();
         let projection = query.format_projection();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sql = format!("SELECT {projection} FROM {table_name} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -1076,15 +1268,17 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         left_columns: &[&str],
         right_columns: &[&str],
     ) -> Result<Vec<T>, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_query(query).await?;
 
         let model_name = Self::model_name();
         let other_model_name = M::model_name();
         let table_name = query.format_table_name::<Self>();
         let other_table_name = query.format_table_name::<M>();
-        let projection = query.format_table_fields::<Self>();
-        let filters = query.format_filters::<Self>();
+        let (projection, joins) = query.format_joins::<Self>(dialect);
+        let filters = query.format_filters::<Self>(dialect);
         let sort = query.format_sort();
         let pagination = query.format_pagination();
         let on_expressions = left_columns
@@ -1102,7 +1296,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let sql = format!(
             "SELECT {projection} FROM {table_name} \
                 LEFT OUTER JOIN {other_table_name} \
-                    ON {on_expressions} {filters} {sort} {pagination};"
+                    ON {on_expressions} {joins} {filters} {sort} {pagination};"
         );
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -1115,6 +1309,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             data.push(T::decode_row(&row)?);
             max_rows -= 1;
         }
+        if query.is_backward() {
+            data.reverse();
+        }
         ctx.set_query(&sql);
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
@@ -1140,11 +1337,13 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Counts the number of rows selected by the query in the table.
     async fn count(query: &Query) -> Result<u64, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_count(query).await?;
 
         let table_name = Self::table_name();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let sql = format!("SELECT count(*) FROM {table_name} {filters};");
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -1162,11 +1361,13 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         query: &Query,
         columns: &[(&str, bool)],
     ) -> Result<T, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
         Self::before_count(query).await?;
 
         let table_name = query.format_table_name::<Self>();
-        let filters = query.format_filters::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
         let projection = columns
             .iter()
             .map(|&(key, distinct)| {
@@ -1191,7 +1392,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(1), true);
         Self::after_scan(&ctx).await?;
         Self::after_count(&ctx).await?;
-        T::decode_row(&row).map_err(Error::from)
+        T::decode_row(&row)
     }
 
     /// Counts the number of rows selected by the query in the table,
@@ -1204,7 +1405,64 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         serde_json::from_value(map.into()).map_err(Error::from)
     }
 
+    /// Aggregates the rows selected by the query in the table with the group fields
+    /// and the aggregations, and decodes it as `Vec<T>`.
+    /// The query is checked with [`Query::validate_aggregations()`] before formatting.
+    async fn aggregate<T: DecodeRow<DatabaseRow, Error = Error>>(
+        query: &Query,
+    ) -> Result<Vec<T>, Error> {
+        if !query.validate_aggregations(Self::columns()).is_success() {
+            bail!(
+                "400 Bad Request: invalid aggregations for the model `{}`",
+                Self::MODEL_NAME
+            );
+        }
+
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
+        let projection = query.format_aggregations::<Self>(dialect);
+        if projection.is_empty() {
+            bail!(
+                "the aggregation for the model `{}` should be nonempty",
+                Self::MODEL_NAME
+            );
+        }
+
+        Self::before_query(query).await?;
+
+        let table_name = query.format_table_name::<Self>();
+        let filters = query.format_filters::<Self>(dialect);
+        let sort = query.format_sort();
+        let pagination = query.format_pagination();
+        let sql = format!("SELECT {projection} FROM {table_name} {filters} {sort} {pagination};");
+
+        let mut ctx = Self::before_scan(&sql).await?;
+        let mut rows = sqlx::query(&sql).fetch(pool);
+        let mut data = Vec::new();
+        let mut max_rows = super::MAX_ROWS.load(Relaxed);
+        while let Some(row) = rows.try_next().await?
+            && max_rows > 0
+        {
+            data.push(T::decode_row(&row)?);
+            max_rows -= 1;
+        }
+        ctx.set_query(&sql);
+        ctx.set_query_result(Some(u64::try_from(data.len())?), true);
+        Self::after_scan(&ctx).await?;
+        Self::after_query(&ctx).await?;
+        Ok(data)
+    }
+
+    /// Aggregates the rows selected by the query in the table with the group fields
+    /// and the aggregations, and parses it as `Vec<T>`.
+    async fn aggregate_as<T: DeserializeOwned>(query: &Query) -> Result<Vec<T>, Error> {
+        let data = Self::aggregate::<Map>(query).await?;
+        serde_json::from_value(data.into()).map_err(Error::from)
+    }
+
     /// Executes the query in the table, and returns the total number of rows affected.
+    /// The cached responses of the model are not invalidated for the raw queries.
     async fn execute(query: &str, params: Option<&Map>) -> Result<QueryContext, Error> {
         let pool = Self::acquire_reader().await?.pool();
         let (sql, values) = Query::prepare_query(query, params);
@@ -1379,19 +1637,26 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Deletes a model selected by the primary key in the table.
     async fn delete_by_id(primary_key: &Self::PrimaryKey) -> Result<QueryContext, Error> {
-        let pool = Self::acquire_writer().await?.pool();
+        let connection_pool = Self::acquire_writer().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
+        let old_values = audit::before_write::<Self>(primary_key).await?;
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let placeholder = Query::placeholder(1);
-        let sql = if cfg!(feature = "orm-postgres") {
-            let type_annotation = Self::primary_key_column().type_annotation();
+        let tenant_filter = tenant::format_tenant_condition::<Self>();
+        let sql = if dialect.is_postgres() {
+            let type_annotation = Self::primary_key_column().type_annotation(dialect);
             format!(
                 "DELETE FROM {table_name} \
-                    WHERE {primary_key_name} = ({placeholder}){type_annotation};"
+                    WHERE {primary_key_name} = ({placeholder}){type_annotation}{tenant_filter};"
             )
         } else {
-            format!("DELETE FROM {table_name} WHERE {primary_key_name} = {placeholder};")
+            format!(
+                "DELETE FROM {table_name} \
+                    WHERE {primary_key_name} = {placeholder}{tenant_filter};"
+            )
         };
 
         let mut ctx = Self::before_scan(&sql).await?;
@@ -1403,6 +1668,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.add_argument(primary_key);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        after_write::<Self>().await;
+        if success {
+            audit::after_delete::<Self>(old_values).await;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -1418,22 +1687,26 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     async fn find_by_id<T: DecodeRow<DatabaseRow, Error = Error>>(
         primary_key: &Self::PrimaryKey,
     ) -> Result<Option<T>, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let query = Self::default_query();
         let projection = query.format_projection();
         let placeholder = Query::placeholder(1);
-        let sql = if cfg!(feature = "orm-postgres") {
-            let type_annotation = Self::primary_key_column().type_annotation();
+        let tenant_filter = tenant::format_tenant_condition::<Self>();
+        let sql = if dialect.is_postgres() {
+            let type_annotation = Self::primary_key_column().type_annotation(dialect);
             format!(
                 "SELECT {projection} FROM {table_name} \
-                    WHERE {primary_key_name} = ({placeholder}){type_annotation};"
+                    WHERE {primary_key_name} = ({placeholder}){type_annotation}{tenant_filter};"
             )
         } else {
             format!(
-                "SELECT {projection} FROM {table_name} WHERE {primary_key_name} = {placeholder};"
+                "SELECT {projection} FROM {table_name} \
+                    WHERE {primary_key_name} = {placeholder}{tenant_filter};"
             )
         };
 
@@ -1454,22 +1727,26 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
     /// Finds a model selected by the primary key in the table, and parses it as `Self`.
     async fn try_get_model(primary_key: &Self::PrimaryKey) -> Result<Self, Error> {
-        let pool = Self::acquire_reader().await?.pool();
+        let connection_pool = Self::acquire_reader().await?;
+        let dialect = connection_pool.dialect();
+        let pool = connection_pool.pool();
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let table_name = Self::table_name();
         let query = Self::default_query();
         let projection = query.format_projection();
         let placeholder = Query::placeholder(1);
-        let sql = if cfg!(feature = "orm-postgres") {
-            let type_annotation = Self::primary_key_column().type_annotation();
+        let tenant_filter = tenant::format_tenant_condition::<Self>();
+        let sql = if dialect.is_postgres() {
+            let type_annotation = Self::primary_key_column().type_annotation(dialect);
             format!(
                 "SELECT {projection} FROM {table_name} \
-                    WHERE {primary_key_name} = ({placeholder}){type_annotation};"
+                    WHERE {primary_key_name} = ({placeholder}){type_annotation}{tenant_filter};"
             )
         } else {
             format!(
-                "SELECT {projection} FROM {table_name} WHERE {primary_key_name} = {placeholder};"
+                "SELECT {projection} FROM {table_name} \
+                    WHERE {primary_key_name} = {placeholder}{tenant_filter};"
             )
         };
 
@@ -1548,3 +1825,164 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
     }
 }
+
+/// Runs the shared steps after writing the rows of a model. It is called exactly once
+/// by each write method, so the cached responses of the model are invalidated only once.
+async fn after_write<M: Schema>() {
+    ResponseCache::invalidate(M::MODEL_NAME).await;
+}
+
+#[cfg(all(test, feature = "runtime-tokio"))]
+#[cfg(not(any(
+    feature = "orm-mariadb",
+    feature = "orm-mysql",
+    feature = "orm-postgres",
+    feature = "orm-tidb"
+)))]
+mod tests {
+    use super::{ConnectionPool, Schema};
+    use crate::{
+        error::Error,
+        model::{Column, Model, ModelHooks, QueryContext},
+        Uuid,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::{
+        env, fs,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering::Relaxed},
+            LazyLock,
+        },
+    };
+    use toml::value::Table;
+
+    #[derive(Default, Serialize, Deserialize)]
+    struct Ticket {
+        id: String,
+        name: String,
+        version: u64,
+    }
+
+    impl Model for Ticket {}
+
+    impl ModelHooks for Ticket {
+        async fn after_update(_ctx: &QueryContext, _data: ()) -> Result<(), Error> {
+            NUM_UPDATES.fetch_add(1, Relaxed);
+            Ok(())
+        }
+    }
+
+    impl Schema for Ticket {
+        type PrimaryKey = String;
+
+        const MODEL_NAME: &'static str = "ticket";
+        const VERSION_CHECK_ENABLED: bool = true;
+
+        fn primary_key(&self) -> &String {
+            &self.id
+        }
+
+        fn schema() -> &'static apache_avro::Schema {
+            static SCHEMA: LazyLock<apache_avro::Schema> =
+                LazyLock::new(|| apache_avro::Schema::Null);
+            &SCHEMA
+        }
+
+        fn columns() -> &'static [Column<'static>] {
+            static COLUMNS: LazyLock<Vec<Column<'static>>> = LazyLock::new(|| {
+                let mut primary_key = Column::new("id", "String", true);
+                primary_key.set_extra_attribute("primary_key", true);
+                vec![
+                    primary_key,
+                    Column::new("name", "String", true),
+                    Column::new("version", "u64", true),
+                ]
+            });
+            &COLUMNS
+        }
+
+        fn fields() -> &'static [&'static str] {
+            &["id", "name", "version"]
+        }
+
+        fn read_only_fields() -> &'static [&'static str] {
+            &[]
+        }
+
+        fn write_only_fields() -> &'static [&'static str] {
+            &[]
+        }
+
+        async fn acquire_reader() -> Result<&'static ConnectionPool, Error> {
+            Ok(&CONNECTION_POOL)
+        }
+
+        async fn acquire_writer() -> Result<&'static ConnectionPool, Error> {
+            Ok(&CONNECTION_POOL)
+        }
+    }
+
+    /// Number of the `after_update` hook calls.
+    static NUM_UPDATES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Path of a temporary SQLite database.
+    static DATABASE_PATH: LazyLock<PathBuf> =
+        LazyLock::new(|| env::temp_dir().join(format!("zino-{}.db", Uuid::now_v7())));
+
+    /// Connection pool of the temporary SQLite database.
+    static CONNECTION_POOL: LazyLock<ConnectionPool> = LazyLock::new(|| {
+        let database = &*DATABASE_PATH;
+        let mut config = Table::new();
+        config.insert("name".to_owned(), "test".into());
+        config.insert(
+            "database".to_owned(),
+            database.to_string_lossy().into_owned().into(),
+        );
+        ConnectionPool::connect_lazy(Box::leak(Box::new(config)))
+    });
+
+    #[test]
+    fn it_guards_updates_by_version() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let pool = CONNECTION_POOL.pool();
+            let table_name = Ticket::table_name();
+            let sql = format!(
+                "CREATE TABLE {table_name} (id TEXT PRIMARY KEY, name TEXT, version INTEGER);"
+            );
+            sqlx::query(&sql).execute(pool).await.unwrap();
+            let sql = format!("INSERT INTO {table_name} VALUES ('t1', 'draft', 1);");
+            sqlx::query(&sql).execute(pool).await.unwrap();
+
+            let ticket = Ticket {
+                id: "t1".to_owned(),
+                name: "review".to_owned(),
+                version: 1,
+            };
+            let ctx = ticket.update().await.unwrap();
+            assert_eq!(ctx.rows_affected(), Some(1));
+            assert_eq!(NUM_UPDATES.load(Relaxed), 1);
+
+            let stale_ticket = Ticket {
+                id: "t1".to_owned(),
+                name: "publish".to_owned(),
+                version: 1,
+            };
+            let err = stale_ticket.update().await.unwrap_err();
+            assert!(err.message().starts_with("409 Conflict"));
+            assert_eq!(NUM_UPDATES.load(Relaxed), 1);
+
+            let sql = format!("SELECT name, version FROM {table_name} WHERE id = 't1';");
+            let (name, version): (String, i64) =
+                sqlx::query_as(&sql).fetch_one(pool).await.unwrap();
+            assert_eq!(name, "review");
+            assert_eq!(version, 2);
+            pool.close().await;
+        });
+        fs::remove_file(&*DATABASE_PATH).unwrap();
+    }
+}
@@ -0,0 +1,399 @@
+use super::{query::QueryExt, Dialect, Schema};
+use crate::{
+    auth::{JwtKeySet, UserSession},
+    bail,
+    error::Error,
+    extension::JsonObjectExt,
+    helper::TaskLocal,
+    model::{EncodeColumn, Query, Reference},
+    request::RequestContext,
+    Map,
+};
+use std::{cell::RefCell, future::Future};
+
+thread_local! {
+    // Slot of the tenant context for the running task.
+    static TENANT_CONTEXT_SLOT: RefCell<Option<TenantContext>> = const { RefCell::new(None) };
+}
+
+/// Tenant context for the current task.
+static TENANT_CONTEXT: TaskLocal<TenantContext> = TaskLocal::new(&TENANT_CONTEXT_SLOT);
+
+/// Task scoped context for the row-level multi-tenancy.
+///
+/// For a model with the `#[schema(tenant = "column")]` annotation, every generated
+/// `WHERE` clause is restricted to the rows of the current tenant, and the tenant column
+/// is filled in for the write operations. If there is no current tenant,
+/// no rows are matched and the write operations are rejected.
+/// The only way to access the rows of other tenants is [`TenantContext::bypass`].
+///
+/// The request context middleware of `zino` runs each request in a tenant scope,
+/// which is populated with the tenant ID of the access token if there is one.
+#[derive(Debug, Clone, Default)]
+pub struct TenantContext {
+    /// Tenant ID.
+    tenant_id: Option<String>,
+    /// A flag to bypass the tenant scoping.
+    bypassed: bool,
+}
+
+impl TenantContext {
+    /// Creates a new instance with the tenant ID.
+    #[inline]
+    pub fn new(tenant_id: impl ToString) -> Self {
+        Self {
+            tenant_id: Some(tenant_id.to_string()),
+            bypassed: false,
+        }
+    }
+
+    /// Creates a new instance with the tenant ID of the user session.
+    #[inline]
+    pub fn from_session<U, R, T: ToString>(session: &UserSession<U, R, T>) -> Self {
+        Self {
+            tenant_id: session.tenant_id().map(|t| t.to_string()),
+            bypassed: false,
+        }
+    }
+
+    /// Creates a new instance with the tenant ID of the access token in the request.
+    /// There is no tenant ID if the request does not carry a valid access token.
+    pub fn from_request<R: RequestContext + ?Sized>(req: &R) -> Self {
+        if req.get_header("authorization").is_none() && req.get_query("access_token").is_none() {
+            return Self::default();
+        }
+        req.parse_jwt_claims(JwtKeySet::shared())
+            .ok()
+            .and_then(|claims| UserSession::<String>::try_from_jwt_claims(claims).ok())
+            .map(|session| Self::from_session(&session))
+            .unwrap_or_default()
+    }
+
+    /// Runs the future with `self` as the tenant context of the current task.
+    #[inline]
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        TENANT_CONTEXT.scope(self, f).await
+    }
+
+    /// Runs the future without the tenant scoping.
+    /// It is the explicit escape hatch for the cross-tenant access.
+    #[inline]
+    pub async fn bypass<F: Future>(f: F) -> F::Output {
+        let mut ctx = Self::current().unwrap_or_default();
+        ctx.bypassed = true;
+        ctx.scope(f).await
+    }
+
+    /// Returns a copy of the tenant context of the current task.
+    #[inline]
+    pub fn current() -> Option<Self> {
+        TENANT_CONTEXT.try_with(|ctx| ctx.clone())
+    }
+
+    /// Sets the tenant ID for the tenant context of the current task.
+    /// It has no effects if the task is not running in a tenant scope.
+    #[inline]
+    pub fn set_current_tenant(tenant_id: impl ToString) {
+        let tenant_id = tenant_id.to_string();
+        TENANT_CONTEXT.try_with(|ctx| ctx.tenant_id = Some(tenant_id));
+    }
+
+    /// Sets the tenant ID of the user session for the tenant context of the current task.
+    /// It has no effects if the task is not running in a tenant scope.
+    #[inline]
+    pub fn set_current_session<U, R, T: ToString>(session: &UserSession<U, R, T>) {
+        if let Some(tenant_id) = session.tenant_id() {
+            Self::set_current_tenant(tenant_id.to_string());
+        }
+    }
+
+    /// Returns the tenant ID.
+    #[inline]
+    pub fn tenant_id(&self) -> Option<&str> {
+        self.tenant_id.as_deref()
+    }
+
+    /// Returns `true` if the tenant scoping is bypassed.
+    #[inline]
+    pub fn is_bypassed(&self) -> bool {
+        self.bypassed
+    }
+}
+
+/// Returns the tenant column name of a model if the tenant scoping is enforced.
+fn enforced_tenant_key<M: Schema>() -> Option<&'static str> {
+    M::TENANT_KEY_NAME.filter(|_| !TenantContext::current().is_some_and(|ctx| ctx.bypassed))
+}
+
+/// Returns `true` if the column can not be changed by the mutations
+/// since it is the tenant column of a model.
+pub(super) fn is_tenant_key<M: Schema>(key: &str) -> bool {
+    enforced_tenant_key::<M>() == Some(key)
+}
+
+/// Formats the condition to restrict the rows to the current tenant.
+pub(super) fn format_tenant_filter<M: Schema>() -> Option<String> {
+    let tenant_key = enforced_tenant_key::<M>()?;
+    Some(format_tenant_predicate::<M>(tenant_key, tenant_key))
+}
+
+/// Formats the predicate of the tenant column for the field.
+fn format_tenant_predicate<M: Schema>(tenant_key: &str, field: &str) -> String {
+    let tenant_id = TenantContext::current().and_then(|ctx| ctx.tenant_id);
+    if let Some(tenant_id) = tenant_id
+        && let Some(col) = M::get_column(tenant_key)
+    {
+        col.format_filter(field, &tenant_id.into())
+    } else {
+        "1 = 0".to_owned()
+    }
+}
+
+/// Formats the condition to restrict the rows to the current tenant,
+/// which is appended to an existing `WHERE` clause.
+pub(super) fn format_tenant_condition<M: Schema>() -> String {
+    format_tenant_filter::<M>()
+        .map(|condition| format!(" AND {condition}"))
+        .unwrap_or_default()
+}
+
+/// Formats the condition to restrict the rows of a referenced model to the current tenant,
+/// where the referenced table is aliased as `table_alias`.
+pub(super) fn format_reference_tenant_filter(
+    reference: &Reference<'_>,
+    table_alias: &str,
+) -> Option<String> {
+    let tenant_key = reference.tenant_key()?;
+    let ctx = TenantContext::current().unwrap_or_default();
+    if ctx.bypassed {
+        return None;
+    }
+
+    let condition = if let Some(tenant_id) = ctx.tenant_id {
+        let field = Query::format_field(&format!("{table_alias}.{tenant_key}")).into_owned();
+        let value = Query::escape_string(tenant_id);
+        format!("{field} = {value}")
+    } else {
+        "1 = 0".to_owned()
+    };
+    Some(condition)
+}
+
+/// Formats the `WHERE` clause of the `ON CONFLICT DO UPDATE` statement,
+/// which prevents an upsert from overwriting a conflicting row of another tenant.
+///
+/// The `ON DUPLICATE KEY UPDATE` statement of MySQL can not be restricted by a condition,
+/// so the upsert is rejected for the models with the tenant scoping.
+pub(super) fn format_upsert_filter<M: Schema>(dialect: Dialect) -> Result<String, Error> {
+    let Some(tenant_key) = enforced_tenant_key::<M>() else {
+        return Ok(String::new());
+    };
+    if dialect.is_mysql_compatible() {
+        bail!(
+            "the upsert is unsupported for the tenant-scoped model `{}` in {}",
+            M::MODEL_NAME,
+            dialect
+        );
+    }
+
+    let field = [M::table_name(), tenant_key].join(".");
+    let condition = format_tenant_predicate::<M>(tenant_key, &field);
+    Ok(format!(" WHERE {condition}"))
+}
+
+/// Fills in the tenant column of the values with the current tenant.
+pub(super) fn fill_tenant<M: Schema>(values: &mut Map) -> Result<(), Error> {
+    if let Some(tenant_key) = enforced_tenant_key::<M>() {
+        let Some(tenant_id) = TenantContext::current().and_then(|ctx| ctx.tenant_id) else {
+            bail!(
+                "403 Forbidden: there is no tenant context for the model `{}`",
+                M::MODEL_NAME
+            );
+        };
+        values.upsert(tenant_key, tenant_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{mutation::MutationExt, query::QueryExt, ConnectionPool, Dialect, Schema},
+        TenantContext,
+    };
+    use crate::{
+        bail,
+        error::Error,
+        extension::JsonObjectExt,
+        json,
+        model::{Column, Model, ModelHooks, Mutation, Query, Reference},
+        Map,
+    };
+    use futures::executor;
+    use serde::{Deserialize, Serialize};
+    use std::sync::LazyLock;
+
+    #[derive(Default, Serialize, Deserialize)]
+    struct Document {
+        id: String,
+        tenant_id: String,
+        name: String,
+        owner_id: String,
+        editor_id: String,
+    }
+
+    impl Model for Document {}
+
+    impl ModelHooks for Document {}
+
+    impl Schema for Document {
+        type PrimaryKey = String;
+
+        const MODEL_NAME: &'static str = "document";
+        const TENANT_KEY_NAME: Option<&'static str> = Some("tenant_id");
+
+        fn primary_key(&self) -> &String {
+            &self.id
+        }
+
+        fn schema() -> &'static apache_avro::Schema {
+            static SCHEMA: LazyLock<apache_avro::Schema> =
+                LazyLock::new(|| apache_avro::Schema::Null);
+            &SCHEMA
+        }
+
+        fn columns() -> &'static [Column<'static>] {
+            static COLUMNS: LazyLock<Vec<Column<'static>>> = LazyLock::new(|| {
+                let mut primary_key = Column::new("id", "String", true);
+                primary_key.set_extra_attribute("primary_key", true);
+                let mut owner_reference = Reference::new("user", "id");
+                owner_reference.set_fields(vec!["id", "name"]);
+                owner_reference.set_tenant_key(Some("tenant_id"));
+                owner_reference.set_soft_deletable(true);
+
+                let mut owner_id = Column::new("owner_id", "String", true);
+                owner_id.set_reference(owner_reference.clone());
+                owner_id.set_extra_attribute("joinable", true);
+
+                let mut editor_id = Column::new("editor_id", "String", true);
+                editor_id.set_reference(owner_reference);
+                vec![
+                    primary_key,
+                    Column::new("tenant_id", "String", true),
+                    Column::new("name", "String", true),
+                    owner_id,
+                    editor_id,
+                ]
+            });
+            &COLUMNS
+        }
+
+        fn fields() -> &'static [&'static str] {
+            &["id", "tenant_id", "name", "owner_id", "editor_id"]
+        }
+
+        fn read_only_fields() -> &'static [&'static str] {
+            &[]
+        }
+
+        fn write_only_fields() -> &'static [&'static str] {
+            &[]
+        }
+
+        async fn acquire_reader() -> Result<&'static ConnectionPool, Error> {
+            bail!("there is no connection pool for the tests");
+        }
+
+        async fn acquire_writer() -> Result<&'static ConnectionPool, Error> {
+            bail!("there is no connection pool for the tests");
+        }
+    }
+
+    #[test]
+    fn it_scopes_tenant_contexts() {
+        assert!(TenantContext::current().is_none());
+        let fut = TenantContext::default().scope(async {
+            TenantContext::set_current_tenant("tenant-a");
+            let ctx = TenantContext::current().unwrap_or_default();
+            assert_eq!(ctx.tenant_id(), Some("tenant-a"));
+            assert!(!ctx.is_bypassed());
+
+            TenantContext::bypass(async {
+                let ctx = TenantContext::current().unwrap_or_default();
+                assert_eq!(ctx.tenant_id(), Some("tenant-a"));
+                assert!(ctx.is_bypassed());
+            })
+            .await;
+            assert!(!TenantContext::current().is_some_and(|ctx| ctx.is_bypassed()));
+        });
+        executor::block_on(fut);
+    }
+
+    #[test]
+    fn it_formats_tenant_predicates() {
+        let query = Query::new(json!({ "name": "report" }));
+        let mutation = Mutation::new(json!({ "tenant_id": "tenant-b", "name": "summary" }));
+
+        let fut = TenantContext::new("tenant-a").scope(async {
+            let tenant_filter = format!("{} = 'tenant-a'", Query::format_field("tenant_id"));
+            let filters = query.format_filters::<Document>(Dialect::Sqlite);
+            assert!(filters.starts_with("WHERE "));
+            assert!(filters.ends_with(&tenant_filter));
+
+            let condition = super::format_tenant_condition::<Document>();
+            assert_eq!(condition, format!(" AND {tenant_filter}"));
+
+            // The tenant column can not be changed by the mutations.
+            let updates = mutation.format_updates::<Document>();
+            assert!(updates.contains("summary"));
+            assert!(!updates.contains("tenant-b"));
+
+            let upsert_filter = super::format_upsert_filter::<Document>(Dialect::Postgres);
+            let table_field = Query::format_field("document.tenant_id");
+            let table_tenant_filter = format!("{table_field} = 'tenant-a'");
+            assert_eq!(
+                upsert_filter.ok(),
+                Some(format!(" WHERE {table_tenant_filter}"))
+            );
+            assert!(super::format_upsert_filter::<Document>(Dialect::MySql).is_err());
+
+            let mut values = Map::new();
+            values.upsert("tenant_id", "tenant-b");
+            assert!(super::fill_tenant::<Document>(&mut values).is_ok());
+            assert_eq!(values.get_str("tenant_id"), Some("tenant-a"));
+
+            TenantContext::bypass(async {
+                assert!(super::format_tenant_filter::<Document>().is_none());
+                assert!(super::format_upsert_filter::<Document>(Dialect::MySql).is_ok());
+            })
+            .await;
+        });
+        executor::block_on(fut);
+
+        let fut = TenantContext::default().scope(async {
+            let filters = query.format_filters::<Document>(Dialect::Sqlite);
+            assert!(filters.ends_with("1 = 0"));
+
+            let upsert_filter = super::format_upsert_filter::<Document>(Dialect::Sqlite);
+            assert_eq!(upsert_filter.ok().as_deref(), Some(" WHERE 1 = 0"));
+            assert!(super::fill_tenant::<Document>(&mut Map::new()).is_err());
+        });
+        executor::block_on(fut);
+    }
+
+    #[test]
+    fn it_scopes_joined_references() {
+        let query = Query::new(json!({ "$join": ["owner_id", "editor_id"] }));
+        let fut = TenantContext::new("tenant-a").scope(async {
+            let (projection, joins) = query.format_joins::<Document>(Dialect::Postgres);
+            assert!(projection.contains("owner_id_populated"));
+            assert!(!projection.contains("editor_id_populated"));
+            assert!(joins.starts_with("LEFT JOIN"));
+            assert!(!joins.contains("SELECT"));
+            assert!(joins.contains("'Deleted'"));
+            assert!(joins.contains("'tenant-a'"));
+            assert!(!joins.contains("editor_id"));
+        });
+        executor::block_on(fut);
+    }
+}
@@ -3,11 +3,12 @@
 use crate::{
     application::http_client,
     auth::{
-        self, AccessKeyId, Authentication, JwtClaims, ParseSecurityTokenError, SecurityToken,
-        SessionId,
+        self, AccessKeyId, Authentication, JwtClaims, JwtVerifier, ParseSecurityTokenError,
+        SecurityToken, SessionId,
     },
     channel::{CloudEvent, Subscription},
     datetime::DateTime,
+    encoding::base64,
     error::Error,
     extension::{HeaderMapExt, JsonObjectExt},
     file::NamedFile,
@@ -20,9 +21,9 @@ use crate::{
 };
 use bytes::Bytes;
 use cookie::{Cookie, SameSite};
+use etag::EntityTag;
 use fluent::FluentArgs;
 use http::Uri;
-use jwt_simple::algorithms::MACLike;
 use multer::Multipart;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
@@ -31,11 +32,17 @@ use std::{
     str::FromStr,
     time::{Duration, Instant},
 };
+use tracing::Span;
 use unic_langid::LanguageIdentifier;
 
 mod context;
+mod rate_limiter;
 
 pub use context::Context;
+pub use rate_limiter::{
+    MemoryRateLimitStore, RateLimitKey, RateLimitQuota, RateLimitRule, RateLimitStatus,
+    RateLimitStore, RateLimiter,
+};
 
 /// Request context.
 pub trait RequestContext {
@@ -110,6 +117,12 @@ pub trait RequestContext {
         ctx.set_trace_id(trace_id);
         ctx.set_session_id(session_id);
 
+        // Record the context fields in the current span.
+        let span = Span::current();
+        span.record("context.request_id", request_id.to_string());
+        span.record("context.trace_id", trace_id.to_string());
+        span.record("http.route", self.matched_route().as_ref());
+
         // Set locale.
         if let Some(cookie) = self.get_cookie("locale") {
             ctx.set_locale(cookie.value());
@@ -349,7 +362,7 @@ pub trait RequestContext {
     }
 
     /// Parses the request body as a multipart, which is commonly used with file uploads.
-    async fn parse_multipart(&mut self) -> Result<Multipart, Rejection> {
+    async fn parse_multipart(&mut self) -> Result<Multipart<'_>, Rejection> {
         let Some(content_type) = self.get_header("content-type") else {
             return Err(Rejection::from_validation_entry(
                 "content_type",
@@ -551,7 +564,7 @@ pub trait RequestContext {
     fn parse_jwt_claims<T, K>(&self, key: &K) -> Result<JwtClaims<T>, Rejection>
     where
         T: Default + Serialize + DeserializeOwned,
-        K: MACLike,
+        K: JwtVerifier,
     {
         let (param, mut token) = match self.get_query("access_token") {
             Some(access_token) => ("access_token", access_token),
@@ -575,8 +588,8 @@ pub trait RequestContext {
             .map(|i| Duration::from_secs(i).into());
         options.required_nonce = self.get_query("nonce").map(|s| s.to_owned());
 
-        match key.verify_token(token, Some(options)) {
-            Ok(claims) => Ok(JwtClaims(claims)),
+        match key.verify_jwt(token, options) {
+            Ok(claims) => Ok(claims),
             Err(err) => {
                 let message = format!("401 Unauthorized: {err}");
                 Err(Rejection::with_message(message).context(self))
@@ -584,6 +597,49 @@ pub trait RequestContext {
         }
     }
 
+    /// Attempts to parse the HTTP Basic credentials from the `authorization` header.
+    /// The user ID and the password are decoded with the `application/x-www-form-urlencoded`
+    /// encoding, which is used for the OAuth2 client credentials.
+    fn parse_basic_credentials(&self) -> Option<(String, String)> {
+        let credentials = self.get_header("authorization")?.strip_prefix("Basic ")?;
+        let bytes = base64::decode(credentials.trim().trim_end_matches('=')).ok()?;
+        let credentials = String::from_utf8(bytes).ok()?;
+        let (user_id, password) = credentials.split_once(':')?;
+        let decode = |s: &str| {
+            url::form_urlencoded::parse(s.as_bytes())
+                .next()
+                .map(|(s, _)| s.into_owned())
+                .unwrap_or_default()
+        };
+        Some((decode(user_id), decode(password)))
+    }
+
+    /// Checks the `if-match` header against the ETag of the JSON data.
+    /// The ETag is computed in the same way as the `etag` header of a response,
+    /// and a `409 Conflict` rejection will be returned if none of the ETags match.
+    fn check_if_match<T: Serialize>(&self, data: &T) -> Result<(), Rejection> {
+        let Some(if_match) = self.get_header("if-match") else {
+            return Ok(());
+        };
+        if if_match.trim() == "*" {
+            return Ok(());
+        }
+
+        let bytes = serde_json::to_vec(data)
+            .map_err(|err| Rejection::internal_server_error(err).context(self))?;
+        let etag = EntityTag::from_data(&bytes);
+        if if_match
+            .split(',')
+            .filter_map(|s| s.trim().parse::<EntityTag>().ok())
+            .any(|tag| tag.strong_eq(&etag))
+        {
+            Ok(())
+        } else {
+            let message = "409 Conflict: the `if-match` precondition has failed";
+            Err(Rejection::with_message(message).context(self))
+        }
+    }
+
     /// Returns a `Response` or `Rejection` from a model query validation.
     /// The data is extracted from [`parse_query()`](RequestContext::parse_query).
     fn query_validation<S>(&self, query: &mut Query) -> Result<Response<S>, Rejection>
@@ -0,0 +1,496 @@
+use super::RequestContext;
+use crate::{
+    auth::{AccessPolicy, JwtKeySet},
+    bail,
+    datetime::DateTime,
+    error::Error,
+    extension::TomlTableExt,
+    state::State,
+    warn, BoxFuture, Map,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex, OnceLock, RwLock},
+    time::Duration,
+};
+use toml::Table;
+
+/// Shared rate limiter.
+static SHARED_RATE_LIMITER: LazyLock<RwLock<Arc<RateLimiter>>> = LazyLock::new(|| {
+    let limiter = State::shared()
+        .get_config("rate-limit")
+        .map(RateLimiter::from_config)
+        .unwrap_or_default();
+    RwLock::new(Arc::new(limiter))
+});
+
+/// Shared rate limit store.
+static SHARED_RATE_LIMIT_STORE: OnceLock<Box<dyn RateLimitStore>> = OnceLock::new();
+
+/// Kinds of the key to identify the clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateLimitKey {
+    /// The client's remote IP. This is the default value.
+    #[default]
+    ClientIp,
+    /// The access key ID. It falls back to the client IP if absent.
+    AccessKeyId,
+    /// The subject of the JWT claims. It falls back to the client IP if absent.
+    UserId,
+}
+
+impl RateLimitKey {
+    /// Parses the key kind.
+    pub fn parse(key: &str) -> Option<Self> {
+        match key {
+            "client-ip" => Some(Self::ClientIp),
+            "access-key-id" => Some(Self::AccessKeyId),
+            "user-id" => Some(Self::UserId),
+            _ => None,
+        }
+    }
+
+    /// Returns the key kind as `str`.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClientIp => "client-ip",
+            Self::AccessKeyId => "access-key-id",
+            Self::UserId => "user-id",
+        }
+    }
+
+    /// Extracts the key value from the request context.
+    fn extract<Ctx: RequestContext + ?Sized>(&self, ctx: &Ctx) -> String {
+        let value = match self {
+            Self::ClientIp => None,
+            Self::AccessKeyId => ctx
+                .parse_access_key_id()
+                .ok()
+                .map(|access_key_id| format!("access-key-id:{access_key_id}")),
+            Self::UserId => ctx
+                .parse_jwt_claims::<Map, _>(JwtKeySet::shared())
+                .ok()
+                .and_then(|claims| Some(format!("user-id:{}", claims.subject()?))),
+        };
+        value.unwrap_or_else(|| match ctx.client_ip() {
+            Some(ip) => format!("client-ip:{ip}"),
+            None => "client-ip:unknown".to_owned(),
+        })
+    }
+}
+
+/// A quota of the requests in a time window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitQuota {
+    /// Max number of the requests in the time window.
+    limit: u32,
+    /// Time window.
+    window: Duration,
+}
+
+impl RateLimitQuota {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit: limit.max(1),
+            window: window.max(Duration::from_millis(1)),
+        }
+    }
+
+    /// Returns the max number of the requests in the time window.
+    #[inline]
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Returns the time window.
+    #[inline]
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Checks the quota with the generic cell rate algorithm, which is equivalent to
+    /// a token bucket with the capacity `limit` refilled evenly in the time window.
+    /// The `tat` is the theoretical arrival time of the bucket in milliseconds,
+    /// and the new value will be returned if a permit has been acquired.
+    pub fn check(&self, tat: Option<i64>, now: i64) -> (RateLimitStatus, Option<i64>) {
+        let window = i64::try_from(self.window.as_millis()).unwrap_or(i64::MAX);
+        let interval = (window / i64::from(self.limit)).max(1);
+        let tat = tat.unwrap_or(now).max(now);
+        let new_tat = tat.saturating_add(interval);
+        let delay = new_tat - now;
+        if delay <= window {
+            let remaining = u32::try_from((window - delay) / interval).unwrap_or(self.limit);
+            let status = RateLimitStatus {
+                limit: self.limit,
+                remaining,
+                reset: Duration::from_millis(delay as u64),
+                retry_after: None,
+            };
+            (status, Some(new_tat))
+        } else {
+            let status = RateLimitStatus {
+                limit: self.limit,
+                remaining: 0,
+                reset: Duration::from_millis((tat - now) as u64),
+                retry_after: Some(Duration::from_millis((delay - window) as u64)),
+            };
+            (status, None)
+        }
+    }
+}
+
+/// Status of the rate limit for a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Max number of the requests in the time window.
+    limit: u32,
+    /// Number of the remaining requests.
+    remaining: u32,
+    /// Time until the quota is fully replenished.
+    reset: Duration,
+    /// Time to wait before making a new request if the request is limited.
+    retry_after: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    /// Returns the max number of the requests in the time window.
+    #[inline]
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Returns the number of the remaining requests.
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Returns the time until the quota is fully replenished.
+    #[inline]
+    pub fn reset(&self) -> Duration {
+        self.reset
+    }
+
+    /// Returns the time to wait before making a new request if the request is limited.
+    #[inline]
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    /// Returns `true` if the request is allowed.
+    #[inline]
+    pub fn is_allowed(&self) -> bool {
+        self.retry_after.is_none()
+    }
+
+    /// Returns the `RateLimit-*` headers and the `Retry-After` header
+    /// if the request is limited. The durations are rounded up to seconds.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("ratelimit-limit", self.limit.to_string()),
+            ("ratelimit-remaining", self.remaining.to_string()),
+            ("ratelimit-reset", ceil_secs(self.reset).to_string()),
+        ];
+        if let Some(retry_after) = self.retry_after {
+            headers.push(("retry-after", ceil_secs(retry_after).to_string()));
+        }
+        headers
+    }
+}
+
+/// A rule of the rate limit for the requests matching a route pattern.
+#[derive(Debug, Clone)]
+pub struct RateLimitRule {
+    /// Rule name. It is used as the prefix of the bucket keys.
+    name: String,
+    /// Route pattern. The segment `*` matches any one segment,
+    /// and the segment `**` matches any remaining segments.
+    route: String,
+    /// Request methods. The rule applies to all methods if it is empty.
+    methods: Vec<String>,
+    /// Kind of the key to identify the clients.
+    key: RateLimitKey,
+    /// Quota for each client.
+    quota: RateLimitQuota,
+}
+
+impl RateLimitRule {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(route: impl Into<String>, key: RateLimitKey, quota: RateLimitQuota) -> Self {
+        let route = route.into();
+        Self {
+            name: route.clone(),
+            route,
+            methods: Vec::new(),
+            key,
+            quota,
+        }
+    }
+
+    /// Attempts to create a new instance from the config.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let Some(route) = config.get_str("route") else {
+            bail!("the route pattern should be specified");
+        };
+        let key = match config.get_str("key") {
+            Some(key) => RateLimitKey::parse(key)
+                .ok_or_else(|| warn!("the rate limit key `{}` is unsupported", key))?,
+            None => RateLimitKey::default(),
+        };
+        let Some(limit) = config.get_u32("limit") else {
+            bail!("the limit should be specified for the route `{}`", route);
+        };
+        let window = config
+            .get_duration("window")
+            .unwrap_or_else(|| Duration::from_secs(60));
+
+        let mut rule = Self::new(route, key, RateLimitQuota::new(limit, window));
+        if let Some(methods) = config.get_str_array("methods") {
+            rule.set_methods(methods.into_iter().map(|s| s.to_owned()).collect());
+        }
+        if let Some(name) = config.get_str("name") {
+            rule.set_name(name);
+        }
+        Ok(rule)
+    }
+
+    /// Sets the rule name.
+    #[inline]
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Sets the request methods.
+    #[inline]
+    pub fn set_methods(&mut self, methods: Vec<String>) {
+        self.methods = methods;
+    }
+
+    /// Returns the rule name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the route pattern.
+    #[inline]
+    pub fn route(&self) -> &str {
+        &self.route
+    }
+
+    /// Returns the kind of the key.
+    #[inline]
+    pub fn key(&self) -> RateLimitKey {
+        self.key
+    }
+
+    /// Returns the quota.
+    #[inline]
+    pub fn quota(&self) -> RateLimitQuota {
+        self.quota
+    }
+
+    /// Returns `true` if the rule applies to the request method and path.
+    pub fn applies_to(&self, method: &str, path: &str) -> bool {
+        (self.methods.is_empty() || self.methods.iter().any(|m| m.eq_ignore_ascii_case(method)))
+            && AccessPolicy::matches_resource(&self.route, path)
+    }
+}
+
+/// A rate limiter with the rules matched in order.
+/// Only the first rule which applies to a request is checked.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    /// Rate limit rules.
+    rules: Vec<RateLimitRule>,
+}
+
+impl RateLimiter {
+    /// Creates a new instance without any rules.
+    #[inline]
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Creates a new instance with the rules in the config.
+    /// The invalid rules will be ignored.
+    pub fn from_config(config: &Table) -> Self {
+        let mut limiter = Self::new();
+        if let Some(rules) = config.get_array("rules") {
+            for config in rules.iter().filter_map(|v| v.as_table()) {
+                match RateLimitRule::try_from_config(config) {
+                    Ok(rule) => limiter.add_rule(rule),
+                    Err(err) => tracing::error!("fail to load the rate limit rule: {err}"),
+                }
+            }
+        }
+        limiter
+    }
+
+    /// Adds a rate limit rule.
+    #[inline]
+    pub fn add_rule(&mut self, rule: RateLimitRule) {
+        self.rules.push(rule);
+    }
+
+    /// Returns the rate limit rules.
+    #[inline]
+    pub fn rules(&self) -> &[RateLimitRule] {
+        &self.rules
+    }
+
+    /// Returns `true` if there are no rules.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the bucket key and the quota for the request
+    /// if there is a rule which applies to it.
+    pub fn resolve<Ctx: RequestContext + ?Sized>(
+        &self,
+        ctx: &Ctx,
+    ) -> Option<(String, RateLimitQuota)> {
+        let method = ctx.request_method().as_ref();
+        let path = ctx.request_path();
+        let rule = self
+            .rules
+            .iter()
+            .find(|rule| rule.applies_to(method, path))?;
+        let key = format!("{}:{}", rule.name, rule.key.extract(ctx));
+        Some((key, rule.quota))
+    }
+
+    /// Sets `self` as the shared rate limiter. It replaces the previous one,
+    /// so the rules can be reloaded at runtime.
+    #[inline]
+    pub fn set_shared(self) {
+        if let Ok(mut limiter) = SHARED_RATE_LIMITER.write() {
+            *limiter = Arc::new(self);
+        }
+    }
+
+    /// Returns the shared rate limiter.
+    /// The rules are loaded from the `rate-limit` config by default.
+    #[inline]
+    pub fn shared() -> Arc<Self> {
+        SHARED_RATE_LIMITER
+            .read()
+            .map(|limiter| limiter.clone())
+            .unwrap_or_default()
+    }
+
+    /// Sets the shared rate limit store, which should be backed by a shared service
+    /// to hold the limits across replicas. It returns `false` if the store has been set or used.
+    #[inline]
+    pub fn set_store(store: impl RateLimitStore) -> bool {
+        SHARED_RATE_LIMIT_STORE.set(Box::new(store)).is_ok()
+    }
+
+    /// Returns the shared rate limit store.
+    /// It defaults to a [`MemoryRateLimitStore`] if no store has been set.
+    #[inline]
+    pub fn store() -> &'static dyn RateLimitStore {
+        SHARED_RATE_LIMIT_STORE
+            .get_or_init(|| Box::new(MemoryRateLimitStore::new()))
+            .as_ref()
+    }
+}
+
+/// A store for the states of the rate limit buckets.
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Acquires a permit of the quota for the bucket key.
+    /// The check and the update of the bucket should be atomic.
+    fn acquire(
+        &self,
+        key: &str,
+        quota: RateLimitQuota,
+    ) -> BoxFuture<'_, Result<RateLimitStatus, Error>>;
+}
+
+/// An in-memory rate limit store. The limits only hold for a single instance.
+#[derive(Debug, Default)]
+pub struct MemoryRateLimitStore {
+    /// Theoretical arrival times of the buckets and the time when they were pruned at.
+    buckets: Mutex<(HashMap<String, i64>, i64)>,
+}
+
+impl MemoryRateLimitStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn acquire(
+        &self,
+        key: &str,
+        quota: RateLimitQuota,
+    ) -> BoxFuture<'_, Result<RateLimitStatus, Error>> {
+        let now = DateTime::current_timestamp_millis();
+        let mut guard = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let (buckets, pruned_at) = &mut *guard;
+        if now - *pruned_at >= 1000 {
+            buckets.retain(|_, tat| *tat > now);
+            *pruned_at = now;
+        }
+
+        let (status, tat) = quota.check(buckets.get(key).copied(), now);
+        if let Some(tat) = tat {
+            buckets.insert(key.to_owned(), tat);
+        }
+        Box::pin(async move { Ok(status) })
+    }
+}
+
+/// Rounds up the duration to seconds.
+#[inline]
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_millis().div_ceil(1000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryRateLimitStore, RateLimitQuota, RateLimitStore};
+    use futures::executor;
+    use std::time::Duration;
+
+    #[test]
+    fn it_limits_requests() {
+        let quota = RateLimitQuota::new(3, Duration::from_secs(3));
+        let (status, tat) = quota.check(None, 0);
+        assert_eq!(status.remaining(), 2);
+        assert_eq!(tat, Some(1000));
+
+        let (status, tat) = quota.check(tat, 0);
+        assert_eq!(status.remaining(), 1);
+        let (status, tat) = quota.check(tat, 0);
+        assert_eq!(status.remaining(), 0);
+        assert_eq!(status.reset(), Duration::from_secs(3));
+
+        let (status, limited) = quota.check(tat, 500);
+        assert!(!status.is_allowed());
+        assert!(limited.is_none());
+        assert_eq!(status.retry_after(), Some(Duration::from_millis(500)));
+        assert!(status.headers().contains(&("retry-after", "1".to_owned())));
+
+        let (status, _) = quota.check(tat, 1000);
+        assert!(status.is_allowed());
+        assert_eq!(status.remaining(), 0);
+
+        let store = MemoryRateLimitStore::new();
+        executor::block_on(async {
+            assert!(store.acquire("a", quota).await.unwrap().is_allowed());
+            assert!(store.acquire("a", quota).await.unwrap().is_allowed());
+            assert!(store.acquire("a", quota).await.unwrap().is_allowed());
+            assert!(!store.acquire("a", quota).await.unwrap().is_allowed());
+            assert!(store.acquire("b", quota).await.unwrap().is_allowed());
+        });
+    }
+}
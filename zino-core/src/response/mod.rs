@@ -8,11 +8,12 @@ use crate::{
     request::RequestContext,
     trace::{ServerTiming, TimingMetric, TraceContext},
     validation::Validation,
-    JsonValue, SharedString, Uuid,
+    JsonValue, Map, SharedString, Uuid,
 };
 use bytes::Bytes;
 use cookie::Cookie;
 use etag::EntityTag;
+use futures::stream::{BoxStream, Stream, StreamExt};
 use http::header::{self, HeaderName, HeaderValue};
 use http_body::Full;
 use serde::Serialize;
@@ -23,10 +24,15 @@ use std::{
 };
 
 mod rejection;
+mod response_cache;
 mod response_code;
+mod stream;
 mod webhook;
 
 pub use rejection::{ExtractRejection, Rejection};
+pub use response_cache::{
+    CacheDirective, CachedResponse, ResponseCache, ResponseCacheRule, ResponseCacheStore,
+};
 pub use response_code::ResponseCode;
 pub use webhook::WebHook;
 
@@ -39,6 +45,9 @@ pub type FullResponse = http::Response<Full<Bytes>>;
 /// A function pointer of transforming the response data.
 pub type DataTransformer = fn(data: &JsonValue) -> Result<Bytes, Error>;
 
+/// A stream of the chunks of the response body.
+pub type DataStream = BoxStream<'static, Result<Bytes, Error>>;
+
 /// An HTTP response.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -84,6 +93,9 @@ pub struct Response<S = StatusCode> {
     /// Transformer of the response data.
     #[serde(skip)]
     data_transformer: Option<DataTransformer>,
+    /// Stream of the response body.
+    #[serde(skip)]
+    data_stream: Option<stream::SharedDataStream>,
     /// Content type.
     #[serde(skip)]
     content_type: Option<SharedString>,
@@ -120,6 +132,7 @@ impl<S: ResponseCode> Response<S> {
             json_data: JsonValue::Null,
             bytes_data: Bytes::new(),
             data_transformer: None,
+            data_stream: None,
             content_type: None,
             trace_context: None,
             server_timing: ServerTiming::new(),
@@ -152,6 +165,7 @@ impl<S: ResponseCode> Response<S> {
             json_data: JsonValue::Null,
             bytes_data: Bytes::new(),
             data_transformer: None,
+            data_stream: None,
             content_type: None,
             trace_context: None,
             server_timing: ServerTiming::new(),
@@ -363,6 +377,62 @@ impl<S: ResponseCode> Response<S> {
         self.set_data_transformer(|data| Ok(data.to_csv(Vec::new())?.into()));
     }
 
+    /// Sets the data stream as the response body, which will be written in chunks.
+    ///
+    /// If the stream yields an error, the error is logged and the body is aborted
+    /// without the final chunk, so the client can detect the incomplete response.
+    #[inline]
+    pub fn set_data_stream(
+        &mut self,
+        stream: impl Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    ) {
+        let stream = stream::abort_on_error(stream).boxed();
+        self.data_stream = Some(stream::SharedDataStream::new(stream));
+    }
+
+    /// Sets the stream of JSON objects as the response body in the JSON format.
+    #[inline]
+    pub fn set_json_stream_response(
+        &mut self,
+        entries: impl Stream<Item = Result<Map, Error>> + Send + 'static,
+    ) {
+        self.set_data_stream(stream::encode_json(entries));
+        self.set_content_type("application/json; charset=utf-8");
+    }
+
+    /// Sets the stream of JSON objects as the response body in the JSON Lines format.
+    #[inline]
+    pub fn set_jsonlines_stream_response(
+        &mut self,
+        entries: impl Stream<Item = Result<Map, Error>> + Send + 'static,
+    ) {
+        self.set_data_stream(stream::encode_jsonlines(entries));
+        self.set_content_type("application/jsonlines; charset=utf-8");
+    }
+
+    /// Sets the stream of JSON objects as the response body in the MsgPack format.
+    ///
+    /// Since the array length is written before the entries, the encoded entries
+    /// are buffered until the stream ends.
+    #[inline]
+    pub fn set_msgpack_stream_response(
+        &mut self,
+        entries: impl Stream<Item = Result<Map, Error>> + Send + 'static,
+    ) {
+        self.set_data_stream(stream::encode_msgpack(entries));
+        self.set_content_type("application/msgpack");
+    }
+
+    /// Sets the stream of JSON objects as the response body in the CSV format.
+    #[inline]
+    pub fn set_csv_stream_response(
+        &mut self,
+        entries: impl Stream<Item = Result<Map, Error>> + Send + 'static,
+    ) {
+        self.set_data_stream(stream::encode_csv(entries));
+        self.set_content_type("text/csv; charset=utf-8");
+    }
+
     /// Sets the plain text as the response body.
     #[inline]
     pub fn set_text_response<T: ?Sized + Serialize>(&mut self, data: impl Into<String>) {
@@ -510,6 +580,12 @@ impl<S: ResponseCode> Response<S> {
         self.server_timing.to_string()
     }
 
+    /// Takes the data stream of the response body if it has been set.
+    #[inline]
+    pub fn take_data_stream(&mut self) -> Option<DataStream> {
+        self.data_stream.as_ref().and_then(|stream| stream.take())
+    }
+
     /// Reads the response into a byte buffer.
     pub fn read_bytes(&mut self) -> Result<Bytes, Error> {
         let has_bytes_data = !self.bytes_data.is_empty();
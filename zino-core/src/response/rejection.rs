@@ -35,6 +35,8 @@ enum RejectionKind {
     MethodNotAllowed(Error),
     /// 409 Conflict
     Conflict(Error),
+    /// 429 Too Many Requests
+    TooManyRequests(Error),
     /// 500 Internal Server Error
     InternalServerError(Error),
     /// 503 Service Unavailable
@@ -102,6 +104,16 @@ impl Rejection {
         }
     }
 
+    /// Creates a `429 Too Many Requests` rejection.
+    #[inline]
+    pub fn too_many_requests(err: impl Into<Error>) -> Self {
+        Self {
+            kind: TooManyRequests(err.into()),
+            context: None,
+            trace_context: None,
+        }
+    }
+
     /// Creates a `500 Internal Server Error` rejection.
     #[inline]
     pub fn internal_server_error(err: impl Into<Error>) -> Self {
@@ -133,7 +145,9 @@ impl Rejection {
     pub fn from_error(err: impl Into<Error>) -> Self {
         let err = err.into();
         let message = err.message();
-        if message.starts_with("401 Unauthorized") {
+        if message.starts_with("400 Bad Request") {
+            Self::from_validation_entry("message", err)
+        } else if message.starts_with("401 Unauthorized") {
             Self::unauthorized(err)
         } else if message.starts_with("403 Forbidden") {
             Self::forbidden(err)
@@ -143,6 +157,8 @@ impl Rejection {
             Self::method_not_allowed(err)
         } else if message.starts_with("409 Conflict") {
             Self::conflict(err)
+        } else if message.starts_with("429 Too Many Requests") {
+            Self::too_many_requests(err)
         } else if message.starts_with("503 Service Unavailable") {
             Self::service_unavailable(err)
         } else {
@@ -174,6 +190,7 @@ impl Rejection {
             NotFound(_) => 404,
             MethodNotAllowed(_) => 405,
             Conflict(_) => 409,
+            TooManyRequests(_) => 429,
             InternalServerError(_) => 500,
             ServiceUnavailable(_) => 503,
         }
@@ -213,6 +230,11 @@ impl From<Rejection> for Response<StatusCode> {
                 res.set_error_message(err);
                 res
             }
+            TooManyRequests(err) => {
+                let mut res = Response::new(StatusCode::TOO_MANY_REQUESTS);
+                res.set_error_message(err);
+                res
+            }
             InternalServerError(err) => {
                 let mut res = Response::new(StatusCode::INTERNAL_SERVER_ERROR);
                 res.set_error_message(err);
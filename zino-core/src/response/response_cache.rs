@@ -0,0 +1,626 @@
+use crate::{
+    auth::AccessPolicy,
+    bail,
+    datetime::DateTime,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    request::RequestContext,
+    state::State,
+    BoxFuture, JsonValue, Map,
+};
+use bytes::Bytes;
+use std::{
+    sync::{Arc, LazyLock, OnceLock, RwLock},
+    time::Duration,
+};
+use toml::Table;
+
+/// Shared response cache.
+static SHARED_RESPONSE_CACHE: LazyLock<RwLock<Arc<ResponseCache>>> = LazyLock::new(|| {
+    let cache = State::shared()
+        .get_config("response-cache")
+        .map(ResponseCache::from_config)
+        .unwrap_or_default();
+    RwLock::new(Arc::new(cache))
+});
+
+/// Shared response cache store.
+static SHARED_RESPONSE_CACHE_STORE: OnceLock<Box<dyn ResponseCacheStore>> = OnceLock::new();
+
+/// Default max size of the response body to be cached.
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Default name of the session cookie.
+const DEFAULT_SESSION_COOKIE: &str = "session_id";
+
+/// Response headers which are specific to a request and should not be cached.
+const UNCACHED_HEADERS: [&str; 7] = [
+    "content-length",
+    "date",
+    "server-timing",
+    "set-cookie",
+    "traceparent",
+    "tracestate",
+    "x-request-id",
+];
+
+/// A cached response.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// Status code.
+    status_code: u16,
+    /// Headers.
+    headers: Vec<(String, String)>,
+    /// Body.
+    body: Bytes,
+    /// Time when the response was cached.
+    cached_at: DateTime,
+}
+
+impl CachedResponse {
+    /// Creates a new instance. The headers specific to the request are discarded.
+    pub fn new(status_code: u16, mut headers: Vec<(String, String)>, body: Bytes) -> Self {
+        headers.retain(|(name, _)| {
+            !UNCACHED_HEADERS
+                .iter()
+                .any(|header| name.eq_ignore_ascii_case(header))
+        });
+        Self {
+            status_code,
+            headers,
+            body,
+            cached_at: DateTime::now(),
+        }
+    }
+
+    /// Attempts to construct an instance from a json object
+    /// created by [`into_json`](Self::into_json).
+    pub fn try_from_json(data: &Map) -> Result<Self, Error> {
+        let Some(status_code) = data.get_u16("status_code") else {
+            bail!("the status code of the cached response is missing");
+        };
+        let headers = data
+            .get_array("headers")
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|header| {
+                        let [name, value] = header.as_array()?.as_slice() else {
+                            return None;
+                        };
+                        Some((name.as_str()?.to_owned(), value.as_str()?.to_owned()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let body = base64::decode(data.get_str("body").unwrap_or_default())?;
+        let cached_at = data
+            .parse_datetime("cached_at")
+            .and_then(|result| result.ok())
+            .unwrap_or_else(DateTime::now);
+        Ok(Self {
+            status_code,
+            headers,
+            body: body.into(),
+            cached_at,
+        })
+    }
+
+    /// Consumes `self` and returns a json object with the body encoded in base64.
+    pub fn into_json(self) -> Map {
+        let headers = self
+            .headers
+            .into_iter()
+            .map(|(name, value)| JsonValue::from(vec![name, value]))
+            .collect::<Vec<_>>();
+        let mut data = Map::new();
+        data.upsert("status_code", self.status_code);
+        data.upsert("headers", headers);
+        data.upsert("body", base64::encode(&self.body));
+        data.upsert("cached_at", self.cached_at);
+        data
+    }
+
+    /// Returns the status code.
+    #[inline]
+    pub fn status_code(&self) -> u16 {
+        self.status_code
+    }
+
+    /// Returns the headers.
+    #[inline]
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the body.
+    #[inline]
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Returns the age of the cached response in seconds.
+    #[inline]
+    pub fn age(&self) -> u64 {
+        self.cached_at
+            .span_before_now()
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    /// Returns the cached headers together with the `Age` header
+    /// and the headers specific to the request context.
+    pub fn headers_with_context<Ctx: RequestContext + ?Sized>(
+        &self,
+        ctx: &Ctx,
+    ) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        headers.push(("age".to_owned(), self.age().to_string()));
+
+        let request_id = ctx.request_id();
+        if !request_id.is_nil() {
+            headers.push(("x-request-id".to_owned(), request_id.to_string()));
+        }
+
+        let trace_context = ctx.new_trace_context();
+        headers.push(("traceparent".to_owned(), trace_context.traceparent()));
+        headers.push(("tracestate".to_owned(), trace_context.tracestate()));
+        headers
+    }
+}
+
+/// A rule of the response cache for the `GET` requests matching a route pattern.
+#[derive(Debug, Clone)]
+pub struct ResponseCacheRule {
+    /// Route pattern. The segment `*` matches any one segment,
+    /// and the segment `**` matches any remaining segments.
+    route: String,
+    /// Name of the model behind the route. The cached responses are invalidated
+    /// when the model has been changed.
+    model: String,
+    /// Time to live of the cached responses.
+    ttl: Duration,
+    /// Names of the request headers which the responses vary on.
+    vary: Vec<String>,
+    /// A flag indicating whether the responses of the authenticated requests are cached.
+    private: bool,
+}
+
+impl ResponseCacheRule {
+    /// Creates a new instance. The model name defaults to
+    /// the first segment of the route pattern.
+    pub fn new(route: impl Into<String>, ttl: Duration) -> Self {
+        let route = route.into();
+        let model = route
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        Self {
+            route,
+            model,
+            ttl,
+            vary: Vec::new(),
+            private: false,
+        }
+    }
+
+    /// Attempts to create a new instance from the config.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let Some(route) = config.get_str("route") else {
+            bail!("the route pattern should be specified");
+        };
+        let Some(ttl) = config.get_duration("ttl") else {
+            bail!("the TTL should be specified for the route `{}`", route);
+        };
+
+        let mut rule = Self::new(route, ttl);
+        if let Some(model) = config.get_str("model") {
+            rule.set_model(model);
+        }
+        if let Some(vary) = config.get_str_array("vary") {
+            rule.set_vary(vary.into_iter().map(|s| s.to_owned()).collect());
+        }
+        if let Some(private) = config.get_bool("private") {
+            rule.set_private(private);
+        }
+        Ok(rule)
+    }
+
+    /// Sets the model name.
+    #[inline]
+    pub fn set_model(&mut self, model: impl Into<String>) {
+        self.model = model.into();
+    }
+
+    /// Sets the names of the request headers which the responses vary on.
+    #[inline]
+    pub fn set_vary(&mut self, vary: Vec<String>) {
+        self.vary = vary
+            .into_iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+    }
+
+    /// Sets the flag to cache the responses of the authenticated requests.
+    /// They are cached separately for each subject and tenant.
+    #[inline]
+    pub fn set_private(&mut self, private: bool) {
+        self.private = private;
+    }
+
+    /// Returns the route pattern.
+    #[inline]
+    pub fn route(&self) -> &str {
+        &self.route
+    }
+
+    /// Returns the model name.
+    #[inline]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the time to live.
+    #[inline]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the names of the request headers which the responses vary on.
+    #[inline]
+    pub fn vary(&self) -> &[String] {
+        &self.vary
+    }
+
+    /// Returns `true` if the responses of the authenticated requests are cached.
+    #[inline]
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Returns the value of the `Vary` header if the responses vary on any request headers.
+    #[inline]
+    pub fn vary_header(&self) -> Option<String> {
+        (!self.vary.is_empty()).then(|| self.vary.join(", "))
+    }
+
+    /// Returns `true` if the rule applies to the request method and path.
+    #[inline]
+    pub fn applies_to(&self, method: &str, path: &str) -> bool {
+        method == "GET" && AccessPolicy::matches_resource(&self.route, path)
+    }
+
+    /// Formats the cache key prefixed with the model name. The authenticated subject
+    /// and the tenant ID are included in the key if the `session` is specified.
+    pub fn format_key<'a>(
+        &self,
+        path: &str,
+        query: Option<&str>,
+        get_header: impl Fn(&str) -> Option<&'a str>,
+        session: Option<(&str, Option<&str>)>,
+    ) -> String {
+        let mut key = format!("{}:{path}", self.model);
+        if let Some(query) = query {
+            key.push('?');
+            key.push_str(query);
+        }
+        for name in self.vary.iter() {
+            let value = get_header(name).unwrap_or_default();
+            key.push_str(&format!("|{name}={value}"));
+        }
+        if let Some((subject, tenant_id)) = session {
+            let tenant_id = tenant_id.unwrap_or_default();
+            key.push_str(&format!("|sub={subject}|tenant={tenant_id}"));
+        }
+        key
+    }
+}
+
+/// Directives of the `Cache-Control` request header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheDirective {
+    /// Serves the response from the cache if it is fresh.
+    Default,
+    /// Bypasses the cached response but stores the new one.
+    NoCache,
+    /// Neither serves nor stores the response.
+    NoStore,
+}
+
+impl CacheDirective {
+    /// Parses the `Cache-Control` header.
+    pub fn parse(cache_control: Option<&str>) -> Self {
+        let mut directive = Self::Default;
+        if let Some(cache_control) = cache_control {
+            for s in cache_control.split(',') {
+                match s.trim().to_ascii_lowercase().as_str() {
+                    "no-store" => return Self::NoStore,
+                    "no-cache" | "max-age=0" => directive = Self::NoCache,
+                    _ => (),
+                }
+            }
+        }
+        directive
+    }
+}
+
+/// A response cache with the rules matched in order.
+/// Only the first rule which applies to a request is used.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    /// Response cache rules.
+    rules: Vec<ResponseCacheRule>,
+    /// Max size of the response body to be cached.
+    max_body_size: usize,
+    /// Name of the session cookie.
+    session_cookie: String,
+}
+
+impl ResponseCache {
+    /// Creates a new instance without any rules.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            session_cookie: DEFAULT_SESSION_COOKIE.to_owned(),
+        }
+    }
+
+    /// Creates a new instance with the rules in the config.
+    /// The invalid rules will be ignored.
+    pub fn from_config(config: &Table) -> Self {
+        let mut cache = Self::new();
+        if let Some(max_body_size) = config.get_usize("max-body-size") {
+            cache.max_body_size = max_body_size;
+        }
+        if let Some(session_cookie) = config.get_str("session-cookie") {
+            cache.session_cookie = session_cookie.to_owned();
+        }
+        if let Some(rules) = config.get_array("rules") {
+            for config in rules.iter().filter_map(|v| v.as_table()) {
+                match ResponseCacheRule::try_from_config(config) {
+                    Ok(rule) => cache.add_rule(rule),
+                    Err(err) => tracing::error!("fail to load the response cache rule: {err}"),
+                }
+            }
+        }
+        cache
+    }
+
+    /// Adds a response cache rule.
+    #[inline]
+    pub fn add_rule(&mut self, rule: ResponseCacheRule) {
+        self.rules.push(rule);
+    }
+
+    /// Returns the response cache rules.
+    #[inline]
+    pub fn rules(&self) -> &[ResponseCacheRule] {
+        &self.rules
+    }
+
+    /// Returns `true` if there are no rules.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the max size of the response body to be cached.
+    #[inline]
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Returns the cache key and the rule for the request if there is a rule which applies to it.
+    /// The key is prefixed with the model name so that the cached responses can be invalidated.
+    ///
+    /// The `session` is the authenticated subject and the tenant ID of the request.
+    /// The requests with credentials are cached only if the rule is private
+    /// and the session has been resolved, i.e. the cache runs inside the auth layer.
+    pub fn resolve<Ctx: RequestContext + ?Sized>(
+        &self,
+        ctx: &Ctx,
+        session: Option<(&str, Option<&str>)>,
+    ) -> Option<(String, &ResponseCacheRule)> {
+        let method = ctx.request_method().as_ref();
+        let path = ctx.request_path();
+        let rule = self
+            .rules
+            .iter()
+            .find(|rule| rule.applies_to(method, path))?;
+        if (session.is_some() || self.has_credentials(ctx)) && (!rule.private || session.is_none())
+        {
+            return None;
+        }
+
+        let query = ctx.original_uri().query();
+        let key = rule.format_key(path, query, |name| ctx.get_header(name), session);
+        Some((key, rule))
+    }
+
+    /// Returns `true` if the request carries any credentials.
+    fn has_credentials<Ctx: RequestContext + ?Sized>(&self, ctx: &Ctx) -> bool {
+        let has_session_cookie = ctx.get_header("cookie").is_some_and(|cookies| {
+            cookies.split(';').any(|cookie| {
+                cookie
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.trim() == self.session_cookie)
+            })
+        });
+        has_session_cookie
+            || ctx.get_header("authorization").is_some()
+            || ctx.get_header("x-session-id").is_some()
+            || ctx.get_header("session_id").is_some()
+            || ctx.get_query("access_token").is_some()
+    }
+
+    /// Returns `true` if a response with the status code and headers can be cached.
+    /// Only the successful responses without cookies are cached, and the
+    /// `Cache-Control: no-store`, `no-cache` or `private` directives are respected.
+    pub fn is_cacheable<'a>(
+        status_code: u16,
+        headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> bool {
+        status_code == 200
+            && headers.into_iter().all(|(name, value)| {
+                if name.eq_ignore_ascii_case("set-cookie") {
+                    false
+                } else if name.eq_ignore_ascii_case("cache-control") {
+                    value.split(',').all(|s| {
+                        let directive = s.trim();
+                        !["no-store", "no-cache", "private"]
+                            .iter()
+                            .any(|d| directive.eq_ignore_ascii_case(d))
+                    })
+                } else {
+                    true
+                }
+            })
+    }
+
+    /// Returns `true` if any rule caches the responses of the model.
+    #[inline]
+    pub fn caches_model(&self, model: &str) -> bool {
+        self.rules.iter().any(|rule| rule.model == model)
+    }
+
+    /// Invalidates the cached responses of the model in the shared store.
+    /// It has no effects if the responses of the model are not cached.
+    pub async fn invalidate(model: &str) {
+        if let Some(store) = Self::store()
+            && Self::shared().caches_model(model)
+            && let Err(err) = store.invalidate(model).await
+        {
+            tracing::error!("fail to invalidate the cached responses of `{model}`: {err}");
+        }
+    }
+
+    /// Sets `self` as the shared response cache. It replaces the previous one,
+    /// so the rules can be reloaded at runtime.
+    #[inline]
+    pub fn set_shared(self) {
+        if let Ok(mut cache) = SHARED_RESPONSE_CACHE.write() {
+            *cache = Arc::new(self);
+        }
+    }
+
+    /// Returns the shared response cache.
+    /// The rules are loaded from the `response-cache` config by default.
+    #[inline]
+    pub fn shared() -> Arc<Self> {
+        SHARED_RESPONSE_CACHE
+            .read()
+            .map(|cache| cache.clone())
+            .unwrap_or_default()
+    }
+
+    /// Sets the shared response cache store. It should be called before booting the application.
+    ///
+    /// Returns `true` if the store has been set successfully.
+    #[inline]
+    pub fn set_store(store: impl ResponseCacheStore) -> bool {
+        SHARED_RESPONSE_CACHE_STORE.set(Box::new(store)).is_ok()
+    }
+
+    /// Returns the shared response cache store.
+    /// The responses are not cached if no store has been set.
+    #[inline]
+    pub fn store() -> Option<&'static dyn ResponseCacheStore> {
+        SHARED_RESPONSE_CACHE_STORE
+            .get()
+            .map(|store| store.as_ref())
+    }
+}
+
+impl Default for ResponseCache {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A store for the cached responses.
+pub trait ResponseCacheStore: Send + Sync + 'static {
+    /// Gets the cached response for the key if it has not expired.
+    fn get(&self, key: &str) -> BoxFuture<'_, Result<Option<CachedResponse>, Error>>;
+
+    /// Puts the response into the cache with a time to live.
+    fn put(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        ttl: Duration,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Removes all the cached responses of the model,
+    /// whose keys are prefixed with the model name and a colon.
+    fn invalidate(&self, model: &str) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheDirective, CachedResponse, ResponseCache, ResponseCacheRule};
+    use std::time::Duration;
+
+    #[test]
+    fn it_resolves_response_cache_rules() {
+        let mut rule = ResponseCacheRule::new("/product/list", Duration::from_secs(60));
+        rule.set_vary(vec!["Accept-Language".to_owned()]);
+        assert_eq!(rule.model(), "product");
+        assert_eq!(rule.vary_header().as_deref(), Some("accept-language"));
+        assert!(rule.applies_to("GET", "/product/list"));
+        assert!(!rule.applies_to("POST", "/product/list"));
+
+        let get_header = |name: &str| (name == "accept-language").then_some("en-US");
+        assert_eq!(
+            rule.format_key("/product/list", Some("page=2"), get_header, None),
+            "product:/product/list?page=2|accept-language=en-US"
+        );
+        assert_eq!(
+            rule.format_key(
+                "/product/list",
+                None,
+                get_header,
+                Some(("alice", Some("acme")))
+            ),
+            "product:/product/list|accept-language=en-US|sub=alice|tenant=acme"
+        );
+        assert!(!rule.is_private());
+
+        assert_eq!(CacheDirective::parse(None), CacheDirective::Default);
+        assert_eq!(
+            CacheDirective::parse(Some("max-age=0, must-revalidate")),
+            CacheDirective::NoCache
+        );
+        assert_eq!(
+            CacheDirective::parse(Some("no-cache, no-store")),
+            CacheDirective::NoStore
+        );
+
+        assert!(ResponseCache::is_cacheable(
+            200,
+            [("cache-control", "max-age=60")]
+        ));
+        assert!(!ResponseCache::is_cacheable(
+            200,
+            [("Cache-Control", "private")]
+        ));
+        assert!(!ResponseCache::is_cacheable(404, []));
+
+        let headers = vec![
+            ("content-type".to_owned(), "application/json".to_owned()),
+            ("x-request-id".to_owned(), "0190b2c8-4aa4-7a3f".to_owned()),
+        ];
+        let response = CachedResponse::new(200, headers, "{}".into());
+        assert_eq!(response.headers().len(), 1);
+        let data = response.into_json();
+        let response = CachedResponse::try_from_json(&data).unwrap();
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.headers()[0].1, "application/json");
+        assert_eq!(response.body().as_ref(), b"{}");
+    }
+}
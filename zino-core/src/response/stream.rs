@@ -0,0 +1,186 @@
+use super::DataStream;
+use crate::{error::Error, extension::JsonObjectExt, Map};
+use bytes::Bytes;
+use csv::{ByteRecord, Writer};
+use futures::{
+    future,
+    stream::{self, Stream, StreamExt, TryStreamExt},
+    TryFutureExt,
+};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// A shared stream of the response body which can be taken only once.
+#[derive(Clone)]
+pub(super) struct SharedDataStream(Arc<Mutex<Option<DataStream>>>);
+
+impl SharedDataStream {
+    /// Creates a new instance.
+    #[inline]
+    pub(super) fn new(stream: DataStream) -> Self {
+        Self(Arc::new(Mutex::new(Some(stream))))
+    }
+
+    /// Takes the stream out of `self`.
+    #[inline]
+    pub(super) fn take(&self) -> Option<DataStream> {
+        self.0.lock().ok().and_then(|mut stream| stream.take())
+    }
+}
+
+impl fmt::Debug for SharedDataStream {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedDataStream")
+    }
+}
+
+/// Ends the stream of chunks after the first error, which is logged.
+pub(super) fn abort_on_error<S>(chunks: S) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Bytes, Error>>,
+{
+    chunks.scan(false, |failed, result| {
+        if *failed {
+            return future::ready(None);
+        }
+        if let Err(err) = &result {
+            tracing::error!("fail to write the response body: {err}");
+            *failed = true;
+        }
+        future::ready(Some(result))
+    })
+}
+
+/// Encodes the stream of JSON objects as chunks of a JSON array.
+pub(super) fn encode_json<S>(entries: S) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Map, Error>>,
+{
+    let chunks = entries.enumerate().map(|(index, result)| {
+        let mut bytes = if index == 0 { Vec::new() } else { vec![b','] };
+        serde_json::to_writer(&mut bytes, &result?)?;
+        Ok(bytes.into())
+    });
+    stream::once(future::ready(Ok(Bytes::from_static(b"["))))
+        .chain(chunks)
+        .chain(stream::once(future::ready(Ok(Bytes::from_static(b"]")))))
+}
+
+/// Encodes the stream of JSON objects as chunks of JSON Lines.
+pub(super) fn encode_jsonlines<S>(entries: S) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Map, Error>>,
+{
+    entries.map(|result| {
+        let mut bytes = serde_json::to_vec(&result?)?;
+        bytes.push(b'\n');
+        Ok(bytes.into())
+    })
+}
+
+/// Encodes the stream of JSON objects as a MsgPack array.
+///
+/// Since the length of the array should be written before the entries,
+/// the encoded entries are buffered and written as a single chunk at the end.
+pub(super) fn encode_msgpack<S>(entries: S) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Map, Error>>,
+{
+    let buffer = entries
+        .map(|result| rmp_serde::to_vec(&result?).map_err(Error::from))
+        .try_fold((0, Vec::new()), |(num_entries, mut buffer), bytes| {
+            buffer.extend_from_slice(&bytes);
+            future::ready(Ok((num_entries + 1, buffer)))
+        });
+    stream::once(buffer.map_ok(|(num_entries, buffer): (usize, Vec<u8>)| {
+        let mut bytes = if num_entries < 16 {
+            vec![0x90 | (num_entries as u8)]
+        } else if let Ok(len) = u16::try_from(num_entries) {
+            let mut header = vec![0xdc];
+            header.extend_from_slice(&len.to_be_bytes());
+            header
+        } else {
+            let len = u32::try_from(num_entries).unwrap_or(u32::MAX);
+            let mut header = vec![0xdd];
+            header.extend_from_slice(&len.to_be_bytes());
+            header
+        };
+        bytes.extend_from_slice(&buffer);
+        bytes.into()
+    }))
+}
+
+/// Encodes the stream of JSON objects as chunks of CSV records.
+/// The headers are obtained from the keys of the first entry.
+pub(super) fn encode_csv<S>(entries: S) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Map, Error>>,
+{
+    let mut headers = Vec::new();
+    entries.map(move |result| {
+        let map = result?;
+        let mut wtr = Writer::from_writer(Vec::new());
+        if headers.is_empty() {
+            headers = map.keys().cloned().collect::<Vec<_>>();
+            wtr.write_record(&headers)?;
+        }
+
+        let num_fields = headers.len();
+        let mut record = ByteRecord::with_capacity(num_fields * 8, num_fields);
+        for field in headers.iter() {
+            let value = map.parse_string(field).unwrap_or("".into());
+            record.push_field(value.as_ref().as_bytes());
+        }
+        wtr.write_byte_record(&record)?;
+        wtr.flush()?;
+
+        let bytes = wtr.into_inner().map_err(|err| err.into_error())?;
+        Ok(bytes.into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, extension::JsonObjectExt, Map};
+    use futures::{executor, stream, Stream, StreamExt, TryStreamExt};
+
+    fn collect(chunks: impl Stream<Item = Result<bytes::Bytes, Error>>) -> Vec<u8> {
+        executor::block_on(chunks.map_ok(|bytes| bytes.to_vec()).try_concat()).unwrap()
+    }
+
+    #[test]
+    fn it_encodes_streams() {
+        let mut entries = Vec::new();
+        for (id, name) in [(1, "alice"), (2, "bob")] {
+            let mut map = Map::from_entry("id", id);
+            map.upsert("name", name);
+            entries.push(map);
+        }
+        let entries = || stream::iter(entries.clone().into_iter().map(Ok));
+
+        let bytes = collect(super::encode_json(entries()));
+        assert_eq!(bytes, br#"[{"id":1,"name":"alice"},{"id":2,"name":"bob"}]"#);
+
+        let bytes = collect(super::encode_csv(entries()));
+        assert_eq!(bytes, b"id,name\n1,alice\n2,bob\n");
+
+        let bytes = collect(super::encode_msgpack(entries()));
+        let data: Vec<Map> = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].get_str("name"), Some("bob"));
+
+        let failed_entries = stream::iter(vec![
+            Ok(Map::from_entry("id", 1)),
+            Err(Error::new("connection reset")),
+            Ok(Map::from_entry("id", 2)),
+        ]);
+        let chunks = executor::block_on(
+            super::abort_on_error(super::encode_json(failed_entries)).collect::<Vec<_>>(),
+        );
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[2].is_err());
+    }
+}
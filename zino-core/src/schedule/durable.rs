@@ -0,0 +1,602 @@
+use super::{Job, JobRun};
+use crate::{bail, datetime::DateTime, error::Error, warn, BoxFuture, Uuid};
+use futures::{
+    future::{self, Either},
+    lock::Mutex as AsyncMutex,
+    pin_mut,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+/// Shared durable scheduler.
+static SHARED_DURABLE_SCHEDULER: OnceLock<DurableScheduler> = OnceLock::new();
+
+/// Persisted state of a job.
+#[derive(Debug, Clone)]
+pub struct JobState {
+    /// Job ID.
+    id: Uuid,
+    /// Job name.
+    name: String,
+    /// Cron expression.
+    schedule: String,
+    /// Priority.
+    priority: u16,
+    /// IDs of the dependencies.
+    dependencies: Vec<Uuid>,
+    /// Time of the last tick.
+    last_time: DateTime,
+    /// Time of the next tick or the expiry of the lease.
+    next_time: DateTime,
+    /// Version of the state.
+    version: u64,
+}
+
+impl JobState {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(id: Uuid, name: impl Into<String>, schedule: impl Into<String>) -> Self {
+        let now = DateTime::now();
+        Self {
+            id,
+            name: name.into(),
+            schedule: schedule.into(),
+            priority: 0,
+            dependencies: Vec::new(),
+            last_time: now,
+            next_time: now,
+            version: 0,
+        }
+    }
+
+    /// Sets the priority.
+    #[inline]
+    pub fn set_priority(&mut self, priority: u16) {
+        self.priority = priority;
+    }
+
+    /// Sets the IDs of the dependencies.
+    #[inline]
+    pub fn set_dependencies(&mut self, dependencies: Vec<Uuid>) {
+        self.dependencies = dependencies;
+    }
+
+    /// Sets the time of the last tick.
+    #[inline]
+    pub fn set_last_time(&mut self, last_time: DateTime) {
+        self.last_time = last_time;
+    }
+
+    /// Sets the time of the next tick.
+    #[inline]
+    pub fn set_next_time(&mut self, next_time: DateTime) {
+        self.next_time = next_time;
+    }
+
+    /// Sets the version.
+    #[inline]
+    pub fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    /// Returns the job ID.
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the job name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the cron expression.
+    #[inline]
+    pub fn schedule(&self) -> &str {
+        &self.schedule
+    }
+
+    /// Returns the priority.
+    #[inline]
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Returns the IDs of the dependencies.
+    #[inline]
+    pub fn dependencies(&self) -> &[Uuid] {
+        &self.dependencies
+    }
+
+    /// Returns the time of the last tick.
+    #[inline]
+    pub fn last_time(&self) -> DateTime {
+        self.last_time
+    }
+
+    /// Returns the time of the next tick.
+    #[inline]
+    pub fn next_time(&self) -> DateTime {
+        self.next_time
+    }
+
+    /// Returns the version.
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+/// A store for the job states, which is shared by all the instances of the application.
+pub trait JobStore: Send + Sync + 'static {
+    /// Registers the job state if it does not exist, and returns the stored state.
+    fn register(&self, state: JobState) -> BoxFuture<'_, Result<JobState, Error>>;
+
+    /// Loads the job state by ID.
+    fn load(&self, job_id: Uuid) -> BoxFuture<'_, Result<Option<JobState>, Error>>;
+
+    /// Saves the `last_time` and `next_time` of the job state if the stored version
+    /// is equal to `state.version()`, and increments the version by 1.
+    /// It returns `false` if the version has been changed by others.
+    fn compare_and_swap(&self, state: &JobState) -> BoxFuture<'_, Result<bool, Error>>;
+
+    /// Records the runs of a job, so that the run history survives restarts
+    /// and is shared by the instances. The default implementation discards them.
+    fn record_runs(&self, _runs: Vec<JobRun>) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(future::ready(Ok(())))
+    }
+}
+
+/// A job store which keeps the job states in memory.
+///
+/// The clones of the store share the same job states, so it can be used
+/// for the schedulers in a single process or in the tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryJobStore {
+    /// Job states.
+    states: Arc<Mutex<HashMap<Uuid, JobState>>>,
+    /// Job runs.
+    runs: Arc<Mutex<Vec<JobRun>>>,
+}
+
+impl MemoryJobStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded runs of the job.
+    pub fn runs(&self, job_id: Uuid) -> Vec<JobRun> {
+        self.runs
+            .lock()
+            .map(|runs| {
+                runs.iter()
+                    .filter(|run| run.job_id() == job_id)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl JobStore for MemoryJobStore {
+    fn register(&self, state: JobState) -> BoxFuture<'_, Result<JobState, Error>> {
+        let result = self
+            .states
+            .lock()
+            .map(|mut states| states.entry(state.id).or_insert(state).clone())
+            .map_err(|err| warn!("fail to acquire the lock: {}", err));
+        Box::pin(future::ready(result))
+    }
+
+    fn load(&self, job_id: Uuid) -> BoxFuture<'_, Result<Option<JobState>, Error>> {
+        let result = self
+            .states
+            .lock()
+            .map(|states| states.get(&job_id).cloned())
+            .map_err(|err| warn!("fail to acquire the lock: {}", err));
+        Box::pin(future::ready(result))
+    }
+
+    fn compare_and_swap(&self, state: &JobState) -> BoxFuture<'_, Result<bool, Error>> {
+        let result = self
+            .states
+            .lock()
+            .map(|mut states| {
+                if let Some(stored_state) = states.get_mut(&state.id)
+                    && stored_state.version == state.version
+                {
+                    stored_state.last_time = state.last_time;
+                    stored_state.next_time = state.next_time;
+                    stored_state.version += 1;
+                    true
+                } else {
+                    false
+                }
+            })
+            .map_err(|err| warn!("fail to acquire the lock: {}", err));
+        Box::pin(future::ready(result))
+    }
+
+    fn record_runs(&self, runs: Vec<JobRun>) -> BoxFuture<'_, Result<(), Error>> {
+        let result = self
+            .runs
+            .lock()
+            .map(|mut records| records.extend(runs))
+            .map_err(|err| warn!("fail to acquire the lock: {}", err));
+        Box::pin(future::ready(result))
+    }
+}
+
+/// Shared data of the durable scheduler.
+struct SchedulerInner {
+    /// Job store.
+    store: Box<dyn JobStore>,
+    /// Duration of the lease.
+    lease_duration: Duration,
+    /// Jobs added but not registered.
+    pending_jobs: Mutex<Vec<Job>>,
+    /// IDs of the jobs to be removed.
+    removed_jobs: Mutex<Vec<Uuid>>,
+    /// Registered jobs.
+    jobs: AsyncMutex<Vec<Job>>,
+}
+
+/// A scheduler which persists the job states in a [`JobStore`].
+///
+/// Each tick of a job is run by only one instance of the application.
+/// The lease of a tick is taken by pushing the `next_time` forward with
+/// a version-guarded update, so that the tick will be retried by others
+/// if the instance crashes before the lease expires. The lease is renewed
+/// while the job is running, and the version acts as a fencing token:
+/// if the lease is lost, an async job is canceled and the tick is not recorded.
+/// Missed ticks, e.g. after a downtime, are caught up with one run at most.
+///
+/// It should be run in a Tokio runtime, since the sync jobs are executed
+/// on the blocking threads.
+#[derive(Clone)]
+pub struct DurableScheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+impl DurableScheduler {
+    /// Creates a new instance with the job store and the lease duration.
+    #[inline]
+    pub fn new(store: impl JobStore, lease_duration: Duration) -> Self {
+        let inner = SchedulerInner {
+            store: Box::new(store),
+            lease_duration,
+            pending_jobs: Mutex::new(Vec::new()),
+            removed_jobs: Mutex::new(Vec::new()),
+            jobs: AsyncMutex::new(Vec::new()),
+        };
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Adds a job and returns the job ID. It can be called at runtime,
+    /// and the job will be registered in the next tick.
+    ///
+    /// The job should have a name, which is used to identify the job state
+    /// across restarts and instances.
+    pub fn add(&self, job: Job) -> Uuid {
+        if job.name.is_none() {
+            tracing::warn!("job `{}` has no name and its state is not stable", job.id);
+        }
+
+        let job_id = job.id;
+        if let Ok(mut jobs) = self.inner.pending_jobs.lock() {
+            jobs.push(job);
+        }
+        job_id
+    }
+
+    /// Removes a job by ID. The job state is kept in the job store.
+    pub fn remove(&self, job_id: Uuid) {
+        if let Ok(mut jobs) = self.inner.pending_jobs.lock() {
+            jobs.retain(|job| job.id != job_id);
+        }
+        if let Ok(mut job_ids) = self.inner.removed_jobs.lock() {
+            job_ids.push(job_id);
+        }
+    }
+
+    /// Sets `self` as the shared durable scheduler which is run by the application.
+    /// It returns `false` if the scheduler has been set.
+    #[inline]
+    pub fn set_shared(self) -> bool {
+        SHARED_DURABLE_SCHEDULER.set(self).is_ok()
+    }
+
+    /// Returns the shared durable scheduler.
+    #[inline]
+    pub fn shared() -> Option<&'static Self> {
+        SHARED_DURABLE_SCHEDULER.get()
+    }
+
+    /// The `tick_async` method registers pending jobs and executes the jobs
+    /// whose ticks are due and whose leases are acquired.
+    pub async fn tick_async(&self) {
+        let inner = &self.inner;
+        let store = inner.store.as_ref();
+        let mut jobs = inner.jobs.lock().await;
+        if let Ok(mut job_ids) = inner.removed_jobs.lock() {
+            jobs.retain(|job| !job_ids.contains(&job.id));
+            job_ids.clear();
+        }
+
+        let pending_jobs = inner
+            .pending_jobs
+            .lock()
+            .map(|mut jobs| jobs.drain(..).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for job in pending_jobs {
+            let name = job.name.clone().unwrap_or_else(|| job.id.to_string());
+            let mut state = JobState::new(job.id, name, job.cron_expr());
+            state.set_priority(job.priority);
+            state.set_dependencies(job.dependencies.clone());
+            if let Some(next_time) = job.upcoming_time(state.last_time) {
+                state.set_next_time(next_time);
+            }
+            if let Err(err) = store.register(state).await {
+                tracing::error!("fail to register the job `{}`: {err}", job.id);
+                if let Ok(mut jobs) = inner.pending_jobs.lock() {
+                    jobs.push(job);
+                }
+                continue;
+            }
+
+            let index = jobs.partition_point(|j| j.priority >= job.priority);
+            jobs.insert(index, job);
+        }
+
+        for job in jobs.iter_mut() {
+            if let Err(err) = self.tick_job(job).await {
+                tracing::error!("fail to execute the job `{}`: {err}", job.id);
+            }
+        }
+    }
+
+    /// Executes the job if its tick is due and its lease is acquired.
+    async fn tick_job(&self, job: &mut Job) -> Result<(), Error> {
+        let store = self.inner.store.as_ref();
+        let Some(mut state) = store.load(job.id).await? else {
+            return Ok(());
+        };
+        let now = DateTime::now();
+        if state.next_time > now {
+            return Ok(());
+        }
+
+        // Defers the job until its dependencies have run since the last tick.
+        for &dependency in job.dependencies.iter() {
+            if let Some(dependency_state) = store.load(dependency).await?
+                && dependency_state.last_time < state.last_time
+            {
+                return Ok(());
+            }
+        }
+
+        // Takes the lease by pushing the `next_time` forward.
+        let last_time = state.last_time;
+        state.set_next_time(now + self.inner.lease_duration);
+        if !store.compare_and_swap(&state).await? {
+            return Ok(());
+        }
+        state.version += 1;
+
+        // Executes one run for the missed ticks while renewing the lease.
+        let runs = {
+            let execution = Self::execute_job(job, last_time);
+            let renewal = self.renew_lease(&mut state);
+            pin_mut!(execution, renewal);
+            match future::select(execution, renewal).await {
+                Either::Left((result, _)) => result,
+                Either::Right((err, _)) => Err(err),
+            }
+        }?;
+        if let Err(err) = store.record_runs(runs).await {
+            tracing::error!("fail to record the runs of the job `{}`: {err}", job.id);
+        }
+
+        // Releases the lease by recording the time of the tick.
+        let last_time = DateTime::now();
+        job.set_last_tick(Some(last_time));
+        state.set_last_time(last_time);
+        state.set_next_time(job.upcoming_time(last_time).unwrap_or(last_time));
+        if !store.compare_and_swap(&state).await? {
+            bail!("the lease of the job `{}` has been lost", job.id);
+        }
+        Ok(())
+    }
+
+    /// Executes one run of the job. The sync job is executed on a blocking thread,
+    /// so that the lease can be renewed meanwhile.
+    async fn execute_job(job: &mut Job, last_time: DateTime) -> Result<Vec<JobRun>, Error> {
+        if job.is_async() {
+            Ok(job.execute_async(last_time).await)
+        } else {
+            let mut sync_job = job.clone();
+            let (sync_job, runs) = tokio::task::spawn_blocking(move || {
+                let runs = sync_job.execute(last_time);
+                (sync_job, runs)
+            })
+            .await?;
+            *job = sync_job;
+            Ok(runs)
+        }
+    }
+
+    /// Renews the lease periodically, and returns an error if the lease has been lost.
+    async fn renew_lease(&self, state: &mut JobState) -> Error {
+        let store = self.inner.store.as_ref();
+        let lease_duration = self.inner.lease_duration;
+        loop {
+            tokio::time::sleep(lease_duration / 3).await;
+
+            let now = DateTime::now();
+            let expiry = state.next_time;
+            state.set_next_time(now + lease_duration);
+            match store.compare_and_swap(state).await {
+                Ok(true) => state.version += 1,
+                Ok(false) => return warn!("the lease of the job `{}` has been lost", state.id),
+                Err(err) if now >= expiry => return err,
+                Err(err) => {
+                    tracing::warn!("fail to renew the lease of the job `{}`: {err}", state.id);
+                    state.set_next_time(expiry);
+                }
+            }
+        }
+    }
+
+    /// The `time_till_next_job` method returns the duration till the next job
+    /// is supposed to run. It does not exceed the lease duration so that
+    /// the expired leases can be taken over in time.
+    pub fn time_till_next_job(&self) -> Duration {
+        let lease_duration = self.inner.lease_duration;
+        let Some(jobs) = self.inner.jobs.try_lock() else {
+            return Duration::from_millis(500);
+        };
+        if jobs.is_empty() {
+            return Duration::from_millis(500);
+        }
+
+        let now = DateTime::now();
+        jobs.iter()
+            .filter_map(|job| job.upcoming_time(now))
+            .map(|dt| dt.duration_since(now))
+            .min()
+            .unwrap_or(lease_duration)
+            .min(lease_duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DurableScheduler, JobState, JobStore, MemoryJobStore};
+    use crate::{datetime::DateTime, schedule::Job, BoxFuture, Map, Uuid};
+    use std::{
+        future::Future,
+        sync::atomic::{AtomicUsize, Ordering::Relaxed},
+        time::Duration,
+    };
+
+    static NUM_COUNT_RUNS: AtomicUsize = AtomicUsize::new(0);
+    static NUM_SLOW_RUNS: AtomicUsize = AtomicUsize::new(0);
+    static NUM_FENCED_RUNS: AtomicUsize = AtomicUsize::new(0);
+    static NUM_FENCED_FINISHES: AtomicUsize = AtomicUsize::new(0);
+
+    fn count_job(_id: Uuid, _data: &mut Map, _last_tick: DateTime) -> BoxFuture<'_> {
+        Box::pin(async {
+            NUM_COUNT_RUNS.fetch_add(1, Relaxed);
+        })
+    }
+
+    fn slow_job(_id: Uuid, _data: &mut Map, _last_tick: DateTime) -> BoxFuture<'_> {
+        Box::pin(async {
+            NUM_SLOW_RUNS.fetch_add(1, Relaxed);
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        })
+    }
+
+    fn fenced_job(_id: Uuid, _data: &mut Map, _last_tick: DateTime) -> BoxFuture<'_> {
+        Box::pin(async {
+            NUM_FENCED_RUNS.fetch_add(1, Relaxed);
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            NUM_FENCED_FINISHES.fetch_add(1, Relaxed);
+        })
+    }
+
+    /// Registers a daily job which has missed the ticks of the last three days.
+    async fn register_overdue_job(store: &MemoryJobStore, job: &mut Job, name: &str) -> Uuid {
+        job.set_name(name);
+
+        let last_time = DateTime::now() - Duration::from_secs(3 * 86400);
+        let mut state = JobState::new(job.id(), name, job.cron_expr());
+        state.set_last_time(last_time);
+        state.set_next_time(last_time);
+        store.register(state).await.unwrap();
+        job.id()
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn it_runs_missed_ticks_once() {
+        block_on(async {
+            let store = MemoryJobStore::new();
+            let mut job = Job::new_async("0 0 0 * * *", count_job);
+            let job_id = register_overdue_job(&store, &mut job, "count").await;
+
+            let scheduler = DurableScheduler::new(store.clone(), Duration::from_secs(10));
+            let other_scheduler = DurableScheduler::new(store.clone(), Duration::from_secs(10));
+            scheduler.add(job.clone());
+            other_scheduler.add(job);
+            futures::join!(scheduler.tick_async(), other_scheduler.tick_async());
+            scheduler.tick_async().await;
+            assert_eq!(NUM_COUNT_RUNS.load(Relaxed), 1);
+
+            let runs = store.runs(job_id);
+            assert_eq!(runs.len(), 1);
+            assert_eq!(runs[0].job_name(), Some("count"));
+
+            let state = store.load(job_id).await.unwrap().unwrap();
+            assert!(state.next_time() > DateTime::now());
+        });
+    }
+
+    #[test]
+    fn it_renews_leases_of_running_jobs() {
+        block_on(async {
+            let store = MemoryJobStore::new();
+            let mut job = Job::new_async("0 0 0 * * *", slow_job);
+            register_overdue_job(&store, &mut job, "slow").await;
+
+            let lease_duration = Duration::from_millis(90);
+            let scheduler = DurableScheduler::new(store.clone(), lease_duration);
+            let other_scheduler = DurableScheduler::new(store.clone(), lease_duration);
+            scheduler.add(job.clone());
+            other_scheduler.add(job);
+            futures::join!(scheduler.tick_async(), async {
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                other_scheduler.tick_async().await;
+            });
+            assert_eq!(NUM_SLOW_RUNS.load(Relaxed), 1);
+        });
+    }
+
+    #[test]
+    fn it_fences_jobs_with_lost_leases() {
+        block_on(async {
+            let store = MemoryJobStore::new();
+            let mut job = Job::new_async("0 0 0 * * *", fenced_job);
+            let job_id = register_overdue_job(&store, &mut job, "fenced").await;
+
+            let scheduler = DurableScheduler::new(store.clone(), Duration::from_millis(90));
+            let (result, ()) = futures::join!(scheduler.tick_job(&mut job), async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let mut state = store.load(job_id).await.unwrap().unwrap();
+                state.set_next_time(DateTime::now() + Duration::from_secs(3600));
+                assert!(store.compare_and_swap(&state).await.unwrap());
+            });
+            assert!(result.is_err());
+            assert_eq!(NUM_FENCED_RUNS.load(Relaxed), 1);
+            assert_eq!(NUM_FENCED_FINISHES.load(Relaxed), 0);
+
+            let state = store.load(job_id).await.unwrap().unwrap();
+            assert!(state.last_time() < DateTime::now() - Duration::from_secs(86400));
+        });
+    }
+}
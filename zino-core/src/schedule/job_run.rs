@@ -0,0 +1,206 @@
+use crate::{datetime::DateTime, extension::JsonObjectExt, Map, Uuid};
+use std::{
+    collections::VecDeque,
+    sync::{LazyLock, Mutex},
+};
+
+/// Max number of job runs kept in the run log.
+const RUN_LOG_CAPACITY: usize = 1024;
+
+/// Run log of the jobs.
+static JOB_RUN_LOG: LazyLock<Mutex<VecDeque<JobRun>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(RUN_LOG_CAPACITY)));
+
+/// Outcome of a job run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    /// The job has succeeded.
+    Succeeded,
+    /// The job has returned an error.
+    Failed,
+    /// The job has exceeded the timeout.
+    TimedOut,
+    /// The job has panicked.
+    Panicked,
+}
+
+impl JobOutcome {
+    /// Returns `true` if the job has succeeded.
+    #[inline]
+    pub fn is_succeeded(&self) -> bool {
+        matches!(self, Self::Succeeded)
+    }
+
+    /// Returns the outcome as `str`.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::Panicked => "panicked",
+        }
+    }
+}
+
+/// A record of a job run.
+#[derive(Debug, Clone)]
+pub struct JobRun {
+    /// Job ID.
+    job_id: Uuid,
+    /// Job name.
+    job_name: Option<String>,
+    /// Attempt number starting from 0.
+    attempt: u32,
+    /// Start time.
+    start_time: DateTime,
+    /// End time.
+    end_time: DateTime,
+    /// Outcome.
+    outcome: JobOutcome,
+    /// Error message.
+    error: Option<String>,
+}
+
+impl JobRun {
+    /// Creates a new instance which starts at the current time.
+    #[inline]
+    pub(super) fn new(job_id: Uuid, job_name: Option<&str>, attempt: u32) -> Self {
+        let now = DateTime::now();
+        Self {
+            job_id,
+            job_name: job_name.map(|s| s.to_owned()),
+            attempt,
+            start_time: now,
+            end_time: now,
+            outcome: JobOutcome::Succeeded,
+            error: None,
+        }
+    }
+
+    /// Finishes the run with the outcome, and records it in the run log.
+    pub(super) fn finish(mut self, outcome: JobOutcome, error: Option<String>) -> Self {
+        self.end_time = DateTime::now();
+        self.outcome = outcome;
+        self.error = error;
+
+        // The job ID is not used as a metric label to keep the cardinality bounded.
+        let job_id = self.job_id.to_string();
+        let job_name = self
+            .job_name
+            .clone()
+            .unwrap_or_else(|| "unnamed".to_owned());
+        let duration = self.end_time.duration_since(self.start_time);
+        metrics::increment_counter!(
+            "zino_job_runs_total",
+            "job" => job_name.clone(),
+            "outcome" => outcome.as_str(),
+        );
+        metrics::histogram!(
+            "zino_job_run_duration_seconds",
+            duration.as_secs_f64(),
+            "job" => job_name.clone(),
+        );
+        if !outcome.is_succeeded() {
+            metrics::increment_counter!(
+                "zino_job_failures_total",
+                "job" => job_name.clone(),
+                "outcome" => outcome.as_str(),
+            );
+            tracing::error!(
+                job_id,
+                job_name,
+                attempt = self.attempt,
+                outcome = outcome.as_str(),
+                "job run failed: {}",
+                self.error.as_deref().unwrap_or_default(),
+            );
+        }
+
+        if let Ok(mut runs) = JOB_RUN_LOG.lock() {
+            if runs.len() >= RUN_LOG_CAPACITY {
+                runs.pop_front();
+            }
+            runs.push_back(self.clone());
+        }
+        self
+    }
+
+    /// Returns the job ID.
+    #[inline]
+    pub fn job_id(&self) -> Uuid {
+        self.job_id
+    }
+
+    /// Returns the job name.
+    #[inline]
+    pub fn job_name(&self) -> Option<&str> {
+        self.job_name.as_deref()
+    }
+
+    /// Returns the attempt number starting from 0.
+    #[inline]
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Returns the start time.
+    #[inline]
+    pub fn start_time(&self) -> DateTime {
+        self.start_time
+    }
+
+    /// Returns the end time.
+    #[inline]
+    pub fn end_time(&self) -> DateTime {
+        self.end_time
+    }
+
+    /// Returns the outcome.
+    #[inline]
+    pub fn outcome(&self) -> JobOutcome {
+        self.outcome
+    }
+
+    /// Returns the error message.
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Converts `self` to a JSON object.
+    pub fn to_map(&self) -> Map {
+        let mut map = Map::from_entry("job_id", self.job_id.to_string());
+        if let Some(job_name) = self.job_name.as_deref() {
+            map.upsert("job_name", job_name);
+        }
+        map.upsert("attempt", self.attempt);
+        map.upsert("start_time", self.start_time);
+        map.upsert("end_time", self.end_time);
+        map.upsert("outcome", self.outcome.as_str());
+        if let Some(error) = self.error.as_deref() {
+            map.upsert("error", error);
+        }
+        map
+    }
+
+    /// Lists the most recent runs in the run log, optionally filtered by the job ID.
+    /// The runs are sorted by the start time in descending order.
+    ///
+    /// The run log is kept in memory for the current process only, so it is lost
+    /// on restart. The runs of the durable jobs are persisted by
+    /// [`JobStore::record_runs()`](super::JobStore::record_runs).
+    pub fn list(job_id: Option<Uuid>, limit: usize) -> Vec<JobRun> {
+        JOB_RUN_LOG
+            .lock()
+            .map(|runs| {
+                runs.iter()
+                    .rev()
+                    .filter(|run| job_id.is_none() || job_id == Some(run.job_id))
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
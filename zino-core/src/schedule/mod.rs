@@ -1,9 +1,25 @@
 //! Scheduler for sync and async cron jobs.
 
-use crate::{datetime::DateTime, BoxFuture, Map, Uuid};
+use crate::{crypto, datetime::DateTime, error::Error, BoxFuture, Map, Uuid};
 use chrono::Local;
 use cron::Schedule;
-use std::{str::FromStr, time::Duration};
+use futures::FutureExt;
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+#[cfg(feature = "runtime-tokio")]
+mod durable;
+mod job_run;
+
+#[cfg(feature = "runtime-tokio")]
+pub use durable::{DurableScheduler, JobState, JobStore, MemoryJobStore};
+pub use job_run::{JobOutcome, JobRun};
 
 /// A function pointer of the cron job.
 pub type CronJob = fn(id: Uuid, data: &mut Map, last_tick: DateTime);
@@ -12,18 +28,38 @@ pub type CronJob = fn(id: Uuid, data: &mut Map, last_tick: DateTime);
 pub type AsyncCronJob =
     for<'a> fn(id: Uuid, data: &'a mut Map, last_tick: DateTime) -> BoxFuture<'a>;
 
+/// A function pointer of the fallible cron job.
+pub type FallibleCronJob = fn(id: Uuid, data: &mut Map, last_tick: DateTime) -> Result<(), Error>;
+
+/// A function pointer of the fallible async cron job.
+pub type AsyncFallibleCronJob = for<'a> fn(
+    id: Uuid,
+    data: &'a mut Map,
+    last_tick: DateTime,
+) -> BoxFuture<'a, Result<(), Error>>;
+
 /// Exectuable job.
+#[derive(Clone, Copy)]
 enum ExecutableJob {
     Fn(CronJob),
     AsyncFn(AsyncCronJob),
+    FallibleFn(FallibleCronJob),
+    AsyncFallibleFn(AsyncFallibleCronJob),
 }
 
 /// A schedulable `Job`.
+#[derive(Clone)]
 pub struct Job {
     id: Uuid,
+    name: Option<String>,
     data: Map,
     schedule: Schedule,
     run: ExecutableJob,
+    priority: u16,
+    dependencies: Vec<Uuid>,
+    timeout: Option<Duration>,
+    max_retries: u32,
+    retry_backoff: Duration,
     last_tick: Option<chrono::DateTime<Local>>,
 }
 
@@ -31,37 +67,129 @@ impl Job {
     /// Creates a new `Job`.
     #[inline]
     pub fn new(cron_expr: &str, exec: CronJob) -> Self {
-        let schedule = Schedule::from_str(cron_expr)
-            .unwrap_or_else(|err| panic!("invalid cron expression `{cron_expr}`: {err}"));
-        Job {
-            id: Uuid::now_v7(),
-            data: Map::new(),
-            schedule,
-            run: ExecutableJob::Fn(exec),
-            last_tick: None,
-        }
+        Self::with_executable(cron_expr, ExecutableJob::Fn(exec))
     }
 
     /// Creates a new async `Job`.
     #[inline]
     pub fn new_async(cron_expr: &str, exec: AsyncCronJob) -> Self {
+        Self::with_executable(cron_expr, ExecutableJob::AsyncFn(exec))
+    }
+
+    /// Creates a new fallible `Job`.
+    #[inline]
+    pub fn new_fallible(cron_expr: &str, exec: FallibleCronJob) -> Self {
+        Self::with_executable(cron_expr, ExecutableJob::FallibleFn(exec))
+    }
+
+    /// Creates a new fallible async `Job`.
+    #[inline]
+    pub fn new_async_fallible(cron_expr: &str, exec: AsyncFallibleCronJob) -> Self {
+        Self::with_executable(cron_expr, ExecutableJob::AsyncFallibleFn(exec))
+    }
+
+    /// Creates a new `Job` with the executable.
+    fn with_executable(cron_expr: &str, run: ExecutableJob) -> Self {
         let schedule = Schedule::from_str(cron_expr)
             .unwrap_or_else(|err| panic!("invalid cron expression `{cron_expr}`: {err}"));
         Job {
             id: Uuid::now_v7(),
+            name: None,
             data: Map::new(),
             schedule,
-            run: ExecutableJob::AsyncFn(exec),
+            run,
+            priority: 0,
+            dependencies: Vec::new(),
+            timeout: None,
+            max_retries: 0,
+            retry_backoff: Duration::from_secs(1),
             last_tick: None,
         }
     }
 
+    /// Sets the job name. The job ID is derived from the name,
+    /// so that it is stable across restarts.
+    #[inline]
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        self.id = Self::derive_id(&name);
+        self.name = Some(name);
+    }
+
+    /// Sets the priority. Jobs with a higher priority are executed first in a tick.
+    #[inline]
+    pub fn set_priority(&mut self, priority: u16) {
+        self.priority = priority;
+    }
+
+    /// Sets the timeout of each run. It only applies to async jobs,
+    /// which should be executed in a Tokio runtime with the `runtime-tokio` feature.
+    /// A sync job can not be canceled, so the timeout is rejected with a warning.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        if self.is_async() {
+            self.timeout = Some(timeout);
+        } else {
+            tracing::warn!("the timeout is unsupported for the sync job `{}`", self.id);
+        }
+    }
+
+    /// Sets the max number of retries for a failed run.
+    #[inline]
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    /// Sets the initial backoff between retries, which is doubled after each retry.
+    #[inline]
+    pub fn set_retry_backoff(&mut self, retry_backoff: Duration) {
+        self.retry_backoff = retry_backoff;
+    }
+
+    /// Adds a dependency by the job name.
+    #[inline]
+    pub fn add_dependency(&mut self, name: &str) {
+        self.dependencies.push(Self::derive_id(name));
+    }
+
     /// Returns the job ID.
     #[inline]
     pub fn id(&self) -> Uuid {
         self.id
     }
 
+    /// Returns the job name.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the cron expression.
+    #[inline]
+    pub fn cron_expr(&self) -> String {
+        self.schedule.to_string()
+    }
+
+    /// Returns the priority.
+    #[inline]
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Returns the IDs of the dependencies.
+    #[inline]
+    pub fn dependencies(&self) -> &[Uuid] {
+        &self.dependencies
+    }
+
+    /// Returns `true` if the job is async.
+    #[inline]
+    pub fn is_async(&self) -> bool {
+        matches!(
+            self.run,
+            ExecutableJob::AsyncFn(_) | ExecutableJob::AsyncFallibleFn(_)
+        )
+    }
+
     /// Returns a reference to the job data.
     #[inline]
     pub fn data(&self) -> &Map {
@@ -80,18 +208,113 @@ impl Job {
         self.last_tick = last_tick.map(|dt| dt.into());
     }
 
+    /// Returns the time of the upcoming run after `dt`.
+    #[inline]
+    pub fn upcoming_time(&self, dt: DateTime) -> Option<DateTime> {
+        let dt = chrono::DateTime::<Local>::from(dt);
+        self.schedule.after(&dt).next().map(|dt| dt.into())
+    }
+
+    /// Derives a stable job ID from the name.
+    fn derive_id(name: &str) -> Uuid {
+        let digest = crypto::digest(name.as_bytes());
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    }
+
+    /// Returns the delay before the retry of the attempt.
+    #[inline]
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        self.retry_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt))
+    }
+
+    /// Executes the job with retries, and returns the runs of the attempts.
+    ///
+    /// It sleeps on the current thread between retries, so it should not be called
+    /// in an async runtime. [`DurableScheduler`] executes it on a blocking thread.
+    fn execute(&mut self, last_tick: DateTime) -> Vec<JobRun> {
+        let (id, name) = (self.id, self.name.clone());
+        let mut runs = Vec::new();
+        let mut attempt = 0;
+        loop {
+            let run = JobRun::new(id, name.as_deref(), attempt);
+            let data = &mut self.data;
+            let result = match self.run {
+                ExecutableJob::Fn(exec) => {
+                    panic::catch_unwind(AssertUnwindSafe(|| exec(id, data, last_tick))).map(Ok)
+                }
+                ExecutableJob::FallibleFn(exec) => {
+                    panic::catch_unwind(AssertUnwindSafe(|| exec(id, data, last_tick)))
+                }
+                _ => {
+                    tracing::warn!("job `{id}` is async");
+                    return runs;
+                }
+            };
+            let run = match result {
+                Ok(Ok(())) => run.finish(JobOutcome::Succeeded, None),
+                Ok(Err(err)) => run.finish(JobOutcome::Failed, Some(err.to_string())),
+                Err(payload) => run.finish(JobOutcome::Panicked, panic_message(payload)),
+            };
+            let succeeded = run.outcome().is_succeeded();
+            runs.push(run);
+            if succeeded || attempt >= self.max_retries {
+                break;
+            }
+            thread::sleep(self.retry_delay(attempt));
+            attempt += 1;
+        }
+        runs
+    }
+
+    /// Executes the job asynchronously with retries, and returns the runs of the attempts.
+    async fn execute_async(&mut self, last_tick: DateTime) -> Vec<JobRun> {
+        let (id, name) = (self.id, self.name.clone());
+        let timeout = self.timeout;
+        let mut runs = Vec::new();
+        let mut attempt = 0;
+        loop {
+            let run = JobRun::new(id, name.as_deref(), attempt);
+            let data = &mut self.data;
+            let future = match self.run {
+                ExecutableJob::AsyncFn(exec) => exec(id, data, last_tick).map(Ok).boxed(),
+                ExecutableJob::AsyncFallibleFn(exec) => exec(id, data, last_tick),
+                _ => {
+                    tracing::warn!("job `{id}` is not async");
+                    return runs;
+                }
+            };
+            let future = AssertUnwindSafe(future).catch_unwind();
+            let run = match with_timeout(timeout, future).await {
+                Some(Ok(Ok(()))) => run.finish(JobOutcome::Succeeded, None),
+                Some(Ok(Err(err))) => run.finish(JobOutcome::Failed, Some(err.to_string())),
+                Some(Err(payload)) => run.finish(JobOutcome::Panicked, panic_message(payload)),
+                None => run.finish(JobOutcome::TimedOut, None),
+            };
+            let succeeded = run.outcome().is_succeeded();
+            runs.push(run);
+            if succeeded || attempt >= self.max_retries {
+                break;
+            }
+            sleep(self.retry_delay(attempt)).await;
+            attempt += 1;
+        }
+        runs
+    }
+
     /// Executes missed runs.
     pub fn tick(&mut self) {
         let now = Local::now();
         if let Some(last_tick) = self.last_tick {
-            for event in self.schedule.after(&last_tick) {
-                if event > now {
-                    break;
-                }
-                match self.run {
-                    ExecutableJob::Fn(exec) => exec(self.id, &mut self.data, last_tick.into()),
-                    ExecutableJob::AsyncFn(_) => tracing::warn!("job `{}` is async", self.id),
-                }
+            let num_runs = self
+                .schedule
+                .after(&last_tick)
+                .take_while(|event| event <= &now)
+                .count();
+            for _ in 0..num_runs {
+                self.execute(last_tick.into());
             }
         }
         self.last_tick = Some(now);
@@ -101,22 +324,54 @@ impl Job {
     pub async fn tick_async(&mut self) {
         let now = Local::now();
         if let Some(last_tick) = self.last_tick {
-            for event in self.schedule.after(&last_tick) {
-                if event > now {
-                    break;
-                }
-                match self.run {
-                    ExecutableJob::Fn(_) => tracing::warn!("job `{}` is not async", self.id),
-                    ExecutableJob::AsyncFn(exec) => {
-                        exec(self.id, &mut self.data, last_tick.into()).await
-                    }
-                }
+            let num_runs = self
+                .schedule
+                .after(&last_tick)
+                .take_while(|event| event <= &now)
+                .count();
+            for _ in 0..num_runs {
+                self.execute_async(last_tick.into()).await;
             }
         }
         self.last_tick = Some(now);
     }
 }
 
+/// Awaits the future with the timeout. It returns `None` if the timeout has elapsed.
+#[cfg(feature = "runtime-tokio")]
+async fn with_timeout<F: Future>(timeout: Option<Duration>, future: F) -> Option<F::Output> {
+    if let Some(timeout) = timeout {
+        tokio::time::timeout(timeout, future).await.ok()
+    } else {
+        Some(future.await)
+    }
+}
+
+/// Awaits the future. The timeout is unsupported without the `runtime-tokio` feature.
+#[cfg(not(feature = "runtime-tokio"))]
+async fn with_timeout<F: Future>(_timeout: Option<Duration>, future: F) -> Option<F::Output> {
+    Some(future.await)
+}
+
+/// Sleeps for the duration without blocking the Tokio runtime.
+#[cfg(feature = "runtime-tokio")]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Returns immediately, since there is no timer without the `runtime-tokio` feature.
+#[cfg(not(feature = "runtime-tokio"))]
+async fn sleep(_duration: Duration) {}
+
+/// Extracts the message from the panic payload.
+fn panic_message(payload: Box<dyn Any + Send>) -> Option<String> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        Some((*message).to_owned())
+    } else {
+        payload.downcast_ref::<String>().cloned()
+    }
+}
+
 /// A type contains and executes the scheduled jobs.
 #[derive(Default)]
 pub struct JobScheduler {
@@ -131,9 +386,11 @@ impl JobScheduler {
     }
 
     /// Adds a job to the `JobScheduler` and returns the job ID.
+    /// The jobs are kept in descending order of the priority.
     pub fn add(&mut self, job: Job) -> Uuid {
         let job_id = job.id;
-        self.jobs.push(job);
+        let index = self.jobs.partition_point(|j| j.priority >= job.priority);
+        self.jobs.insert(index, job);
         job_id
     }
 
@@ -189,3 +446,75 @@ impl JobScheduler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Job, JobOutcome, JobRun};
+    use crate::{bail, datetime::DateTime, error::Error, extension::JsonObjectExt, Map, Uuid};
+    use std::time::Duration;
+
+    fn fail_twice(_id: Uuid, data: &mut Map, _last_tick: DateTime) -> Result<(), Error> {
+        let count = data.get_u64("count").unwrap_or_default();
+        data.upsert("count", count + 1);
+        if count < 2 {
+            bail!("attempt {} has failed", count);
+        }
+        Ok(())
+    }
+
+    fn panic_job(_id: Uuid, _data: &mut Map, _last_tick: DateTime) {
+        panic!("job has panicked");
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    fn sleep_job(_id: Uuid, _data: &mut Map, _last_tick: DateTime) -> crate::BoxFuture<'_> {
+        Box::pin(tokio::time::sleep(Duration::from_secs(1)))
+    }
+
+    #[test]
+    fn it_executes_jobs_with_retries() {
+        let mut job = Job::new_fallible("* * * * * *", fail_twice);
+        job.set_max_retries(3);
+        job.set_retry_backoff(Duration::from_millis(1));
+        job.set_timeout(Duration::from_millis(1));
+        assert!(job.timeout.is_none());
+        assert_eq!(job.execute(DateTime::now()).len(), 3);
+        let outcomes = JobRun::list(Some(job.id()), 10)
+            .into_iter()
+            .map(|run| run.outcome())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                JobOutcome::Succeeded,
+                JobOutcome::Failed,
+                JobOutcome::Failed
+            ]
+        );
+
+        let mut job = Job::new("* * * * * *", panic_job);
+        job.execute(DateTime::now());
+        let runs = JobRun::list(Some(job.id()), 10);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].outcome(), JobOutcome::Panicked);
+        assert_eq!(runs[0].error(), Some("job has panicked"));
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[test]
+    fn it_executes_async_jobs_with_timeouts() {
+        let mut job = Job::new_async("* * * * * *", sleep_job);
+        job.set_timeout(Duration::from_millis(10));
+        job.set_max_retries(1);
+        job.set_retry_backoff(Duration::from_millis(1));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(job.execute_async(DateTime::now()));
+        let runs = JobRun::list(Some(job.id()), 10);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].attempt(), 1);
+        assert!(runs.iter().all(|run| run.outcome() == JobOutcome::TimedOut));
+    }
+}
@@ -0,0 +1,484 @@
+use crate::{application, datetime, error::Error, validation::Validation};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        LazyLock, RwLock,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+use toml::{Table, Value};
+
+/// A subscriber for the changes of a config section.
+type ConfigSubscriber = Box<dyn Fn(&Table) + Send + Sync>;
+
+/// Subscribers for the config sections.
+static CONFIG_SUBSCRIBERS: LazyLock<RwLock<Vec<(String, ConfigSubscriber)>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// A flag to indicate the config files are being watched.
+static CONFIG_WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Max depth of the included config files.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Kinds of the values for the known config entries.
+#[derive(Debug, Clone, Copy)]
+enum ValueKind {
+    /// A string.
+    Str,
+    /// An integer.
+    Integer,
+    /// A boolean.
+    Bool,
+    /// A string which can be parsed as a duration.
+    Duration,
+    /// An array of strings.
+    StrArray,
+    /// A table.
+    Table,
+    /// An array of tables.
+    Tables,
+}
+
+/// Known config entries.
+const KNOWN_ENTRIES: [(&str, ValueKind); 68] = [
+    ("name", ValueKind::Str),
+    ("version", ValueKind::Str),
+    ("domain", ValueKind::Str),
+    ("include", ValueKind::StrArray),
+    ("dirs", ValueKind::Table),
+    ("config", ValueKind::Table),
+    ("config.hot-reload", ValueKind::Bool),
+    ("config.watch-interval", ValueKind::Duration),
+    ("debug", ValueKind::Table),
+    ("debug.host", ValueKind::Str),
+    ("debug.port", ValueKind::Integer),
+    ("main", ValueKind::Table),
+    ("main.host", ValueKind::Str),
+    ("main.port", ValueKind::Integer),
+    ("standby", ValueKind::Tables),
+    ("server", ValueKind::Table),
+    ("server.body-limit", ValueKind::Integer),
+    ("server.request-timeout", ValueKind::Duration),
+    ("database", ValueKind::Table),
+    ("database.namespace", ValueKind::Str),
+    ("database.max-rows", ValueKind::Integer),
+    ("tracing", ValueKind::Table),
+    ("tracing.filter", ValueKind::Str),
+    ("tracing.format", ValueKind::Str),
+    ("tracing.log-dir", ValueKind::Str),
+    ("tracing.log-rotation", ValueKind::Str),
+    ("tracing.log-rolling-period", ValueKind::Duration),
+    ("tracing.otlp", ValueKind::Table),
+    ("tracing.otlp.protocol", ValueKind::Str),
+    ("tracing.otlp.endpoint", ValueKind::Str),
+    ("tracing.otlp.timeout", ValueKind::Duration),
+    ("tracing.sink", ValueKind::Table),
+    ("tracing.sink.level", ValueKind::Str),
+    ("tracing.sink.batch-size", ValueKind::Integer),
+    ("tracing.sink.flush-interval", ValueKind::Duration),
+    ("metrics", ValueKind::Table),
+    ("metrics.exporter", ValueKind::Str),
+    ("metrics.host", ValueKind::Str),
+    ("metrics.port", ValueKind::Integer),
+    ("metrics.otlp", ValueKind::Table),
+    ("metrics.otlp.protocol", ValueKind::Str),
+    ("metrics.otlp.endpoint", ValueKind::Str),
+    ("metrics.otlp.interval", ValueKind::Duration),
+    ("jwt", ValueKind::Table),
+    ("jwt.max-age", ValueKind::Duration),
+    ("jwt.refresh-interval", ValueKind::Duration),
+    ("jwt.time-tolerance", ValueKind::Duration),
+    ("jwt.signing-key-id", ValueKind::Str),
+    ("jwt.hmac-verification", ValueKind::Bool),
+    ("jwt.keys", ValueKind::Tables),
+    ("mfa", ValueKind::Table),
+    ("mfa.challenge-max-age", ValueKind::Duration),
+    ("mfa.max-failed-attempts", ValueKind::Integer),
+    ("mfa.lockout-duration", ValueKind::Duration),
+    ("webauthn", ValueKind::Table),
+    ("webauthn.origins", ValueKind::StrArray),
+    ("rate-limit", ValueKind::Table),
+    ("rate-limit.rules", ValueKind::Tables),
+    ("response-cache", ValueKind::Table),
+    ("response-cache.max-body-size", ValueKind::Integer),
+    ("response-cache.rules", ValueKind::Tables),
+    ("response-cache.session-cookie", ValueKind::Str),
+    ("secrets", ValueKind::Table),
+    ("secrets.providers", ValueKind::StrArray),
+    ("secrets.vault-file", ValueKind::Str),
+    ("secrets.env-prefix", ValueKind::Str),
+    ("secrets.mount-dir", ValueKind::Str),
+    ("openapi", ValueKind::Table),
+];
+
+/// Loads the layered config for the env, and resolves the secrets.
+/// It also returns the paths of the config files which should be watched.
+pub(super) fn load(env: &str) -> Result<(Table, Vec<PathBuf>), Error> {
+    let (mut config, files) = read(env);
+    super::secrets::resolve_secrets(&mut config)?;
+    Ok((config, files))
+}
+
+/// Reads the layered config for the env. The layers are merged in order:
+/// `config.toml`, `config.{env}.toml`, `config.local.toml` and the environment variables.
+pub(super) fn read(env: &str) -> (Table, Vec<PathBuf>) {
+    let config_dir = application::PROJECT_DIR.join("config");
+    let mut config = Table::new();
+    let mut files = Vec::new();
+    if env.is_empty()
+        || !env
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        tracing::error!("the env `{env}` should only contain alphanumeric characters, `-` and `_`");
+    } else {
+        let env_file = config_dir.join(format!("config.{env}.toml"));
+        if !env_file.exists() {
+            let env_file = env_file.display();
+            tracing::error!("the config file `{env_file}` does not exist");
+        }
+        for path in [
+            config_dir.join("config.toml"),
+            env_file,
+            config_dir.join("config.local.toml"),
+        ] {
+            if path.exists() {
+                let table = read_file(&path, &mut files, 0);
+                merge(&mut config, table);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    apply_env_overrides(&mut config, std::env::vars());
+    (config, files)
+}
+
+/// Reads the config file with the included files. The entries in the file
+/// take precedence over the included files.
+fn read_file(path: &Path, files: &mut Vec<PathBuf>, depth: usize) -> Table {
+    files.push(path.to_path_buf());
+
+    let mut table = match fs::read_to_string(path).map(|s| s.parse::<Table>()) {
+        Ok(Ok(table)) => {
+            tracing::warn!("`{}` loaded", path.display());
+            table
+        }
+        Ok(Err(err)) => {
+            tracing::error!("fail to parse the config file `{}`: {err}", path.display());
+            return Table::new();
+        }
+        Err(err) => {
+            tracing::error!("fail to read the config file `{}`: {err}", path.display());
+            return Table::new();
+        }
+    };
+
+    let mut config = Table::new();
+    if let Some(Value::Array(includes)) = table.remove("include") {
+        if depth >= MAX_INCLUDE_DEPTH {
+            tracing::error!("the config file `{}` is nested too deeply", path.display());
+        } else {
+            let dir = path.parent().unwrap_or(path);
+            for include in includes.iter().filter_map(|v| v.as_str()) {
+                let table = read_file(&dir.join(include), files, depth + 1);
+                merge(&mut config, table);
+            }
+        }
+    }
+    merge(&mut config, table);
+    config
+}
+
+/// Merges the overlay into the base config. The tables are merged recursively,
+/// and other values are replaced.
+pub(super) fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Applies the environment variables prefixed with `ZINO_` to the config.
+/// The segments of a key path are separated by `__`, and `_` in a segment is converted to `-`
+/// unless the key with `_` exists. For example, `ZINO_MAIN__PORT=6081` sets the `main.port`,
+/// and `ZINO_POSTGRES__0__PASSWORD` sets the `password` of the first `postgres` table.
+pub(super) fn apply_env_overrides(
+    config: &mut Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) {
+    for (key, value) in vars {
+        if let Some(key_path) = key.strip_prefix("ZINO_")
+            && key_path.contains("__")
+        {
+            let segments = key_path
+                .split("__")
+                .map(|s| s.to_ascii_lowercase())
+                .collect::<Vec<_>>();
+            if !set_table_value(config, &segments, parse_env_value(&value)) {
+                tracing::warn!("fail to override the config with the environment variable `{key}`");
+            }
+        }
+    }
+}
+
+/// Parses the value of an environment variable. Integers, floats, booleans and arrays
+/// are parsed as TOML values, and other values are treated as strings.
+fn parse_env_value(value: &str) -> Value {
+    if let Ok(mut table) = format!("value = {value}").parse::<Table>()
+        && let Some(value) = table.remove("value")
+        && matches!(
+            value,
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) | Value::Array(_)
+        )
+    {
+        value
+    } else {
+        Value::String(value.to_owned())
+    }
+}
+
+/// Sets the value for the key path in a table.
+fn set_table_value(table: &mut Table, segments: &[String], value: Value) -> bool {
+    let Some((segment, segments)) = segments.split_first() else {
+        return false;
+    };
+    let key = if table.contains_key(segment) {
+        segment.to_owned()
+    } else {
+        segment.replace('_', "-")
+    };
+    if segments.is_empty() {
+        table.insert(key, value);
+        true
+    } else {
+        let entry = table
+            .entry(key)
+            .or_insert_with(|| Value::Table(Table::new()));
+        set_value(entry, segments, value)
+    }
+}
+
+/// Sets the value for the key path in a table or an array.
+fn set_value(target: &mut Value, segments: &[String], value: Value) -> bool {
+    match target {
+        Value::Table(table) => set_table_value(table, segments, value),
+        Value::Array(vec) => {
+            let Some((segment, segments)) = segments.split_first() else {
+                return false;
+            };
+            let Some(item) = segment.parse::<usize>().ok().and_then(|i| vec.get_mut(i)) else {
+                return false;
+            };
+            if segments.is_empty() {
+                *item = value;
+                true
+            } else {
+                set_value(item, segments, value)
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Validates the types of the known config entries.
+pub(super) fn validate(config: &Table) -> Validation {
+    let mut validation = Validation::new();
+    for (key, kind) in KNOWN_ENTRIES {
+        let Some(value) = get_value(config, key) else {
+            continue;
+        };
+        let message = match kind {
+            ValueKind::Str if !value.is_str() => "should be a string",
+            ValueKind::Integer if !value.is_integer() => "should be an integer",
+            ValueKind::Bool if !value.is_bool() => "should be a boolean",
+            ValueKind::Duration
+                if !value
+                    .as_str()
+                    .is_some_and(|s| datetime::parse_duration(s).is_ok()) =>
+            {
+                "should be a duration string"
+            }
+            ValueKind::StrArray
+                if !value
+                    .as_array()
+                    .is_some_and(|vec| vec.iter().all(|v| v.is_str())) =>
+            {
+                "should be an array of strings"
+            }
+            ValueKind::Table if !value.is_table() => "should be a table",
+            ValueKind::Tables
+                if !value
+                    .as_array()
+                    .is_some_and(|vec| vec.iter().all(|v| v.is_table())) =>
+            {
+                "should be an array of tables"
+            }
+            _ => continue,
+        };
+        validation.record(key, message);
+    }
+    validation
+}
+
+/// Gets the value for the dotted key path.
+fn get_value<'a>(config: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut segments = key.split('.');
+    let mut value = config.get(segments.next()?)?;
+    for segment in segments {
+        value = value.as_table()?.get(segment)?;
+    }
+    Some(value)
+}
+
+/// Subscribes to the changes of a config section.
+pub(super) fn subscribe(section: String, subscriber: ConfigSubscriber) {
+    if let Ok(mut subscribers) = CONFIG_SUBSCRIBERS.write() {
+        subscribers.push((section, subscriber));
+    }
+}
+
+/// Watches the config files in a background thread, and notifies the subscribers
+/// of the changed sections when the files have been modified.
+pub(super) fn watch(
+    env: &'static str,
+    mut config: Table,
+    mut files: Vec<PathBuf>,
+    interval: Duration,
+) {
+    if CONFIG_WATCHING.swap(true, Relaxed) {
+        tracing::warn!("the config files are already being watched");
+        return;
+    }
+
+    let spawn_result = thread::Builder::new()
+        .name("config-watcher".to_owned())
+        .spawn(move || {
+            let mut modified_times = get_modified_times(&files);
+            loop {
+                thread::sleep(interval);
+
+                let current_modified_times = get_modified_times(&files);
+                if current_modified_times == modified_times {
+                    continue;
+                }
+                modified_times = current_modified_times;
+
+                let (new_config, new_files) = match load(env) {
+                    Ok(loaded) => loaded,
+                    Err(err) => {
+                        tracing::error!("fail to reload the config: {err}");
+                        continue;
+                    }
+                };
+                let validation = validate(&new_config);
+                if !validation.is_success() {
+                    for (key, message) in validation.into_map() {
+                        tracing::error!("invalid config `{key}`: {message}");
+                    }
+                    continue;
+                }
+                if let Ok(subscribers) = CONFIG_SUBSCRIBERS.read() {
+                    for (section, subscriber) in subscribers.iter() {
+                        let value = new_config.get(section);
+                        if config.get(section) != value {
+                            tracing::warn!("the config section `{section}` is reloaded");
+                            match value.and_then(|v| v.as_table()) {
+                                Some(table) => subscriber(table),
+                                None => subscriber(&Table::new()),
+                            }
+                        }
+                    }
+                }
+                config = new_config;
+                if new_files != files {
+                    files = new_files;
+                    modified_times = get_modified_times(&files);
+                }
+            }
+        });
+    if let Err(err) = spawn_result {
+        CONFIG_WATCHING.store(false, Relaxed);
+        tracing::error!("fail to spawn the config watcher: {err}");
+    }
+}
+
+/// Gets the modified times of the files.
+fn get_modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_env_overrides, merge, validate};
+    use toml::Table;
+
+    #[test]
+    fn it_merges_layered_config() {
+        let mut config = r#"
+            name = "data-cube"
+
+            [main]
+            host = "127.0.0.1"
+            port = 6080
+
+            [jwt]
+            max-age = "20m"
+
+            [[postgres]]
+            host = "127.0.0.1"
+            password = "secret"
+        "#
+        .parse::<Table>()
+        .unwrap();
+        let overlay = r#"
+            [main]
+            port = 6081
+
+            [tracing]
+            filter = "info"
+        "#
+        .parse::<Table>()
+        .unwrap();
+        merge(&mut config, overlay);
+        assert_eq!(config["main"]["host"].as_str(), Some("127.0.0.1"));
+        assert_eq!(config["main"]["port"].as_integer(), Some(6081));
+        assert_eq!(config["tracing"]["filter"].as_str(), Some("info"));
+
+        let vars = [
+            ("ZINO_APP_ENV", "prod"),
+            ("ZINO_MAIN__PORT", "8080"),
+            ("ZINO_JWT__MAX_AGE", "1h"),
+            ("ZINO_POSTGRES__0__PASSWORD", "s3cret"),
+            ("ZINO_RATE_LIMIT__ENABLED", "true"),
+        ];
+        apply_env_overrides(
+            &mut config,
+            vars.map(|(key, value)| (key.to_owned(), value.to_owned())),
+        );
+        assert!(!config.contains_key("app-env"));
+        assert_eq!(config["main"]["port"].as_integer(), Some(8080));
+        assert_eq!(config["jwt"]["max-age"].as_str(), Some("1h"));
+        assert_eq!(config["postgres"][0]["password"].as_str(), Some("s3cret"));
+        assert_eq!(config["rate-limit"]["enabled"].as_bool(), Some(true));
+        assert!(validate(&config).is_success());
+
+        apply_env_overrides(
+            &mut config,
+            [("ZINO_MAIN__PORT".to_owned(), "localhost".to_owned())],
+        );
+        let validation = validate(&config);
+        assert!(validation.contains_key("main.port"));
+    }
+}
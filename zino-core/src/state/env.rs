@@ -27,6 +27,18 @@ impl Env {
         matches!(self, Prod)
     }
 
+    /// Returns `true` if `self` is the `staging` environment.
+    #[inline]
+    pub fn is_staging(&self) -> bool {
+        matches!(self, Custom("staging"))
+    }
+
+    /// Returns `true` if `self` is the `test` environment.
+    #[inline]
+    pub fn is_test(&self) -> bool {
+        matches!(self, Custom("test"))
+    }
+
     /// Returns `self` as `&'static str`.
     #[inline]
     pub fn as_str(&self) -> &'static str {
@@ -10,15 +10,23 @@ use crate::{
 use std::{
     borrow::Cow,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
     sync::LazyLock,
+    time::Duration,
 };
 use toml::value::Table;
 
+mod config;
 mod data;
 mod env;
+mod secrets;
 
 pub use data::{Data, SharedData};
 pub use env::Env;
+pub use secrets::{
+    EnvSecretProvider, FileSecretProvider, SecretCipher, SecretProvider, SecretResolver,
+    SecretVault,
+};
 
 /// A state is a record of the env, config and associated data.
 #[derive(Debug, Clone)]
@@ -27,6 +35,8 @@ pub struct State<T = ()> {
     env: Env,
     /// Configuration.
     config: Table,
+    /// Paths of the config files.
+    config_files: Vec<PathBuf>,
     /// Associated data.
     data: T,
 }
@@ -38,14 +48,23 @@ impl<T> State<T> {
         Self {
             env,
             config: Table::new(),
+            config_files: Vec::new(),
             data,
         }
     }
 
-    /// Loads the config file according to the specific env.
+    /// Loads the config according to the specific env. The config is layered by
+    /// `config/config.toml`, `config/config.{env}.toml`, `config/config.local.toml`
+    /// and the environment variables prefixed with `ZINO_`. A config file can also
+    /// include other files by `include = [...]`. The values written as `secret://{name}`
+    /// or `enc:{ciphertext}` are resolved by [`SecretResolver`], and it panics if
+    /// any of them can not be resolved.
+    ///
+    /// If the environment variable `ZINO_APP_CONFIG_URL` has been set,
+    /// the config will be fetched from the URL instead of the files.
     pub fn load_config(&mut self) {
         let env = self.env.as_str();
-        let config = if let Ok(config_url) = std::env::var("ZINO_APP_CONFIG_URL") {
+        let (config, config_files) = if let Ok(config_url) = std::env::var("ZINO_APP_CONFIG_URL") {
             match ureq::get(&config_url)
                 .query("env", env)
                 .call()
@@ -53,28 +72,53 @@ impl<T> State<T> {
             {
                 Ok(toml_str) => {
                     tracing::warn!(env, "`{config_url}` fetched");
-                    toml_str.parse().unwrap_or_default()
+                    let mut config = toml_str.parse().unwrap_or_default();
+                    config::apply_env_overrides(&mut config, std::env::vars());
+                    if let Err(err) = secrets::resolve_secrets(&mut config) {
+                        panic!("fail to resolve the secrets in the config: {err}");
+                    }
+                    (config, Vec::new())
                 }
                 Err(err) => {
                     tracing::error!("fail to fetch the config url `{config_url}`: {err}");
-                    Table::new()
+                    (Table::new(), Vec::new())
                 }
             }
         } else {
-            let config_file = application::PROJECT_DIR.join(format!("./config/config.{env}.toml"));
-            match std::fs::read_to_string(&config_file) {
-                Ok(toml_str) => {
-                    tracing::warn!(env, "`config.{env}.toml` loaded");
-                    toml_str.parse().unwrap_or_default()
-                }
-                Err(err) => {
-                    let config_file = config_file.display();
-                    tracing::error!("fail to read the config file `{config_file}`: {err}");
-                    Table::new()
-                }
-            }
+            config::load(env)
+                .unwrap_or_else(|err| panic!("fail to resolve the secrets in the config: {err}"))
         };
+
+        let validation = config::validate(&config);
+        for (key, message) in validation.into_map() {
+            tracing::error!(env, "invalid config `{key}`: {message}");
+        }
+        self.config = config;
+        self.config_files = config_files;
+    }
+
+    /// Reads the layered config in the same way as [`load_config`](Self::load_config),
+    /// but leaves the secrets unresolved and skips the validation.
+    pub fn read_config(&mut self) {
+        let (config, config_files) = config::read(self.env.as_str());
         self.config = config;
+        self.config_files = config_files;
+    }
+
+    /// Watches the config files in a background thread. When the files have been modified,
+    /// the config is reloaded, and the subscribers of the changed sections will be notified.
+    /// The new config is discarded if it fails to pass the validation.
+    ///
+    /// Note that the config of `self` and the shared state will not be changed.
+    /// The reloaded sections are only passed to [`State::subscribe_config()`].
+    pub fn watch_config(&self, interval: Duration) {
+        if std::env::var("ZINO_APP_CONFIG_URL").is_ok() {
+            tracing::warn!("the config fetched from `ZINO_APP_CONFIG_URL` can not be watched");
+        } else {
+            let config = self.config.clone();
+            let config_files = self.config_files.clone();
+            config::watch(self.env.as_str(), config, config_files, interval);
+        }
     }
 
     /// Set the state data.
@@ -182,6 +226,20 @@ impl State {
         LazyLock::force(&SHARED_STATE)
     }
 
+    /// Subscribes to the changes of a config section when the config files are reloaded.
+    /// The subscriber receives an empty table if the section has been removed.
+    ///
+    /// Note that a reload does not change the shared state, so [`State::shared()`]
+    /// and the config getters still return the config loaded at startup.
+    /// The subscribers should keep the reloaded values they need.
+    #[inline]
+    pub fn subscribe_config(
+        section: impl Into<String>,
+        subscriber: impl Fn(&Table) + Send + Sync + 'static,
+    ) {
+        config::subscribe(section.into(), Box::new(subscriber));
+    }
+
     /// Encrypts the password in the config.
     pub fn encrypt_password(config: &Table) -> Option<Cow<'_, str>> {
         let password = config.get_str("password")?;
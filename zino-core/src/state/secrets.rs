@@ -0,0 +1,448 @@
+use crate::{
+    application, bail, crypto, encoding::base64, error::Error, extension::TomlTableExt, warn,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, RwLock},
+};
+use toml::{Table, Value};
+
+/// Custom secret providers.
+static CUSTOM_PROVIDERS: LazyLock<RwLock<Vec<Arc<dyn SecretProvider>>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Prefix for the encrypted values.
+const ENCRYPTED_PREFIX: &str = "enc:";
+
+/// Prefix for the references to the secrets.
+const SECRET_URL_PREFIX: &str = "secret://";
+
+/// A cipher for the encrypted config values formatted as `enc:{ciphertext}`.
+/// It uses `AES-GCM-SIV`, or `SM4` if the feature `crypto-sm` has been enabled.
+#[derive(Clone)]
+pub struct SecretCipher {
+    /// Secret key.
+    key: [u8; 64],
+}
+
+impl SecretCipher {
+    /// Creates a new instance with the key material.
+    #[inline]
+    pub fn new(key: &[u8]) -> Self {
+        let checksum = crypto::digest(key);
+        Self {
+            key: crypto::derive_key("ZINO:SECRETS", &checksum),
+        }
+    }
+
+    /// Creates a new instance for the application config. The key material is the
+    /// environment variable `ZINO_SECRETS_KEY`, or the `checksum` in the config.
+    pub fn from_config(config: &Table) -> Result<Self, Error> {
+        if let Ok(key) = std::env::var("ZINO_SECRETS_KEY") {
+            Ok(Self::new(key.as_bytes()))
+        } else if let Some(checksum) = config.get_str("checksum") {
+            Ok(Self::new(checksum.as_bytes()))
+        } else {
+            bail!("either `ZINO_SECRETS_KEY` or `checksum` should be set for the secret key");
+        }
+    }
+
+    /// Encrypts the plaintext as a value formatted as `enc:{ciphertext}`.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, Error> {
+        let data = crypto::encrypt(plaintext.as_bytes(), &self.key)?;
+        Ok(format!("{ENCRYPTED_PREFIX}{}", base64::encode(data)))
+    }
+
+    /// Decrypts the value formatted as `enc:{ciphertext}`.
+    pub fn decrypt(&self, value: &str) -> Result<String, Error> {
+        let Some(ciphertext) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            bail!(
+                "the encrypted value should be prefixed with `{}`",
+                ENCRYPTED_PREFIX
+            );
+        };
+        let data = base64::decode(ciphertext)?;
+        let plaintext = crypto::decrypt(&data, &self.key)?;
+        String::from_utf8(plaintext).map_err(|err| err.into())
+    }
+}
+
+/// A provider for the secrets referenced by `secret://{name}`.
+pub trait SecretProvider: Send + Sync + 'static {
+    /// Returns the provider name used in the `secrets.providers` config.
+    fn name(&self) -> &str;
+
+    /// Gets the secret. It returns `None` if the secret does not exist.
+    fn get_secret(&self, name: &str) -> Result<Option<String>, Error>;
+}
+
+/// A vault file which stores the secrets as encrypted values in a TOML table.
+pub struct SecretVault {
+    /// Path of the vault file.
+    path: PathBuf,
+    /// Cipher for the secrets.
+    cipher: SecretCipher,
+    /// Encrypted secrets.
+    secrets: Table,
+}
+
+impl SecretVault {
+    /// Opens the vault file. An empty vault is created if the file does not exist.
+    pub fn open(path: impl AsRef<Path>, cipher: SecretCipher) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let secrets = if path.exists() {
+            fs::read_to_string(path)?.parse()?
+        } else {
+            Table::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            cipher,
+            secrets,
+        })
+    }
+
+    /// Returns the names of the secrets.
+    #[inline]
+    pub fn names(&self) -> Vec<&str> {
+        self.secrets.keys().map(|s| s.as_str()).collect()
+    }
+
+    /// Gets the decrypted secret.
+    pub fn get(&self, name: &str) -> Result<Option<String>, Error> {
+        match self.secrets.get(name) {
+            Some(Value::String(value)) => self.cipher.decrypt(value).map(Some),
+            Some(_) => bail!("the secret `{}` should be an encrypted string", name),
+            None => Ok(None),
+        }
+    }
+
+    /// Encrypts and sets the secret.
+    pub fn set(&mut self, name: impl Into<String>, plaintext: &str) -> Result<(), Error> {
+        let value = self.cipher.encrypt(plaintext)?;
+        self.secrets.insert(name.into(), value.into());
+        Ok(())
+    }
+
+    /// Removes the secret. It returns `true` if the secret existed.
+    #[inline]
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.secrets.remove(name).is_some()
+    }
+
+    /// Saves the vault to the file.
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, toml::to_string(&self.secrets)?)?;
+        Ok(())
+    }
+}
+
+impl SecretProvider for SecretVault {
+    #[inline]
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    #[inline]
+    fn get_secret(&self, name: &str) -> Result<Option<String>, Error> {
+        self.get(name)
+    }
+}
+
+/// A provider for the secrets in the environment variables. The variable name is
+/// the prefix followed by the uppercased secret name, with `-` and `.` replaced by `_`.
+#[derive(Debug, Clone)]
+pub struct EnvSecretProvider {
+    /// Prefix of the variable names.
+    prefix: String,
+}
+
+impl EnvSecretProvider {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl SecretProvider for EnvSecretProvider {
+    #[inline]
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    fn get_secret(&self, name: &str) -> Result<Option<String>, Error> {
+        let name = name.to_ascii_uppercase().replace(['-', '.'], "_");
+        Ok(std::env::var(format!("{}{name}", self.prefix)).ok())
+    }
+}
+
+/// A provider for the secrets stored as files in a directory,
+/// such as the Kubernetes secrets mounted as a volume.
+#[derive(Debug, Clone)]
+pub struct FileSecretProvider {
+    /// Directory of the secret files.
+    dir: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    #[inline]
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn get_secret(&self, name: &str) -> Result<Option<String>, Error> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("invalid secret name `{}`", name);
+        }
+
+        let path = self.dir.join(name);
+        if path.is_file() {
+            let secret = fs::read_to_string(path)?;
+            Ok(Some(secret.trim_end_matches(['\r', '\n']).to_owned()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A resolver for the config values written as `secret://{name}` or `enc:{ciphertext}`.
+pub struct SecretResolver {
+    /// Cipher for the encrypted values.
+    cipher: SecretCipher,
+    /// Secret providers in order.
+    providers: Vec<Arc<dyn SecretProvider>>,
+}
+
+impl SecretResolver {
+    /// Creates a new instance without any providers.
+    #[inline]
+    pub fn new(cipher: SecretCipher) -> Self {
+        Self {
+            cipher,
+            providers: Vec::new(),
+        }
+    }
+
+    /// Creates a new instance with the providers in the `secrets` config.
+    /// The providers default to `["vault", "env", "file"]` and the custom providers.
+    pub fn from_config(config: &Table) -> Result<Self, Error> {
+        let cipher = SecretCipher::from_config(config)?;
+        let mut resolver = Self::new(cipher.clone());
+        let secrets_config = config.get_table("secrets").cloned().unwrap_or_default();
+        let custom_providers = CUSTOM_PROVIDERS
+            .read()
+            .map(|providers| providers.clone())
+            .unwrap_or_default();
+        let provider_names = secrets_config
+            .get_str_array("providers")
+            .unwrap_or_else(|| {
+                let mut names = vec!["vault", "env", "file"];
+                names.extend(custom_providers.iter().map(|provider| provider.name()));
+                names
+            });
+        for name in provider_names {
+            match name {
+                "vault" => {
+                    let vault_file = secrets_config
+                        .get_str("vault-file")
+                        .unwrap_or("config/secrets.toml");
+                    let path = application::PROJECT_DIR.join(vault_file);
+                    resolver.add_provider(SecretVault::open(path, cipher.clone())?);
+                }
+                "env" => {
+                    let prefix = secrets_config
+                        .get_str("env-prefix")
+                        .unwrap_or("ZINO_SECRET_");
+                    resolver.add_provider(EnvSecretProvider::new(prefix));
+                }
+                "file" => {
+                    let mount_dir = secrets_config
+                        .get_str("mount-dir")
+                        .unwrap_or("/run/secrets");
+                    resolver.add_provider(FileSecretProvider::new(mount_dir));
+                }
+                _ => {
+                    if let Some(provider) = custom_providers.iter().find(|p| p.name() == name) {
+                        resolver.providers.push(provider.clone());
+                    } else {
+                        bail!("the secret provider `{}` is not registered", name);
+                    }
+                }
+            }
+        }
+        Ok(resolver)
+    }
+
+    /// Registers a custom secret provider which can be referenced in the `secrets` config.
+    /// It should be registered before the config is loaded.
+    pub fn register_provider(provider: impl SecretProvider) {
+        if let Ok(mut providers) = CUSTOM_PROVIDERS.write() {
+            providers.push(Arc::new(provider));
+        }
+    }
+
+    /// Adds a secret provider.
+    #[inline]
+    pub fn add_provider(&mut self, provider: impl SecretProvider) {
+        self.providers.push(Arc::new(provider));
+    }
+
+    /// Resolves the value. It returns `None` if the value is not a secret.
+    pub fn resolve(&self, value: &str) -> Result<Option<String>, Error> {
+        if value.starts_with(ENCRYPTED_PREFIX) {
+            self.cipher.decrypt(value).map(Some)
+        } else if let Some(name) = value.strip_prefix(SECRET_URL_PREFIX) {
+            for provider in self.providers.iter() {
+                if let Some(secret) = provider.get_secret(name)? {
+                    return Ok(Some(secret));
+                }
+            }
+            Err(warn!("the secret `{}` is not found", name))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Resolves all the string values in the config except for the `secrets` section.
+    /// It returns the key paths and the errors of the values which can not be resolved.
+    pub fn resolve_config(&self, config: &mut Table) -> Vec<(String, Error)> {
+        let mut errors = Vec::new();
+        for (key, value) in config.iter_mut() {
+            if key != "secrets" {
+                self.resolve_value(key.to_owned(), value, &mut errors);
+            }
+        }
+        errors
+    }
+
+    /// Resolves the value recursively.
+    fn resolve_value(&self, path: String, value: &mut Value, errors: &mut Vec<(String, Error)>) {
+        match value {
+            Value::String(s) => match self.resolve(s) {
+                Ok(Some(secret)) => *s = secret,
+                Ok(None) => (),
+                Err(err) => errors.push((path, err)),
+            },
+            Value::Array(vec) => {
+                for (index, value) in vec.iter_mut().enumerate() {
+                    self.resolve_value(format!("{path}.{index}"), value, errors);
+                }
+            }
+            Value::Table(table) => {
+                for (key, value) in table.iter_mut() {
+                    self.resolve_value(format!("{path}.{key}"), value, errors);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Resolves the secrets in the config. It fails if any of the secrets can not be resolved.
+pub(super) fn resolve_secrets(config: &mut Table) -> Result<(), Error> {
+    let has_secrets = config
+        .iter()
+        .any(|(key, value)| key != "secrets" && contains_secrets(value));
+    if !has_secrets {
+        return Ok(());
+    }
+
+    let resolver = SecretResolver::from_config(config)?;
+    let errors = resolver.resolve_config(config);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        let num_errors = errors.len();
+        for (key, err) in errors {
+            tracing::error!("fail to resolve the secret for `{key}`: {err}");
+        }
+        Err(warn!("{} secrets can not be resolved", num_errors))
+    }
+}
+
+/// Returns `true` if the value contains any strings written as `secret://{name}`
+/// or `enc:{ciphertext}`.
+fn contains_secrets(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.starts_with(ENCRYPTED_PREFIX) || s.starts_with(SECRET_URL_PREFIX),
+        Value::Array(vec) => vec.iter().any(contains_secrets),
+        Value::Table(table) => table.values().any(contains_secrets),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnvSecretProvider, SecretCipher, SecretResolver, SecretVault};
+    use toml::Table;
+
+    #[test]
+    fn it_resolves_secrets() {
+        let cipher = SecretCipher::new(b"checksum");
+        let encrypted = cipher.encrypt("s3cret").unwrap();
+        assert!(encrypted.starts_with("enc:"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "s3cret");
+        assert!(SecretCipher::new(b"other").decrypt(&encrypted).is_err());
+
+        let path = std::env::temp_dir().join("zino-secrets-test.toml");
+        let mut vault = SecretVault::open(&path, cipher.clone()).unwrap();
+        vault.set("db-password", "p@ss").unwrap();
+        vault.save().unwrap();
+        let vault = SecretVault::open(&path, cipher.clone()).unwrap();
+        assert_eq!(vault.get("db-password").unwrap().as_deref(), Some("p@ss"));
+        std::fs::remove_file(&path).ok();
+
+        let mut resolver = SecretResolver::new(cipher);
+        resolver.add_provider(vault);
+        resolver.add_provider(EnvSecretProvider::new("CARGO_PKG_"));
+
+        let mut config = format!(
+            r#"
+            token = "{encrypted}"
+            plain = "value"
+
+            [[postgres]]
+            password = "secret://db-password"
+
+            [openai]
+            project = "secret://name"
+            org-id = "secret://org-id"
+        "#
+        )
+        .parse::<Table>()
+        .unwrap();
+        let errors = resolver.resolve_config(&mut config);
+        assert_eq!(config["token"].as_str(), Some("s3cret"));
+        assert_eq!(config["plain"].as_str(), Some("value"));
+        assert_eq!(config["postgres"][0]["password"].as_str(), Some("p@ss"));
+        assert_eq!(config["openai"]["project"].as_str(), Some("zino-core"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "openai.org-id");
+
+        let mut config = r#"
+            [openai]
+            org-id = "secret://org-id"
+        "#
+        .parse::<Table>()
+        .unwrap();
+        assert!(super::resolve_secrets(&mut config).is_err());
+
+        let mut config = "plain = \"value\"".parse::<Table>().unwrap();
+        assert!(super::resolve_secrets(&mut config).is_ok());
+    }
+}
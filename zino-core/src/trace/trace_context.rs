@@ -30,14 +30,12 @@ pub struct TraceContext {
 impl TraceContext {
     /// Creates a new instance without parent.
     pub fn new() -> Self {
-        let span_id = Span::current()
-            .id()
-            .map(|id| id.into_u64())
-            .unwrap_or_else(rand::random);
+        let span_id = current_span_id();
+        let trace_id = current_trace_id().unwrap_or_else(|| Uuid::now_v7().as_u128());
         Self {
             span_id,
             version: 0,
-            trace_id: Uuid::now_v7().as_u128(),
+            trace_id,
             parent_id: None,
             trace_flags: FLAG_SAMPLED | FLAG_RANDOM_TRACE_ID,
             trace_state: TraceState::new(),
@@ -46,10 +44,7 @@ impl TraceContext {
 
     /// Creates a new instance with the specific `trace-id`.
     pub fn with_trace_id(trace_id: Uuid) -> Self {
-        let span_id = Span::current()
-            .id()
-            .map(|id| id.into_u64())
-            .unwrap_or_else(rand::random);
+        let span_id = current_span_id();
         Self {
             span_id,
             version: 0,
@@ -62,10 +57,7 @@ impl TraceContext {
 
     /// Creates a child of the current trace context.
     pub fn child(&self) -> Self {
-        let span_id = Span::current()
-            .id()
-            .map(|id| id.into_u64())
-            .unwrap_or_else(rand::random);
+        let span_id = current_span_id();
         Self {
             span_id,
             version: self.version,
@@ -78,10 +70,7 @@ impl TraceContext {
 
     /// Constructs an instance from the `traceparent` header value.
     pub fn from_traceparent(traceparent: &str) -> Option<Self> {
-        let span_id = Span::current()
-            .id()
-            .map(|id| id.into_u64())
-            .unwrap_or_else(rand::random);
+        let span_id = current_span_id();
         let parts = traceparent.split('-').collect::<Vec<_>>();
         (parts.len() == 4).then_some(Self {
             span_id,
@@ -177,6 +166,35 @@ impl TraceContext {
     pub fn tracestate(&self) -> String {
         self.trace_state.to_string()
     }
+
+    /// Sets the caller identified by `parent-id` as the remote parent of the span,
+    /// so that the span will be exported as a part of the distributed trace.
+    /// It is a no-op if the `otlp` feature is disabled or the `parent-id` is absent.
+    pub fn set_remote_parent(&self, span: &Span) {
+        #[cfg(feature = "otlp")]
+        if let Some(cx) = self.otel_context() {
+            use tracing_opentelemetry::OpenTelemetrySpanExt;
+            span.set_parent(cx);
+        }
+        #[cfg(not(feature = "otlp"))]
+        let _ = span;
+    }
+
+    /// Returns the OpenTelemetry context with the remote span identified by `parent-id`.
+    #[cfg(feature = "otlp")]
+    pub fn otel_context(&self) -> Option<opentelemetry::Context> {
+        use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId};
+
+        let parent_id = self.parent_id?;
+        let span_context = SpanContext::new(
+            TraceId::from(self.trace_id),
+            SpanId::from(parent_id),
+            TraceFlags::new(self.trace_flags),
+            true,
+            self.tracestate().parse().unwrap_or_default(),
+        );
+        Some(opentelemetry::Context::new().with_remote_span_context(span_context))
+    }
 }
 
 impl Default for TraceContext {
@@ -185,6 +203,38 @@ impl Default for TraceContext {
     }
 }
 
+/// Returns the span ID of the current span.
+/// The OpenTelemetry span ID takes precedence if it is valid.
+fn current_span_id() -> u64 {
+    #[cfg(feature = "otlp")]
+    if let Some(span_context) = current_span_context() {
+        return u64::from_be_bytes(span_context.span_id().to_bytes());
+    }
+    Span::current()
+        .id()
+        .map(|id| id.into_u64())
+        .unwrap_or_else(rand::random)
+}
+
+/// Returns the trace ID of the current span if it has a valid OpenTelemetry context.
+fn current_trace_id() -> Option<u128> {
+    #[cfg(feature = "otlp")]
+    if let Some(span_context) = current_span_context() {
+        return Some(u128::from_be_bytes(span_context.trace_id().to_bytes()));
+    }
+    None
+}
+
+/// Returns the OpenTelemetry span context of the current span if it is valid.
+#[cfg(feature = "otlp")]
+fn current_span_context() -> Option<opentelemetry::trace::SpanContext> {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let span_context = Span::current().context().span().span_context().clone();
+    span_context.is_valid().then_some(span_context)
+}
+
 #[cfg(test)]
 mod tests {
     use super::TraceContext;
@@ -46,9 +46,16 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
     let mut writer_name = String::from("main");
     let mut table_name = None;
     let mut model_comment = None;
+    let mut audit_enabled = false;
+    let mut version_check_enabled = false;
+    let mut tenant_key_name = None;
     for attr in input.attrs.iter() {
         for (key, value) in parser::parse_schema_attr(attr).into_iter() {
-            if let Some(value) = value {
+            if key == "audit" {
+                audit_enabled = true;
+            } else if key == "version_check" {
+                version_check_enabled = true;
+            } else if let Some(value) = value {
                 match key.as_str() {
                     "model_name" => {
                         model_name = value;
@@ -65,6 +72,9 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
                     "comment" => {
                         model_comment = Some(value);
                     }
+                    "tenant" => {
+                        tenant_key_name = Some(value);
+                    }
                     _ => (),
                 }
             }
@@ -95,6 +105,7 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
                 let mut default_value = None;
                 let mut index_type = None;
                 let mut reference = None;
+                let mut joinable = false;
                 let mut comment = None;
                 let mut extra_attributes = Vec::new();
                 'inner: for attr in field.attrs.iter() {
@@ -161,6 +172,9 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
                             "reference" => {
                                 reference = value;
                             }
+                            "joinable" => {
+                                joinable = true;
+                            }
                             "comment" => {
                                 comment = value;
                             }
@@ -227,10 +241,28 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
                 };
                 let quote_reference = if let Some(ref model_name) = reference {
                     let model_ident = format_ident!("{}", model_name);
+                    let reference_scoping = if joinable {
+                        quote! {
+                            let snapshot_fields =
+                                <#model_ident as zino_core::orm::ModelAccessor<_, _>>::default_snapshot_query()
+                                    .fields()
+                                    .iter()
+                                    .map(|field| &*field.clone().leak())
+                                    .collect::<Vec<_>>();
+                            let soft_deletable = <#model_ident>::fields().contains(&"status");
+                            reference.set_fields(snapshot_fields);
+                            reference.set_tenant_key(<#model_ident>::TENANT_KEY_NAME);
+                            reference.set_soft_deletable(soft_deletable);
+                        }
+                    } else {
+                        quote! {}
+                    };
                     quote! {{
                         let table_name = <#model_ident>::table_name();
                         let column_name = <#model_ident>::PRIMARY_KEY_NAME;
-                        Some(zino_core::model::Reference::new(table_name, column_name))
+                        let mut reference = zino_core::model::Reference::new(table_name, column_name);
+                        #reference_scoping
+                        Some(reference)
                     }}
                 } else {
                     quote! { None }
@@ -293,6 +325,11 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
     } else {
         quote! { None }
     };
+    let quote_tenant_key_name = if let Some(tenant_key_name) = tenant_key_name {
+        quote! { Some(#tenant_key_name) }
+    } else {
+        quote! { None }
+    };
     let quote_model_comment = if let Some(comment) = model_comment {
         quote! { Some(#comment) }
     } else {
@@ -347,6 +384,9 @@ pub fn derive_schema(item: TokenStream) -> TokenStream {
             const READER_NAME: &'static str = #reader_name;
             const WRITER_NAME: &'static str = #writer_name;
             const TABLE_NAME: Option<&'static str> = #quote_table_name;
+            const AUDIT_ENABLED: bool = #audit_enabled;
+            const VERSION_CHECK_ENABLED: bool = #version_check_enabled;
+            const TENANT_KEY_NAME: Option<&'static str> = #quote_tenant_key_name;
 
             #[inline]
             fn primary_key(&self) -> &Self::PrimaryKey {
@@ -990,6 +1030,14 @@ pub fn derive_model_accessor(item: TokenStream) -> TokenStream {
             });
         } else {
             populated_queries.push(quote! {
+                let joined_columns = query
+                    .joined_columns()
+                    .into_iter()
+                    .filter(|&col| {
+                        <Self as zino_core::orm::Schema>::get_column(col)
+                            .is_some_and(|c| c.has_attribute("joinable"))
+                    })
+                    .collect::<Vec<_>>();
                 let mut models = Self::find::<Map>(query).await?;
                 for model in models.iter_mut() {
                     Self::after_decode(model).await?;
@@ -1006,9 +1054,11 @@ pub fn derive_model_accessor(item: TokenStream) -> TokenStream {
             for (model, ref_fields) in model_references.into_iter() {
                 let model_ident = format_ident!("{}", model);
                 let populated_query = quote! {
-                    let mut query = #model_ident::default_snapshot_query();
-                    query.add_filter("translate", translate_enabled);
-                    #model_ident::populate(&mut query, &mut models, [#(#ref_fields),*]).await?;
+                    if [#(#ref_fields),*].iter().any(|col| !joined_columns.contains(col)) {
+                        let mut query = #model_ident::default_snapshot_query();
+                        query.add_filter("translate", translate_enabled);
+                        #model_ident::populate(&mut query, &mut models, [#(#ref_fields),*]).await?;
+                    }
                 };
                 let populated_one_query = quote! {
                     let mut query = #model_ident::default_query();
@@ -66,7 +66,7 @@ impl<'a> Class<'a> {
     /// Returns `true` if a given class has been added.
     #[inline]
     pub fn contains(&self, class: &str) -> bool {
-        self.classes.iter().any(|&s| s == class)
+        self.classes.contains(&class)
     }
 
     /// Returns `true` if the class list is empty.
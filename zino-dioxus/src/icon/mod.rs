@@ -4,7 +4,7 @@ use crate::{class::Class, format_class};
 use dioxus::prelude::*;
 
 /// A container for any type of icon fonts or SVG icon shapes.
-pub fn Icon<'a>(cx: Scope<'a, IconProps<'a>>) -> Element {
+pub fn Icon<'a>(cx: Scope<'a, IconProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "icon");
     if let Some(icon) = cx.props.icon_class.as_ref() {
         let icon_class = icon.format();
@@ -40,7 +40,7 @@ pub struct IconProps<'a> {
 }
 
 /// A wrapper for combining an icon with text.
-pub fn IconText<'a>(cx: Scope<'a, IconTextProps<'a>>) -> Element {
+pub fn IconText<'a>(cx: Scope<'a, IconTextProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "icon-text");
     render! {
         span {
@@ -2,7 +2,7 @@ use crate::{class::Class, format_class};
 use dioxus::prelude::*;
 
 /// A responsive, fixed-width container with the `max-width` changes at each breakpoint.
-pub fn Container<'a>(cx: Scope<'a, ContainerProps<'a>>) -> Element {
+pub fn Container<'a>(cx: Scope<'a, ContainerProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "container");
     render! {
         main {
@@ -23,7 +23,7 @@ pub struct ContainerProps<'a> {
 }
 
 /// A full width container spanning the entire width of the viewport.
-pub fn FluidContainer<'a>(cx: Scope<'a, FluidContainerProps<'a>>) -> Element {
+pub fn FluidContainer<'a>(cx: Scope<'a, FluidContainerProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "container-fluid");
     render! {
         main {
@@ -44,7 +44,7 @@ pub struct FluidContainerProps<'a> {
 }
 
 /// A container rendered as the `<main>` element.
-pub fn MainContainer<'a>(cx: Scope<'a, MainContainerProps<'a>>) -> Element {
+pub fn MainContainer<'a>(cx: Scope<'a, MainContainerProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "container-fluid my-4 px-4");
     render! {
         main {
@@ -2,7 +2,7 @@ use crate::theme::Theme;
 use dioxus::prelude::*;
 
 /// An interactive dropdown menu for discoverable content.
-pub fn Dropdown<'a>(cx: Scope<'a, DropdownProps<'a>>) -> Element {
+pub fn Dropdown<'a>(cx: Scope<'a, DropdownProps<'a>>) -> Element<'a> {
     render! {
         div {}
     }
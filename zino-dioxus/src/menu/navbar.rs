@@ -3,7 +3,7 @@ use dioxus::prelude::*;
 use dioxus_router::components::{IntoRoutable, Link};
 
 /// A responsive navigation header.
-pub fn Navbar<'a>(cx: Scope<'a, NavbarProps<'a>>) -> Element {
+pub fn Navbar<'a>(cx: Scope<'a, NavbarProps<'a>>) -> Element<'a> {
     let children = cx.props.children.as_ref()?;
     let class = format_class!(cx, "navbar is-link");
     if children.has_component("NavbarBrand") {
@@ -36,7 +36,7 @@ pub struct NavbarProps<'a> {
 }
 
 /// A container for the logo and optionally some links or icons.
-pub fn NavbarBrand<'a>(cx: Scope<'a, NavbarBrandProps<'a>>) -> Element {
+pub fn NavbarBrand<'a>(cx: Scope<'a, NavbarBrandProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "navbar-brand");
     render! {
         div {
@@ -57,7 +57,7 @@ pub struct NavbarBrandProps<'a> {
 }
 
 /// A horizontal menu used in the navigation header.
-pub fn NavbarMenu<'a>(cx: Scope<'a, NavbarMenuProps<'a>>) -> Element {
+pub fn NavbarMenu<'a>(cx: Scope<'a, NavbarMenuProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "navbar-menu is-active");
     render! {
         div {
@@ -78,7 +78,7 @@ pub struct NavbarMenuProps<'a> {
 }
 
 /// The left section of the navbar menu.
-pub fn NavbarStart<'a>(cx: Scope<'a, NavbarStartProps<'a>>) -> Element {
+pub fn NavbarStart<'a>(cx: Scope<'a, NavbarStartProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "navbar-start");
     render! {
         div {
@@ -99,7 +99,7 @@ pub struct NavbarStartProps<'a> {
 }
 
 /// The middle section of the navbar menu.
-pub fn NavbarCenter<'a>(cx: Scope<'a, NavbarCenterProps<'a>>) -> Element {
+pub fn NavbarCenter<'a>(cx: Scope<'a, NavbarCenterProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "navbar-center");
     render! {
         div {
@@ -120,7 +120,7 @@ pub struct NavbarCenterProps<'a> {
 }
 
 /// The right section of the navbar menu.
-pub fn NavbarEnd<'a>(cx: Scope<'a, NavbarEndProps<'a>>) -> Element {
+pub fn NavbarEnd<'a>(cx: Scope<'a, NavbarEndProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "navbar-end");
     render! {
         div {
@@ -141,7 +141,7 @@ pub struct NavbarEndProps<'a> {
 }
 
 /// A link to navigate to another route in the navigation header.
-pub fn NavbarLink<'a>(cx: Scope<'a, NavbarLinkProps<'a>>) -> Element {
+pub fn NavbarLink<'a>(cx: Scope<'a, NavbarLinkProps<'a>>) -> Element<'a> {
     let class = format_class!(cx, "navbar-item");
     let active_class = format_class!(cx, "is-active");
     render! {
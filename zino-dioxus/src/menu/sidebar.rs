@@ -2,7 +2,7 @@ use crate::theme::Theme;
 use dioxus::prelude::*;
 
 /// A vertical menu used in the navigation aside.
-pub fn Sidebar<'a>(cx: Scope<'a, SidebarProps<'a>>) -> Element {
+pub fn Sidebar<'a>(cx: Scope<'a, SidebarProps<'a>>) -> Element<'a> {
     render! {
         div {}
     }
@@ -2,10 +2,53 @@
 
 use lru::LruCache;
 use parking_lot::RwLock;
-use std::{num::NonZeroUsize, sync::LazyLock};
-use zino_core::{state::State, JsonValue};
+use std::{
+    num::NonZeroUsize,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use zino_core::{
+    error::Error,
+    response::{CachedResponse, ResponseCacheStore},
+    state::State,
+    BoxFuture, JsonValue,
+};
+
+/// An entry of the global cache.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    /// Value.
+    value: JsonValue,
+    /// Time when the entry expires at.
+    expires_at: Option<Instant>,
+}
+
+impl CacheEntry {
+    /// Creates a new instance.
+    #[inline]
+    fn new(value: JsonValue, ttl: Option<Duration>) -> Self {
+        Self {
+            value,
+            expires_at: ttl.and_then(|ttl| Instant::now().checked_add(ttl)),
+        }
+    }
+
+    /// Returns `true` if the entry has expired.
+    #[inline]
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Instant::now())
+    }
+
+    /// Returns the value if the entry has not expired.
+    #[inline]
+    fn into_value(self) -> Option<JsonValue> {
+        (!self.is_expired()).then_some(self.value)
+    }
+}
 
 /// Global cache built on the top of [`LruCache`].
+/// The entries put with a TTL are treated as absent once they have expired.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct GlobalCache;
 
@@ -16,7 +59,24 @@ impl GlobalCache {
     #[inline]
     pub fn put(key: impl Into<String>, value: impl Into<JsonValue>) -> Option<JsonValue> {
         let mut cache = GLOBAL_CACHE.write();
-        cache.put(key.into(), value.into())
+        cache
+            .put(key.into(), CacheEntry::new(value.into(), None))
+            .and_then(|entry| entry.into_value())
+    }
+
+    /// Puts a key-value pair into the global cache with a time to live.
+    /// If the key already exists in the cache, then it updates the key’s value and
+    /// returns the old value. Otherwise, `None` is returned.
+    #[inline]
+    pub fn put_with_ttl(
+        key: impl Into<String>,
+        value: impl Into<JsonValue>,
+        ttl: Duration,
+    ) -> Option<JsonValue> {
+        let mut cache = GLOBAL_CACHE.write();
+        cache
+            .put(key.into(), CacheEntry::new(value.into(), Some(ttl)))
+            .and_then(|entry| entry.into_value())
     }
 
     /// Pushes a key-value pair into the global cache. If an entry with the key already
@@ -28,7 +88,9 @@ impl GlobalCache {
         value: impl Into<JsonValue>,
     ) -> Option<(String, JsonValue)> {
         let mut cache = GLOBAL_CACHE.write();
-        cache.push(key.into(), value.into())
+        cache
+            .push(key.into(), CacheEntry::new(value.into(), None))
+            .and_then(|(key, entry)| Some((key, entry.into_value()?)))
     }
 
     /// Returns a cloned value of the key in the global cache or `None`
@@ -36,7 +98,13 @@ impl GlobalCache {
     #[inline]
     pub fn get(key: &str) -> Option<JsonValue> {
         let mut cache = GLOBAL_CACHE.write();
-        cache.get(key).cloned()
+        match cache.get(key) {
+            Some(entry) if entry.is_expired() => {
+                cache.pop(key);
+                None
+            }
+            entry => entry.map(|entry| entry.value.clone()),
+        }
     }
 
     /// Returns a cloned value of the key in the global cache or `None`
@@ -45,7 +113,10 @@ impl GlobalCache {
     #[inline]
     pub fn peek(key: &str) -> Option<JsonValue> {
         let cache = GLOBAL_CACHE.read();
-        cache.peek(key).cloned()
+        cache
+            .peek(key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.value.clone())
     }
 
     /// Returns a bool indicating whether the given key is in the global cache.
@@ -53,7 +124,7 @@ impl GlobalCache {
     #[inline]
     pub fn contains(key: &str) -> bool {
         let cache = GLOBAL_CACHE.read();
-        cache.contains(key)
+        cache.peek(key).is_some_and(|entry| !entry.is_expired())
     }
 
     /// Removes and returns the value corresponding to the key from the global cache or
@@ -61,7 +132,7 @@ impl GlobalCache {
     #[inline]
     pub fn pop(key: &str) -> Option<JsonValue> {
         let mut cache = GLOBAL_CACHE.write();
-        cache.pop(key)
+        cache.pop(key).and_then(|entry| entry.into_value())
     }
 
     /// Removes and returns the key-value pair from the global cache or
@@ -69,7 +140,24 @@ impl GlobalCache {
     #[inline]
     pub fn pop_entry(key: &str) -> Option<(String, JsonValue)> {
         let mut cache = GLOBAL_CACHE.write();
-        cache.pop_entry(key)
+        cache
+            .pop_entry(key)
+            .and_then(|(key, entry)| Some((key, entry.into_value()?)))
+    }
+
+    /// Removes all the key-value pairs whose keys start with the prefix,
+    /// and returns the number of the removed pairs.
+    pub fn pop_prefix(prefix: &str) -> usize {
+        let mut cache = GLOBAL_CACHE.write();
+        let keys = cache
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in keys.iter() {
+            cache.pop(key);
+        }
+        keys.len()
     }
 
     /// Removes and returns the key-value pair corresponding to the least recently used item
@@ -77,7 +165,7 @@ impl GlobalCache {
     #[inline]
     pub fn pop_lru() -> Option<(String, JsonValue)> {
         let mut cache = GLOBAL_CACHE.write();
-        cache.pop_lru()
+        cache.pop_lru().map(|(key, entry)| (key, entry.value))
     }
 
     /// Marks the key as the most recently used one.
@@ -94,7 +182,8 @@ impl GlobalCache {
         cache.demote(key)
     }
 
-    /// Returns the number of key-value pairs that are currently in the global cache.
+    /// Returns the number of key-value pairs that are currently in the global cache,
+    /// including the expired ones which have not been removed.
     #[inline]
     pub fn len() -> usize {
         let cache = GLOBAL_CACHE.read();
@@ -131,7 +220,7 @@ impl GlobalCache {
 }
 
 /// Global cache.
-static GLOBAL_CACHE: LazyLock<RwLock<LruCache<String, JsonValue>>> = LazyLock::new(|| {
+static GLOBAL_CACHE: LazyLock<RwLock<LruCache<String, CacheEntry>>> = LazyLock::new(|| {
     let capacity = if let Some(cache) = State::shared().get_config("cache") {
         cache
             .get("capacity")
@@ -147,3 +236,28 @@ static GLOBAL_CACHE: LazyLock<RwLock<LruCache<String, JsonValue>>> = LazyLock::n
         NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
     ))
 });
+
+impl ResponseCacheStore for GlobalCache {
+    fn get(&self, key: &str) -> BoxFuture<'_, Result<Option<CachedResponse>, Error>> {
+        let result = match Self::get(&format!("response:{key}")) {
+            Some(JsonValue::Object(data)) => CachedResponse::try_from_json(&data).map(Some),
+            _ => Ok(None),
+        };
+        Box::pin(async move { result })
+    }
+
+    fn put(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        ttl: Duration,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Self::put_with_ttl(format!("response:{key}"), response.into_json(), ttl);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn invalidate(&self, model: &str) -> BoxFuture<'_, Result<(), Error>> {
+        Self::pop_prefix(&format!("response:{model}:"));
+        Box::pin(async { Ok(()) })
+    }
+}
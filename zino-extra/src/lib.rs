@@ -3,7 +3,6 @@
 #![doc(html_logo_url = "https://photino.github.io/zino-docs-zh/assets/zino-logo.svg")]
 #![forbid(unsafe_code)]
 #![feature(doc_auto_cfg)]
-#![feature(lazy_cell)]
 
 #[cfg(feature = "cache")]
 pub mod cache;
@@ -2,14 +2,18 @@
 
 use crate::user::User;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use zino_core::{
-    auth::AccessKeyId,
+    auth::{
+        AccessKeyId, AuthorizationProvider, AuthorizationServer, ClientCredentials,
+        ClientRegistration,
+    },
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
     model::{Model, ModelHooks},
     validation::Validation,
-    Map, Uuid,
+    warn, Map, Uuid,
 };
 use zino_derive::{DecodeRow, ModelAccessor, Schema};
 
@@ -44,6 +48,8 @@ pub struct Application {
     manager_id: Uuid, // user.id
     #[schema(not_null, unique, write_only)]
     access_key_id: String,
+    #[schema(write_only)]
+    client_secret_hash: String,
     #[cfg(feature = "tags")]
     #[schema(reference = "Tag", index_type = "gin")]
     tags: Vec<Uuid>, // tag.id, tag.namespace = "*:application"
@@ -150,4 +156,74 @@ impl Application {
     pub fn set_access_key_id(&mut self, access_key_id: AccessKeyId) {
         self.access_key_id = access_key_id.to_string();
     }
+
+    /// Generates a new OAuth2 client secret and stores its hash.
+    /// The secret should be shown to the client only once since it can not be recovered.
+    #[inline]
+    pub fn reset_client_secret(&mut self) -> String {
+        let client_secret = ClientRegistration::generate_client_secret();
+        self.client_secret_hash = ClientRegistration::hash_client_secret(&client_secret);
+        client_secret
+    }
+}
+
+/// The application is an OAuth2 client identified by the `access_key_id`,
+/// and only the hash of the client secret is stored in `client_secret_hash`.
+/// A confidential client can not be authenticated until the secret is generated
+/// by [`Application::reset_client_secret()`].
+///
+/// The client registration is read from the `content` field with the keys
+/// `redirect_uris`, `grant_types`, `scopes` and `public_client`.
+/// The grant types default to `client_credentials`.
+impl AuthorizationServer for Application {
+    async fn find_client(client_id: &str) -> Result<Option<ClientRegistration>, Error> {
+        let mut query = Query::default();
+        query.allow_fields(&["id", "access_key_id", "client_secret_hash", "content"]);
+        query.add_filter("access_key_id", client_id);
+        query.add_filter("status", "Active");
+
+        let Some(application) = Self::find_one::<Map>(&query).await? else {
+            return Ok(None);
+        };
+        let content = application.get_object("content");
+        let mut client = ClientRegistration::new(client_id);
+        if !content
+            .and_then(|m| m.get_bool("public_client"))
+            .unwrap_or_default()
+        {
+            let client_secret_hash = application.get_str("client_secret_hash");
+            client.set_client_secret_hash(client_secret_hash.unwrap_or_default());
+        }
+        if let Some(redirect_uris) = content.and_then(|m| m.parse_str_array("redirect_uris")) {
+            client.set_redirect_uris(redirect_uris.into_iter().map(|s| s.to_owned()).collect());
+        }
+        if let Some(grant_types) = content.and_then(|m| m.parse_str_array("grant_types")) {
+            client.set_grant_types(grant_types.into_iter().map(|s| s.to_owned()).collect());
+        } else {
+            client.set_grant_types(vec!["client_credentials".to_owned()]);
+        }
+        if let Some(scopes) = content.and_then(|m| m.parse_str_array("scopes")) {
+            client.set_scopes(scopes.into_iter().map(|s| s.to_owned()).collect());
+        }
+        Ok(Some(client))
+    }
+}
+
+impl AuthorizationProvider for Application {
+    async fn grant_client_credentials(
+        client_credentials: &ClientCredentials<Self>,
+    ) -> Result<(), Error> {
+        let mut params = Map::from_entry("grant_type", "client_credentials");
+        params.upsert("client_id", client_credentials.client_id());
+        params.upsert("client_secret", client_credentials.client_secret());
+
+        let data = Self::grant_token(&params).await?;
+        let access_token = data
+            .get_str("access_token")
+            .ok_or_else(|| warn!("fail to grant the access token"))?;
+        let expires_in = data.get_u64("expires_in").unwrap_or_default();
+        client_credentials.set_access_token(access_token);
+        client_credentials.set_expires(Duration::from_secs(expires_in));
+        Ok(())
+    }
 }
@@ -117,6 +117,15 @@ impl Model for Dataset {
     }
 }
 
+impl Dataset {
+    /// Links the dataset to the task which produces it.
+    #[inline]
+    pub(crate) fn link_task(&mut self, project_id: Uuid, task_id: Uuid) {
+        self.project_id = project_id;
+        self.task_id = Some(task_id);
+    }
+}
+
 impl ModelHooks for Dataset {
     #[cfg(feature = "maintainer-id")]
     type Extension = UserSession<Uuid, String>;
@@ -12,6 +12,7 @@
 pub mod group;
 pub mod policy;
 pub mod resource;
+pub mod session;
 pub mod tag;
 pub mod user;
 
@@ -31,6 +32,7 @@ pub mod record;
 pub use group::Group;
 pub use policy::Policy;
 pub use resource::Resource;
+pub use session::Session;
 pub use tag::Tag;
 pub use user::User;
 
@@ -2,12 +2,14 @@
 
 use serde::{Deserialize, Serialize};
 use zino_core::{
+    application::{LogRecord, LogSink},
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
     model::{Model, ModelHooks},
+    schedule::JobRun,
     validation::Validation,
-    Map, Uuid,
+    BoxFuture, Map, Uuid,
 };
 use zino_derive::{DecodeRow, ModelAccessor, Schema};
 
@@ -139,3 +141,76 @@ impl ModelHooks for Log {
         Ok(())
     }
 }
+
+/// A log sink which inserts the log records into the `log` table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogStore;
+
+impl LogStore {
+    /// Converts a log record into the `log` model.
+    fn convert_record(record: LogRecord) -> Log {
+        let mut content = Map::new();
+        let pairs = [
+            ("request_id", record.request_id()),
+            ("trace_id", record.trace_id()),
+            ("user_id", record.user_id()),
+            ("route", record.route()),
+        ];
+        for (key, value) in pairs {
+            if let Some(value) = value {
+                content.upsert(key, value);
+            }
+        }
+        if !record.fields().is_empty() {
+            content.upsert("fields", record.fields().clone());
+        }
+
+        let mut log = Log::new();
+        log.name = record.topic().to_owned();
+        log.service = record.service().to_owned();
+        log.server_host = record.server_host().to_owned();
+        log.client_ip = record.client_ip().unwrap_or_default().to_owned();
+        log.topic = record.topic().to_owned();
+        log.level = record.level().to_owned();
+        log.message = record.message().to_owned();
+        log.source = record.source().unwrap_or_default().to_owned();
+        log.recorded_at = record.recorded_at();
+        log.content = content;
+        log
+    }
+
+    /// Converts a job run into the `log` model with the topic `job_run`.
+    pub(crate) fn convert_job_run(run: JobRun) -> Log {
+        let outcome = run.outcome();
+        let mut log = Log::new();
+        log.name = run
+            .job_name()
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| run.job_id().to_string());
+        log.service = "scheduler".to_owned();
+        log.topic = "job_run".to_owned();
+        log.level = if outcome.is_succeeded() {
+            "INFO"
+        } else {
+            "ERROR"
+        }
+        .to_owned();
+        log.message = run.error().unwrap_or(outcome.as_str()).to_owned();
+        log.recorded_at = run.end_time();
+        log.content = run.to_map();
+        log
+    }
+}
+
+impl LogSink for LogStore {
+    fn insert_logs(&self, records: Vec<LogRecord>) -> BoxFuture<'_, Result<(), Error>> {
+        let logs = records
+            .into_iter()
+            .map(Self::convert_record)
+            .collect::<Vec<_>>();
+        Box::pin(async move {
+            Log::insert_many(logs).await?;
+            Ok(())
+        })
+    }
+}
@@ -3,6 +3,7 @@
 use crate::group::Group;
 use serde::{Deserialize, Serialize};
 use zino_core::{
+    auth::{AccessPolicy, PolicyEffect, PolicyEngine},
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
@@ -48,6 +49,7 @@ pub struct Policy {
     resource: String,
     actions: Vec<String>,
     effect: String,
+    roles: Vec<String>,
     valid_from: DateTime,
     expires_at: DateTime,
     #[cfg(feature = "tags")]
@@ -77,8 +79,11 @@ pub struct Policy {
 impl Model for Policy {
     #[inline]
     fn new() -> Self {
+        let now = DateTime::now();
         Self {
             id: Uuid::now_v7(),
+            valid_from: now,
+            expires_at: now,
             ..Self::default()
         }
     }
@@ -97,6 +102,45 @@ impl Model for Policy {
         if let Some(description) = data.parse_string("description") {
             self.description = description.into_owned();
         }
+        if let Some(result) = data.parse_uuid("tenant_id") {
+            match result {
+                Ok(tenant_id) => self.tenant_id = tenant_id,
+                Err(err) => validation.record_fail("tenant_id", err),
+            }
+        }
+        if let Some(resource) = data.parse_string("resource") {
+            self.resource = resource.into_owned();
+        }
+        if let Some(actions) = data.parse_array("actions") {
+            self.actions = actions;
+        }
+        if let Some(effect) = data.parse_string("effect") {
+            if matches!(effect.as_ref(), "allow" | "deny") {
+                self.effect = effect.into_owned();
+            } else {
+                validation.record("effect", "should be `allow` or `deny`");
+            }
+        }
+        if let Some(roles) = data.parse_array("roles") {
+            self.roles = roles;
+        }
+        if let Some(result) = data.parse_datetime("valid_from") {
+            match result {
+                Ok(valid_from) => {
+                    if self.expires_at == self.valid_from {
+                        self.expires_at = valid_from;
+                    }
+                    self.valid_from = valid_from;
+                }
+                Err(err) => validation.record_fail("valid_from", err),
+            }
+        }
+        if let Some(result) = data.parse_datetime("expires_at") {
+            match result {
+                Ok(expires_at) => self.expires_at = expires_at,
+                Err(err) => validation.record_fail("expires_at", err),
+            }
+        }
         #[cfg(feature = "tags")]
         if let Some(tags) = data.parse_array("tags") {
             self.tags = tags;
@@ -142,3 +186,75 @@ impl ModelHooks for Policy {
         Ok(())
     }
 }
+
+impl Policy {
+    /// Converts `self` to an access policy. The policy never expires if `expires_at`
+    /// is the same as `valid_from`, and it has expired if `expires_at` is earlier.
+    pub fn access_policy(&self) -> AccessPolicy {
+        let effect = if self.effect == "allow" {
+            PolicyEffect::Allow
+        } else {
+            PolicyEffect::Deny
+        };
+        let mut policy = AccessPolicy::new(&self.name, &self.resource, effect);
+        policy.set_actions(self.actions.clone());
+        policy.set_roles(self.roles.clone());
+        if !self.tenant_id.is_nil() {
+            policy.set_tenant_id(self.tenant_id.to_string());
+        }
+
+        let expires_at = (self.expires_at != self.valid_from).then_some(self.expires_at);
+        policy.set_time_window(Some(self.valid_from), expires_at);
+        policy
+    }
+
+    /// Loads the active policies into a new policy engine.
+    pub async fn load_policy_engine() -> Result<PolicyEngine, Error> {
+        let mut query = Self::default_query();
+        query.add_filter("status", "Active");
+        query.set_limit(usize::MAX);
+
+        let mut engine = PolicyEngine::new();
+        for policy in Self::find_as::<Self>(&query).await? {
+            engine.add_policy(policy.access_policy());
+        }
+        Ok(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Policy;
+    use std::time::Duration;
+    use zino_core::{
+        auth::{PolicyEngine, UserSession},
+        datetime::DateTime,
+        extension::JsonObjectExt,
+        model::Model,
+        Map,
+    };
+
+    #[test]
+    fn it_expires_policies() {
+        let mut policy = Policy::new();
+        let mut data = Map::new();
+        data.upsert("resource", "/user/**");
+        data.upsert("actions", vec!["*"]);
+        data.upsert("effect", "allow");
+        data.upsert("valid_from", DateTime::now() - Duration::from_secs(60));
+        assert!(policy.read_map(&data).is_success());
+
+        let session = UserSession::<i64>::new(1, None);
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(policy.access_policy());
+        assert!(engine.is_allowed(&session, "GET", "/user/list"));
+
+        let mut data = Map::new();
+        data.upsert("expires_at", DateTime::now() - Duration::from_secs(120));
+        assert!(policy.read_map(&data).is_success());
+
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(policy.access_policy());
+        assert!(!engine.is_allowed(&session, "GET", "/user/list"));
+    }
+}
@@ -6,8 +6,9 @@ use zino_core::{
     error::Error,
     extension::JsonObjectExt,
     model::{Model, ModelHooks},
+    orm::{AuditEntry, AuditSink},
     validation::Validation,
-    Map, Uuid,
+    BoxFuture, Map, Uuid,
 };
 use zino_derive::{DecodeRow, ModelAccessor, Schema};
 
@@ -130,3 +131,27 @@ impl ModelHooks for Record {
         Ok(())
     }
 }
+
+/// An audit sink which stores the entries as records.
+///
+/// The record name is formatted as `{model_name}:{action}`,
+/// and the content contains the fields of the audit entry.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuditRecorder;
+
+impl AuditSink for AuditRecorder {
+    fn record(&self, entry: AuditEntry) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut record = Record::new();
+            record.name = format!("{}:{}", entry.model_name(), entry.action());
+            record.recorded_at = entry.recorded_at();
+            #[cfg(feature = "maintainer-id")]
+            {
+                record.maintainer_id = entry.context().user_id().and_then(|s| s.parse().ok());
+            }
+            record.content = entry.into_map();
+            record.insert().await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,225 @@
+//! The `session` model and related services.
+
+use serde::{Deserialize, Serialize};
+use zino_core::{
+    auth::{TokenFamily, TokenStore},
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Model, ModelHooks},
+    validation::Validation,
+    BoxFuture, Map, Uuid,
+};
+use zino_derive::{DecodeRow, ModelAccessor, Schema};
+
+#[cfg(any(feature = "owner-id", feature = "maintainer-id"))]
+use crate::user::User;
+
+#[cfg(feature = "maintainer-id")]
+use zino_core::auth::UserSession;
+
+/// The `session` model.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema, ModelAccessor)]
+#[serde(default)]
+pub struct Session {
+    // Basic fields.
+    #[schema(read_only)]
+    id: Uuid,
+    #[schema(not_null, index_type = "text")]
+    name: String,
+    #[cfg(feature = "namespace")]
+    #[schema(default_value = "Session::model_namespace", index_type = "hash")]
+    namespace: String,
+    #[cfg(feature = "visibility")]
+    #[schema(default_value = "Internal")]
+    visibility: String,
+    #[schema(default_value = "Active", index_type = "hash")]
+    status: String,
+    #[schema(index_type = "text")]
+    description: String,
+
+    // Info fields.
+    #[schema(not_null, read_only, index_type = "hash")]
+    subject: String,
+    #[schema(index_type = "btree")]
+    expires_at: DateTime,
+
+    // Extensions.
+    content: Map,
+    extra: Map,
+
+    // Revisions.
+    #[cfg(feature = "owner-id")]
+    #[schema(reference = "User")]
+    owner_id: Option<Uuid>, // user.id
+    #[cfg(feature = "maintainer-id")]
+    #[schema(reference = "User")]
+    maintainer_id: Option<Uuid>, // user.id
+    #[schema(read_only, default_value = "now", index_type = "btree")]
+    created_at: DateTime,
+    #[schema(default_value = "now", index_type = "btree")]
+    updated_at: DateTime,
+    version: u64,
+    #[cfg(feature = "edition")]
+    edition: u32,
+}
+
+impl Model for Session {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            ..Self::default()
+        }
+    }
+
+    fn read_map(&mut self, data: &Map) -> Validation {
+        let mut validation = Validation::new();
+        if let Some(result) = data.parse_uuid("id") {
+            match result {
+                Ok(id) => self.id = id,
+                Err(err) => validation.record_fail("id", err),
+            }
+        }
+        if let Some(name) = data.parse_string("name") {
+            self.name = name.into_owned();
+        }
+        if let Some(description) = data.parse_string("description") {
+            self.description = description.into_owned();
+        }
+        #[cfg(feature = "owner-id")]
+        if let Some(result) = data.parse_uuid("owner_id") {
+            match result {
+                Ok(owner_id) => self.owner_id = Some(owner_id),
+                Err(err) => validation.record_fail("owner_id", err),
+            }
+        }
+        #[cfg(feature = "maintainer-id")]
+        if let Some(result) = data.parse_uuid("maintainer_id") {
+            match result {
+                Ok(maintainer_id) => self.maintainer_id = Some(maintainer_id),
+                Err(err) => validation.record_fail("maintainer_id", err),
+            }
+        }
+        validation
+    }
+}
+
+impl Session {
+    /// Returns the `subject`.
+    #[inline]
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Returns the `expires_at`.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+}
+
+impl ModelHooks for Session {
+    #[cfg(feature = "maintainer-id")]
+    type Extension = UserSession<Uuid, String>;
+
+    #[cfg(feature = "maintainer-id")]
+    #[inline]
+    async fn after_extract(&mut self, session: Self::Extension) -> Result<(), Error> {
+        self.maintainer_id = Some(*session.user_id());
+        Ok(())
+    }
+
+    #[cfg(feature = "maintainer-id")]
+    #[inline]
+    async fn before_validation(
+        data: &mut Map,
+        extension: Option<&Self::Extension>,
+    ) -> Result<(), Error> {
+        if let Some(session) = extension {
+            data.upsert("maintainer_id", session.user_id().to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A token store which persists the token families in the `session` table.
+///
+/// Each token family is stored as a session with the same ID,
+/// and the generation of the refresh token is stored as the `version`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionStore;
+
+impl SessionStore {
+    /// Returns the query for the active session.
+    fn active_session_query(family_id: &str) -> Query {
+        let mut query = Session::default_query();
+        query.add_filter("id", family_id);
+        query.add_filter("status", "Active");
+        query.add_filter("expires_at", Map::from_entry("$gt", DateTime::now()));
+        query
+    }
+
+    /// Revokes the sessions selected by the query.
+    async fn revoke_sessions(query: Query) -> Result<(), Error> {
+        let mut updates = Map::from_entry("status", "Revoked");
+        updates.upsert("updated_at", DateTime::now());
+
+        let mut mutation = Session::default_mutation();
+        mutation.append_updates(&mut updates);
+        Session::update_many(&query, &mut mutation).await?;
+        Ok(())
+    }
+}
+
+impl TokenStore for SessionStore {
+    fn create(&self, family: TokenFamily) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut session = Session::new();
+            session.id = family.id().parse()?;
+            session.name = format!("{}:{}", Session::MODEL_NAME, family.subject());
+            session.subject = family.subject().to_owned();
+            session.expires_at = family.expires_at();
+            session.version = family.generation();
+            session.insert().await?;
+            Ok(())
+        })
+    }
+
+    fn rotate(&self, family_id: &str, generation: u64) -> BoxFuture<'_, Result<bool, Error>> {
+        let mut query = Self::active_session_query(family_id);
+        query.add_filter("version", generation);
+
+        let mut updates = Map::new();
+        updates.upsert("version", generation + 1);
+        updates.upsert("updated_at", DateTime::now());
+        Box::pin(async move {
+            let mut mutation = Session::default_mutation();
+            mutation.append_updates(&mut updates);
+
+            let ctx = Session::update_many(&query, &mut mutation).await?;
+            Ok(ctx.rows_affected() == Some(1))
+        })
+    }
+
+    fn revoke(&self, family_id: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let mut query = Session::default_query();
+        query.add_filter("id", family_id);
+        Box::pin(Self::revoke_sessions(query))
+    }
+
+    fn revoke_all(&self, subject: &str) -> BoxFuture<'_, Result<(), Error>> {
+        let mut query = Session::default_query();
+        query.add_filter("subject", subject);
+        query.add_filter("status", "Active");
+        Box::pin(Self::revoke_sessions(query))
+    }
+
+    fn is_active(&self, family_id: &str) -> BoxFuture<'_, Result<bool, Error>> {
+        let query = Self::active_session_query(family_id);
+        Box::pin(async move {
+            let count = Session::count(&query).await?;
+            Ok(count > 0)
+        })
+    }
+}
@@ -0,0 +1,354 @@
+use super::{Task, TaskStatus};
+use crate::dataset::Dataset;
+use futures::{
+    future::FutureExt,
+    stream::{FuturesUnordered, StreamExt},
+};
+use std::{
+    any::Any,
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    panic::AssertUnwindSafe,
+};
+use zino_core::{
+    bail, datetime::DateTime, error::Error, extension::JsonObjectExt, orm::Schema, BoxFuture, Map,
+    Uuid,
+};
+
+/// A function pointer of the task handler, which returns the datasets produced by the task.
+pub type TaskHandler = fn(task: Task) -> BoxFuture<'static, Result<Vec<Dataset>, Error>>;
+
+/// An executor which runs the tasks of a project as a DAG honoring the dependencies.
+///
+/// Ready tasks are run concurrently with at most `max_workers` tasks at a time,
+/// and the tasks depending on a failed task are canceled. A panic in the task handler
+/// fails the task, and a failure to record the status transition is logged
+/// without interrupting the other tasks.
+#[derive(Debug, Clone, Copy)]
+pub struct TaskExecutor {
+    /// Task handler.
+    handler: TaskHandler,
+    /// Max number of concurrent tasks.
+    max_workers: usize,
+}
+
+impl TaskExecutor {
+    /// Creates a new instance with the task handler.
+    #[inline]
+    pub fn new(handler: TaskHandler) -> Self {
+        Self {
+            handler,
+            max_workers: 4,
+        }
+    }
+
+    /// Sets the max number of concurrent tasks.
+    #[inline]
+    pub fn set_max_workers(&mut self, max_workers: usize) {
+        self.max_workers = max_workers.max(1);
+    }
+
+    /// Runs the active tasks of a project, and returns the final status of each task.
+    pub async fn run_project(&self, project_id: Uuid) -> Result<Map, Error> {
+        let mut query = Task::default_query();
+        query.add_filter("project_id", project_id.to_string());
+        query.add_filter(
+            "status",
+            Map::from_entry("$nin", vec!["Deleted", "Inactive"]),
+        );
+        query.set_limit(usize::MAX);
+        let tasks = Task::find_as::<Task>(&query).await?;
+        self.run_tasks(tasks).await
+    }
+
+    /// Runs the tasks, and returns the final status of each task.
+    /// Dependencies which are not in the tasks are assumed to be satisfied.
+    pub async fn run_tasks(&self, tasks: Vec<Task>) -> Result<Map, Error> {
+        let mut graph = TaskGraph::new(&tasks);
+        graph.check_cycle()?;
+
+        let mut tasks = tasks
+            .into_iter()
+            .map(|task| (task.id, task))
+            .collect::<HashMap<_, _>>();
+        for task in tasks.values_mut() {
+            record_transition(task, TaskStatus::Pending, None).await;
+        }
+
+        let mut ready_tasks = graph.initial_tasks();
+        let mut running_tasks = FuturesUnordered::new();
+        let mut statuses = Map::new();
+        loop {
+            while running_tasks.len() < self.max_workers
+                && let Some(task_id) = ready_tasks.pop_front()
+                && let Some(task) = tasks.get_mut(&task_id)
+            {
+                record_transition(task, TaskStatus::Running, None).await;
+                running_tasks.push(run_task(self.handler, task.clone()));
+            }
+
+            let Some((task_id, result)) = running_tasks.next().await else {
+                break;
+            };
+            let Some(task) = tasks.get_mut(&task_id) else {
+                continue;
+            };
+            match result {
+                Ok(()) => {
+                    record_transition(task, TaskStatus::Succeeded, None).await;
+                    statuses.upsert(task_id.to_string(), TaskStatus::Succeeded);
+
+                    let mut next_tasks = graph
+                        .complete(task_id)
+                        .into_iter()
+                        .filter_map(|task_id| tasks.get(&task_id))
+                        .map(|task| (task.priority, task.id))
+                        .collect::<Vec<_>>();
+                    next_tasks.sort_by_key(|&(priority, _)| Reverse(priority));
+                    ready_tasks.extend(next_tasks.into_iter().map(|(_, task_id)| task_id));
+                }
+                Err(err) => {
+                    let message = err.to_string();
+                    tracing::error!(task_id = task_id.to_string(), "task failed: {message}");
+                    record_transition(task, TaskStatus::Failed, Some(&message)).await;
+                    statuses.upsert(task_id.to_string(), TaskStatus::Failed);
+
+                    let message = format!("dependency `{task_id}` has failed");
+                    for task_id in graph.descendants(task_id) {
+                        if !statuses.contains_key(&task_id.to_string())
+                            && let Some(task) = tasks.get_mut(&task_id)
+                        {
+                            record_transition(task, TaskStatus::Canceled, Some(&message)).await;
+                            statuses.upsert(task_id.to_string(), TaskStatus::Canceled);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(statuses)
+    }
+}
+
+/// Runs the task and inserts the produced datasets linked to the task.
+/// A panic in the task handler is caught as an error.
+async fn run_task(handler: TaskHandler, task: Task) -> (Uuid, Result<(), Error>) {
+    let task_id = task.id;
+    let project_id = task.project_id;
+    let result = async move {
+        for mut dataset in handler(task).await? {
+            dataset.link_task(project_id, task_id);
+            dataset.insert().await?;
+        }
+        Ok(())
+    };
+    let result = match AssertUnwindSafe(result).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => Err(Error::new(format!(
+            "task handler panicked: {}",
+            panic_message(payload.as_ref())
+        ))),
+    };
+    (task_id, result)
+}
+
+/// Extracts the message from a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Records the status transition in the task.
+/// A failure of the update is logged, so that the execution can continue.
+async fn record_transition(task: &mut Task, status: TaskStatus, error: Option<&str>) {
+    let now = DateTime::now();
+    let mut transition = Map::from_entry("status", status);
+    transition.upsert("time", now);
+    if let Some(error) = error {
+        transition.upsert("error", error);
+    }
+    if let Some(transitions) = task
+        .extra
+        .get_mut("transitions")
+        .and_then(|v| v.as_array_mut())
+    {
+        transitions.push(transition.into());
+    } else {
+        task.extra.upsert("transitions", vec![transition]);
+    }
+    task.status = status.to_string();
+
+    let mut updates = Map::from_entry("status", status);
+    updates.upsert("extra", task.extra.clone());
+    updates.upsert("updated_at", now);
+    if status == TaskStatus::Running {
+        task.last_time = now;
+        updates.upsert("last_time", now);
+    }
+
+    let mut query = Task::default_query();
+    query.add_filter("id", task.id.to_string());
+
+    let mut mutation = Task::default_mutation();
+    mutation.append_updates(&mut updates);
+    if let Err(err) = Task::update_one(&query, &mut mutation).await {
+        tracing::error!(
+            task_id = task.id.to_string(),
+            "fail to record the `{status}` status transition: {err}"
+        );
+    }
+}
+
+/// Dependency graph of the tasks.
+struct TaskGraph {
+    /// Task IDs in the order of the priority.
+    task_ids: Vec<Uuid>,
+    /// Number of unfinished dependencies for each task.
+    in_degrees: HashMap<Uuid, usize>,
+    /// Dependents for each task.
+    dependents: HashMap<Uuid, Vec<Uuid>>,
+}
+
+impl TaskGraph {
+    /// Creates a new instance.
+    fn new(tasks: &[Task]) -> Self {
+        let mut tasks = tasks.iter().collect::<Vec<_>>();
+        tasks.sort_by_key(|task| Reverse(task.priority));
+
+        let task_ids = tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        let mut in_degrees = HashMap::with_capacity(tasks.len());
+        let mut dependents = HashMap::<_, Vec<_>>::new();
+        for task in tasks {
+            let mut in_degree = 0;
+            for dependency in task.dependencies.iter() {
+                if task_ids.contains(dependency) {
+                    dependents.entry(*dependency).or_default().push(task.id);
+                    in_degree += 1;
+                } else {
+                    tracing::warn!(
+                        task_id = task.id.to_string(),
+                        "dependency `{dependency}` is not in the tasks"
+                    );
+                }
+            }
+            in_degrees.insert(task.id, in_degree);
+        }
+        Self {
+            task_ids,
+            in_degrees,
+            dependents,
+        }
+    }
+
+    /// Returns the tasks without dependencies.
+    fn initial_tasks(&self) -> VecDeque<Uuid> {
+        self.task_ids
+            .iter()
+            .filter(|task_id| self.in_degrees.get(task_id) == Some(&0))
+            .copied()
+            .collect()
+    }
+
+    /// Marks the task as completed, and returns the tasks which become ready.
+    fn complete(&mut self, task_id: Uuid) -> Vec<Uuid> {
+        let mut ready_tasks = Vec::new();
+        for dependent in self.dependents.get(&task_id).into_iter().flatten() {
+            if let Some(in_degree) = self.in_degrees.get_mut(dependent) {
+                *in_degree -= 1;
+                if *in_degree == 0 {
+                    ready_tasks.push(*dependent);
+                }
+            }
+        }
+        ready_tasks
+    }
+
+    /// Returns all the tasks which depend on the task directly or indirectly.
+    fn descendants(&self, task_id: Uuid) -> Vec<Uuid> {
+        let mut descendants = Vec::new();
+        let mut queue = VecDeque::from([task_id]);
+        while let Some(task_id) = queue.pop_front() {
+            for dependent in self.dependents.get(&task_id).into_iter().flatten() {
+                if !descendants.contains(dependent) {
+                    descendants.push(*dependent);
+                    queue.push_back(*dependent);
+                }
+            }
+        }
+        descendants
+    }
+
+    /// Checks the graph for cycles with the Kahn's algorithm.
+    fn check_cycle(&self) -> Result<(), Error> {
+        let mut in_degrees = self.in_degrees.clone();
+        let mut queue = self.initial_tasks();
+        let mut num_visited = 0;
+        while let Some(task_id) = queue.pop_front() {
+            num_visited += 1;
+            for dependent in self.dependents.get(&task_id).into_iter().flatten() {
+                if let Some(in_degree) = in_degrees.get_mut(dependent) {
+                    *in_degree -= 1;
+                    if *in_degree == 0 {
+                        queue.push_back(*dependent);
+                    }
+                }
+            }
+        }
+        if num_visited < self.task_ids.len() {
+            let task_ids = in_degrees
+                .into_iter()
+                .filter(|(_, in_degree)| *in_degree > 0)
+                .map(|(task_id, _)| format!("`{task_id}`"))
+                .collect::<Vec<_>>();
+            bail!(
+                "there is a dependency cycle among the tasks: {}",
+                task_ids.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskGraph;
+    use crate::{dataset::Dataset, task::Task};
+    use futures::executor;
+    use zino_core::{error::Error, model::Model, BoxFuture, Uuid};
+
+    fn panicking_handler(_task: Task) -> BoxFuture<'static, Result<Vec<Dataset>, Error>> {
+        Box::pin(async { panic!("the handler is broken") })
+    }
+
+    #[test]
+    fn it_checks_task_graphs() {
+        let mut tasks = vec![Task::new(), Task::new(), Task::new()];
+        let ids = tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        tasks[1].dependencies = vec![ids[0]];
+        tasks[2].dependencies = vec![ids[0], ids[1], Uuid::now_v7()];
+
+        let mut graph = TaskGraph::new(&tasks);
+        assert!(graph.check_cycle().is_ok());
+        assert_eq!(graph.initial_tasks(), [ids[0]]);
+        assert_eq!(graph.descendants(ids[0]), [ids[1], ids[2]]);
+        assert_eq!(graph.complete(ids[0]), [ids[1]]);
+        assert_eq!(graph.complete(ids[1]), [ids[2]]);
+
+        tasks[0].dependencies = vec![ids[2]];
+        let graph = TaskGraph::new(&tasks);
+        assert!(graph.check_cycle().is_err());
+    }
+
+    #[test]
+    fn it_catches_handler_panics() {
+        let task = Task::new();
+        let task_id = task.id;
+        let (id, result) = executor::block_on(super::run_task(panicking_handler, task));
+        assert_eq!(id, task_id);
+        assert!(result.is_err_and(|err| err.to_string().contains("the handler is broken")));
+    }
+}
@@ -1,17 +1,28 @@
 //! The `task` model and related services.
 
-use crate::{project::Project, source::Source};
+use crate::{
+    log::{Log, LogStore},
+    project::Project,
+    source::Source,
+};
 use serde::{Deserialize, Serialize};
 use zino_core::{
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
     model::{Model, ModelHooks},
+    schedule::{JobRun, JobState, JobStore},
     validation::Validation,
-    Map, Uuid,
+    BoxFuture, Map, Uuid,
 };
 use zino_derive::{DecodeRow, ModelAccessor, Schema};
 
+mod executor;
+mod status;
+
+pub use executor::{TaskExecutor, TaskHandler};
+pub use status::TaskStatus;
+
 #[cfg(feature = "tags")]
 use crate::tag::Tag;
 
@@ -125,6 +136,40 @@ impl Model for Task {
     }
 }
 
+impl Task {
+    /// Returns the `project_id`.
+    #[inline]
+    pub fn project_id(&self) -> &Uuid {
+        &self.project_id
+    }
+
+    /// Returns the `input_id`.
+    #[inline]
+    pub fn input_id(&self) -> &Uuid {
+        &self.input_id
+    }
+
+    /// Returns the `output_id`.
+    #[inline]
+    pub fn output_id(&self) -> Option<&Uuid> {
+        self.output_id
+            .as_ref()
+            .filter(|output_id| !output_id.is_nil())
+    }
+
+    /// Returns the `dependencies`.
+    #[inline]
+    pub fn dependencies(&self) -> &[Uuid] {
+        &self.dependencies
+    }
+
+    /// Returns the `priority`.
+    #[inline]
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+}
+
 impl ModelHooks for Task {
     #[cfg(feature = "maintainer-id")]
     type Extension = UserSession<Uuid, String>;
@@ -148,3 +193,101 @@ impl ModelHooks for Task {
         Ok(())
     }
 }
+
+/// A job store which persists the job states in the `task` table,
+/// and the job runs in the `log` table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TaskStore;
+
+impl TaskStore {
+    /// Parses the job state from the task data.
+    fn parse_job_state(data: &Map) -> Option<JobState> {
+        let id = data.parse_uuid("id")?.ok()?;
+        let name = data.get_str("name").unwrap_or_default();
+        let schedule = data.get_str("schedule").unwrap_or_default();
+        let mut state = JobState::new(id, name, schedule);
+        if let Some(Ok(priority)) = data.parse_u16("priority") {
+            state.set_priority(priority);
+        }
+        if let Some(dependencies) = data.parse_array("dependencies") {
+            state.set_dependencies(dependencies);
+        }
+        if let Some(Ok(last_time)) = data.parse_datetime("last_time") {
+            state.set_last_time(last_time);
+        }
+        if let Some(Ok(next_time)) = data.parse_datetime("next_time") {
+            state.set_next_time(next_time);
+        }
+        if let Some(Ok(version)) = data.parse_u64("version") {
+            state.set_version(version);
+        }
+        Some(state)
+    }
+
+    /// Loads the job state by ID.
+    async fn load_job_state(job_id: Uuid) -> Result<Option<JobState>, Error> {
+        let data = Task::find_by_id::<Map>(&job_id).await?;
+        Ok(data.as_ref().and_then(Self::parse_job_state))
+    }
+}
+
+impl JobStore for TaskStore {
+    fn register(&self, state: JobState) -> BoxFuture<'_, Result<JobState, Error>> {
+        Box::pin(async move {
+            if let Some(stored_state) = Self::load_job_state(state.id()).await? {
+                return Ok(stored_state);
+            }
+
+            let mut task = Task::new();
+            task.id = state.id();
+            task.name = state.name().to_owned();
+            task.schedule = state.schedule().to_owned();
+            task.priority = state.priority();
+            task.dependencies = state.dependencies().to_vec();
+            task.last_time = state.last_time();
+            task.next_time = state.next_time();
+            task.version = state.version();
+            if let Err(err) = task.insert().await {
+                // The job may have been registered by another instance.
+                return Self::load_job_state(state.id()).await?.ok_or(err);
+            }
+            Ok(state)
+        })
+    }
+
+    fn load(&self, job_id: Uuid) -> BoxFuture<'_, Result<Option<JobState>, Error>> {
+        Box::pin(Self::load_job_state(job_id))
+    }
+
+    fn compare_and_swap(&self, state: &JobState) -> BoxFuture<'_, Result<bool, Error>> {
+        let job_id = state.id();
+        let version = state.version();
+        let mut updates = Map::new();
+        updates.upsert("last_time", state.last_time());
+        updates.upsert("next_time", state.next_time());
+        updates.upsert("version", version + 1);
+        updates.upsert("updated_at", DateTime::now());
+        Box::pin(async move {
+            let mut query = Task::default_query();
+            query.add_filter("id", job_id.to_string());
+            query.add_filter("version", version);
+
+            let mut mutation = Task::default_mutation();
+            mutation.append_updates(&mut updates);
+
+            let ctx = Task::update_many(&query, &mut mutation).await?;
+            Ok(ctx.rows_affected() == Some(1))
+        })
+    }
+
+    fn record_runs(&self, runs: Vec<JobRun>) -> BoxFuture<'_, Result<(), Error>> {
+        let logs = runs
+            .into_iter()
+            .map(LogStore::convert_job_run)
+            .collect::<Vec<_>>();
+        Box::pin(async move {
+            Log::insert_many(logs).await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumString, IntoStaticStr};
+use zino_core::JsonValue;
+
+/// Task status in the execution of a workflow.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    Display,
+    EnumString,
+    IntoStaticStr,
+)]
+#[non_exhaustive]
+pub enum TaskStatus {
+    /// It indicates that the task is waiting for its dependencies.
+    /// This is the default value.
+    #[default]
+    Pending,
+    /// It indicates that the task is running.
+    Running,
+    /// It indicates that the task has succeeded.
+    Succeeded,
+    /// It indicates that the task has failed.
+    Failed,
+    /// It indicates that the task has been canceled since a dependency has failed.
+    Canceled,
+}
+
+impl From<TaskStatus> for JsonValue {
+    #[inline]
+    fn from(value: TaskStatus) -> Self {
+        value.as_ref().into()
+    }
+}
@@ -1,6 +1,6 @@
 use std::{fmt::Display, str::FromStr};
 use zino_core::{
-    auth::JwtClaims,
+    auth::{JwtClaims, TokenFamily},
     bail,
     datetime::DateTime,
     error::Error,
@@ -29,6 +29,10 @@ where
     const LOGIN_AT_FIELD: Option<&'static str> = None;
     /// Login-IP field name.
     const LOGIN_IP_FIELD: Option<&'static str> = None;
+    /// MFA-required field name.
+    const MFA_REQUIRED_FIELD: Option<&'static str> = None;
+    /// MFA-factors field name.
+    const MFA_FACTORS_FIELD: Option<&'static str> = None;
 
     /// Returns the standard claims parsed from the `content` field.
     /// See [the spec](https://openid.net/specs/openid-connect-core-1_0.html#StandardClaims).
@@ -82,16 +86,9 @@ where
         claims
     }
 
-    /// Generates the access token and refresh token.
-    async fn generate_token(body: Map) -> Result<(K, Map), Error> {
-        let account = body
-            .get_str("account")
-            .ok_or_else(|| warn!("401 Unauthorized: the user `account` should be specified"))?;
-        let passowrd = body
-            .get_str("password")
-            .ok_or_else(|| warn!("401 Unauthorized: the user `password` should be specified"))?;
-        let mut query = Query::default();
-        let mut fields = vec![Self::PRIMARY_KEY_NAME, Self::PASSWORD_FIELD];
+    /// Returns the fields used for issuing the tokens.
+    fn token_fields() -> Vec<&'static str> {
+        let mut fields = vec![Self::PRIMARY_KEY_NAME];
         if let Some(role_field) = Self::ROLE_FIELD {
             fields.push(role_field);
         }
@@ -104,60 +101,125 @@ where
         if let Some(login_ip_field) = Self::LOGIN_IP_FIELD {
             fields.push(login_ip_field);
         }
+        fields
+    }
+
+    /// Generates the access token and refresh token.
+    /// If the second factor is required for the user, an MFA challenge
+    /// with the `mfa_token` is returned instead of the tokens.
+    async fn generate_token(body: Map) -> Result<(K, Map), Error> {
+        let account = body
+            .get_str("account")
+            .ok_or_else(|| warn!("401 Unauthorized: the user `account` should be specified"))?;
+        let passowrd = body
+            .get_str("password")
+            .ok_or_else(|| warn!("401 Unauthorized: the user `password` should be specified"))?;
+        let mut query = Query::default();
+        let mut fields = Self::token_fields();
+        fields.push(Self::PASSWORD_FIELD);
+        if let Some(mfa_required_field) = Self::MFA_REQUIRED_FIELD {
+            fields.push(mfa_required_field);
+        }
+        if let Some(mfa_factors_field) = Self::MFA_FACTORS_FIELD {
+            fields.push(mfa_factors_field);
+        }
         query.allow_fields(&fields);
         query.add_filter("status", Map::from_entry("$nin", vec!["Locked", "Deleted"]));
         query.add_filter(Self::ACCOUNT_FIELD, account);
 
-        let mut user: Map = Self::find_one(&query)
+        let user: Map = Self::find_one(&query)
             .await?
             .ok_or_else(|| warn!("404 Not Found: invalid user account or password"))?;
         let encrypted_password = user
             .get_str(Self::PASSWORD_FIELD)
             .ok_or_else(|| warn!("404 Not Found: the user password is absent"))?;
         if Self::verify_password(passowrd, encrypted_password)? {
-            // Cann't use `get_str` because the primary key may be an integer
-            let user_id = user
-                .parse_string(Self::PRIMARY_KEY_NAME)
-                .ok_or_else(|| warn!("404 Not Found: the user id is absent"))?;
-            let mut claims = JwtClaims::new(user_id.as_ref());
-
-            let user_id = user_id.parse()?;
-            if let Some(role_field) = Self::ROLE_FIELD
-                && user.contains_key(role_field)
-            {
-                claims.add_data_entry("roles", user.parse_str_array(role_field));
-            }
-            if let Some(tenant_id_field) = Self::TENANT_ID_FIELD
-                && let Some(tenant_id) = user.remove(tenant_id_field)
+            let mfa_required = Self::MFA_REQUIRED_FIELD
+                .and_then(|field| user.get_bool(field))
+                .unwrap_or_default();
+            let mfa_factors = Self::MFA_FACTORS_FIELD.and_then(|field| user.get_object(field));
+            if let Some(user_id) = user.parse_string(Self::PRIMARY_KEY_NAME)
+                && let Some(data) =
+                    super::mfa_auth::mfa_challenge(&user_id, mfa_required, mfa_factors)?
             {
-                claims.add_data_entry("tenant_id", tenant_id);
-            }
-
-            let mut data = Map::new();
-            data.upsert("expires_in", claims.expires_in().as_secs());
-            data.upsert("refresh_token", claims.refresh_token()?);
-            data.upsert("access_token", claims.access_token()?);
-            if let Some(login_at_field) = Self::LOGIN_AT_FIELD {
-                data.upsert(login_at_field, user.remove(login_at_field));
-            }
-            if let Some(login_ip_field) = Self::LOGIN_IP_FIELD {
-                data.upsert(login_ip_field, user.remove(login_ip_field));
+                return Ok((user_id.parse()?, data));
             }
-            Ok((user_id, data))
+            Self::issue_token(user).await
         } else {
             Err(warn!("fail to generate access token"))
         }
     }
 
-    /// Refreshes the access token.
+    /// Issues the access token and refresh token for the user whose identity has been verified.
+    /// The user data should contain the fields returned by [`token_fields()`](Self::token_fields).
+    async fn issue_token(mut user: Map) -> Result<(K, Map), Error> {
+        // Cann't use `get_str` because the primary key may be an integer
+        let user_id = user
+            .parse_string(Self::PRIMARY_KEY_NAME)
+            .ok_or_else(|| warn!("404 Not Found: the user id is absent"))?;
+        let mut claims = JwtClaims::new(user_id.as_ref());
+        let family = TokenFamily::new(user_id.as_ref());
+        claims.add_data_entry("sid", family.id());
+
+        let user_id = user_id.parse()?;
+        if let Some(role_field) = Self::ROLE_FIELD
+            && user.contains_key(role_field)
+        {
+            claims.add_data_entry("roles", user.parse_str_array(role_field));
+        }
+        if let Some(tenant_id_field) = Self::TENANT_ID_FIELD
+            && let Some(tenant_id) = user.remove(tenant_id_field)
+        {
+            claims.add_data_entry("tenant_id", tenant_id);
+        }
+
+        let mut data = Map::new();
+        data.upsert("expires_in", claims.expires_in().as_secs());
+        data.upsert(
+            "refresh_token",
+            claims.refresh_token_with_id(family.token_id())?,
+        );
+        data.upsert("access_token", claims.access_token()?);
+        JwtClaims::token_store().create(family).await?;
+        if let Some(login_at_field) = Self::LOGIN_AT_FIELD {
+            data.upsert(login_at_field, user.remove(login_at_field));
+        }
+        if let Some(login_ip_field) = Self::LOGIN_IP_FIELD {
+            data.upsert(login_ip_field, user.remove(login_ip_field));
+        }
+        Ok((user_id, data))
+    }
+
+    /// Refreshes the access token and rotates the refresh token.
+    ///
+    /// A refresh token can only be used once. If a rotated refresh token is reused,
+    /// the whole token family is revoked since the token may have been stolen.
     async fn refresh_token(claims: &JwtClaims) -> Result<Map, Error> {
-        if !claims.data().is_empty() {
+        if !claims.is_refresh_token() {
             bail!("401 Unauthorized: the JWT token is not a refresh token");
         }
+        if claims.has_audience() {
+            bail!("401 Unauthorized: the refresh token was issued to an OAuth client");
+        }
 
         let Some(user_id) = claims.subject() else {
             bail!("401 Unauthorized: the JWT token does not have a subject");
         };
+        let Some((family_id, generation)) = claims.jwt_id().and_then(TokenFamily::parse_token_id)
+        else {
+            bail!("401 Unauthorized: the refresh token does not have a valid ID");
+        };
+
+        let token_store = JwtClaims::token_store();
+        if !token_store.rotate(family_id, generation).await? {
+            tracing::warn!(
+                family_id,
+                user_id,
+                "the refresh token has been reused or revoked"
+            );
+            token_store.revoke(family_id).await?;
+            bail!("401 Unauthorized: the refresh token has been reused or revoked");
+        }
 
         let mut query = Query::default();
         let mut fields = vec![Self::PRIMARY_KEY_NAME];
@@ -188,14 +250,20 @@ where
         {
             claims.add_data_entry("tenant_id", tenant_id);
         }
+        claims.add_data_entry("sid", family_id);
 
+        let token_id = format!("{family_id}:{}", generation + 1);
         let mut data = Map::new();
         data.upsert("expires_in", claims.expires_in().as_secs());
+        data.upsert("refresh_token", claims.refresh_token_with_id(token_id)?);
         data.upsert("access_token", claims.access_token()?);
         Ok(data)
     }
 
     /// Verfifies the JWT claims.
+    ///
+    /// The token family of an access token is only checked against the revocation list
+    /// if a token store has been set by [`JwtClaims::set_token_store()`].
     async fn verify_jwt_claims(claims: &JwtClaims) -> Result<bool, Error> {
         let Some(user_id) = claims.subject() else {
             bail!("401 Unauthorized: the JWT token does not have a subject");
@@ -248,9 +316,30 @@ where
                 login_at_field
             );
         }
+        if JwtClaims::has_token_store()
+            && let Some(family_id) = claims.token_family_id()
+            && !JwtClaims::token_store().is_active(family_id).await?
+        {
+            bail!("401 Unauthorized: the JWT token has been revoked");
+        }
         Ok(true)
     }
 
+    /// Revokes the token family of the JWT claims, which signs out the current session.
+    async fn revoke_token(claims: &JwtClaims) -> Result<(), Error> {
+        let Some(family_id) = claims.token_family_id() else {
+            bail!("401 Unauthorized: the JWT token does not belong to a session");
+        };
+        JwtClaims::token_store().revoke(family_id).await
+    }
+
+    /// Revokes all the token families of the user, which signs out all the sessions.
+    async fn revoke_all_tokens(user_id: &K) -> Result<(), Error> {
+        JwtClaims::token_store()
+            .revoke_all(&user_id.to_string())
+            .await
+    }
+
     /// Verifies the user identity.
     async fn verify_identity(user_id: K, body: &Map) -> Result<Map, Error> {
         let mut query = Query::default();
@@ -291,4 +380,6 @@ where
 impl JwtAuthService<Uuid> for super::User {
     const LOGIN_AT_FIELD: Option<&'static str> = Some("current_login_at");
     const LOGIN_IP_FIELD: Option<&'static str> = Some("current_login_ip");
+    const MFA_REQUIRED_FIELD: Option<&'static str> = Some("mfa_required");
+    const MFA_FACTORS_FIELD: Option<&'static str> = Some("mfa_factors");
 }
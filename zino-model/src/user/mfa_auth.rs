@@ -0,0 +1,695 @@
+use super::JwtAuthService;
+use std::{fmt::Display, str::FromStr, sync::LazyLock, time::Duration};
+use zino_core::{
+    auth::{AccessKeyId, MfaChallenge, SecretAccessKey},
+    bail,
+    datetime::DateTime,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    model::Query,
+    orm::{ModelAccessor, ModelHelper},
+    state::State,
+    warn, JsonValue, Map, Uuid,
+};
+
+#[cfg(feature = "auth-webauthn")]
+use zino_core::auth::{WebAuthnCredential, WebAuthnRelyingParty};
+
+/// Multi-factor authentication service.
+///
+/// The second factors are stored as a JSON object in the field
+/// [`MFA_FACTORS_FIELD`](JwtAuthService::MFA_FACTORS_FIELD), which consists of
+/// a TOTP secret, WebAuthn credentials and hashed recovery codes.
+/// The enrollment methods should only be called for an authenticated user,
+/// or with an enrollment token verified by [`verify_enrollment_token()`](Self::verify_enrollment_token).
+///
+/// The second factors are updated with the optimistic concurrency control
+/// of the `version` column, so the model is required to have it.
+pub trait MfaAuthService<K = Uuid>: JwtAuthService<K>
+where
+    Self: ModelAccessor<K> + ModelHelper<K>,
+    K: Default + Display + FromStr + PartialEq + serde::de::DeserializeOwned,
+    <K as FromStr>::Err: std::error::Error,
+{
+    /// Fetches the second factors of the user, together with the `version` of the user.
+    async fn fetch_mfa_factors(user_id: &K) -> Result<(Map, u64), Error> {
+        let Some(mfa_factors_field) = Self::MFA_FACTORS_FIELD else {
+            bail!("the MFA factors field is not specified");
+        };
+        if Self::get_column("version").is_none() {
+            bail!("the `version` column is required for the MFA factors");
+        }
+
+        let mut query = Query::default();
+        query.allow_fields(&[Self::PRIMARY_KEY_NAME, mfa_factors_field, "version"]);
+        query.add_filter(Self::PRIMARY_KEY_NAME, user_id.to_string());
+        query.add_filter("status", Map::from_entry("$nin", vec!["Locked", "Deleted"]));
+
+        let mut user: Map = Self::find_one(&query)
+            .await?
+            .ok_or_else(|| warn!("404 Not Found: cannot get the user `{}`", user_id))?;
+        let version = user.get_u64("version").unwrap_or_default();
+        match user.remove(mfa_factors_field) {
+            Some(JsonValue::Object(factors)) => Ok((factors, version)),
+            _ => Ok((Map::new(), version)),
+        }
+    }
+
+    /// Updates the second factors of the user if the `version` has not been changed.
+    /// It returns a `409 Conflict` error if the factors have been updated concurrently.
+    async fn update_mfa_factors(user_id: &K, factors: Map, version: u64) -> Result<(), Error> {
+        let Some(mfa_factors_field) = Self::MFA_FACTORS_FIELD else {
+            bail!("the MFA factors field is not specified");
+        };
+
+        let mut query = Query::default();
+        query.add_filter(Self::PRIMARY_KEY_NAME, user_id.to_string());
+        query.add_filter("version", version);
+
+        let mut updates = Map::from_entry(mfa_factors_field, factors);
+        let mut mutation = Self::default_mutation();
+        mutation.append_updates(&mut updates);
+        Self::update_one(&query, &mut mutation).await?;
+        Ok(())
+    }
+
+    /// Verifies an enrollment token issued for a user who is required to use MFA
+    /// but has not enrolled any second factors, and returns the user ID.
+    /// The user should log in again after the enrollment.
+    async fn verify_enrollment_token(mfa_token: &str) -> Result<K, Error> {
+        let challenge = MfaChallenge::parse(mfa_token)?;
+        if !challenge.is_enrollment() {
+            bail!("403 Forbidden: the MFA token is not issued for the enrollment");
+        }
+
+        let user_id = challenge.subject().parse()?;
+        let (factors, _) = Self::fetch_mfa_factors(&user_id).await?;
+        if !enrolled_methods(&factors).is_empty() {
+            bail!("403 Forbidden: the second factors have been enrolled");
+        }
+        Ok(user_id)
+    }
+
+    /// Verifies the second factor with the `mfa_token` issued by
+    /// [`generate_token()`](JwtAuthService::generate_token),
+    /// and issues the access token and refresh token.
+    ///
+    /// One of `totp_code`, `recovery_code` and `webauthn` should be specified,
+    /// where a recovery code can only be used once. The `mfa_token` is consumed
+    /// by the verification whether it succeeds or not.
+    async fn verify_second_factor(body: Map) -> Result<(K, Map), Error> {
+        let Some(mfa_factors_field) = Self::MFA_FACTORS_FIELD else {
+            bail!("the MFA factors field is not specified");
+        };
+        if Self::get_column("version").is_none() {
+            bail!("the `version` column is required for the MFA factors");
+        }
+        let mfa_token = body
+            .get_str("mfa_token")
+            .ok_or_else(|| warn!("401 Unauthorized: the `mfa_token` should be specified"))?;
+        let challenge = MfaChallenge::parse(mfa_token)?;
+        if challenge.is_enrollment() {
+            bail!("403 Forbidden: the MFA token is issued for the enrollment");
+        }
+
+        let mut query = Query::default();
+        let mut fields = Self::token_fields();
+        fields.push(mfa_factors_field);
+        fields.push("version");
+        query.allow_fields(&fields);
+        query.add_filter(Self::PRIMARY_KEY_NAME, challenge.subject());
+        query.add_filter("status", Map::from_entry("$nin", vec!["Locked", "Deleted"]));
+
+        let mut user: Map = Self::find_one(&query).await?.ok_or_else(|| {
+            warn!(
+                "404 Not Found: cannot get the user `{}`",
+                challenge.subject()
+            )
+        })?;
+        let user_id = challenge.subject().parse()?;
+        let version = user.get_u64("version").unwrap_or_default();
+        let mut factors = match user.remove(mfa_factors_field) {
+            Some(JsonValue::Object(factors)) => factors,
+            _ => Map::new(),
+        };
+        consume_challenge(&mut factors, &challenge)?;
+        check_lockout(&mut factors)?;
+
+        let verified = if let Some(recovery_code) = body.get_str("recovery_code") {
+            verify_recovery_code(&mut factors, recovery_code)
+        } else if let Some(totp_code) = body.get_str("totp_code") {
+            verify_totp_code(&mut factors, totp_code)?
+        } else if let Some(assertion) = body.get_object("webauthn") {
+            verify_webauthn_assertion(&mut factors, challenge.challenge(), assertion)?
+        } else {
+            bail!("401 Unauthorized: the second factor should be specified");
+        };
+        record_attempt(&mut factors, verified);
+        Self::update_mfa_factors(&user_id, factors, version).await?;
+        if !verified {
+            bail!("401 Unauthorized: fail to verify the second factor");
+        }
+        Self::issue_token(user).await
+    }
+
+    /// Enrolls a TOTP secret for the user. The secret is pending
+    /// until it has been confirmed by [`confirm_totp()`](Self::confirm_totp).
+    /// It returns the `secret` and the `otpauth_url` for the authenticator apps.
+    #[cfg(feature = "auth-totp")]
+    async fn enroll_totp(user_id: &K, account_name: &str) -> Result<Map, Error> {
+        let (mut factors, version) = Self::fetch_mfa_factors(user_id).await?;
+        if factors
+            .get_object("totp")
+            .and_then(|totp| totp.get_bool("confirmed"))
+            .is_some_and(|confirmed| confirmed)
+        {
+            bail!("409 Conflict: the TOTP has been enrolled");
+        }
+
+        let key_id = AccessKeyId::new();
+        let issuer = State::shared()
+            .get_config("mfa")
+            .and_then(|config| config.get_str("issuer"))
+            .map(|s| s.to_owned());
+        let totp = SecretAccessKey::new(&key_id).generate_totp(issuer, account_name.to_owned());
+
+        let mut data = Map::from_entry("secret", totp.get_secret_base32());
+        data.upsert("otpauth_url", totp.get_url());
+        factors.upsert("totp", Map::from_entry("key_id", key_id.as_str()));
+        Self::update_mfa_factors(user_id, factors, version).await?;
+        Ok(data)
+    }
+
+    /// Confirms the pending TOTP secret with a code generated by the authenticator app.
+    /// The recovery codes are returned if they have not been generated.
+    #[cfg(feature = "auth-totp")]
+    async fn confirm_totp(user_id: &K, totp_code: &str) -> Result<Map, Error> {
+        let (mut factors, version) = Self::fetch_mfa_factors(user_id).await?;
+        let Some(totp) = factors.get_mut("totp").and_then(|v| v.as_object_mut()) else {
+            bail!("404 Not Found: the TOTP has not been enrolled");
+        };
+        if totp
+            .get_bool("confirmed")
+            .is_some_and(|confirmed| confirmed)
+        {
+            bail!("409 Conflict: the TOTP has been confirmed");
+        }
+        if !check_totp_code(totp, totp_code)? {
+            bail!("401 Unauthorized: fail to verify the TOTP code");
+        }
+        totp.upsert("confirmed", true);
+
+        let data = issue_recovery_codes_if_absent(&mut factors);
+        Self::update_mfa_factors(user_id, factors, version).await?;
+        Ok(data)
+    }
+
+    /// Returns the options for registering a WebAuthn credential,
+    /// together with an `mfa_token` which should be sent back for the registration.
+    #[cfg(feature = "auth-webauthn")]
+    async fn webauthn_creation_options(user_id: &K, user_name: &str) -> Result<Map, Error> {
+        let (factors, _) = Self::fetch_mfa_factors(user_id).await?;
+        let credentials = factors.get_map_array("webauthn").unwrap_or_default();
+        let credential_ids = credentials
+            .iter()
+            .filter_map(|credential| credential.get_str("id"))
+            .collect::<Vec<_>>();
+
+        let subject = user_id.to_string();
+        let challenge = MfaChallenge::new(&subject);
+        let options = WebAuthnRelyingParty::shared().creation_options(
+            &subject,
+            user_name,
+            challenge.challenge(),
+            &credential_ids,
+        );
+
+        let mut data = Map::from_entry("mfa_token", challenge.encode()?);
+        data.upsert("public_key", options);
+        Ok(data)
+    }
+
+    /// Registers a WebAuthn credential with the `mfa_token`, the `client_data_json`
+    /// and the `attestation_object`. The recovery codes are returned
+    /// if they have not been generated.
+    #[cfg(feature = "auth-webauthn")]
+    async fn register_webauthn(user_id: &K, body: &Map) -> Result<Map, Error> {
+        let mfa_token = body
+            .get_str("mfa_token")
+            .ok_or_else(|| warn!("401 Unauthorized: the `mfa_token` should be specified"))?;
+        let challenge = MfaChallenge::parse(mfa_token)?;
+        if challenge.subject() != user_id.to_string() {
+            bail!("403 Forbidden: the MFA token is not issued for the user");
+        }
+
+        let (Some(client_data_json), Some(attestation_object)) = (
+            body.get_str("client_data_json"),
+            body.get_str("attestation_object"),
+        ) else {
+            bail!("the `client_data_json` and `attestation_object` should be specified");
+        };
+        let credential = WebAuthnRelyingParty::shared()
+            .register(challenge.challenge(), client_data_json, attestation_object)
+            .map_err(|err| warn!("401 Unauthorized: {}", err.message()))?;
+
+        let (mut factors, version) = Self::fetch_mfa_factors(user_id).await?;
+        let mut credentials = factors
+            .get_map_array("webauthn")
+            .unwrap_or_default()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        if credentials
+            .iter()
+            .any(|c| c.get_str("id") == Some(credential.id()))
+        {
+            bail!("409 Conflict: the WebAuthn credential has been registered");
+        }
+
+        let mut entry = credential.to_map();
+        if let Some(name) = body.get_str("name") {
+            entry.upsert("name", name);
+        }
+        entry.upsert("created_at", DateTime::now().to_string());
+        credentials.push(entry);
+        factors.upsert("webauthn", credentials);
+
+        let mut data = issue_recovery_codes_if_absent(&mut factors);
+        data.upsert("credential_id", credential.id());
+        Self::update_mfa_factors(user_id, factors, version).await?;
+        Ok(data)
+    }
+
+    /// Regenerates the recovery codes for the user, which invalidates the previous ones.
+    async fn generate_recovery_codes(user_id: &K) -> Result<Vec<String>, Error> {
+        let (mut factors, version) = Self::fetch_mfa_factors(user_id).await?;
+        if enrolled_methods(&factors).is_empty() {
+            bail!("403 Forbidden: the second factors have not been enrolled");
+        }
+
+        let (recovery_codes, hashed_codes) = new_recovery_codes();
+        factors.upsert("recovery_codes", hashed_codes);
+        Self::update_mfa_factors(user_id, factors, version).await?;
+        Ok(recovery_codes)
+    }
+
+    /// Removes all the second factors of the user after the re-verification
+    /// with a `totp_code` or a `recovery_code`. The failed attempts are counted
+    /// in the same way as [`verify_second_factor()`](Self::verify_second_factor).
+    async fn disable_mfa(user_id: &K, body: &Map) -> Result<(), Error> {
+        let (mut factors, version) = Self::fetch_mfa_factors(user_id).await?;
+        if enrolled_methods(&factors).is_empty() {
+            bail!("404 Not Found: the second factors have not been enrolled");
+        }
+        check_lockout(&mut factors)?;
+
+        let verified = if let Some(recovery_code) = body.get_str("recovery_code") {
+            verify_recovery_code(&mut factors, recovery_code)
+        } else if let Some(totp_code) = body.get_str("totp_code") {
+            verify_totp_code(&mut factors, totp_code)?
+        } else {
+            bail!("401 Unauthorized: the `totp_code` or `recovery_code` should be specified");
+        };
+        if !verified {
+            record_attempt(&mut factors, false);
+            Self::update_mfa_factors(user_id, factors, version).await?;
+            bail!("401 Unauthorized: fail to verify the second factor");
+        }
+        Self::update_mfa_factors(user_id, Map::new(), version).await
+    }
+}
+
+impl MfaAuthService<Uuid> for super::User {}
+
+/// Creates an MFA challenge if the second factor is required for the user.
+/// If the user is required to use MFA but has not enrolled any second factors,
+/// the challenge is issued for the enrollment.
+pub(super) fn mfa_challenge(
+    subject: &str,
+    mfa_required: bool,
+    factors: Option<&Map>,
+) -> Result<Option<Map>, Error> {
+    let methods = factors.map(enrolled_methods).unwrap_or_default();
+    if methods.is_empty() && !mfa_required {
+        return Ok(None);
+    }
+
+    let mut challenge = MfaChallenge::new(subject);
+    challenge.set_enrollment(methods.is_empty());
+
+    let mut data = Map::from_entry("mfa_required", true);
+    data.upsert("mfa_token", challenge.encode()?);
+    data.upsert("expires_in", challenge.expires_in().as_secs());
+    data.upsert("mfa_methods", methods.clone());
+    data.upsert("mfa_enrollment_required", challenge.is_enrollment());
+
+    #[cfg(feature = "auth-webauthn")]
+    if methods.contains(&"webauthn") {
+        let credentials = factors
+            .and_then(|factors| factors.get_map_array("webauthn"))
+            .unwrap_or_default();
+        let credential_ids = credentials
+            .iter()
+            .filter_map(|credential| credential.get_str("id"))
+            .collect::<Vec<_>>();
+        let options =
+            WebAuthnRelyingParty::shared().request_options(challenge.challenge(), &credential_ids);
+        data.upsert("public_key", options);
+    }
+    Ok(Some(data))
+}
+
+/// Returns the enrolled methods of the second factors.
+fn enrolled_methods(factors: &Map) -> Vec<&'static str> {
+    let mut methods = Vec::new();
+    if cfg!(feature = "auth-totp")
+        && factors
+            .get_object("totp")
+            .and_then(|totp| totp.get_bool("confirmed"))
+            .is_some_and(|confirmed| confirmed)
+    {
+        methods.push("totp");
+    }
+    if cfg!(feature = "auth-webauthn")
+        && factors
+            .get_array("webauthn")
+            .is_some_and(|credentials| !credentials.is_empty())
+    {
+        methods.push("webauthn");
+    }
+    if !methods.is_empty()
+        && factors
+            .get_array("recovery_codes")
+            .is_some_and(|codes| !codes.is_empty())
+    {
+        methods.push("recovery_code");
+    }
+    methods
+}
+
+/// Consumes the MFA challenge so that the `mfa_token` can only be used once.
+/// The consumed challenges are kept in the factors until they expire.
+fn consume_challenge(factors: &mut Map, challenge: &MfaChallenge) -> Result<(), Error> {
+    let now = DateTime::now().timestamp();
+    let mut consumed_challenges = factors
+        .get_map_array("consumed_challenges")
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| entry.get_i64("expires_at").is_some_and(|exp| exp > now))
+        .cloned()
+        .collect::<Vec<_>>();
+    if consumed_challenges
+        .iter()
+        .any(|entry| entry.get_str("challenge") == Some(challenge.challenge()))
+    {
+        bail!("401 Unauthorized: the MFA token has been used");
+    }
+
+    let mut entry = Map::from_entry("challenge", challenge.challenge());
+    entry.upsert("expires_at", challenge.expires_at().timestamp());
+    consumed_challenges.push(entry);
+    factors.upsert("consumed_challenges", consumed_challenges);
+    Ok(())
+}
+
+/// Checks whether the second factor has been locked out for too many failed attempts.
+fn check_lockout(factors: &mut Map) -> Result<(), Error> {
+    if factors.get_u32("failed_attempts").unwrap_or_default() >= *MAX_FAILED_ATTEMPTS {
+        if let Some(failed_at) = factors.parse_datetime("failed_at").and_then(|r| r.ok())
+            && failed_at.span_between_now() < *LOCKOUT_DURATION
+        {
+            bail!("403 Forbidden: too many failed attempts of the second factor");
+        }
+        factors.remove("failed_attempts");
+    }
+    Ok(())
+}
+
+/// Records an attempt of the second factor.
+fn record_attempt(factors: &mut Map, verified: bool) {
+    if verified {
+        factors.remove("failed_attempts");
+        factors.remove("failed_at");
+    } else {
+        let failed_attempts = factors.get_u32("failed_attempts").unwrap_or_default();
+        factors.upsert("failed_attempts", failed_attempts.saturating_add(1));
+        factors.upsert("failed_at", DateTime::now().to_string());
+    }
+}
+
+/// Hashes the recovery code with the shared secret.
+fn hash_recovery_code(recovery_code: &str) -> String {
+    let normalized = recovery_code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    SecretAccessKey::new(&normalized.into()).to_string()
+}
+
+/// Generates new recovery codes and returns them with the hashed values.
+fn new_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let recovery_codes = (0..*NUM_RECOVERY_CODES)
+        .map(|_| {
+            let chars = AccessKeyId::new().as_str().to_ascii_lowercase();
+            format!("{}-{}", &chars[..5], &chars[5..10])
+        })
+        .collect::<Vec<_>>();
+    let hashed_codes = recovery_codes
+        .iter()
+        .map(|code| hash_recovery_code(code))
+        .collect();
+    (recovery_codes, hashed_codes)
+}
+
+/// Generates the recovery codes if they are absent, and returns them as `recovery_codes`.
+#[cfg(any(feature = "auth-totp", feature = "auth-webauthn"))]
+fn issue_recovery_codes_if_absent(factors: &mut Map) -> Map {
+    if factors
+        .get_array("recovery_codes")
+        .is_some_and(|codes| !codes.is_empty())
+    {
+        return Map::new();
+    }
+
+    let (recovery_codes, hashed_codes) = new_recovery_codes();
+    factors.upsert("recovery_codes", hashed_codes);
+    Map::from_entry("recovery_codes", recovery_codes)
+}
+
+/// Verifies and consumes a recovery code.
+fn verify_recovery_code(factors: &mut Map, recovery_code: &str) -> bool {
+    if enrolled_methods(factors).is_empty() {
+        return false;
+    }
+
+    let hashed_code = hash_recovery_code(recovery_code);
+    let Some(codes) = factors
+        .get_mut("recovery_codes")
+        .and_then(|v| v.as_array_mut())
+    else {
+        return false;
+    };
+    let num_codes = codes.len();
+    codes.retain(|code| code.as_str() != Some(hashed_code.as_str()));
+    codes.len() < num_codes
+}
+
+/// Verifies a TOTP code with the confirmed secret.
+/// A pending secret which has not been confirmed is rejected.
+#[cfg(feature = "auth-totp")]
+fn verify_totp_code(factors: &mut Map, totp_code: &str) -> Result<bool, Error> {
+    let Some(totp) = factors.get_mut("totp").and_then(|v| v.as_object_mut()) else {
+        return Ok(false);
+    };
+    if !totp
+        .get_bool("confirmed")
+        .is_some_and(|confirmed| confirmed)
+    {
+        return Ok(false);
+    }
+    check_totp_code(totp, totp_code)
+}
+
+/// Checks a TOTP code with the secret. A code can not be reused
+/// in the same or a previous time step.
+#[cfg(feature = "auth-totp")]
+fn check_totp_code(totp: &mut Map, totp_code: &str) -> Result<bool, Error> {
+    let Some(key_id) = totp.get_str("key_id") else {
+        return Ok(false);
+    };
+
+    let secret_access_key = SecretAccessKey::new(&key_id.into());
+    let generator = secret_access_key.generate_totp(None, String::new());
+    let last_step = totp.get_u64("last_step").unwrap_or_default();
+    let timestamp = u64::try_from(DateTime::now().timestamp())?;
+    let step = [timestamp.saturating_sub(30), timestamp, timestamp + 30]
+        .into_iter()
+        .find(|&time| generator.generate(time) == totp_code)
+        .map(|time| time / 30);
+    match step {
+        Some(step) if step > last_step => {
+            totp.upsert("last_step", step);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Verifies a TOTP code, which is unsupported without the `auth-totp` feature.
+#[cfg(not(feature = "auth-totp"))]
+fn verify_totp_code(_factors: &mut Map, _totp_code: &str) -> Result<bool, Error> {
+    bail!("the TOTP is unsupported")
+}
+
+/// Verifies a WebAuthn assertion with the `id`, `client_data_json`,
+/// `authenticator_data` and `signature`.
+#[cfg(feature = "auth-webauthn")]
+fn verify_webauthn_assertion(
+    factors: &mut Map,
+    challenge: &str,
+    assertion: &Map,
+) -> Result<bool, Error> {
+    let (Some(id), Some(client_data_json), Some(authenticator_data), Some(signature)) = (
+        assertion.get_str("id"),
+        assertion.get_str("client_data_json"),
+        assertion.get_str("authenticator_data"),
+        assertion.get_str("signature"),
+    ) else {
+        bail!("the WebAuthn assertion is invalid");
+    };
+    let Some(entry) = factors
+        .get_mut("webauthn")
+        .and_then(|v| v.as_array_mut())
+        .and_then(|credentials| {
+            credentials
+                .iter_mut()
+                .filter_map(|v| v.as_object_mut())
+                .find(|credential| credential.get_str("id") == Some(id))
+        })
+    else {
+        return Ok(false);
+    };
+
+    let mut credential = WebAuthnCredential::try_from_map(entry)?;
+    let result = WebAuthnRelyingParty::shared().authenticate(
+        &mut credential,
+        challenge,
+        client_data_json,
+        authenticator_data,
+        signature,
+    );
+    if let Err(err) = result {
+        tracing::warn!(credential_id = id, "{err}");
+        return Ok(false);
+    }
+    entry.append(&mut credential.to_map());
+    Ok(true)
+}
+
+/// Verifies a WebAuthn assertion, which is unsupported without the `auth-webauthn` feature.
+#[cfg(not(feature = "auth-webauthn"))]
+fn verify_webauthn_assertion(
+    _factors: &mut Map,
+    _challenge: &str,
+    _assertion: &Map,
+) -> Result<bool, Error> {
+    bail!("the WebAuthn is unsupported")
+}
+
+/// Number of the recovery codes.
+static NUM_RECOVERY_CODES: LazyLock<usize> = LazyLock::new(|| {
+    State::shared()
+        .get_config("mfa")
+        .and_then(|config| config.get_usize("recovery-codes"))
+        .unwrap_or(10)
+});
+
+/// Max number of the failed attempts before the second factor is locked out.
+static MAX_FAILED_ATTEMPTS: LazyLock<u32> = LazyLock::new(|| {
+    State::shared()
+        .get_config("mfa")
+        .and_then(|config| config.get_u32("max-failed-attempts"))
+        .unwrap_or(5)
+});
+
+/// Lockout duration after too many failed attempts.
+static LOCKOUT_DURATION: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("mfa")
+        .and_then(|config| config.get_duration("lockout-duration"))
+        .unwrap_or_else(|| Duration::from_secs(60 * 15))
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        consume_challenge, enrolled_methods, hash_recovery_code, new_recovery_codes,
+        verify_recovery_code,
+    };
+    use zino_core::{auth::MfaChallenge, extension::JsonObjectExt, Map};
+
+    #[test]
+    fn it_consumes_recovery_codes() {
+        let (recovery_codes, hashed_codes) = new_recovery_codes();
+        assert_eq!(recovery_codes.len(), hashed_codes.len());
+        assert_eq!(
+            hash_recovery_code(&recovery_codes[0].to_ascii_uppercase()),
+            hashed_codes[0]
+        );
+
+        let mut factors = Map::from_entry("recovery_codes", hashed_codes);
+        assert!(enrolled_methods(&factors).is_empty());
+        assert!(!verify_recovery_code(&mut factors, &recovery_codes[0]));
+
+        let mut credential = Map::from_entry("id", "cred");
+        credential.upsert("public_key", "");
+        factors.upsert("webauthn", vec![credential]);
+        if cfg!(feature = "auth-webauthn") {
+            assert!(verify_recovery_code(&mut factors, &recovery_codes[0]));
+            assert!(!verify_recovery_code(&mut factors, &recovery_codes[0]));
+            assert!(verify_recovery_code(&mut factors, &recovery_codes[1]));
+        } else {
+            assert!(!verify_recovery_code(&mut factors, &recovery_codes[0]));
+        }
+    }
+
+    #[test]
+    fn it_consumes_mfa_challenges() {
+        let challenge = MfaChallenge::new("alice");
+        let mut factors = Map::new();
+        assert!(consume_challenge(&mut factors, &challenge).is_ok());
+        assert!(consume_challenge(&mut factors, &challenge).is_err());
+        assert!(consume_challenge(&mut factors, &MfaChallenge::new("alice")).is_ok());
+        assert_eq!(
+            factors
+                .get_array("consumed_challenges")
+                .map(|v| v.len())
+                .unwrap_or_default(),
+            2
+        );
+    }
+
+    #[cfg(feature = "auth-totp")]
+    #[test]
+    fn it_rejects_pending_totp_secrets() {
+        use super::verify_totp_code;
+        use zino_core::{
+            auth::{AccessKeyId, SecretAccessKey},
+            datetime::DateTime,
+        };
+
+        let key_id = AccessKeyId::new();
+        let generator = SecretAccessKey::new(&key_id).generate_totp(None, String::new());
+        let timestamp = u64::try_from(DateTime::now().timestamp()).unwrap();
+        let totp_code = generator.generate(timestamp);
+
+        let mut factors = Map::from_entry("totp", Map::from_entry("key_id", key_id.as_str()));
+        assert!(!verify_totp_code(&mut factors, &totp_code).unwrap());
+        if let Some(totp) = factors.get_mut("totp").and_then(|v| v.as_object_mut()) {
+            totp.upsert("confirmed", true);
+        }
+        assert!(verify_totp_code(&mut factors, &totp_code).unwrap());
+        assert!(!verify_totp_code(&mut factors, &totp_code).unwrap());
+    }
+}
@@ -20,9 +20,11 @@ use zino_derive::{DecodeRow, ModelAccessor, Schema};
 use crate::tag::Tag;
 
 mod jwt_auth;
+mod mfa_auth;
 mod status;
 
 pub use jwt_auth::JwtAuthService;
+pub use mfa_auth::MfaAuthService;
 pub use status::UserStatus;
 
 #[cfg(feature = "visibility")]
@@ -77,7 +79,7 @@ pub struct User {
     #[schema(snapshot, nonempty, unique_items, index_type = "gin")]
     roles: Vec<String>,
     #[cfg(feature = "tags")]
-    #[schema(unique_items, reference = "Tag", index_type = "gin")]
+    #[schema(unique_items, reference = "Tag", joinable, index_type = "gin")]
     tags: Vec<Uuid>, // tag.id, tag.namespace = "*:user"
 
     // Security.
@@ -87,6 +89,9 @@ pub struct User {
     current_login_ip: String,
     login_count: u32,
     failed_login_count: u8,
+    mfa_required: bool,
+    #[schema(write_only)]
+    mfa_factors: Map,
 
     // Extensions.
     content: Map,
@@ -94,7 +99,7 @@ pub struct User {
 
     // Revisions.
     #[cfg(feature = "owner-id")]
-    #[schema(reference = "User")]
+    #[schema(reference = "User", joinable)]
     owner_id: Option<Uuid>, // user.id
     #[cfg(feature = "maintainer-id")]
     #[schema(reference = "User")]
@@ -141,6 +146,12 @@ impl Model for User {
                 Err(err) => validation.record_fail("password", err),
             }
         }
+        if let Some(result) = data.parse_bool("mfa_required") {
+            match result {
+                Ok(mfa_required) => self.mfa_required = mfa_required,
+                Err(err) => validation.record_fail("mfa_required", err),
+            }
+        }
         if let Some(roles) = data.parse_str_array("roles") {
             if let Err(err) = self.set_roles(roles) {
                 validation.record_fail("roles", err);
@@ -237,6 +248,12 @@ impl User {
         self.roles.as_slice()
     }
 
+    /// Returns `true` if the second factor is required for the user.
+    #[inline]
+    pub fn mfa_required(&self) -> bool {
+        self.mfa_required
+    }
+
     /// Returns a session for the user.
     pub fn user_session(&self) -> UserSession<Uuid, String> {
         let mut user_session = UserSession::new(self.id, None);
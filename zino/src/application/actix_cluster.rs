@@ -1,4 +1,4 @@
-use crate::{middleware, ActixResponse, Request, RouterConfigure};
+use crate::{endpoint, middleware, ActixResponse, Request, RouterConfigure};
 use actix_files::{Files, NamedFile};
 use actix_web::{
     dev::{fn_service, ServiceRequest, ServiceResponse},
@@ -11,10 +11,10 @@ use actix_web::{
 use std::{fs, path::PathBuf, time::Duration};
 use utoipa_rapidoc::RapiDoc;
 use zino_core::{
-    application::{Application, ServerTag, StaticRecord},
+    application::{Application, LogShipper, ServerTag, StaticRecord},
     extension::TomlTableExt,
     response::Response,
-    schedule::{AsyncCronJob, Job, JobScheduler},
+    schedule::{AsyncCronJob, DurableScheduler, Job, JobScheduler},
 };
 
 /// An HTTP server cluster for `actix-web`.
@@ -53,8 +53,22 @@ impl Application for ActixCluster {
                 rt::time::sleep(scheduler.time_till_next_job()).await;
             }
         });
+        if let Some(scheduler) = DurableScheduler::shared() {
+            runtime.spawn(async move {
+                loop {
+                    scheduler.tick_async().await;
+                    rt::time::sleep(scheduler.time_till_next_job()).await;
+                }
+            });
+        }
+        runtime.spawn(LogShipper::run_flusher());
 
         runtime.block_on(async {
+            // Apply the pending migrations before binding the listeners.
+            if let Err(err) = Self::migrate_database().await {
+                panic!("fail to apply the database migrations: {err}");
+            }
+
             let default_routes = self.default_routes.leak() as &'static [_];
             let tagged_routes = self.tagged_routes.leak() as &'static [_];
             let app_state = Self::shared_state();
@@ -80,6 +94,7 @@ impl Application for ActixCluster {
                 let default_public_dir = project_dir.join("public");
                 let mut public_route_prefix = "/public";
                 let mut public_dir = PathBuf::new();
+                let mut jwks_route = "/.well-known/jwks.json";
                 let mut backlog = 2048; // Maximum number of pending connections
                 let mut max_connections = 25000; // Maximum number of concurrent connections
                 let mut body_limit = 128 * 1024 * 1024; // 128MB
@@ -96,6 +111,9 @@ impl Application for ActixCluster {
                     if let Some(route_prefix) = config.get_str("public-route-prefix") {
                         public_route_prefix = route_prefix;
                     }
+                    if let Some(path) = config.get_str("jwks-route") {
+                        jwks_route = path;
+                    }
                     if let Some(value) = config.get_u32("backlog") {
                         backlog = value;
                     }
@@ -130,6 +148,7 @@ impl Application for ActixCluster {
                     let mut app = App::new()
                         .route("/", index_file_handler)
                         .route("/favicon.ico", favicon_file_handler)
+                        .route(jwks_route, web::get().to(endpoint::jwks_handler))
                         .service(static_files)
                         .default_service(web::to(|req: Request| async {
                             let res = Response::new(StatusCode::NOT_FOUND);
@@ -187,10 +206,11 @@ impl Application for ActixCluster {
                         .app_data(JsonConfig::default().limit(body_limit))
                         .app_data(PayloadConfig::default().limit(body_limit))
                         .wrap(Compress::default())
-                        .wrap(middleware::RequestContextInitializer::default())
+                        .wrap(middleware::RateLimitInitializer)
+                        .wrap(middleware::RequestContextInitializer)
                         .wrap(middleware::tracing_middleware())
                         .wrap(middleware::cors_middleware())
-                        .wrap(middleware::ETagFinalizer::default())
+                        .wrap(middleware::ETagFinalizer)
                 })
                 .server_hostname(app_domain)
                 .backlog(backlog)
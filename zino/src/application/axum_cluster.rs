@@ -24,10 +24,10 @@ use tower_http::{
 };
 use utoipa_rapidoc::RapiDoc;
 use zino_core::{
-    application::{Application, ServerTag, StaticRecord},
+    application::{Application, LogShipper, ServerTag, StaticRecord},
     extension::TomlTableExt,
     response::{FullResponse, Response},
-    schedule::{AsyncCronJob, Job, JobScheduler},
+    schedule::{AsyncCronJob, DurableScheduler, Job, JobScheduler},
 };
 
 /// An HTTP server cluster for `axum`.
@@ -72,8 +72,22 @@ impl Application for AxumCluster {
                 tokio::time::sleep(scheduler.time_till_next_job()).await;
             }
         });
+        if let Some(scheduler) = DurableScheduler::shared() {
+            runtime.spawn(async move {
+                loop {
+                    scheduler.tick_async().await;
+                    tokio::time::sleep(scheduler.time_till_next_job()).await;
+                }
+            });
+        }
+        runtime.spawn(LogShipper::run_flusher());
 
         runtime.block_on(async {
+            // Apply the pending migrations before binding the listeners.
+            if let Err(err) = Self::migrate_database().await {
+                panic!("fail to apply the database migrations: {err}");
+            }
+
             let default_routes = self.default_routes;
             let tagged_routes = self.tagged_routes;
             let app_state = Self::shared_state();
@@ -100,6 +114,7 @@ impl Application for AxumCluster {
                 let mut public_dir = PathBuf::new();
                 let mut sse_route = None;
                 let mut websocket_route = None;
+                let mut jwks_route = "/.well-known/jwks.json";
                 let mut body_limit = 128 * 1024 * 1024; // 128MB
                 let mut request_timeout = Duration::from_secs(30); // 30 seconds
                 if let Some(config) = app_state.get_config("server") {
@@ -120,6 +135,9 @@ impl Application for AxumCluster {
                     if let Some(path) = config.get_str("websocket-route") {
                         websocket_route = Some(path);
                     }
+                    if let Some(path) = config.get_str("jwks-route") {
+                        jwks_route = path;
+                    }
                     if let Some(limit) = config.get_usize("body-limit") {
                         body_limit = limit;
                     }
@@ -161,6 +179,7 @@ impl Application for AxumCluster {
                 if let Some(path) = websocket_route {
                     app = app.route(path, routing::get(endpoint::websocket_handler));
                 }
+                app = app.route(jwks_route, routing::get(endpoint::jwks_handler));
                 for route in &default_routes {
                     app = app.merge(route.clone());
                 }
@@ -226,6 +245,7 @@ impl Application for AxumCluster {
                             .layer(LazyLock::force(&middleware::TRACING_MIDDLEWARE))
                             .layer(LazyLock::force(&middleware::CORS_MIDDLEWARE))
                             .layer(from_fn(middleware::request_context))
+                            .layer(from_fn(middleware::limit_rate))
                             .layer(from_fn(middleware::extract_etag))
                             .layer(HandleErrorLayer::new(|err: BoxError| async move {
                                 let status_code = if err.is::<Elapsed>() {
@@ -245,8 +265,8 @@ impl Application for AxumCluster {
                     .with_graceful_shutdown(Self::shutdown())
             });
             if let Some(config) = app_state.get_config("database") {
-                if let Some(check)=config.get_bool("check"){
-                    if check{
+                if let Some(check) = config.get_bool("check") {
+                    if check {
                         Self::check_database();
                     }
                 }
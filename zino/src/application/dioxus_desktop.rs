@@ -8,9 +8,9 @@ use image::{error::ImageError, io::Reader};
 use std::{fmt::Display, fs, marker::PhantomData, str::FromStr, time::Duration};
 use tokio::runtime::Builder;
 use zino_core::{
-    application::{Application, ServerTag, StaticRecord},
+    application::{Application, LogShipper, ServerTag, StaticRecord},
     extension::TomlTableExt,
-    schedule::{AsyncCronJob, Job, JobScheduler},
+    schedule::{AsyncCronJob, DurableScheduler, Job, JobScheduler},
     Map,
 };
 
@@ -67,6 +67,15 @@ where
                 tokio::time::sleep(scheduler.time_till_next_job()).await;
             }
         });
+        if let Some(scheduler) = DurableScheduler::shared() {
+            runtime.spawn(async move {
+                loop {
+                    scheduler.tick_async().await;
+                    tokio::time::sleep(scheduler.time_till_next_job()).await;
+                }
+            });
+        }
+        runtime.spawn(LogShipper::run_flusher());
 
         let app_env = Self::env();
         let app_name = Self::name();
@@ -0,0 +1,141 @@
+/// Controller for the OAuth2 endpoints of an `AuthorizationServer`.
+pub trait AuthorizationController {
+    /// The request extractor.
+    type Request;
+    /// The response result.
+    type Result;
+
+    /// Issues an authorization code for the user who has been authenticated
+    /// by a bearer access token.
+    async fn authorize(req: Self::Request) -> Self::Result;
+
+    /// Issues the tokens for an authorization grant.
+    async fn token(req: Self::Request) -> Self::Result;
+
+    /// Introspects a token.
+    async fn introspect(req: Self::Request) -> Self::Result;
+
+    /// Revokes a token.
+    async fn revoke(req: Self::Request) -> Self::Result;
+}
+
+#[cfg(any(feature = "actix", feature = "axum"))]
+use zino_core::{
+    auth::{AuthorizationServer, JwtClaims, JwtKeySet},
+    error::Error,
+    extension::JsonObjectExt,
+    request::RequestContext,
+    response::{ExtractRejection, Rejection, StatusCode},
+    JsonValue, Map,
+};
+
+#[cfg(any(feature = "actix", feature = "axum"))]
+impl<S: AuthorizationServer> AuthorizationController for S {
+    type Request = crate::Request;
+    type Result = crate::Result;
+
+    async fn authorize(req: Self::Request) -> Self::Result {
+        let claims = req.parse_jwt_claims::<Map, _>(JwtKeySet::shared())?;
+        if claims.data().contains_key("client_id") {
+            let message = "403 Forbidden: the token issued for a client can not be used";
+            return Err(Rejection::with_message(message).context(&req).into());
+        }
+        if JwtClaims::has_token_store()
+            && let Some(family_id) = claims.token_family_id()
+            && !JwtClaims::token_store()
+                .is_active(family_id)
+                .await
+                .extract(&req)?
+        {
+            let message = "401 Unauthorized: the token has been revoked";
+            return Err(Rejection::with_message(message).context(&req).into());
+        }
+
+        let Some(subject) = claims.subject() else {
+            let message = "401 Unauthorized: the subject of the token should be specified";
+            return Err(Rejection::with_message(message).context(&req).into());
+        };
+        let params = req.parse_query::<Map>()?;
+        let result = <S as AuthorizationServer>::authorize(subject, &params).await;
+        Ok(oauth2_response(&req, result.map(JsonValue::from)).into())
+    }
+
+    async fn token(mut req: Self::Request) -> Self::Result {
+        let params = parse_oauth2_params(&mut req).await?;
+        let result = Self::grant_token(&params).await;
+        let mut res = oauth2_response(&req, result.map(JsonValue::from));
+        res.insert_header("cache-control", "no-store");
+        Ok(res.into())
+    }
+
+    async fn introspect(mut req: Self::Request) -> Self::Result {
+        let params = parse_oauth2_params(&mut req).await?;
+        let result = Self::introspect_token(&params).await;
+        Ok(oauth2_response(&req, result.map(JsonValue::from)).into())
+    }
+
+    async fn revoke(mut req: Self::Request) -> Self::Result {
+        let params = parse_oauth2_params(&mut req).await?;
+        let result = Self::revoke_token(&params).await;
+        Ok(oauth2_response(&req, result.map(|_| Map::new().into())).into())
+    }
+}
+
+/// Parses the OAuth2 parameters from the request body,
+/// with the client credentials in the `authorization` header.
+#[cfg(any(feature = "actix", feature = "axum"))]
+async fn parse_oauth2_params(req: &mut crate::Request) -> Result<Map, Rejection> {
+    let mut params = req.parse_body::<Map>().await?;
+    if let Some((client_id, client_secret)) = req.parse_basic_credentials() {
+        params.upsert("client_id", client_id);
+        params.upsert("client_secret", client_secret);
+    }
+    Ok(params)
+}
+
+/// Creates the OAuth2 response with the error response defined in RFC 6749.
+#[cfg(any(feature = "actix", feature = "axum"))]
+fn oauth2_response(req: &crate::Request, result: Result<JsonValue, Error>) -> crate::Response {
+    let err = match result {
+        Ok(data) => {
+            let mut res = crate::Response::default().context(req);
+            res.set_json_response(data);
+            return res;
+        }
+        Err(err) => err,
+    };
+
+    let message = err.message();
+    let (status_code, error, description) = match message.split_once(": ") {
+        Some(("invalid_client", description)) => {
+            (StatusCode::UNAUTHORIZED, "invalid_client", description)
+        }
+        Some((
+            error @ ("invalid_request"
+            | "invalid_grant"
+            | "invalid_scope"
+            | "unauthorized_client"
+            | "unsupported_grant_type"
+            | "unsupported_response_type"),
+            description,
+        )) => (StatusCode::BAD_REQUEST, error, description),
+        _ => {
+            tracing::error!("{message}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                "the authorization server encountered an unexpected error",
+            )
+        }
+    };
+    let mut data = Map::from_entry("error", error);
+    data.upsert("error_description", description);
+
+    let mut res = crate::Response::new(status_code).context(req);
+    res.set_content_type("application/json; charset=utf-8");
+    res.set_json_response(data);
+    if status_code == StatusCode::UNAUTHORIZED {
+        res.insert_header("www-authenticate", "Basic");
+    }
+    res
+}
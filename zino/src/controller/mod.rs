@@ -1,3 +1,7 @@
+mod authorization;
+
+pub use authorization::AuthorizationController;
+
 /// Default controller for the `Model`.
 pub trait DefaultController<K, U = K> {
     /// The request extractor.
@@ -12,12 +16,14 @@ pub trait DefaultController<K, U = K> {
     async fn delete(req: Self::Request) -> Self::Result;
 
     /// Updates a model.
+    /// The `if-match` header is checked against the ETag of the model view if present.
     async fn update(req: Self::Request) -> Self::Result;
 
     /// Views a model.
     async fn view(req: Self::Request) -> Self::Result;
 
     /// Lists models.
+    /// The keyset cursors are returned if a cursor or the `keyset` flag is specified.
     async fn list(req: Self::Request) -> Self::Result;
 
     /// Logically deletes a model.
@@ -36,8 +42,13 @@ pub trait DefaultController<K, U = K> {
     async fn import(req: Self::Request) -> Self::Result;
 
     /// Exports model data.
+    /// The rows are fetched and written as a streaming body, and the hooks
+    /// `after_decode` and `before_respond` are called for each row.
     async fn export(req: Self::Request) -> Self::Result;
 
+    /// Aggregates model data with the group fields and the aggregations.
+    async fn aggregate(req: Self::Request) -> Self::Result;
+
     /// Gets the tree hierarchy data.
     async fn tree(req: Self::Request) -> Self::Result;
 
@@ -48,12 +59,17 @@ pub trait DefaultController<K, U = K> {
     async fn definition(req: Self::Request) -> Self::Result;
 }
 
+#[cfg(any(feature = "actix", feature = "axum"))]
+#[cfg(feature = "orm")]
+use futures::TryStreamExt;
+
 #[cfg(any(feature = "actix", feature = "axum"))]
 #[cfg(feature = "orm")]
 use zino_core::{
     extension::JsonObjectExt,
     model::{ModelHooks, Mutation, Query},
     orm::{ModelAccessor, ModelHelper},
+    reject,
     request::RequestContext,
     response::{ExtractRejection, Rejection, StatusCode},
     JsonValue, Map,
@@ -63,6 +79,12 @@ use zino_core::{
 #[cfg(feature = "orm")]
 impl<K, U, M: ModelAccessor<K, U>> DefaultController<K, U> for M
 where
+    M: ModelHooks<
+        after_decode(..): Send,
+        before_respond(..): Send,
+        after_scan(..): Send,
+        after_query(..): Send,
+    >,
     K: Default + std::fmt::Display + PartialEq + std::str::FromStr,
     U: Default + std::fmt::Display + PartialEq,
     <K as std::str::FromStr>::Err: std::error::Error,
@@ -110,7 +132,14 @@ where
 
     async fn update(mut req: Self::Request) -> Self::Result {
         let id = req.parse_param::<K>("id")?;
-        let mut body = req.parse_body().await?;
+        let mut body = req.parse_body::<Map>().await?;
+        if req.get_header("if-match").is_some() {
+            let model = Self::fetch_by_id(&id).await.extract(&req)?;
+            if let Some(version) = model.get_u64("version") {
+                body.entry("version").or_insert_with(|| version.into());
+            }
+            req.check_if_match(&Map::data_entry(model))?;
+        }
 
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
         let (validation, model) = Self::update_by_id(&id, &mut body, extension)
@@ -140,6 +169,36 @@ where
             .extract(&req)?;
 
         let mut res = req.query_validation(&mut query)?;
+        let cursor_enabled = query.cursor_enabled();
+        if cursor_enabled {
+            let primary_key_name = Self::PRIMARY_KEY_NAME;
+            if !query
+                .sort_order()
+                .iter()
+                .any(|(field, _)| field == primary_key_name)
+            {
+                let descending = query
+                    .sort_order()
+                    .last()
+                    .is_some_and(|&(_, descending)| descending);
+                query.set_sort_order(primary_key_name, descending);
+            }
+
+            let mut validation = query.validate_cursor(Self::fields());
+            let fields = query.fields();
+            if !fields.is_empty() {
+                for (field, _) in query.sort_order() {
+                    if !fields.iter().any(|f| f == field) {
+                        let message = format!("the sort field `{field}` should be selected");
+                        validation.record("fields", message);
+                    }
+                }
+            }
+            if !validation.is_success() {
+                return Err(Rejection::bad_request(validation).context(&req).into());
+            }
+        }
+
         let models = if query.populate_enabled() {
             let mut models = Self::fetch(&query).await.extract(&req)?;
             for model in models.iter_mut() {
@@ -161,11 +220,28 @@ where
             models
         };
 
+        let (next_cursor, prev_cursor) = if cursor_enabled {
+            (query.next_cursor(&models), query.prev_cursor(&models))
+        } else {
+            (None, None)
+        };
         let mut data = Map::data_entries(models);
         if req.get_query("page_size").is_some() && req.get_query("total_rows").is_none() {
-            let total_rows = Self::count(&query).await.extract(&req)?;
+            let total_rows = if query.cursor().is_some() {
+                let mut query = query.clone();
+                query.set_cursor(None, false);
+                Self::count(&query).await.extract(&req)?
+            } else {
+                Self::count(&query).await.extract(&req)?
+            };
             data.upsert("total_rows", total_rows);
         }
+        if let Some(next_cursor) = next_cursor {
+            data.upsert("next_cursor", next_cursor);
+        }
+        if let Some(prev_cursor) = prev_cursor {
+            data.upsert("prev_cursor", prev_cursor);
+        }
         res.set_json_data(data);
         Ok(res.into())
     }
@@ -319,26 +395,52 @@ where
             .extract(&req)?;
 
         let mut res = req.query_validation(&mut query)?;
-        let mut models = Self::find(&query).await.extract(&req)?;
         let translate_enabled = query.translate_enabled();
-        for model in models.iter_mut() {
-            Self::after_decode(model).await.extract(&req)?;
-            translate_enabled.then(|| Self::translate_model(model));
-            Self::before_respond(model, extension.as_ref())
-                .await
-                .extract(&req)?;
-        }
+        let models = Self::stream::<Map>(&query)
+            .await
+            .extract(&req)?
+            .and_then(move |mut model| {
+                let extension = extension.clone();
+                async move {
+                    Self::after_decode(&mut model).await?;
+                    translate_enabled.then(|| Self::translate_model(&mut model));
+                    Self::before_respond(&mut model, extension.as_ref()).await?;
+                    Ok(model)
+                }
+            });
 
         let format = req.get_query("format").unwrap_or("json");
         match format {
-            "csv" => res.set_csv_response(models),
-            "jsonlines" => res.set_jsonlines_response(models),
-            "msgpack" => res.set_msgpack_response(models),
-            _ => res.set_json_response(models),
+            "csv" => res.set_csv_stream_response(models),
+            "jsonlines" => res.set_jsonlines_stream_response(models),
+            "msgpack" => res.set_msgpack_stream_response(models),
+            _ => res.set_json_stream_response(models),
         }
         Ok(res.into())
     }
 
+    async fn aggregate(req: Self::Request) -> Self::Result {
+        let mut query = Self::default_query();
+        query.set_limit(0);
+        let extension = req.get_data::<<Self as ModelHooks>::Extension>();
+        Self::before_list(&mut query, extension.as_ref())
+            .await
+            .extract(&req)?;
+
+        let mut res = req.query_validation(&mut query)?;
+        let validation = query.validate_aggregations(Self::columns());
+        if !validation.is_success() {
+            reject!(req, validation);
+        }
+
+        let records = Self::aggregate::<Map>(&query).await.extract(&req)?;
+        let num_records = records.len();
+        let mut data = Map::data_entries(records);
+        data.upsert("num_records", num_records);
+        res.set_json_data(data);
+        Ok(res.into())
+    }
+
     async fn tree(req: Self::Request) -> Self::Result {
         let mut query = Self::default_list_query();
         let extension = req.get_data::<<Self as ModelHooks>::Extension>();
@@ -379,9 +481,10 @@ where
         let total_rows = children.len();
         for model in models.iter_mut() {
             let model_id = model.get(primary_key_name);
-            let model_children = children
-                .extract_if(|child| child.get("parent_id") == model_id)
-                .collect::<Vec<_>>();
+            let (model_children, other_children) = std::mem::take(&mut children)
+                .into_iter()
+                .partition::<Vec<_>, _>(|child| child.get("parent_id") == model_id);
+            children = other_children;
             model.upsert("children", model_children);
         }
 
@@ -406,10 +509,10 @@ where
         if matches!(action, "insert" | "import") {
             let required_fields = columns
                 .iter()
-                .filter_map(|col| {
-                    (col.is_not_null() && !col.is_primary_key() || col.has_attribute("nonempty"))
-                        .then(|| col.name())
+                .filter(|col| {
+                    col.is_not_null() && !col.is_primary_key() || col.has_attribute("nonempty")
                 })
+                .map(|col| col.name())
                 .collect::<Vec<_>>();
             definition.upsert("required", required_fields);
         }
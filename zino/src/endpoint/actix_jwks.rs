@@ -0,0 +1,10 @@
+use actix_web::HttpResponse;
+use zino_core::{auth::JwtKeySet, JsonValue};
+
+/// JWKS endpoint handler.
+pub(crate) async fn jwks_handler() -> HttpResponse {
+    let jwks = JsonValue::from(JwtKeySet::shared().to_jwks());
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(jwks.to_string())
+}
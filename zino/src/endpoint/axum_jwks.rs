@@ -0,0 +1,12 @@
+use axum::{
+    http::header::{self, HeaderValue},
+    response::IntoResponse,
+};
+use zino_core::{auth::JwtKeySet, JsonValue};
+
+/// JWKS endpoint handler.
+pub(crate) async fn jwks_handler() -> impl IntoResponse {
+    let jwks = JsonValue::from(JwtKeySet::shared().to_jwks());
+    let content_type = HeaderValue::from_static("application/json");
+    ([(header::CONTENT_TYPE, content_type)], jwks.to_string())
+}
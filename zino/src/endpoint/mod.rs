@@ -1,8 +1,14 @@
 cfg_if::cfg_if! {
-    if #[cfg(feature = "axum")] {
+    if #[cfg(feature = "actix")] {
+        mod actix_jwks;
+
+        pub(crate) use self::actix_jwks::jwks_handler;
+    } else if #[cfg(feature = "axum")] {
+        mod axum_jwks;
         mod axum_sse;
         mod axum_websocket;
 
+        pub(crate) use self::axum_jwks::jwks_handler;
         pub(crate) use self::axum_sse::sse_handler;
         pub(crate) use self::axum_websocket::websocket_handler;
     }
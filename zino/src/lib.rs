@@ -2,6 +2,7 @@
 #![doc(html_favicon_url = "https://photino.github.io/zino-docs-zh/assets/zino-logo.png")]
 #![doc(html_logo_url = "https://photino.github.io/zino-docs-zh/assets/zino-logo.svg")]
 #![allow(async_fn_in_trait)]
+#![allow(clippy::result_large_err)]
 #![allow(stable_features)]
 #![forbid(unsafe_code)]
 #![feature(async_fn_in_trait)]
@@ -9,6 +10,7 @@
 #![feature(extract_if)]
 #![feature(lazy_cell)]
 #![feature(let_chains)]
+#![feature(return_type_notation)]
 
 mod application;
 mod channel;
@@ -20,7 +22,7 @@ mod response;
 
 pub mod prelude;
 
-pub use controller::DefaultController;
+pub use controller::{AuthorizationController, DefaultController};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "actix")] {
@@ -30,6 +32,8 @@ cfg_if::cfg_if! {
         use request::actix_request::ActixExtractor;
         use response::actix_response::{ActixRejection, ActixResponse};
 
+        pub use middleware::{PolicyEnforcer, ResponseCacheInitializer};
+
         /// HTTP server cluster for `actix-web`.
         pub type Cluster = ActixCluster;
 
@@ -52,6 +56,7 @@ cfg_if::cfg_if! {
         use response::axum_response::{AxumRejection, AxumResponse};
 
         pub use channel::axum_channel::MessageChannel;
+        pub use middleware::{cache_response, enforce_policies};
 
         /// HTTP server cluster for `axum`.
         pub type Cluster = AxumCluster;
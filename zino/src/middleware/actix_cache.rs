@@ -0,0 +1,173 @@
+use crate::Request;
+use actix_web::{
+    body::{self, BodySize, EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderName, HeaderValue, VARY},
+        StatusCode,
+    },
+    Error, HttpResponse,
+};
+use std::{
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+};
+use zino_core::{
+    auth::UserSession,
+    request::RequestContext,
+    response::{CacheDirective, CachedResponse, ResponseCache},
+};
+
+/// A middleware which caches the responses with the rules of the shared response cache.
+/// It should be applied after the user session is set, so that the responses of
+/// the authenticated requests are cached for each subject and tenant.
+pub struct ResponseCacheInitializer<U, T = U>(PhantomData<(U, T)>);
+
+impl<U, T> Default for ResponseCacheInitializer<U, T> {
+    #[inline]
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S, B, U, T> Transform<S, ServiceRequest> for ResponseCacheInitializer<U, T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    U: Clone + Send + Sync + ToString + 'static,
+    T: Clone + Send + Sync + ToString + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ResponseCacheMiddleware<S, U, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ResponseCacheMiddleware {
+            service: Rc::new(service),
+            phantom: PhantomData,
+        }))
+    }
+}
+
+pub struct ResponseCacheMiddleware<S, U, T> {
+    service: Rc<S>,
+    phantom: PhantomData<(U, T)>,
+}
+
+impl<S, B, U, T> Service<ServiceRequest> for ResponseCacheMiddleware<S, U, T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    U: Clone + Send + Sync + ToString + 'static,
+    T: Clone + Send + Sync + ToString + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        Box::pin(async move {
+            let Some(store) = ResponseCache::store() else {
+                let res = service.call(req).await?;
+                return Ok(res.map_into_left_body());
+            };
+            let req = Request::from(req);
+            let session = req.get_data::<UserSession<U, String, T>>().map(|session| {
+                let user_id = session.user_id().to_string();
+                let tenant_id = session.tenant_id().map(|t| t.to_string());
+                (user_id, tenant_id)
+            });
+            let session = session
+                .as_ref()
+                .map(|(user_id, tenant_id)| (user_id.as_str(), tenant_id.as_deref()));
+            let response_cache = ResponseCache::shared();
+            let Some((key, rule)) = response_cache.resolve(&req, session) else {
+                let res = service.call(ServiceRequest::from(req)).await?;
+                return Ok(res.map_into_left_body());
+            };
+            let directive = CacheDirective::parse(req.get_header("cache-control"));
+            if directive == CacheDirective::NoStore {
+                let res = service.call(ServiceRequest::from(req)).await?;
+                return Ok(res.map_into_left_body());
+            }
+            if directive == CacheDirective::Default {
+                match store.get(&key).await {
+                    Ok(Some(cached_response)) => {
+                        let status_code =
+                            StatusCode::from_u16(cached_response.status_code()).unwrap_or_default();
+                        let mut res =
+                            HttpResponse::with_body(status_code, cached_response.body().clone());
+                        for (key, value) in cached_response.headers_with_context(&req) {
+                            if let Ok(header_name) = HeaderName::try_from(key)
+                                && let Ok(header_value) = HeaderValue::try_from(value)
+                            {
+                                res.headers_mut().append(header_name, header_value);
+                            }
+                        }
+
+                        let (http_req, _) = ServiceRequest::from(req).into_parts();
+                        let res = ServiceResponse::new(http_req, res);
+                        return Ok(res.map_into_boxed_body().map_into_right_body());
+                    }
+                    Ok(None) => (),
+                    Err(err) => tracing::error!("fail to get the cached response: {err}"),
+                }
+            }
+
+            let mut res = service.call(ServiceRequest::from(req)).await?;
+            if let Some(vary) = rule.vary_header()
+                && let Ok(header_value) = HeaderValue::try_from(vary)
+            {
+                res.headers_mut().insert(VARY, header_value);
+            }
+
+            let headers = res
+                .headers()
+                .iter()
+                .filter_map(|(key, value)| Some((key.as_str(), value.to_str().ok()?)));
+            let body_size = match res.response().body().size() {
+                BodySize::Sized(size) => Some(size),
+                _ => None,
+            };
+            if !ResponseCache::is_cacheable(res.status().as_u16(), headers)
+                || !body_size.is_some_and(|size| size <= response_cache.max_body_size() as u64)
+            {
+                return Ok(res.map_into_left_body());
+            }
+
+            let (http_req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let bytes = match body::to_bytes(body).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    let err: Box<dyn std::error::Error> = err.into();
+                    tracing::error!("fail to read the response body: {err}");
+                    let res = ServiceResponse::new(http_req, res.set_body(()));
+                    return Ok(res.map_into_boxed_body().map_into_right_body());
+                }
+            };
+            let headers = res
+                .headers()
+                .iter()
+                .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_owned())))
+                .collect();
+            let cached_response =
+                CachedResponse::new(res.status().as_u16(), headers, bytes.clone());
+            if let Err(err) = store.put(&key, cached_response, rule.ttl()).await {
+                tracing::error!("fail to put the response into the cache: {err}");
+            }
+
+            let res = ServiceResponse::new(http_req, res.set_body(bytes));
+            Ok(res.map_into_boxed_body().map_into_right_body())
+        })
+    }
+}
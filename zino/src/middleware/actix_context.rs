@@ -44,14 +44,25 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let req = crate::Request::from(req);
-        let new_context = req.get_context().is_none().then(|| req.new_context());
+        let current_context = req.get_context();
+        let new_context = current_context.is_none().then(|| req.new_context());
+        #[cfg(feature = "orm")]
+        let audit_context = current_context
+            .as_ref()
+            .or(new_context.as_ref())
+            .map(zino_core::orm::AuditContext::new)
+            .unwrap_or_default();
+        #[cfg(feature = "orm")]
+        let tenant_context = zino_core::orm::TenantContext::from_request(&req);
 
         let req = ServiceRequest::from(req);
         if let Some(ctx) = new_context {
             req.extensions_mut().insert(ctx);
         }
 
-        let fut = self.service.call(req.into());
+        let fut = self.service.call(req);
+        #[cfg(feature = "orm")]
+        let fut = tenant_context.scope(audit_context.scope(fut));
         Box::pin(async move {
             let res = fut.await?;
             Ok(res)
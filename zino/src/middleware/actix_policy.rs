@@ -0,0 +1,92 @@
+use crate::{response::actix_response::ActixRejection, Request};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error as ActixError,
+};
+use std::{
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    pin::Pin,
+};
+use zino_core::{
+    auth::{PolicyEngine, UserSession},
+    error::Error,
+    request::RequestContext,
+    response::Rejection,
+    warn,
+};
+
+/// A middleware which enforces the shared policy engine for the user session
+/// stored in the request data. It should be applied after the user session is set.
+pub struct PolicyEnforcer<U, T = U>(PhantomData<(U, T)>);
+
+impl<U, T> Default for PolicyEnforcer<U, T> {
+    #[inline]
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S, B: 'static, U, T> Transform<S, ServiceRequest> for PolicyEnforcer<U, T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    U: Clone + Send + Sync + 'static,
+    T: Clone + Send + Sync + ToString + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type InitError = ();
+    type Transform = PolicyMiddleware<S, U, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(PolicyMiddleware {
+            service,
+            phantom: PhantomData,
+        }))
+    }
+}
+
+pub struct PolicyMiddleware<S, U, T> {
+    service: S,
+    phantom: PhantomData<(U, T)>,
+}
+
+impl<S, B: 'static, U, T> Service<ServiceRequest> for PolicyMiddleware<S, U, T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    U: Clone + Send + Sync + 'static,
+    T: Clone + Send + Sync + ToString + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let req = Request::from(req);
+        let rejection = if let Some(session) = req.get_data::<UserSession<U, String, T>>() {
+            let action = req.request_method().as_str();
+            let resource = req.request_path();
+            if PolicyEngine::shared().is_allowed(&session, action, resource) {
+                None
+            } else {
+                let err = warn!("the access to `{resource}` is denied");
+                Some(Rejection::forbidden(err))
+            }
+        } else {
+            let err = warn!("the user session is missing");
+            Some(Rejection::unauthorized(err))
+        };
+        if let Some(rejection) = rejection {
+            let err = ActixError::from(ActixRejection::from(rejection.context(&req)));
+            return Box::pin(ready(Err(err)));
+        }
+
+        let fut = self.service.call(ServiceRequest::from(req));
+        Box::pin(fut)
+    }
+}
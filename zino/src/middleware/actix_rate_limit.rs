@@ -0,0 +1,89 @@
+use crate::{response::actix_response::ActixRejection, Request};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error as ActixError,
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+use zino_core::{
+    error::Error,
+    request::{RateLimiter, RequestContext},
+    response::{Rejection, Response},
+    warn,
+};
+
+#[derive(Default)]
+pub struct RateLimitInitializer;
+
+impl<S, B: 'static> Transform<S, ServiceRequest> for RateLimitInitializer
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B: 'static> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let req = Request::from(req);
+        let Some((key, quota)) = RateLimiter::shared().resolve(&req) else {
+            return Box::pin(self.service.call(ServiceRequest::from(req)));
+        };
+
+        let service = self.service.clone();
+        Box::pin(async move {
+            let status = match RateLimiter::store().acquire(&key, quota).await {
+                Ok(status) => status,
+                Err(err) => {
+                    tracing::error!("fail to acquire a permit for the rate limit: {err}");
+                    return service.call(ServiceRequest::from(req)).await;
+                }
+            };
+            if !status.is_allowed() {
+                let err = warn!("the rate limit of `{}` is exceeded", req.request_path());
+                let mut res = Response::from(Rejection::too_many_requests(err).context(&req));
+                for (key, value) in status.headers() {
+                    res.insert_header(key, value);
+                }
+                return Err(ActixError::from(ActixRejection::from(res)));
+            }
+
+            let mut res = service.call(ServiceRequest::from(req)).await?;
+            for (key, value) in status.headers() {
+                if let Ok(header_value) = HeaderValue::try_from(value) {
+                    res.headers_mut()
+                        .insert(HeaderName::from_static(key), header_value);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
@@ -41,11 +41,12 @@ impl RootSpanBuilder for CustomRootSpanBuilder {
         let tracestate = headers.get("tracestate").and_then(|v| v.to_str().ok());
         let trace_context = traceparent.and_then(TraceContext::from_traceparent);
         let parent_id = trace_context
+            .as_ref()
             .and_then(|ctx| ctx.parent_id())
             .map(|parent_id| format!("{parent_id:x}"));
         let session_id = headers.get("session-id").and_then(|v| v.to_str().ok());
 
-        if method.is_safe() {
+        let span = if method.is_safe() {
             tracing::info_span!(
                 "HTTP request",
                 "otel.kind" = "server",
@@ -67,6 +68,7 @@ impl RootSpanBuilder for CustomRootSpanBuilder {
                 "server.port" = port,
                 "user_agent.original" = user_agent,
                 "context.session_id" = session_id,
+                "context.user_id" = Empty,
                 "context.trace_id" = Empty,
                 "context.request_id" = Empty,
                 "context.span_id" = Empty,
@@ -94,12 +96,17 @@ impl RootSpanBuilder for CustomRootSpanBuilder {
                 "server.port" = port,
                 "user_agent.original" = user_agent,
                 "context.session_id" = session_id,
+                "context.user_id" = Empty,
                 "context.trace_id" = Empty,
                 "context.request_id" = Empty,
                 "context.span_id" = Empty,
                 "context.parent_id" = parent_id,
             )
+        };
+        if let Some(trace_context) = trace_context {
+            trace_context.set_remote_parent(&span);
         }
+        span
     }
 
     fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
@@ -0,0 +1,101 @@
+use crate::request::axum_request::to_bytes;
+use axum::{
+    body::{self, Body, Full, HttpBody},
+    http::{
+        self,
+        header::{HeaderName, HeaderValue, VARY},
+        StatusCode,
+    },
+    middleware::Next,
+    response::Response,
+};
+use zino_core::{
+    auth::UserSession,
+    request::RequestContext,
+    response::{CacheDirective, CachedResponse, ResponseCache},
+};
+
+/// A middleware which caches the responses with the rules of the shared response cache.
+/// It should be applied after the user session is set, so that the responses of
+/// the authenticated requests are cached for each subject and tenant.
+pub async fn cache_response<U, T>(req: crate::Request, next: Next<Body>) -> Response
+where
+    U: Clone + Send + Sync + ToString + 'static,
+    T: Clone + Send + Sync + ToString + 'static,
+{
+    let Some(store) = ResponseCache::store() else {
+        return next.run(http::Request::from(req)).await;
+    };
+    let session = req.get_data::<UserSession<U, String, T>>().map(|session| {
+        let user_id = session.user_id().to_string();
+        let tenant_id = session.tenant_id().map(|t| t.to_string());
+        (user_id, tenant_id)
+    });
+    let session = session
+        .as_ref()
+        .map(|(user_id, tenant_id)| (user_id.as_str(), tenant_id.as_deref()));
+    let response_cache = ResponseCache::shared();
+    let Some((key, rule)) = response_cache.resolve(&req, session) else {
+        return next.run(http::Request::from(req)).await;
+    };
+    let directive = CacheDirective::parse(req.get_header("cache-control"));
+    if directive == CacheDirective::NoStore {
+        return next.run(http::Request::from(req)).await;
+    }
+    if directive == CacheDirective::Default {
+        match store.get(&key).await {
+            Ok(Some(cached_response)) => {
+                let mut res = Response::new(body::boxed(Full::new(cached_response.body().clone())));
+                *res.status_mut() =
+                    StatusCode::from_u16(cached_response.status_code()).unwrap_or_default();
+                for (key, value) in cached_response.headers_with_context(&req) {
+                    if let Ok(header_name) = HeaderName::try_from(key)
+                        && let Ok(header_value) = HeaderValue::try_from(value)
+                    {
+                        res.headers_mut().append(header_name, header_value);
+                    }
+                }
+                return res;
+            }
+            Ok(None) => (),
+            Err(err) => tracing::error!("fail to get the cached response: {err}"),
+        }
+    }
+
+    let mut res = next.run(http::Request::from(req)).await;
+    if let Some(vary) = rule.vary_header()
+        && let Ok(header_value) = HeaderValue::try_from(vary)
+    {
+        res.headers_mut().insert(VARY, header_value);
+    }
+
+    let headers = res
+        .headers()
+        .iter()
+        .filter_map(|(key, value)| Some((key.as_str(), value.to_str().ok()?)));
+    let body_size = res.body().size_hint().exact();
+    if !ResponseCache::is_cacheable(res.status().as_u16(), headers)
+        || !body_size.is_some_and(|size| size <= response_cache.max_body_size() as u64)
+    {
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let bytes = match to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::error!("fail to read the response body: {err}");
+            return Response::from_parts(parts, body::boxed(Full::default()));
+        }
+    };
+    let headers = parts
+        .headers
+        .iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_owned())))
+        .collect();
+    let cached_response = CachedResponse::new(parts.status.as_u16(), headers, bytes.clone());
+    if let Err(err) = store.put(&key, cached_response, rule.ttl()).await {
+        tracing::error!("fail to put the response into the cache: {err}");
+    }
+    Response::from_parts(parts, body::boxed(Full::new(bytes)))
+}
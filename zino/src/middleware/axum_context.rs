@@ -2,11 +2,24 @@ use axum::{body::Body, http, middleware::Next, response::Response};
 use zino_core::request::RequestContext;
 
 pub(crate) async fn request_context(req: crate::Request, next: Next<Body>) -> Response {
-    let new_context = req.get_context().is_none().then(|| req.new_context());
+    let current_context = req.get_context();
+    let new_context = current_context.is_none().then(|| req.new_context());
+    #[cfg(feature = "orm")]
+    let audit_context = current_context
+        .as_ref()
+        .or(new_context.as_ref())
+        .map(zino_core::orm::AuditContext::new)
+        .unwrap_or_default();
+    #[cfg(feature = "orm")]
+    let tenant_context = zino_core::orm::TenantContext::from_request(&req);
 
     let mut req = http::Request::from(req);
     if let Some(ctx) = new_context {
         req.extensions_mut().insert(ctx);
     }
-    next.run(req).await
+
+    let fut = next.run(req);
+    #[cfg(feature = "orm")]
+    let fut = tenant_context.scope(audit_context.scope(fut));
+    fut.await
 }
@@ -0,0 +1,31 @@
+use axum::{body::Body, http, middleware::Next, response::Response};
+use zino_core::{
+    auth::{PolicyEngine, UserSession},
+    error::Error,
+    reject,
+    request::RequestContext,
+    response::Rejection,
+    warn,
+};
+
+/// A middleware which enforces the shared policy engine for the user session
+/// stored in the request data. It should be applied after the user session is set.
+pub async fn enforce_policies<U, T>(
+    req: crate::Request,
+    next: Next<Body>,
+) -> crate::Result<Response>
+where
+    U: Clone + Send + Sync + 'static,
+    T: Clone + Send + Sync + ToString + 'static,
+{
+    let Some(session) = req.get_data::<UserSession<U, String, T>>() else {
+        reject!(req, unauthorized, "the user session is missing");
+    };
+
+    let action = req.request_method().as_str();
+    let resource = req.request_path();
+    if !PolicyEngine::shared().is_allowed(&session, action, resource) {
+        reject!(req, forbidden, "the access to `{resource}` is denied");
+    }
+    Ok(next.run(http::Request::from(req)).await)
+}
@@ -0,0 +1,46 @@
+use axum::{
+    body::Body,
+    http::{
+        self,
+        header::{HeaderName, HeaderValue},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use zino_core::{
+    error::Error,
+    request::{RateLimiter, RequestContext},
+    response::{FullResponse, Rejection, Response as ZinoResponse, StatusCode},
+    warn,
+};
+
+pub(crate) async fn limit_rate(req: crate::Request, next: Next<Body>) -> Response {
+    let Some((key, quota)) = RateLimiter::shared().resolve(&req) else {
+        return next.run(http::Request::from(req)).await;
+    };
+    let status = match RateLimiter::store().acquire(&key, quota).await {
+        Ok(status) => status,
+        Err(err) => {
+            tracing::error!("fail to acquire a permit for the rate limit: {err}");
+            return next.run(http::Request::from(req)).await;
+        }
+    };
+    if !status.is_allowed() {
+        let err = warn!("the rate limit of `{}` is exceeded", req.request_path());
+        let mut res =
+            ZinoResponse::<StatusCode>::from(Rejection::too_many_requests(err).context(&req));
+        for (key, value) in status.headers() {
+            res.insert_header(key, value);
+        }
+        return FullResponse::from(res).into_response();
+    }
+
+    let mut res = next.run(http::Request::from(req)).await;
+    for (key, value) in status.headers() {
+        if let Ok(header_value) = HeaderValue::try_from(value) {
+            res.headers_mut()
+                .insert(HeaderName::from_static(key), header_value);
+        }
+    }
+    res
+}
@@ -1,23 +1,35 @@
 cfg_if::cfg_if! {
     if #[cfg(feature = "actix")] {
+        mod actix_cache;
         mod actix_context;
         mod actix_cors;
         mod actix_etag;
+        mod actix_policy;
+        mod actix_rate_limit;
         mod actix_tracing;
 
+        pub use self::actix_cache::ResponseCacheInitializer;
         pub(crate) use self::actix_context::RequestContextInitializer;
         pub(crate) use self::actix_cors::cors_middleware;
         pub(crate) use self::actix_etag::ETagFinalizer;
+        pub use self::actix_policy::PolicyEnforcer;
+        pub(crate) use self::actix_rate_limit::RateLimitInitializer;
         pub(crate) use self::actix_tracing::tracing_middleware;
     } else if #[cfg(feature = "axum")] {
+        mod axum_cache;
         mod axum_context;
         mod axum_etag;
+        mod axum_policy;
+        mod axum_rate_limit;
         mod axum_static_pages;
         mod tower_cors;
         mod tower_tracing;
 
+        pub use self::axum_cache::cache_response;
         pub(crate) use self::axum_context::request_context;
         pub(crate) use self::axum_etag::extract_etag;
+        pub use self::axum_policy::enforce_policies;
+        pub(crate) use self::axum_rate_limit::limit_rate;
         pub(crate) use self::axum_static_pages::serve_static_pages;
         pub(crate) use self::tower_cors::CORS_MIDDLEWARE;
         pub(crate) use self::tower_tracing::TRACING_MIDDLEWARE;
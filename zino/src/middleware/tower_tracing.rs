@@ -56,7 +56,7 @@ fn custom_make_span(request: &Request<Body>) -> Span {
     let client_ip = headers.get_client_ip().map(|ip| ip.to_string());
     let user_agent = headers.get_str("user-agent");
 
-    if method.is_safe() {
+    let span = if method.is_safe() {
         tracing::info_span!(
             "HTTP request",
             "otel.kind" = "server",
@@ -65,6 +65,7 @@ fn custom_make_span(request: &Request<Body>) -> Span {
             "url.scheme" = scheme,
             "url.path" = path,
             "url.query" = query,
+            "http.route" = Empty,
             "http.request.method" = method.as_str(),
             "http.request.header.traceparent" = Empty,
             "http.request.header.tracestate" = Empty,
@@ -77,6 +78,7 @@ fn custom_make_span(request: &Request<Body>) -> Span {
             "server.port" = port,
             "user_agent.original" = user_agent,
             "context.session_id" = Empty,
+            "context.user_id" = Empty,
             "context.trace_id" = Empty,
             "context.request_id" = Empty,
             "context.span_id" = Empty,
@@ -91,6 +93,7 @@ fn custom_make_span(request: &Request<Body>) -> Span {
             "url.scheme" = scheme,
             "url.path" = path,
             "url.query" = query,
+            "http.route" = Empty,
             "http.request.method" = method.as_str(),
             "http.request.header.traceparent" = Empty,
             "http.request.header.tracestate" = Empty,
@@ -103,12 +106,17 @@ fn custom_make_span(request: &Request<Body>) -> Span {
             "server.port" = port,
             "user_agent.original" = user_agent,
             "context.session_id" = Empty,
+            "context.user_id" = Empty,
             "context.trace_id" = Empty,
             "context.request_id" = Empty,
             "context.span_id" = Empty,
             "context.parent_id" = Empty,
         )
+    };
+    if let Some(trace_context) = TraceContext::from_headers(headers) {
+        trace_context.set_remote_parent(&span);
     }
+    span
 }
 
 fn custom_on_request(request: &Request<Body>, span: &Span) {
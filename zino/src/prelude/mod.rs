@@ -6,7 +6,8 @@
 pub use zino_core::{
     application::{Application, StaticRecord},
     auth::{
-        AccessKeyId, AuthorizationProvider, JwtClaims, SecretAccessKey, SecurityToken, UserSession,
+        AccessKeyId, AuthorizationProvider, JwtClaims, JwtKeySet, PolicyEngine, SecretAccessKey,
+        SecurityToken, UserSession,
     },
     bail,
     datetime::DateTime,
@@ -18,7 +19,7 @@ pub use zino_core::{
     reject,
     request::RequestContext,
     response::{ExtractRejection, Rejection, StatusCode, WebHook},
-    schedule::{AsyncCronJob, CronJob},
+    schedule::{AsyncCronJob, AsyncFallibleCronJob, CronJob, DurableScheduler, FallibleCronJob},
     state::State,
     validation::Validation,
     warn, BoxFuture, Map, Record, Uuid,
@@ -26,4 +27,4 @@ pub use zino_core::{
 
 #[cfg(feature = "orm")]
 #[doc(no_inline)]
-pub use zino_core::orm::{ModelAccessor, ModelHelper, Schema};
+pub use zino_core::orm::{AuditContext, ModelAccessor, ModelHelper, Schema, TenantContext};
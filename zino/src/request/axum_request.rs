@@ -138,7 +138,7 @@ impl FromRequest<(), Body> for AxumExtractor<Request<Body>> {
 /// Concatenates the buffers from a body into a single `Bytes` asynchronously.
 ///
 /// Copy from https://docs.rs/hyper/0.14.27/hyper/body/fn.to_bytes.html
-async fn to_bytes<T: HttpBody + Unpin>(mut body: T) -> Result<Bytes, T::Error> {
+pub(crate) async fn to_bytes<T: HttpBody + Unpin>(mut body: T) -> Result<Bytes, T::Error> {
     let _ = Pin::new(&mut body);
 
     // If there's only 1 chunk, we can just return Buf::to_bytes()
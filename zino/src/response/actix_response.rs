@@ -1,16 +1,14 @@
 use actix_web::{
-    body::BoxBody,
+    body::{BodyStream, BoxBody},
     http::{
         header::{self, HeaderName, HeaderValue},
         StatusCode,
     },
     HttpRequest, HttpResponse, Responder, ResponseError,
 };
-use std::fmt;
-use zino_core::{
-    response::{Rejection, Response, ResponseCode},
-    trace::TimingMetric,
-};
+use futures::TryStreamExt;
+use std::{fmt, io};
+use zino_core::response::{Rejection, Response, ResponseCode};
 
 /// An HTTP response for `actix-web`.
 pub struct ActixResponse<S>(Response<S>);
@@ -32,16 +30,7 @@ impl Responder for ActixResponse<StatusCode> {
             response = response.context(&req);
         }
 
-        let mut res = build_http_response(&mut response);
-        for (key, value) in response.finalize() {
-            if let Ok(header_name) = HeaderName::try_from(key.as_ref())
-                && let Ok(header_value) = HeaderValue::try_from(value)
-            {
-                res.headers_mut().insert(header_name, header_value);
-            }
-        }
-
-        res
+        build_http_response(response)
     }
 }
 
@@ -67,6 +56,13 @@ impl From<Rejection> for ActixRejection {
     }
 }
 
+impl From<Response<StatusCode>> for ActixRejection {
+    #[inline]
+    fn from(response: Response<StatusCode>) -> Self {
+        Self(response)
+    }
+}
+
 impl ResponseError for ActixRejection {
     #[inline]
     fn status_code(&self) -> StatusCode {
@@ -77,70 +73,57 @@ impl ResponseError for ActixRejection {
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
     }
 
+    #[inline]
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        let mut response = self.0.clone();
-        let mut res = build_http_response(&mut response);
-        let request_id = response.request_id();
-        if !request_id.is_nil() {
-            if let Ok(header_value) = HeaderValue::try_from(request_id.to_string()) {
-                let header_name = HeaderName::from_static("x-request-id");
-                res.headers_mut().insert(header_name, header_value);
-            }
-        }
-
-        let (traceparent, tracestate) = response.trace_context();
-        if let Ok(header_value) = HeaderValue::try_from(traceparent) {
-            let header_name = HeaderName::from_static("traceparent");
-            res.headers_mut().insert(header_name, header_value);
-        }
-        if let Ok(header_value) = HeaderValue::try_from(tracestate) {
-            let header_name = HeaderName::from_static("tracestate");
-            res.headers_mut().insert(header_name, header_value);
-        }
-
-        let response_time = response.response_time();
-        let timing = TimingMetric::new("total".into(), None, response_time.into());
-        if let Ok(header_value) = HeaderValue::try_from(timing.to_string()) {
-            let header_name = HeaderName::from_static("server-timing");
-            res.headers_mut().insert(header_name, header_value);
-        }
-
-        for (key, value) in response.headers() {
-            if let Ok(header_name) = HeaderName::try_from(key.as_ref())
-                && let Ok(header_value) = HeaderValue::try_from(value)
-            {
-                res.headers_mut().insert(header_name, header_value);
-            }
-        }
-
-        res
+        build_http_response(self.0.clone())
     }
 }
 
 /// Build http response from `zino_core::response::Response`.
-fn build_http_response(response: &mut Response<StatusCode>) -> HttpResponse<BoxBody> {
-    match response.read_bytes() {
-        Ok(data) => {
-            let status_code = response
-                .status_code()
-                .try_into()
-                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-            let body = BoxBody::new(data);
-            let mut res = HttpResponse::with_body(status_code, body);
-            if let Ok(header_value) = HeaderValue::try_from(response.content_type()) {
-                res.headers_mut().insert(header::CONTENT_TYPE, header_value);
+fn build_http_response(mut response: Response<StatusCode>) -> HttpResponse<BoxBody> {
+    let status_code = response
+        .status_code()
+        .try_into()
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut res = if let Some(stream) = response.take_data_stream() {
+        // Writes the data stream as a chunked body
+        let stream = stream.map_err(|err| io::Error::other(err.to_string()));
+        let body = BoxBody::new(BodyStream::new(stream));
+        let mut res = HttpResponse::with_body(status_code, body);
+        if let Ok(header_value) = HeaderValue::try_from(response.content_type()) {
+            res.headers_mut().insert(header::CONTENT_TYPE, header_value);
+        }
+        res
+    } else {
+        match response.read_bytes() {
+            Ok(data) => {
+                let body = BoxBody::new(data);
+                let mut res = HttpResponse::with_body(status_code, body);
+                if let Ok(header_value) = HeaderValue::try_from(response.content_type()) {
+                    res.headers_mut().insert(header::CONTENT_TYPE, header_value);
+                }
+                res
+            }
+            Err(err) => {
+                let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+                let body = BoxBody::new(err.to_string());
+                let mut res = HttpResponse::with_body(status_code, body);
+                res.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("text/plain; charset=utf-8"),
+                );
+                res
             }
-            res
         }
-        Err(err) => {
-            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
-            let body = BoxBody::new(err.to_string());
-            let mut res = HttpResponse::with_body(status_code, body);
-            res.headers_mut().insert(
-                header::CONTENT_TYPE,
-                HeaderValue::from_static("text/plain; charset=utf-8"),
-            );
-            res
+    };
+
+    for (key, value) in response.finalize() {
+        if let Ok(header_name) = HeaderName::try_from(key.as_ref())
+            && let Ok(header_value) = HeaderValue::try_from(value)
+        {
+            res.headers_mut().insert(header_name, header_value);
         }
     }
+
+    res
 }